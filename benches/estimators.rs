@@ -0,0 +1,119 @@
+//! Compares the heuristic estimator with the exact sizer, in speed and in accuracy, for the
+//! optimal schedule of a few representative parameter sets:
+//!
+//! ```text
+//! cargo bench --bench estimators
+//! ```
+//!
+//! The heuristic prices a schedule in time independent of the query positions, while the exact
+//! sizer builds the opened cosets and Merkle nodes of every layer for one set of positions.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use fri_dynamic_folding_scheme::exact_size::{exact_proof_size, heuristic_error_bound};
+use fri_dynamic_folding_scheme::prelude::*;
+
+/// The number of random sets of query positions sized exactly per parameter set.
+const TRIALS: usize = 100;
+
+/// The minimum time each estimator is run for, so that short calls are timed accurately.
+const MIN_DURATION: Duration = Duration::from_millis(200);
+
+/// Returns the mean duration of `f`, called repeatedly for at least [`MIN_DURATION`].
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut calls = 0;
+    while start.elapsed() < MIN_DURATION {
+        f();
+        calls += 1;
+    }
+    start.elapsed() / calls
+}
+
+/// Returns `count` query positions in a domain of size `degree`, drawn with SplitMix64.
+fn positions(seed: u64, degree: usize, count: usize) -> Vec<usize> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            ((z ^ (z >> 31)) % degree as u64) as usize
+        })
+        .collect()
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>6} {:>7} {:>12} {:>12} {:>9} {:>10} {:>10}",
+        "degree", "blowup", "queries", "heuristic", "exact", "slowdown", "mean ratio", "worst"
+    );
+    for (degree_bits, blowup_factor, num_queries) in [
+        (16, 4, 40),
+        (20, 8, 27),
+        (24, 8, 27),
+        (25, 16, 20),
+        (28, 8, 27),
+    ] {
+        let degree = 1usize << degree_bits;
+        let cost_model = CostModel::default();
+        let (_, schedule) = optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            vec![0],
+            &HashMap::new(),
+            &cost_model,
+            &mut SearchStats::default(),
+        )
+        .expect("the unconstrained search always finds a schedule");
+
+        let heuristic = time(|| {
+            black_box(
+                estimate_proof_size(
+                    black_box(degree),
+                    blowup_factor,
+                    num_queries,
+                    black_box(&schedule),
+                    &cost_model,
+                )
+                .unwrap(),
+            );
+        });
+        let query_sets: Vec<_> = (0..TRIALS as u64)
+            .map(|seed| positions(seed, degree, num_queries))
+            .collect();
+        let mut trial = 0;
+        let exact = time(|| {
+            black_box(exact_proof_size(
+                black_box(degree),
+                blowup_factor,
+                &query_sets[trial % TRIALS],
+                black_box(&schedule),
+                &cost_model,
+            ));
+            trial += 1;
+        });
+        let (mean_ratio, worst_ratio) = heuristic_error_bound(
+            degree,
+            blowup_factor,
+            num_queries,
+            &schedule,
+            &cost_model,
+            TRIALS,
+            0,
+        );
+
+        println!(
+            "{:>8} {blowup_factor:>6} {num_queries:>7} {:>12} {:>12} {:>8.0}x {mean_ratio:>10.3} \
+             {worst_ratio:>10.3}",
+            format!("2^{degree_bits}"),
+            format!("{heuristic:?}"),
+            format!("{exact:?}"),
+            exact.as_secs_f64() / heuristic.as_secs_f64(),
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Times the optimizer under named scenarios, each combining optimizer features with fixed
+//! inputs, so that a regression confined to one combination of features shows up:
+//!
+//! ```text
+//! cargo bench --bench scenarios
+//! ```
+//!
+//! Every scenario is reported with its mean time and its time relative to the `plain` scenario.
+//! To compare a change against the tree before it, save the timings of the old tree under a name
+//! and compare the new tree against them:
+//!
+//! ```text
+//! git stash && cargo bench --bench scenarios -- --save-baseline before
+//! git stash pop && cargo bench --bench scenarios -- --baseline before
+//! ```
+//!
+//! Baselines are kept in `target/scenario-baselines`. Since each scenario calls the public API,
+//! a feature combination that no longer composes fails to compile here.
+
+use std::collections::HashMap;
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use fri_dynamic_folding_scheme::analysis::min_regret_schedule;
+use fri_dynamic_folding_scheme::objective::{optimal_lexicographic, Objective};
+use fri_dynamic_folding_scheme::optimized_schedule::{
+    optimal_for_query_counts, optimal_under_verifier_memory, optimal_with_max_folding_factor,
+};
+use fri_dynamic_folding_scheme::prelude::*;
+
+/// The degree every scenario proves, with a blowup factor of 8 and 27 queries.
+const DEGREE: usize = 1 << 20;
+
+/// The minimum time each scenario is run for, so that short scenarios are timed accurately.
+const MIN_DURATION: Duration = Duration::from_millis(500);
+
+/// A named combination of optimizer features, returning the schedules or sizes it finds.
+struct Scenario {
+    name: &'static str,
+    run: fn() -> Vec<usize>,
+}
+
+const SCENARIOS: [Scenario; 6] = [
+    Scenario {
+        name: "plain",
+        run: plain,
+    },
+    Scenario {
+        name: "shared-paths",
+        run: shared_paths,
+    },
+    Scenario {
+        name: "query-sweep",
+        run: query_sweep,
+    },
+    Scenario {
+        name: "constrained",
+        run: constrained,
+    },
+    Scenario {
+        name: "composite-objective",
+        run: composite_objective,
+    },
+    Scenario {
+        name: "robustness",
+        run: robustness,
+    },
+];
+
+/// The unconstrained search under the default cost model.
+fn plain() -> Vec<usize> {
+    let (_, schedule) = optimal_folding_strategy(
+        black_box(DEGREE),
+        8,
+        27,
+        vec![0],
+        &HashMap::new(),
+        &CostModel::default(),
+        &mut SearchStats::default(),
+    )
+    .expect("the unconstrained search always finds a schedule");
+    schedule
+}
+
+/// The unconstrained search with authentication paths shared between queries and the second
+/// layer pinned.
+fn shared_paths() -> Vec<usize> {
+    let cost_model = CostModel {
+        path_model: PathModel::IdealCompression,
+        ..CostModel::default()
+    };
+    let (_, schedule) = optimal_folding_strategy(
+        black_box(DEGREE),
+        8,
+        27,
+        vec![0],
+        &HashMap::from([(1, 3)]),
+        &cost_model,
+        &mut SearchStats::default(),
+    )
+    .expect("a pin of 3 bits leaves room to fold");
+    schedule
+}
+
+/// The optimal schedules for a range of query counts, with shared authentication paths.
+fn query_sweep() -> Vec<usize> {
+    let cost_model = CostModel {
+        path_model: PathModel::IdealCompression,
+        ..CostModel::default()
+    };
+    let optima = optimal_for_query_counts(black_box(DEGREE), 8, &[16, 20, 27, 32, 40], &cost_model)
+        .expect("the sweep always finds a schedule");
+    optima.into_iter().map(|(size, _)| size).collect()
+}
+
+/// Searches under a folding factor cap and a verifier memory budget.
+fn constrained() -> Vec<usize> {
+    let (_, capped) = optimal_with_max_folding_factor(
+        black_box(DEGREE),
+        8,
+        27,
+        3,
+        &GOLDILOCKS_QUADRATIC,
+        &mut SearchStats::default(),
+    )
+    .expect("a cap of 3 bits leaves room to fold");
+    let (_, budgeted) = optimal_under_verifier_memory(
+        black_box(DEGREE),
+        8,
+        27,
+        1 << 16,
+        &GOLDILOCKS_QUADRATIC,
+        &mut SearchStats::default(),
+    )
+    .expect("64 KiB of verifier memory fits a schedule");
+    [capped, budgeted].concat()
+}
+
+/// Ranks schedules by the verifier's hashes, then by proof size, with shared authentication
+/// paths.
+fn composite_objective() -> Vec<usize> {
+    let cost_model = CostModel {
+        path_model: PathModel::IdealCompression,
+        ..POSEIDON_256
+    };
+    let (_, schedule) = optimal_lexicographic(
+        black_box(DEGREE),
+        8,
+        27,
+        &[Objective::VerifierHashes, Objective::ProofSize],
+        &cost_model,
+    );
+    schedule
+}
+
+/// The schedule with the smallest worst-case regret over the presets.
+fn robustness() -> Vec<usize> {
+    min_regret_schedule(
+        black_box(DEGREE),
+        8,
+        27,
+        &[GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256],
+    )
+    .unwrap()
+    .schedule
+}
+
+/// Returns the mean duration of `f`, called repeatedly for at least [`MIN_DURATION`].
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut calls = 0;
+    while start.elapsed() < MIN_DURATION {
+        f();
+        calls += 1;
+    }
+    start.elapsed() / calls
+}
+
+/// Returns the file holding the baseline saved under `name`.
+fn baseline_path(name: &str) -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target"));
+    target.join("scenario-baselines").join(name)
+}
+
+/// Reads the mean time of every scenario from the baseline saved under `name`.
+fn read_baseline(name: &str) -> HashMap<String, Duration> {
+    let path = baseline_path(name);
+    let contents = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("cannot read the baseline {}: {err}", path.display()));
+    contents
+        .lines()
+        .map(|line| {
+            let (scenario, nanos) = line
+                .split_once(' ')
+                .expect("a baseline line is `name nanos`");
+            let nanos = nanos.parse().expect("a baseline time is in nanoseconds");
+            (scenario.to_string(), Duration::from_nanos(nanos))
+        })
+        .collect()
+}
+
+fn main() {
+    // cargo passes `--bench` to every bench target, and the baseline flags after `--`.
+    let mut save_baseline = None;
+    let mut baseline = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--save-baseline" => save_baseline = args.next(),
+            "--baseline" => baseline = args.next(),
+            _ => {}
+        }
+    }
+    let baseline = baseline.as_deref().map(read_baseline);
+
+    println!(
+        "{:<20} {:>12} {:>9} {:>12}",
+        "scenario", "elapsed", "vs plain", "vs baseline"
+    );
+    let mut timings = Vec::new();
+    for scenario in &SCENARIOS {
+        let elapsed = time(|| {
+            black_box((scenario.run)());
+        });
+        let plain = timings.first().map_or(elapsed, |&(_, plain)| plain);
+        let vs_baseline = baseline
+            .as_ref()
+            .and_then(|baseline| baseline.get(scenario.name))
+            .map_or("-".to_string(), |before| {
+                format!(
+                    "{:+.1}%",
+                    100.0 * (elapsed.as_secs_f64() / before.as_secs_f64() - 1.0)
+                )
+            });
+        println!(
+            "{:<20} {:>12} {:>9} {:>12}",
+            scenario.name,
+            format!("{elapsed:?}"),
+            format!("{:.2}x", elapsed.as_secs_f64() / plain.as_secs_f64()),
+            vs_baseline,
+        );
+        timings.push((scenario.name, elapsed));
+    }
+
+    if let Some(name) = save_baseline {
+        let path = baseline_path(&name);
+        fs::create_dir_all(path.parent().expect("the baseline lies in a directory"))
+            .expect("the baseline directory can be created");
+        let contents: String = timings
+            .iter()
+            .map(|(scenario, elapsed)| format!("{scenario} {}\n", elapsed.as_nanos()))
+            .collect();
+        fs::write(&path, contents).expect("the baseline can be written");
+        println!("saved the baseline `{name}` to {}", path.display());
+    }
+}
@@ -0,0 +1,44 @@
+//! Times the exhaustive search for the optimal folding schedule with a blowup factor of 2, where
+//! schedules are longest:
+//!
+//! ```text
+//! cargo bench --bench search
+//! ```
+//!
+//! The number of schedules the search visits grows by a factor of about 1.9 per bit of degree,
+//! so the time per visited schedule is reported alongside the total.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use fri_dynamic_folding_scheme::prelude::*;
+
+fn main() {
+    println!(
+        "{:>8} {:>6} {:>10} {:>12} {:>12} {:>10}",
+        "degree", "blowup", "length", "nodes", "elapsed", "per node"
+    );
+    for degree_bits in [16, 20, 24, 26, 28] {
+        let (degree, blowup_factor) = (1usize << degree_bits, 2);
+        let mut stats = SearchStats::default();
+        let (_, schedule) = optimal_folding_strategy(
+            black_box(degree),
+            blowup_factor,
+            27,
+            vec![0],
+            &HashMap::new(),
+            &CostModel::default(),
+            &mut stats,
+        )
+        .expect("the unconstrained search always finds a schedule");
+
+        println!(
+            "{:>8} {blowup_factor:>6} {:>10} {:>12} {:>12} {:>10}",
+            format!("2^{degree_bits}"),
+            schedule.len(),
+            stats.nodes_explored,
+            format!("{:?}", stats.elapsed),
+            format!("{:?}", stats.elapsed / stats.nodes_explored as u32),
+        );
+    }
+}
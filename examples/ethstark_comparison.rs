@@ -0,0 +1,208 @@
+//! Compares the estimated size of FRI proofs with proof sizes published for ethSTARK, as an
+//! external check of the cost model:
+//!
+//! ```text
+//! cargo run --release --example ethstark_comparison -- <published.csv> [--tolerance <percent>]
+//! ```
+//!
+//! The published parameter sets are read from a CSV file with one proof per line:
+//!
+//! ```text
+//! # degree,blowup,queries,grinding_bits,fri_steps,published_bytes,source
+//! <degree>,<blowup>,<queries>,<grinding bits>,<bits> <bits> ...,<bytes>,<publication and table>
+//! ```
+//!
+//! where `degree` is the size of the LDE domain, `fri_steps` the FRI step list of the proof as
+//! space-separated folding bits starting with the unfolded first layer, and `source` a free-form
+//! reference to where the size was published. Lines starting with `#` are ignored. The numbers
+//! are kept out of the example so that every one of them is copied from, and traceable to, the
+//! publication it comes from.
+//!
+//! Every proof is priced with [`GOLDILOCKS_QUADRATIC`], the preset whose 8-byte field elements
+//! come closest to ethSTARK's 61-bit prime field, and the example fails if any estimate is off by more than the
+//! tolerance, 25% by default. Grinding is not part of the FRI layers and only adds a nonce to the
+//! proof, which is not counted. The published sizes cover whole STARK proofs, whose trace and
+//! composition openings this crate does not model, and are hashed and serialized differently, so
+//! the estimate is expected to fall below them; the output says so for every proof.
+//!
+//! The tolerance is checked under `cargo test` against `tests/fixtures/ethstark_reference.csv`,
+//! which holds reference sizes rather than published ones, so that a change to the cost model
+//! breaking the reconciliation fails the test suite.
+
+use std::process;
+
+use fri_dynamic_folding_scheme::prelude::*;
+
+/// The tolerance, in percent of the published size, used unless `--tolerance` is given.
+const DEFAULT_TOLERANCE: f64 = 25.0;
+
+/// A proof whose size was published, as read from one line of the CSV file.
+struct PublishedProof {
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    grinding_bits: usize,
+    fri_steps: Vec<usize>,
+    published_bytes: usize,
+    source: String,
+}
+
+/// Parses the CSV file of published proofs.
+fn parse_published(contents: &str) -> Result<Vec<PublishedProof>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let invalid = || format!("line {}: invalid published proof `{line}`", i + 1);
+            let fields: Vec<_> = line.splitn(7, ',').map(str::trim).collect();
+            let [degree, blowup, queries, grinding, steps, bytes, source] = fields[..] else {
+                return Err(invalid());
+            };
+            let number = |field: &str| field.parse().map_err(|_| invalid());
+            Ok(PublishedProof {
+                degree: number(degree)?,
+                blowup_factor: number(blowup)?,
+                num_queries: number(queries)?,
+                grinding_bits: number(grinding)?,
+                fri_steps: steps
+                    .split_whitespace()
+                    .map(number)
+                    .collect::<Result<_, _>>()?,
+                published_bytes: number(bytes)?,
+                source: source.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The estimate of a published proof.
+struct Comparison {
+    estimated_bytes: usize,
+    /// The signed error of the estimate, in percent of the published size.
+    error: f64,
+}
+
+/// Estimates the size of every published proof under `cost_model`.
+fn compare(proofs: &[PublishedProof], cost_model: &CostModel) -> Result<Vec<Comparison>, String> {
+    proofs
+        .iter()
+        .map(|proof| {
+            let size = estimate_proof_size(
+                proof.degree,
+                proof.blowup_factor,
+                proof.num_queries,
+                &proof.fri_steps,
+                cost_model,
+            )
+            .map_err(|err| format!("{}: {err}", proof.source))?;
+            let estimated_bytes = size_in_bytes(size, cost_model);
+            Ok(Comparison {
+                estimated_bytes,
+                error: 100.0 * (estimated_bytes as f64 / proof.published_bytes as f64 - 1.0),
+            })
+        })
+        .collect()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let tolerance = match args.iter().position(|arg| arg == "--tolerance") {
+        Some(i) => args.get(i + 1).and_then(|value| value.parse().ok()),
+        None => Some(DEFAULT_TOLERANCE),
+    };
+    let (Some(path), Some(tolerance)) =
+        (args.first().filter(|arg| !arg.starts_with("--")), tolerance)
+    else {
+        eprintln!("usage: ethstark_comparison <published.csv> [--tolerance <percent>]");
+        process::exit(2);
+    };
+
+    let proofs = std::fs::read_to_string(path)
+        .map_err(|err| format!("cannot read `{path}`: {err}"))
+        .and_then(|contents| parse_published(&contents))
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            process::exit(2);
+        });
+
+    let comparisons = compare(&proofs, &GOLDILOCKS_QUADRATIC).unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        process::exit(2);
+    });
+    let mut worst_error: f64 = 0.0;
+    for (proof, comparison) in proofs.iter().zip(&comparisons) {
+        let error = comparison.error;
+        worst_error = worst_error.max(error.abs());
+        println!(
+            "{}: degree {}, blowup {}, {} queries, {} grinding bits, steps {:?}: estimated {} \
+             bytes against {} published ({error:+.1}%)",
+            proof.source,
+            proof.degree,
+            proof.blowup_factor,
+            proof.num_queries,
+            proof.grinding_bits,
+            proof.fri_steps,
+            comparison.estimated_bytes,
+            proof.published_bytes
+        );
+        println!(
+            "  not modeled: trace and composition openings, the grinding nonce, ethSTARK's hash \
+             digests and serialization"
+        );
+    }
+
+    println!(
+        "{} proofs compared, worst error {worst_error:.1}% (tolerance {tolerance}%)",
+        proofs.len()
+    );
+    if worst_error > tolerance {
+        eprintln!("error: the estimates no longer reconcile with the published sizes");
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_sizes_reconcile_within_the_tolerance() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/ethstark_reference.csv"
+        );
+        let proofs = parse_published(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(proofs.len(), 3);
+        for (proof, comparison) in proofs
+            .iter()
+            .zip(compare(&proofs, &GOLDILOCKS_QUADRATIC).unwrap())
+        {
+            assert!(
+                comparison.error.abs() <= DEFAULT_TOLERANCE,
+                "{}: estimated {} bytes, {:+.1}% off",
+                proof.source,
+                comparison.estimated_bytes,
+                comparison.error
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        assert_eq!(
+            parse_published("# a comment\n1024,4,27,0,0 3").err(),
+            Some("line 2: invalid published proof `1024,4,27,0,0 3`".to_string())
+        );
+        let proofs = [PublishedProof {
+            degree: 1024,
+            blowup_factor: 4,
+            num_queries: 27,
+            grinding_bits: 0,
+            fri_steps: vec![1, 3],
+            published_bytes: 1000,
+            source: "unfolded first layer missing".to_string(),
+        }];
+        assert!(compare(&proofs, &GOLDILOCKS_QUADRATIC).is_err());
+    }
+}
@@ -0,0 +1,1718 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+use super::cost_model::{CostModel, PathModel, QueryValueTransmission};
+use super::error::FoldingError;
+use super::optimized_schedule::{
+    estimate_proof_size_unchecked, for_each_schedule, optimal_folding_strategy, size_in_bytes,
+    validate_parameters, validate_schedule, MAX_FOLDING_BITS,
+};
+use super::prover_cost::total_hash_input_elements;
+use super::report::FoldingReport;
+use super::simple_schedule::simple_schedule_with_best_remainder;
+use super::soundness::required_queries;
+use super::stats::SearchStats;
+use super::table::ReportTable;
+
+/// The range of query counts over which a folding schedule stays optimal, together with the
+/// schedules that overtake it just outside that range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryBand {
+    /// The smallest query count (at least 1) for which the schedule is optimal.
+    pub min_queries: usize,
+    /// The largest query count for which the schedule is optimal, or `None` if it stays optimal
+    /// for any larger number of queries.
+    pub max_queries: Option<usize>,
+    /// The schedule that is optimal just below `min_queries`.
+    pub below: Option<Vec<usize>>,
+    /// The schedule that is optimal just above `max_queries`.
+    pub above: Option<Vec<usize>>,
+}
+
+/// Computes the range of query counts over which `schedule` yields the smallest estimated proof
+/// size among all schedules the optimizer considers.
+///
+/// For a fixed schedule the estimated size is affine in the number of queries: every Merkle path
+/// and opened coset is charged per query, while the remainder is not. Writing the size of a
+/// schedule `s` as `F(s) + q * P(s)`, `schedule` is no larger than `s` exactly when
+/// `q * (P(schedule) - P(s)) <= F(s) - F(schedule)`, so every other schedule bounds the band from
+/// one side and a single pass over all schedules yields both edges.
+///
+/// Shared authentication paths grow sublinearly in the number of queries, so the band is only
+/// exact under [`PathModel::Naive`].
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries the schedule was optimized for
+/// * `schedule` - The folding schedule, optimal for `num_queries`
+/// * `cost_model` - The cost model used to price each schedule
+pub fn query_sensitivity_band(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    schedule: &[usize],
+    cost_model: &CostModel,
+) -> QueryBand {
+    // splits the size of a schedule into its fixed and per-query parts.
+    let coefficients = |seq: &[usize]| {
+        let fixed =
+            estimate_proof_size_unchecked(degree, blowup_factor, 0, seq, cost_model) as i128;
+        let per_query = estimate_proof_size_unchecked(degree, blowup_factor, 1, seq, cost_model)
+            as i128
+            - fixed;
+        (fixed, per_query)
+    };
+    let (fixed, per_query) = coefficients(schedule);
+
+    // the tightest bound on each side, with the size and schedule that overtakes `schedule` right
+    // past it.
+    let mut lower: Option<(i128, i128, Vec<usize>)> = None;
+    let mut upper: Option<(i128, i128, Vec<usize>)> = None;
+    for_each_schedule(degree, blowup_factor, &mut |seq| {
+        let (other_fixed, other_per_query) = coefficients(seq);
+        if other_per_query > per_query {
+            // `seq` wins for few queries: `schedule` needs q >= ceil(bound).
+            let bound = (fixed - other_fixed).div_euclid(other_per_query - per_query)
+                + ((fixed - other_fixed).rem_euclid(other_per_query - per_query) != 0) as i128;
+            let size_past = other_fixed + (bound - 1) * other_per_query;
+            if lower
+                .as_ref()
+                .is_none_or(|(b, size, _)| bound > *b || (bound == *b && size_past < *size))
+            {
+                lower = Some((bound, size_past, seq.to_vec()));
+            }
+        } else if other_per_query < per_query {
+            // `seq` wins for many queries: `schedule` needs q <= floor(bound).
+            let bound = (other_fixed - fixed).div_euclid(per_query - other_per_query);
+            let size_past = other_fixed + (bound + 1) * other_per_query;
+            if upper
+                .as_ref()
+                .is_none_or(|(b, size, _)| bound < *b || (bound == *b && size_past < *size))
+            {
+                upper = Some((bound, size_past, seq.to_vec()));
+            }
+        }
+    });
+
+    // a bound at or below a single query does not restrict the band.
+    let (min_queries, below) = match lower {
+        Some((bound, _, seq)) if bound > 1 => (bound as usize, Some(seq)),
+        _ => (1, None),
+    };
+    let (max_queries, above) = match upper {
+        Some((bound, _, seq)) => (Some(bound.max(0) as usize), Some(seq)),
+        None => (None, None),
+    };
+    debug_assert!(min_queries <= num_queries && max_queries.is_none_or(|max| num_queries <= max));
+
+    QueryBand {
+        min_queries,
+        max_queries,
+        below,
+        above,
+    }
+}
+
+/// Expresses the size difference between two schedules in queries: the number of queries the
+/// proof of `schedule_b` could add before it is as large as the proof of `schedule_a`. The
+/// difference is divided by the marginal cost of one more query for `schedule_b`, the smaller one
+/// when the result is positive; a negative result counts the queries `schedule_a` could add
+/// instead, priced at the marginal cost of `schedule_b`.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::size_diff_in_queries;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// // the optimal schedule leaves room for 4 more queries within the size of the uniform one.
+/// let (optimal, uniform) = (vec![0, 4, 4, 4, 3], vec![0, 3, 3, 3, 3, 3, 3]);
+/// let cost_model = CostModel::default();
+/// let worth = size_diff_in_queries(1 << 25, 8, 27, &uniform, &optimal, &cost_model);
+/// assert_eq!(worth, (14774 - 12622) as f64 / (13080 - 12622) as f64);
+/// assert_eq!(worth.floor(), 4.0);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries both proofs are sized for
+/// * `schedule_a` - The folding schedule whose size is matched
+/// * `schedule_b` - The folding schedule queries are added to
+/// * `cost_model` - The cost model used to price both schedules
+///
+/// # Returns
+/// * `queries` - The difference in queries, fractional since the last query generally overshoots;
+///   infinite if more queries do not grow the proof of `schedule_b`, e.g. when every queried
+///   component is excluded
+pub fn size_diff_in_queries(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    schedule_a: &[usize],
+    schedule_b: &[usize],
+    cost_model: &CostModel,
+) -> f64 {
+    let size = |queries, schedule: &[usize]| {
+        estimate_proof_size_unchecked(degree, blowup_factor, queries, schedule, cost_model) as f64
+    };
+    let difference = size(num_queries, schedule_a) - size(num_queries, schedule_b);
+    if difference == 0.0 {
+        return 0.0;
+    }
+    // with shared paths the marginal cost falls as queries are added, so it is taken at the
+    // given count.
+    let marginal = size(num_queries + 1, schedule_b) - size(num_queries, schedule_b);
+    difference / marginal
+}
+
+/// Computes the optimal proof for each blowup factor at a fixed security level. A larger blowup
+/// needs fewer queries, see [`required_queries`], but commits to larger layers, so the proof size
+/// usually bottoms out at some intermediate blowup.
+///
+/// The polynomial is the same for every blowup factor, so each one evaluates it over a domain of
+/// `poly_degree * blowup_factor`.
+///
+/// # Arguments
+/// * `poly_degree` - The degree of the polynomial to be proved, before the blowup
+/// * `blowup_set` - The blowup factors to compare
+/// * `security_bits` - The target security level in bits
+/// * `conjectured` - Whether to derive the queries from the conjectured rather than the proven
+///   soundness bound
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `reports` - For every blowup factor, in the order given, the blowup factor and the report of
+///   its optimal folding schedule
+///
+/// # Errors
+/// * If the polynomial degree or a blowup factor is not a power of 2
+/// * If an evaluation domain does not fit in a `usize`
+///
+/// # Panics
+/// * If a blowup factor is 1
+pub fn size_vs_blowup_at_security(
+    poly_degree: usize,
+    blowup_set: &[usize],
+    security_bits: u32,
+    conjectured: bool,
+    cost_model: &CostModel,
+) -> Result<Vec<(usize, FoldingReport)>, FoldingError> {
+    blowup_set
+        .iter()
+        .map(|&blowup_factor| {
+            let report = optimal_at_security(
+                poly_degree,
+                blowup_factor,
+                security_bits,
+                conjectured,
+                cost_model,
+            )?;
+            Ok((blowup_factor, report))
+        })
+        .collect()
+}
+
+/// Returns the size of the domain a polynomial of degree `poly_degree` is evaluated over with the
+/// given blowup factor, checking that both are powers of 2.
+pub(crate) fn evaluation_domain(
+    poly_degree: usize,
+    blowup_factor: usize,
+) -> Result<usize, FoldingError> {
+    validate_parameters(poly_degree, 1)?;
+    let degree =
+        poly_degree
+            .checked_mul(blowup_factor)
+            .ok_or(FoldingError::ArithmeticOverflow {
+                quantity: "evaluation domain",
+            })?;
+    validate_parameters(degree, blowup_factor)?;
+    Ok(degree)
+}
+
+/// Returns the report of the optimal proof for one blowup factor, as by
+/// [`size_vs_blowup_at_security`].
+pub(crate) fn optimal_at_security(
+    poly_degree: usize,
+    blowup_factor: usize,
+    security_bits: u32,
+    conjectured: bool,
+    cost_model: &CostModel,
+) -> Result<FoldingReport, FoldingError> {
+    let degree = evaluation_domain(poly_degree, blowup_factor)?;
+    let num_queries = required_queries(blowup_factor, security_bits, conjectured);
+    let (_, schedule) = optimal_folding_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        vec![0],
+        &HashMap::new(),
+        cost_model,
+        &mut SearchStats::default(),
+    )?;
+    Ok(FoldingReport::new(
+        degree,
+        blowup_factor,
+        num_queries,
+        schedule,
+        cost_model,
+    ))
+}
+
+/// Finds the smallest blowup factor whose optimal proof fits within `size_budget` bytes at a
+/// fixed security level, i.e. the configuration cheapest to prove that meets a size cap. A
+/// smaller blowup commits to smaller domains but needs more queries, see [`required_queries`],
+/// and the proof size need not be monotonic in the blowup, so every blowup factor is sized, from
+/// the smallest up, as by [`size_vs_blowup_at_security`].
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::min_blowup_for_size;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let blowups = [2, 4, 8, 16, 32];
+/// let cost_model = CostModel::default();
+/// let report = min_blowup_for_size(1 << 16, &blowups, 100, true, 100_000, &cost_model)
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!((report.blowup_factor, report.num_queries), (4, 50));
+/// assert!(size_in_bytes(report.total_elements(), &cost_model) <= 100_000);
+///
+/// assert_eq!(min_blowup_for_size(1 << 16, &blowups, 100, true, 10_000, &cost_model), Ok(None));
+/// ```
+///
+/// # Arguments
+/// * `poly_degree` - The degree of the polynomial to be proved, before the blowup
+/// * `blowup_set` - The blowup factors to choose from, in any order
+/// * `security_bits` - The target security level in bits
+/// * `conjectured` - Whether to derive the queries from the conjectured rather than the proven
+///   soundness bound
+/// * `size_budget` - The largest acceptable proof size in bytes
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `report` - The report of the optimal folding schedule for the smallest blowup factor that
+///   fits the budget, or `None` if none does
+///
+/// # Errors
+/// * On the parameters [`size_vs_blowup_at_security`] rejects
+pub fn min_blowup_for_size(
+    poly_degree: usize,
+    blowup_set: &[usize],
+    security_bits: u32,
+    conjectured: bool,
+    size_budget: usize,
+    cost_model: &CostModel,
+) -> Result<Option<FoldingReport>, FoldingError> {
+    let mut blowups = blowup_set.to_vec();
+    blowups.sort_unstable();
+    for blowup_factor in blowups {
+        let report = optimal_at_security(
+            poly_degree,
+            blowup_factor,
+            security_bits,
+            conjectured,
+            cost_model,
+        )?;
+        if size_in_bytes(report.total_elements(), cost_model) <= size_budget {
+            return Ok(Some(report));
+        }
+    }
+    Ok(None)
+}
+
+/// The optimal proof at one level of grinding, see [`optimal_with_grinding`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrindingLevel {
+    /// The number of grinding bits, each of which replaces one bit of query security.
+    pub grinding_bits: u32,
+    /// The expected time the prover spends grinding.
+    pub grinding_time: Duration,
+    /// The report of the optimal folding schedule for the queries still needed.
+    pub report: FoldingReport,
+}
+
+/// The optimal proof at every level of grinding within a prover-time ceiling, see
+/// [`optimal_with_grinding`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrindingTradeoff {
+    /// Every level of grinding within the ceiling, from no grinding up.
+    pub levels: Vec<GrindingLevel>,
+    /// The index in `levels` of the smallest proof, the least grinding breaking ties.
+    pub best: usize,
+}
+
+impl GrindingTradeoff {
+    /// Returns the level of grinding yielding the smallest proof.
+    pub fn best(&self) -> &GrindingLevel {
+        &self.levels[self.best]
+    }
+
+    /// Lays the tradeoff out as a table with one row per level of grinding.
+    pub fn to_table(&self) -> ReportTable {
+        let mut table = ReportTable::new([
+            "grinding bits",
+            "grinding time",
+            "queries",
+            "schedule",
+            "size (elements)",
+            "size (B)",
+        ]);
+        for level in &self.levels {
+            let report = &level.report;
+            table.push_row([
+                level.grinding_bits.to_string(),
+                format!("{:?}", level.grinding_time),
+                report.num_queries.to_string(),
+                format!("{:?}", report.schedule),
+                report.total_elements().to_string(),
+                size_in_bytes(report.total_elements(), &report.cost_model).to_string(),
+            ]);
+        }
+        table
+    }
+}
+
+/// Finds the level of grinding, number of queries and folding schedule yielding the smallest
+/// proof at a fixed security level, spending at most `max_grinding_time` on grinding. Every
+/// grinding bit replaces one bit of query security, see [`required_queries`], and takes the prover
+/// an expected `2^bits / hash_rate` seconds. Every level from no grinding up to the most the
+/// ceiling allows is sized with its optimal schedule, stopping short of the security level
+/// itself, which would need no queries at all.
+///
+/// A ceiling below the time of a single grinding bit leaves no grinding as the only level.
+///
+/// With a synthetic rate of 2^20 hashes per second, a second of grinding buys up to 20 bits. At a
+/// blowup factor of 8, where a query brings 3 bits, 19 of them already save 7 of the 34 queries:
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use fri_dynamic_folding_scheme::analysis::optimal_with_grinding;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let hash_rate = (1 << 20) as f64;
+/// let (ceiling, cost_model) = (Duration::from_secs(1), CostModel::default());
+/// let tradeoff = optimal_with_grinding(1 << 20, 8, 100, hash_rate, ceiling, &cost_model).unwrap();
+/// let best = tradeoff.best();
+/// assert_eq!((best.grinding_bits, best.report.num_queries), (19, 27));
+/// assert_eq!(best.grinding_time, Duration::from_millis(500));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `security_bits` - The target security level in bits, reached with the conjectured soundness
+///   bound
+/// * `hash_rate` - The number of hashes per second the prover grinds with
+/// * `max_grinding_time` - The most time the prover may spend grinding
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `tradeoff` - The optimal proof at every level of grinding within the ceiling, and the best
+///   of them
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If the security level is 0, which needs no queries
+///
+/// # Panics
+/// * If the blowup factor is 1
+/// * If the hash rate is not positive
+pub fn optimal_with_grinding(
+    degree: usize,
+    blowup_factor: usize,
+    security_bits: u32,
+    hash_rate: f64,
+    max_grinding_time: Duration,
+    cost_model: &CostModel,
+) -> Result<GrindingTradeoff, FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    assert!(
+        hash_rate > 0.0,
+        "the hash rate must be positive, got {hash_rate}"
+    );
+    if security_bits == 0 {
+        return Err(FoldingError::NoQueriesNeeded);
+    }
+
+    // levels with the same number of queries share their optimal schedule.
+    let mut optima = HashMap::new();
+    let mut levels: Vec<GrindingLevel> = Vec::new();
+    for grinding_bits in 0..security_bits {
+        let grinding_secs = match grinding_bits {
+            0 => 0.0,
+            bits => 2f64.powi(bits as i32) / hash_rate,
+        };
+        if grinding_secs > max_grinding_time.as_secs_f64() {
+            break;
+        }
+        let num_queries = required_queries(blowup_factor, security_bits - grinding_bits, true);
+        let schedule = match optima.get(&num_queries) {
+            Some(schedule) => Vec::clone(schedule),
+            None => {
+                let (_, schedule) = optimal_folding_strategy(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    vec![0],
+                    &HashMap::new(),
+                    cost_model,
+                    &mut SearchStats::default(),
+                )?;
+                optima.insert(num_queries, schedule.clone());
+                schedule
+            }
+        };
+        levels.push(GrindingLevel {
+            grinding_bits,
+            grinding_time: Duration::from_secs_f64(grinding_secs),
+            report: FoldingReport::new(degree, blowup_factor, num_queries, schedule, cost_model),
+        });
+    }
+
+    let best = (0..levels.len())
+        .min_by_key(|&level| levels[level].report.total_elements())
+        .expect("no grinding always fits the ceiling");
+    Ok(GrindingTradeoff { levels, best })
+}
+
+/// A way of raising the security of a proof, see [`security_increase_options`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityLever {
+    /// More queries, with the folding schedule re-optimized for them.
+    Queries,
+    /// Grinding bits on top of the queries, with the folding schedule unchanged.
+    Grinding,
+    /// A doubled blowup factor, with the queries it needs and the folding schedule re-optimized
+    /// for them.
+    Blowup,
+}
+
+impl fmt::Display for SecurityLever {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityLever::Queries => write!(f, "more queries"),
+            SecurityLever::Grinding => write!(f, "grinding"),
+            SecurityLever::Blowup => write!(f, "double blowup"),
+        }
+    }
+}
+
+/// The proof one lever raises the security to, see [`security_increase_options`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecurityOption {
+    /// The lever pulled.
+    pub lever: SecurityLever,
+    /// The number of grinding bits added.
+    pub grinding_bits: u32,
+    /// The expected time the prover spends grinding.
+    pub grinding_time: Duration,
+    /// The report of the proof's folding schedule.
+    pub report: FoldingReport,
+    /// The field elements the prover absorbs into hashes committing to the proof's layers, see
+    /// [`total_hash_input_elements`].
+    pub hash_input_elements: u64,
+}
+
+/// The current proof and the proofs each lever raises its security to, see
+/// [`security_increase_options`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecurityIncrease {
+    /// The number of bits of security added.
+    pub extra_bits: u32,
+    /// The current proof, with the optimal folding schedule for the current queries.
+    pub current: SecurityOption,
+    /// The proof of every lever, in the order more queries, grinding, doubled blowup.
+    pub options: Vec<SecurityOption>,
+}
+
+impl SecurityIncrease {
+    /// Returns the proof of a lever.
+    pub fn option(&self, lever: SecurityLever) -> &SecurityOption {
+        self.options
+            .iter()
+            .find(|option| option.lever == lever)
+            .expect("every lever is priced")
+    }
+
+    /// Returns whether adding queries yields no larger a proof than doubling the blowup factor,
+    /// i.e. whether the queries are the cheaper of the two levers that cost no grinding. Each
+    /// query of the doubled blowup brings another bit, so the queries are rarely cheaper, and
+    /// the doubled blowup's price is the prover's hashing instead.
+    pub fn queries_cheaper(&self) -> bool {
+        let size = |lever| self.option(lever).report.total_elements();
+        size(SecurityLever::Queries) <= size(SecurityLever::Blowup)
+    }
+
+    /// Lays the options out as a table with one row for the current proof and one per lever,
+    /// each with its size and prover work relative to the current proof.
+    pub fn to_table(&self) -> ReportTable {
+        let mut table = ReportTable::new([
+            "option",
+            "blowup",
+            "queries",
+            "grinding bits",
+            "schedule",
+            "size (elements)",
+            "size delta",
+            "hash input delta",
+            "grinding time",
+        ]);
+        let delta = |new: u64, old: u64| format!("{:+}", new as i128 - old as i128);
+        let current = &self.current;
+        let rows = std::iter::once(("current".to_string(), current)).chain(
+            self.options
+                .iter()
+                .map(|option| (option.lever.to_string(), option)),
+        );
+        for (name, option) in rows {
+            let report = &option.report;
+            table.push_row([
+                name,
+                report.blowup_factor.to_string(),
+                report.num_queries.to_string(),
+                option.grinding_bits.to_string(),
+                format!("{:?}", report.schedule),
+                report.total_elements().to_string(),
+                delta(
+                    report.total_elements() as u64,
+                    current.report.total_elements() as u64,
+                ),
+                delta(option.hash_input_elements, current.hash_input_elements),
+                format!("{:?}", option.grinding_time),
+            ]);
+        }
+        table
+    }
+}
+
+/// Prices the three levers that raise the security of a proof by `extra_bits`, side by side, so
+/// that folding harder, which only shrinks the proof, is not mistaken for one of them:
+///
+/// * more queries, as many as the extra bits need, see [`required_queries`], with the folding
+///   schedule re-optimized for them;
+/// * as many grinding bits as the extra bits, which leave the proof as it is and take the prover
+///   an expected `2^bits / hash_rate` seconds;
+/// * a doubled blowup factor over the same polynomial, so over a domain twice as large, with the
+///   queries it needs for the current security and the extra bits, usually fewer than before, and
+///   the folding schedule re-optimized for them.
+///
+/// The current proof takes the optimal folding schedule for its queries, whose conjectured
+/// security of `log2(blowup_factor)` bits per query every lever starts from. The prover's work is
+/// given as the field elements it absorbs into hashes committing to the layers, see
+/// [`total_hash_input_elements`], apart from the time it spends grinding.
+///
+/// At a blowup factor of 8, 27 queries give 81 bits. Twenty more bits take 7 more queries, a
+/// second of grinding at 2^20 hashes per second, or 26 queries at a blowup factor of 16, which
+/// barely grow the proof but double the prover's hashing:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::{security_increase_options, SecurityLever};
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let hash_rate = (1 << 20) as f64;
+/// let cost_model = CostModel::default();
+/// let increase = security_increase_options(1 << 25, 8, 27, 20, hash_rate, &cost_model).unwrap();
+/// assert_eq!(increase.to_table().to_plain(), "\
+/// option         blowup  queries  grinding bits  schedule         size (elements)  size delta  hash input delta  grinding time
+/// current        8       27       0              [0, 4, 4, 4, 3]  12622            +0          +0                0ns
+/// more queries   8       34       0              [0, 4, 4, 4]     15716            +3094       -24568            0ns
+/// grinding       8       27       20             [0, 4, 4, 4, 3]  12622            +0          +0                1s
+/// double blowup  16      26       0              [0, 4, 4, 4, 3]  12684            +62         +425025536        0ns
+/// ");
+/// assert!(!increase.queries_cheaper());
+///
+/// assert_eq!(increase.option(SecurityLever::Queries).report.num_queries, 34);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `extra_bits` - The bits of security to add
+/// * `hash_rate` - The number of hashes per second the prover grinds with
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `increase` - The current proof and the proof of every lever
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If the hash input of a proof does not fit in a `u64`, or the doubled domain in a `usize`
+///
+/// # Panics
+/// * If the blowup factor is 1
+/// * If the hash rate is not positive
+pub fn security_increase_options(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    extra_bits: u32,
+    hash_rate: f64,
+    cost_model: &CostModel,
+) -> Result<SecurityIncrease, FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    assert!(
+        blowup_factor > 1,
+        "the blowup factor must be greater than 1, got {blowup_factor}"
+    );
+    assert!(
+        hash_rate > 0.0,
+        "the hash rate must be positive, got {hash_rate}"
+    );
+
+    let option = |lever, degree, blowup_factor, num_queries| {
+        let (_, schedule) = optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            vec![0],
+            &HashMap::new(),
+            cost_model,
+            &mut SearchStats::default(),
+        )?;
+        Ok::<_, FoldingError>(SecurityOption {
+            lever,
+            grinding_bits: 0,
+            grinding_time: Duration::ZERO,
+            hash_input_elements: total_hash_input_elements(degree, &schedule, cost_model)?,
+            report: FoldingReport::new(degree, blowup_factor, num_queries, schedule, cost_model),
+        })
+    };
+
+    let current = option(SecurityLever::Queries, degree, blowup_factor, num_queries)?;
+    let more_queries = num_queries + required_queries(blowup_factor, extra_bits, true);
+    let current_bits = num_queries as u32 * blowup_factor.ilog2();
+    let doubled_queries = required_queries(2 * blowup_factor, current_bits + extra_bits, true);
+    let grinding_secs = match extra_bits {
+        0 => 0.0,
+        bits => 2f64.powi(bits as i32) / hash_rate,
+    };
+    let options = vec![
+        option(SecurityLever::Queries, degree, blowup_factor, more_queries)?,
+        SecurityOption {
+            lever: SecurityLever::Grinding,
+            grinding_bits: extra_bits,
+            grinding_time: Duration::from_secs_f64(grinding_secs),
+            ..current.clone()
+        },
+        option(
+            SecurityLever::Blowup,
+            degree
+                .checked_mul(2)
+                .ok_or(FoldingError::ArithmeticOverflow {
+                    quantity: "doubled domain",
+                })?,
+            2 * blowup_factor,
+            doubled_queries,
+        )?,
+    ];
+    Ok(SecurityIncrease {
+        extra_bits,
+        current,
+        options,
+    })
+}
+
+/// A folding schedule chosen to do well under several cost models at once, together with how it
+/// fares under each of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RobustSchedule {
+    /// The folding schedule with the smallest worst-case regret.
+    pub schedule: Vec<usize>,
+    /// The size of the schedule under each cost model, in field elements of that model.
+    pub sizes: Vec<usize>,
+    /// The size of each cost model's own optimal schedule.
+    pub optima: Vec<usize>,
+    /// The largest relative regret over all cost models, i.e. the largest `size / optimum - 1`.
+    pub regret: f64,
+}
+
+/// Finds the folding schedule whose worst-case regret across `cost_models` is smallest, for a
+/// deployment whose cost model is not known yet. Sizes under different cost models are counted in
+/// different elements, so the regret of a schedule under a model is relative to that model's own
+/// optimum.
+///
+/// The candidates are every model's optimal schedule and their single-step perturbations: one
+/// layer folding by one bit more or less, one bit moved from one layer to another, and a 1-bit
+/// layer appended. Ties are broken in favor of the lexicographically smallest schedule.
+///
+/// With Poseidon over a quartic extension, larger openings favor smaller folding factors than
+/// Goldilocks does, and the schedule both tolerate is neither one's optimum:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::min_regret_schedule;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let poseidon_quartic = CostModel { extension_degree: 4, ..POSEIDON_256 };
+/// let robust =
+///     min_regret_schedule(1 << 20, 8, 27, &[poseidon_quartic, GOLDILOCKS_QUADRATIC]).unwrap();
+/// assert_eq!(robust.schedule, vec![0, 3, 3, 3, 2]);
+/// for cost_model in [poseidon_quartic, GOLDILOCKS_QUADRATIC] {
+///     let (_, optimum) = fri_dynamic_folding_scheme::optimized_schedule::optimal_folding_strategy(
+///         1 << 20,
+///         8,
+///         27,
+///         vec![0],
+///         &Default::default(),
+///         &cost_model,
+///         &mut SearchStats::default(),
+///     )
+///     .unwrap();
+///     assert_ne!(robust.schedule, optimum);
+/// }
+/// assert!(robust.regret < 0.2);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `cost_models` - The cost models the deployment may use
+///
+/// # Returns
+/// * `robust_schedule` - The schedule with the smallest worst-case regret and its sizes
+///
+/// # Errors
+/// * If the degree or the blowup factor is not a power of 2
+///
+/// # Panics
+/// * If `cost_models` is empty
+pub fn min_regret_schedule(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_models: &[CostModel],
+) -> Result<RobustSchedule, FoldingError> {
+    assert!(!cost_models.is_empty(), "at least one cost model is needed");
+    let optima = cost_models
+        .iter()
+        .map(|cost_model| {
+            optimal_folding_strategy(
+                degree,
+                blowup_factor,
+                num_queries,
+                vec![0],
+                &HashMap::new(),
+                cost_model,
+                &mut SearchStats::default(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut candidates = BTreeSet::new();
+    for (_, schedule) in &optima {
+        candidates.extend(perturbations(schedule));
+        candidates.insert(schedule.clone());
+    }
+
+    let mut best: Option<RobustSchedule> = None;
+    for schedule in candidates {
+        if validate_schedule(degree, blowup_factor, &schedule).is_err()
+            || schedule.iter().any(|&bits| bits > MAX_FOLDING_BITS)
+        {
+            continue;
+        }
+        let sizes: Vec<_> = cost_models
+            .iter()
+            .map(|cost_model| {
+                estimate_proof_size_unchecked(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    &schedule,
+                    cost_model,
+                )
+            })
+            .collect();
+        let regret = sizes
+            .iter()
+            .zip(&optima)
+            .map(|(&size, &(optimum, _))| size as f64 / optimum as f64 - 1.0)
+            .fold(0.0, f64::max);
+        if best.as_ref().is_none_or(|best| regret < best.regret) {
+            best = Some(RobustSchedule {
+                schedule,
+                sizes,
+                optima: optima.iter().map(|&(optimum, _)| optimum).collect(),
+                regret,
+            });
+        }
+    }
+    Ok(best.expect("every optimal schedule is a valid candidate"))
+}
+
+/// Returns the schedules one step away from `schedule`, which may over-fold or use more than
+/// `MAX_FOLDING_BITS` bits in a layer; the caller filters them.
+fn perturbations(schedule: &[usize]) -> Vec<Vec<usize>> {
+    let mut neighbors = Vec::new();
+    for layer in 1..schedule.len() {
+        let mut more = schedule.to_vec();
+        more[layer] += 1;
+        neighbors.push(more);
+
+        // folding by one bit less removes a 1-bit layer altogether.
+        let mut less = schedule.to_vec();
+        less[layer] -= 1;
+        if less[layer] == 0 {
+            less.remove(layer);
+        }
+        neighbors.push(less);
+
+        for to in (1..schedule.len()).filter(|&to| to != layer && schedule[layer] > 1) {
+            let mut moved = schedule.to_vec();
+            moved[layer] -= 1;
+            moved[to] += 1;
+            neighbors.push(moved);
+        }
+    }
+    let mut appended = schedule.to_vec();
+    appended.push(1);
+    neighbors.push(appended);
+    neighbors
+}
+
+/// Finds the order in which a fixed multiset of folding factors yields the smallest proof, which
+/// isolates the ordering of the layers from the choice of their factors. Every order folds down to
+/// the same remainder, but the paths of a layer get shorter the later it is committed. Orders of
+/// equal size are broken in favor of the lexicographically smallest one.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::best_reordering;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// assert_eq!(best_reordering(1 << 25, 8, 27, &[3, 3, 4, 4], &cost_model), vec![0, 4, 4, 3, 3]);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_bits` - The folding factors, in bits, of the layers after the unfolded first one
+/// * `cost_model` - The cost model used to price each order
+///
+/// # Returns
+/// * `schedule` - The best folding sequence, starting with the unfolded first layer
+pub fn best_reordering(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_bits: &[usize],
+    cost_model: &CostModel,
+) -> Vec<usize> {
+    // permutations are visited in lexicographic order, each distinct one once.
+    let mut order = folding_bits.to_vec();
+    order.sort_unstable();
+    let mut best: Option<(usize, Vec<usize>)> = None;
+    loop {
+        let schedule: Vec<_> = std::iter::once(0).chain(order.iter().copied()).collect();
+        let size = estimate_proof_size_unchecked(
+            degree,
+            blowup_factor,
+            num_queries,
+            &schedule,
+            cost_model,
+        );
+        if best.as_ref().is_none_or(|(best_size, _)| size < *best_size) {
+            best = Some((size, schedule));
+        }
+        if !next_permutation(&mut order) {
+            break;
+        }
+    }
+    best.expect("the sorted order is always visited").1
+}
+
+/// Checks whether no reordering of the layers after the first makes the proof of `schedule`
+/// smaller, see [`best_reordering`].
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `schedule` - The folding sequence, starting with the unfolded first layer
+/// * `cost_model` - The cost model used to price each order
+///
+/// # Errors
+/// * If the folding sequence, the degree or the blowup factor is rejected by
+///   [`validate_schedule`], as an empty sequence is
+pub fn is_order_optimal(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    schedule: &[usize],
+    cost_model: &CostModel,
+) -> Result<bool, FoldingError> {
+    validate_schedule(degree, blowup_factor, schedule)?;
+    let best = best_reordering(
+        degree,
+        blowup_factor,
+        num_queries,
+        &schedule[1..],
+        cost_model,
+    );
+    Ok(
+        estimate_proof_size_unchecked(degree, blowup_factor, num_queries, schedule, cost_model)
+            <= estimate_proof_size_unchecked(degree, blowup_factor, num_queries, &best, cost_model),
+    )
+}
+
+/// Rearranges `items` into the next permutation in lexicographic order, and returns `false`,
+/// leaving them sorted again, once the last one has been reached.
+fn next_permutation(items: &mut [usize]) -> bool {
+    let Some(pivot) = items.windows(2).rposition(|pair| pair[0] < pair[1]) else {
+        items.reverse();
+        return false;
+    };
+    let successor = items
+        .iter()
+        .rposition(|&item| item > items[pivot])
+        .expect("the item after the pivot is larger");
+    items.swap(pivot, successor);
+    items[pivot + 1..].reverse();
+    true
+}
+
+/// The size of a proof after one more refinement of the cost model has been applied, see
+/// [`refinement_waterfall`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefinementStep {
+    /// The name of the refinement.
+    pub refinement: &'static str,
+    /// The size of the proof with this and every earlier refinement applied, in field elements.
+    pub elements: usize,
+    /// The number of field elements this refinement saves over the previous step.
+    pub saved_elements: usize,
+}
+
+/// The size of a proof under the naive cost model and after each refinement of it, see
+/// [`refinement_waterfall`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefinementWaterfall {
+    /// The size of the proof under the naive cost model, in field elements.
+    pub naive_elements: usize,
+    /// The refinements enabled in the cost model, in the order they were applied.
+    pub steps: Vec<RefinementStep>,
+    /// The fully refined cost model, which prices the elements of every step.
+    pub cost_model: CostModel,
+}
+
+impl RefinementWaterfall {
+    /// Returns the size of the proof with every enabled refinement applied, in field elements.
+    pub fn refined_elements(&self) -> usize {
+        self.steps
+            .last()
+            .map_or(self.naive_elements, |step| step.elements)
+    }
+
+    /// Lays the waterfall out as a table with one row for the naive size, one per refinement
+    /// and one for the refined size, in KiB.
+    pub fn to_table(&self) -> ReportTable {
+        let kib = |elements| {
+            format!(
+                "{:.2}",
+                size_in_bytes(elements, &self.cost_model) as f64 / 1024.0
+            )
+        };
+        let mut table = ReportTable::new(["refinement", "size (KiB)", "saved (KiB)"]);
+        table.push_row(["naive".to_string(), kib(self.naive_elements)]);
+        for step in &self.steps {
+            table.push_row([
+                step.refinement.to_string(),
+                kib(step.elements),
+                kib(step.saved_elements),
+            ]);
+        }
+        table.push_row([
+            "refined".to_string(),
+            kib(self.refined_elements()),
+            kib(self.naive_elements - self.refined_elements()),
+        ]);
+        table
+    }
+}
+
+/// Breaks down what each refinement of the cost model saves on the proof of a folding schedule.
+/// The schedule is first priced under the naive cost model, which charges a full path for every
+/// query and every opened value, and then the refinements enabled in `cost_model` are applied one
+/// at a time, always in this order:
+/// 1. `path-sharing`: authentication paths shared between queries, see
+///    [`PathModel::IdealCompression`].
+/// 2. `omit-folded-value`: the values the verifier folded from the previous layer are not sent,
+///    see [`QueryValueTransmission::OmitFoldedValue`].
+///
+/// Every other knob of `cost_model`, such as the size of its digests or the components it
+/// excludes, applies to every step alike. The coset layout sends the same paths either way, see
+/// [`CosetLayout::path_digests`](super::cost_model::CosetLayout::path_digests), so it is no
+/// refinement of the size.
+///
+/// The waterfall of the default parameters with every refinement enabled:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::refinement_waterfall;
+/// use fri_dynamic_folding_scheme::cost_model::{PathModel, QueryValueTransmission};
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel {
+///     coset_layout: CosetLayout::SplitLeaves,
+///     path_model: PathModel::IdealCompression,
+///     query_values: QueryValueTransmission::OmitFoldedValue,
+///     ..CostModel::default()
+/// };
+/// let waterfall = refinement_waterfall(1 << 25, 8, 27, &[0, 4, 4, 3, 3], &cost_model);
+/// assert_eq!(
+///     waterfall.refined_elements(),
+///     estimate_proof_size(1 << 25, 8, 27, &[0, 4, 4, 3, 3], &cost_model).unwrap()
+/// );
+/// assert_eq!(waterfall.to_table().to_plain(), "\
+/// refinement         size (KiB)  saved (KiB)
+/// naive              98.92
+/// path-sharing       26.95       71.97
+/// omit-folded-value  25.27       1.69
+/// refined            25.27       73.66
+/// ");
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model whose refinements are broken down
+///
+/// # Returns
+/// * `waterfall` - The naive size and the size after each enabled refinement
+pub fn refinement_waterfall(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> RefinementWaterfall {
+    let naive = CostModel {
+        path_model: PathModel::Naive,
+        query_values: QueryValueTransmission::AllValues,
+        ..*cost_model
+    };
+    let size = |model: &CostModel| {
+        estimate_proof_size_unchecked(degree, blowup_factor, num_queries, folding_seq, model)
+    };
+    let naive_elements = size(&naive);
+
+    // each model applies one more refinement to the previous one.
+    let path_sharing = CostModel {
+        path_model: cost_model.path_model,
+        ..naive
+    };
+    let omit_folded_value = CostModel {
+        query_values: cost_model.query_values,
+        ..path_sharing
+    };
+
+    let mut steps = Vec::new();
+    let (mut previous, mut elements) = (naive, naive_elements);
+    for (refinement, model) in [
+        ("path-sharing", path_sharing),
+        ("omit-folded-value", omit_folded_value),
+    ] {
+        // a refinement the cost model does not enable leaves the model unchanged.
+        if model == previous {
+            continue;
+        }
+        let refined = size(&model);
+        steps.push(RefinementStep {
+            refinement,
+            elements: refined,
+            saved_elements: elements - refined,
+        });
+        (previous, elements) = (model, refined);
+    }
+    RefinementWaterfall {
+        naive_elements,
+        steps,
+        cost_model: *cost_model,
+    }
+}
+
+/// How a deployed folding schedule fares once the degree has grown by some factor, compared with
+/// the optimal schedule for the grown degree, see [`degree_growth_sensitivity`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DegreeGrowth {
+    /// The factor the degree has grown by.
+    pub growth: usize,
+    /// The deployed schedule, extended with the best layers for the grown degree.
+    pub extended_schedule: Vec<usize>,
+    /// The size of the extended schedule, in field elements.
+    pub extended_elements: usize,
+    /// The optimal schedule for the grown degree.
+    pub optimal_schedule: Vec<usize>,
+    /// The size of the optimal schedule, in field elements.
+    pub optimal_elements: usize,
+}
+
+impl DegreeGrowth {
+    /// Returns how much larger the extended schedule is than the optimal one, as a fraction of
+    /// the optimal size. It may be negative when the cost model charges the verifier's check of
+    /// the remainder, which the optimizer weighs alongside the size.
+    pub fn overhead(&self) -> f64 {
+        self.extended_elements as f64 / self.optimal_elements as f64 - 1.0
+    }
+}
+
+/// The sizes of a deployed folding schedule over growing degrees, see
+/// [`degree_growth_sensitivity`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DegreeSensitivity {
+    /// The degree the schedule was deployed for.
+    pub degree: usize,
+    /// One entry per growth factor, in the order given.
+    pub growths: Vec<DegreeGrowth>,
+}
+
+impl DegreeSensitivity {
+    /// Lays the sensitivity out as a table with one row per growth factor.
+    pub fn to_table(&self) -> ReportTable {
+        let mut table = ReportTable::new([
+            "degree",
+            "extended schedule",
+            "size (elements)",
+            "optimal schedule",
+            "optimal (elements)",
+            "overhead",
+        ]);
+        for growth in &self.growths {
+            table.push_row([
+                format!("2^{}", self.degree.ilog2() + growth.growth.ilog2()),
+                format!("{:?}", growth.extended_schedule),
+                growth.extended_elements.to_string(),
+                format!("{:?}", growth.optimal_schedule),
+                growth.optimal_elements.to_string(),
+                format!("{:.2}%", growth.overhead() * 100.0),
+            ]);
+        }
+        table
+    }
+}
+
+/// Sizes a deployed folding schedule as the degree grows, and compares it with the optimal
+/// schedule for each grown degree. A proof over a larger degree keeps the deployed layers, which
+/// fold the same bits, and appends the layers the optimizer finds best for the extra bits, as it
+/// does for a fixed prefix in [`optimal_folding_strategy`]. The optimizer may append nothing
+/// and leave a larger remainder instead, and at a growth of 1 it appends nothing to an optimal
+/// schedule.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::degree_growth_sensitivity;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let sensitivity =
+///     degree_growth_sensitivity(1 << 12, 8, 27, &[0, 3, 3], &[1, 2], &cost_model).unwrap();
+/// for growth in &sensitivity.growths {
+///     assert!(growth.extended_schedule.starts_with(&[0, 3, 3]));
+///     println!("x{}: {:.2}% over the optimum", growth.growth, growth.overhead() * 100.0);
+/// }
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree the schedule was deployed for
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The deployed folding sequence
+/// * `growths` - The factors the degree may grow by, each a power of 2
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `sensitivity` - The extended and the optimal schedule for every growth factor
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the folding sequence is rejected by [`validate_schedule`]
+/// * If a growth factor is not a power of 2
+/// * If a grown degree does not fit in a `usize`
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+pub fn degree_growth_sensitivity(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    growths: &[usize],
+    cost_model: &CostModel,
+) -> Result<DegreeSensitivity, FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    validate_schedule(degree, blowup_factor, folding_seq)?;
+    let growths = growths
+        .iter()
+        .map(|&growth| {
+            if !growth.is_power_of_two() {
+                return Err(FoldingError::NotPowerOfTwo {
+                    parameter: "degree growth",
+                    value: growth,
+                });
+            }
+            let grown_degree =
+                degree
+                    .checked_mul(growth)
+                    .ok_or(FoldingError::ArithmeticOverflow {
+                        quantity: "grown degree",
+                    })?;
+            let search = |prefix: Vec<usize>| {
+                optimal_folding_strategy(
+                    grown_degree,
+                    blowup_factor,
+                    num_queries,
+                    prefix,
+                    &HashMap::new(),
+                    cost_model,
+                    &mut SearchStats::default(),
+                )
+            };
+            let (extended_elements, extended_schedule) = search(folding_seq.to_vec())?;
+            let (optimal_elements, optimal_schedule) = search(vec![0])?;
+            Ok(DegreeGrowth {
+                growth,
+                extended_schedule,
+                extended_elements,
+                optimal_schedule,
+                optimal_elements,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(DegreeSensitivity { degree, growths })
+}
+
+/// Where two adjacent uniform folding factors swap places as the smaller proof, see
+/// [`uniform_crossovers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformCrossover {
+    /// The smaller of the two folding factors, in bits; the larger folds by one bit more.
+    pub folding_bits: usize,
+    /// The folding factor, in bits, yielding the smaller proof at the smallest degree where the
+    /// two differ, or `None` if they never differ over the range.
+    pub smaller_first: Option<usize>,
+    /// The degrees from which the other factor yields the smaller proof, in increasing order.
+    pub degrees: Vec<usize>,
+}
+
+/// The crossovers of every pair of adjacent uniform folding factors over a range of degrees, see
+/// [`uniform_crossovers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformCrossovers {
+    /// The smallest degree compared.
+    pub min_degree: usize,
+    /// The largest degree compared.
+    pub max_degree: usize,
+    /// One entry per pair of adjacent factors, from the smallest factors up.
+    pub pairs: Vec<UniformCrossover>,
+}
+
+impl UniformCrossovers {
+    /// Lays the crossovers out as a table with one row per pair of adjacent factors.
+    pub fn to_table(&self) -> ReportTable {
+        let mut table = ReportTable::new(["factors", "smaller first", "crossover degrees"]);
+        for pair in &self.pairs {
+            // the factors take turns from the one smaller first.
+            let degrees = pair
+                .degrees
+                .iter()
+                .zip(pair.smaller_first.iter().cycle())
+                .enumerate()
+                .map(|(index, (degree, &first))| {
+                    let other = 2 * pair.folding_bits + 1 - first;
+                    let winner = if index % 2 == 0 { other } else { first };
+                    format!("2^{} ({})", degree.ilog2(), 1 << winner)
+                })
+                .collect::<Vec<_>>();
+            table.push_row([
+                format!(
+                    "{} vs {}",
+                    1 << pair.folding_bits,
+                    1 << (pair.folding_bits + 1)
+                ),
+                pair.smaller_first
+                    .map_or("-".to_string(), |bits| (1 << bits).to_string()),
+                if degrees.is_empty() {
+                    "none".to_string()
+                } else {
+                    degrees.join(", ")
+                },
+            ]);
+        }
+        table
+    }
+}
+
+/// Finds the degrees at which adjacent uniform folding factors swap places as the smaller proof.
+/// Every power of 2 from a polynomial of degree 2 up to `max_degree` is sized with the uniform
+/// schedule of each factor, each with its own best remainder bound as in
+/// [`simple_schedule_with_best_remainder`], and a crossover is a degree where the sign of the
+/// difference between two factors flips. Degrees where both are equally large do not flip it.
+///
+/// The sizes of uniform schedules jump as either factor adds a round, so a pair may cross more
+/// than once, and the table names the factor smaller from each crossover on. Under the default
+/// cost model folding by 4 always beats folding by 2, and 8 beats 4, while 8 and 16 trade places
+/// up to domains of 2^18, from which 16 stays ahead:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::analysis::uniform_crossovers;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let crossovers = uniform_crossovers(1 << 30, 8, 27, &CostModel::default()).unwrap();
+/// assert_eq!(crossovers.to_table().to_plain(), "\
+/// factors  smaller first  crossover degrees
+/// 2 vs 4   4              none
+/// 4 vs 8   8              none
+/// 8 vs 16  8              2^15 (16), 2^17 (8), 2^18 (16)
+/// ");
+/// ```
+///
+/// # Arguments
+/// * `max_degree` - The largest degree to compare the factors at
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `cost_model` - The cost model used to price each uniform schedule
+///
+/// # Returns
+/// * `crossovers` - The crossovers of every pair of adjacent factors up to
+///   [`MAX_FOLDING_BITS`] bits
+///
+/// # Errors
+/// * If the largest degree or blowup factor is rejected by [`validate_parameters`], or leaves
+///   the polynomial a degree below 2
+/// * If the cost model's remainder strategy cannot interpolate the remainder of any uniform
+///   schedule of some factor and degree
+pub fn uniform_crossovers(
+    max_degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_model: &CostModel,
+) -> Result<UniformCrossovers, FoldingError> {
+    validate_parameters(max_degree, blowup_factor)?;
+    let min_degree = 2 * blowup_factor;
+    validate_schedule(max_degree, blowup_factor, &[0, 1])?;
+
+    let degrees: Vec<_> = (min_degree.ilog2()..=max_degree.ilog2())
+        .map(|bits| 1 << bits)
+        .collect();
+    let sizes = (1..=MAX_FOLDING_BITS)
+        .map(|folding_bits| {
+            degrees
+                .iter()
+                .map(|&degree| {
+                    simple_schedule_with_best_remainder(
+                        degree,
+                        blowup_factor,
+                        num_queries,
+                        folding_bits,
+                        cost_model,
+                    )
+                    .map(|(size, _)| size)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pairs = (1..MAX_FOLDING_BITS)
+        .map(|folding_bits| {
+            let (smaller, larger) = (&sizes[folding_bits - 1], &sizes[folding_bits]);
+            let mut smaller_first = None;
+            let mut current = None;
+            let mut crossover_degrees = Vec::new();
+            for (index, &degree) in degrees.iter().enumerate() {
+                let winner = match smaller[index].cmp(&larger[index]) {
+                    Ordering::Less => folding_bits,
+                    Ordering::Greater => folding_bits + 1,
+                    Ordering::Equal => continue,
+                };
+                match current {
+                    None => smaller_first = Some(winner),
+                    Some(previous) if previous != winner => crossover_degrees.push(degree),
+                    Some(_) => {}
+                }
+                current = Some(winner);
+            }
+            UniformCrossover {
+                folding_bits,
+                smaller_first,
+                degrees: crossover_degrees,
+            }
+        })
+        .collect();
+    Ok(UniformCrossovers {
+        min_degree,
+        max_degree,
+        pairs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimized_schedule::estimate_proof_size;
+
+    /// Returns every order of `items`, repeated ones included.
+    fn every_order(items: &[usize]) -> Vec<Vec<usize>> {
+        if items.is_empty() {
+            return vec![Vec::new()];
+        }
+        (0..items.len())
+            .flat_map(|first| {
+                let mut rest = items.to_vec();
+                let first = rest.remove(first);
+                every_order(&rest).into_iter().map(move |mut order| {
+                    order.insert(0, first);
+                    order
+                })
+            })
+            .collect()
+    }
+
+    const HASH_RATE: f64 = (1 << 20) as f64;
+
+    #[test]
+    fn grinding_levels_stop_at_the_ceiling() {
+        let cost_model = CostModel::default();
+        let tradeoff = optimal_with_grinding(
+            1 << 20,
+            8,
+            100,
+            HASH_RATE,
+            Duration::from_secs(1),
+            &cost_model,
+        )
+        .unwrap();
+        // 2^20 hashes take exactly the second, 2^21 do not fit.
+        assert_eq!(tradeoff.levels.len(), 21);
+        assert_eq!(tradeoff.levels[20].grinding_time, Duration::from_secs(1));
+        let best = tradeoff.best();
+        assert_eq!((best.grinding_bits, best.report.num_queries), (19, 27));
+
+        // without grinding the proof needs every query, and more grinding never grows it.
+        assert_eq!(tradeoff.levels[0].report.num_queries, 34);
+        assert_eq!(tradeoff.levels[0].grinding_time, Duration::ZERO);
+        for pair in tradeoff.levels.windows(2) {
+            assert!(pair[1].report.total_elements() <= pair[0].report.total_elements());
+        }
+        // the least grinding breaks ties.
+        for level in &tradeoff.levels[..tradeoff.best] {
+            assert!(level.report.total_elements() > best.report.total_elements());
+        }
+    }
+
+    #[test]
+    fn every_level_is_optimal_for_its_queries() {
+        let cost_model = CostModel::default();
+        let tradeoff = optimal_with_grinding(
+            1 << 16,
+            4,
+            64,
+            HASH_RATE,
+            Duration::from_secs(60),
+            &cost_model,
+        )
+        .unwrap();
+        for level in &tradeoff.levels {
+            let report = &level.report;
+            assert_eq!(
+                report.num_queries,
+                required_queries(4, 64 - level.grinding_bits, true)
+            );
+            let (optimum, _) = optimal_folding_strategy(
+                1 << 16,
+                4,
+                report.num_queries,
+                vec![0],
+                &HashMap::new(),
+                &cost_model,
+                &mut SearchStats::default(),
+            )
+            .unwrap();
+            assert_eq!(report.total_elements(), optimum);
+        }
+    }
+
+    #[test]
+    fn grinding_beyond_the_ceiling_leaves_no_grinding() {
+        let cost_model = CostModel::default();
+        let tradeoff =
+            optimal_with_grinding(1 << 20, 8, 100, HASH_RATE, Duration::ZERO, &cost_model).unwrap();
+        assert_eq!(tradeoff.levels.len(), 1);
+        assert_eq!(tradeoff.best().grinding_bits, 0);
+    }
+
+    #[test]
+    fn grinding_stops_short_of_the_security_level() {
+        let cost_model = CostModel::default();
+        let tradeoff =
+            optimal_with_grinding(1 << 12, 8, 10, HASH_RATE, Duration::MAX, &cost_model).unwrap();
+        assert_eq!(tradeoff.levels.len(), 10);
+        assert_eq!(tradeoff.levels[9].report.num_queries, 1);
+        assert_eq!(
+            optimal_with_grinding(1 << 20, 8, 0, HASH_RATE, Duration::MAX, &cost_model),
+            Err(FoldingError::NoQueriesNeeded)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the hash rate must be positive")]
+    fn hash_rate_must_be_positive() {
+        let _ = optimal_with_grinding(1 << 12, 8, 10, 0.0, Duration::MAX, &CostModel::default());
+    }
+
+    // the optimal schedule of the default parameters stays within 3% of the optimum as the
+    // degree grows eightfold, and only needs another layer once it has.
+    #[test]
+    fn default_deployment_overheads() {
+        let sensitivity = degree_growth_sensitivity(
+            1 << 25,
+            8,
+            27,
+            &[0, 4, 4, 4, 3],
+            &[1, 2, 4, 8],
+            &CostModel::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            sensitivity.to_table().to_plain(),
+            "\
+degree  extended schedule   size (elements)  optimal schedule  optimal (elements)  overhead
+2^25    [0, 4, 4, 4, 3]     12622            [0, 4, 4, 4, 3]   12622               0.00%
+2^26    [0, 4, 4, 4, 3]     13418            [0, 4, 4, 4, 3]   13418               0.00%
+2^27    [0, 4, 4, 4, 3]     14470            [0, 4, 4, 4, 4]   14282               1.32%
+2^28    [0, 4, 4, 4, 3, 3]  15754            [0, 4, 4, 4, 4]   15334               2.74%
+"
+        );
+    }
+
+    #[test]
+    fn extended_schedules_keep_the_deployed_prefix() {
+        let cost_model = CostModel::default();
+        let deployed = [0, 3, 2];
+        let sensitivity =
+            degree_growth_sensitivity(1 << 10, 4, 16, &deployed, &[1, 2, 4, 8, 16], &cost_model)
+                .unwrap();
+        assert_eq!(sensitivity.growths.len(), 5);
+        for growth in &sensitivity.growths {
+            assert_eq!(growth.extended_schedule[..deployed.len()], deployed);
+            assert!(growth.extended_elements >= growth.optimal_elements);
+            assert!(growth.overhead() >= 0.0);
+            let grown = (1 << 10) * growth.growth;
+            let estimate =
+                |seq: &[usize]| estimate_proof_size(grown, 4, 16, seq, &cost_model).unwrap();
+            assert_eq!(
+                estimate(&growth.extended_schedule),
+                growth.extended_elements
+            );
+            assert_eq!(estimate(&growth.optimal_schedule), growth.optimal_elements);
+        }
+    }
+
+    #[test]
+    fn growth_must_be_a_power_of_two() {
+        let cost_model = CostModel::default();
+        assert_eq!(
+            degree_growth_sensitivity(1 << 10, 4, 16, &[0, 3], &[1, 3], &cost_model),
+            Err(FoldingError::NotPowerOfTwo {
+                parameter: "degree growth",
+                value: 3
+            })
+        );
+        // the largest power of two overflows any degree above one, whatever the pointer width.
+        let growth = 1 << (usize::BITS - 1);
+        assert_eq!(
+            degree_growth_sensitivity(1 << 10, 4, 16, &[0, 3], &[growth], &cost_model),
+            Err(FoldingError::ArithmeticOverflow {
+                quantity: "grown degree"
+            })
+        );
+    }
+
+    #[test]
+    fn next_permutation_visits_every_distinct_order_once() {
+        for (items, count) in [(vec![1, 2, 3], 6), (vec![1, 2, 2], 3), (vec![2, 2], 1)] {
+            let mut order = items.clone();
+            let mut visited = vec![order.clone()];
+            while next_permutation(&mut order) {
+                visited.push(order.clone());
+            }
+            assert_eq!(visited.len(), count);
+            assert!(visited.windows(2).all(|pair| pair[0] < pair[1]));
+            // the last call leaves the items sorted again.
+            assert_eq!(order, items);
+        }
+    }
+
+    #[test]
+    fn best_reordering_is_the_smallest_of_every_order() {
+        let cost_model = CostModel::default();
+        for (degree, folding_bits) in [(1 << 20, vec![1, 2, 3, 3]), (1 << 16, vec![4, 1, 2])] {
+            let size = |schedule: &[usize]| {
+                estimate_proof_size_unchecked(degree, 8, 27, schedule, &cost_model)
+            };
+            let best = every_order(&folding_bits)
+                .into_iter()
+                .map(|order| [vec![0], order].concat())
+                .min_by_key(|schedule| (size(schedule), schedule.clone()))
+                .unwrap();
+            let reordered = best_reordering(degree, 8, 27, &folding_bits, &cost_model);
+            assert_eq!(reordered, best);
+            assert_eq!(
+                is_order_optimal(degree, 8, 27, &best, &cost_model),
+                Ok(true)
+            );
+        }
+    }
+
+    // the paths of a layer get shorter the later it is committed, so larger factors go first.
+    #[test]
+    fn larger_factors_fold_first() {
+        let cost_model = CostModel::default();
+        let optimal = |seq: &[usize]| is_order_optimal(1 << 25, 8, 27, seq, &cost_model);
+        assert_eq!(optimal(&[0, 4, 4, 3, 3]), Ok(true));
+        assert_eq!(optimal(&[0, 3, 3, 4, 4]), Ok(false));
+        assert_eq!(optimal(&[0, 4, 3, 4, 3]), Ok(false));
+    }
+
+    #[test]
+    fn order_of_an_invalid_schedule_is_an_error() {
+        let cost_model = CostModel::default();
+        let optimal = |seq: &[usize]| is_order_optimal(1 << 10, 4, 3, seq, &cost_model);
+        assert_eq!(optimal(&[]), Err(FoldingError::MissingInitialLayer));
+        assert_eq!(optimal(&[2, 2]), Err(FoldingError::MissingInitialLayer));
+        assert_eq!(optimal(&[0, 2, 2]), Ok(true));
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn release_order_of_an_empty_schedule_is_an_error() {
+        let cost_model = CostModel::default();
+        let optimal = is_order_optimal(1 << 25, 8, 27, &[], &cost_model);
+        assert_eq!(optimal, Err(FoldingError::MissingInitialLayer));
+    }
+
+    #[test]
+    fn blowup_comparisons_reject_invalid_parameters() {
+        let cost_model = CostModel::default();
+        assert_eq!(
+            size_vs_blowup_at_security(1000, &[8], 100, true, &cost_model),
+            Err(FoldingError::NotPowerOfTwo {
+                parameter: "degree",
+                value: 1000
+            })
+        );
+        assert!(size_vs_blowup_at_security(1 << 10, &[3], 100, true, &cost_model).is_err());
+        assert_eq!(
+            min_blowup_for_size(1 << (usize::BITS - 2), &[8], 100, true, 0, &cost_model),
+            Err(FoldingError::ArithmeticOverflow {
+                quantity: "evaluation domain"
+            })
+        );
+        assert!(min_regret_schedule(1000, 8, 27, &[cost_model]).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Proof size estimates for batched FRI over columns of mixed degrees.
+//!
+//! The columns of a trace are committed in one Merkle tree per degree group. A single FRI
+//! instance proves all groups at once: each group is combined into the running codeword at the
+//! first layer whose domain is no larger than the group's own, and every query opens the group's
+//! tree at the positions that layer needs.
+
+use super::cost_model::CostModel;
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::path_elements;
+
+/// A set of columns sharing one evaluation domain and committed in their own Merkle tree, with
+/// one row of `num_columns` base field elements per leaf.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DegreeGroup {
+    /// The size of the evaluation domain of the group's columns; must be a power of 2.
+    pub domain_size: usize,
+    /// The number of columns in the group.
+    pub num_columns: usize,
+}
+
+/// Returns the index of the layer a degree group is combined into, i.e. the first layer of
+/// `folding_seq` whose domain is no larger than `domain_size`, together with the number of rows
+/// of the group's tree each query opens. A layer index equal to `folding_seq.len()` stands for
+/// the remainder.
+///
+/// A layer over a domain of size `n` folded by `factor` needs the `factor` values of a coset. When
+/// the group's domain matches `n`, those are `factor` rows of the group. When the folding
+/// sequence skips past the group's domain, the group first has to be folded down to `n` by the
+/// verifier, which takes `domain_size / n` times as many rows. Either way a query opens
+/// `domain_size / (n / factor)` rows, so folding past a group makes its openings more expensive.
+///
+/// # Panics
+/// * If the remainder domain is larger than `domain_size`, as the group can then never be
+///   combined into the codeword
+pub fn injection_layer(degree: usize, folding_seq: &[usize], domain_size: usize) -> (usize, usize) {
+    // the domains of the layers do not depend on the cost model.
+    let mut injection = None;
+    let remainder = walk_schedule(
+        degree,
+        1,
+        folding_seq,
+        &CostModel::default(),
+        &mut |layer: &LayerFacts| {
+            if injection.is_none() && layer.domain_size <= domain_size {
+                injection = Some((layer.layer, domain_size / layer.folded_domain_size()));
+            }
+        },
+    );
+    injection.unwrap_or_else(|| {
+        assert!(
+            remainder.domain_size <= domain_size,
+            "a degree group over a domain of size {domain_size} is smaller than the remainder \
+             domain of size {}",
+            remainder.domain_size
+        );
+        (folding_seq.len(), domain_size / remainder.domain_size)
+    })
+}
+
+/// Estimates the number of elements the degree groups add to a FRI proof for a given folding
+/// strategy: for every query, the opened rows of each group and the Merkle paths authenticating
+/// them. The rows of a query are adjacent in the group's tree and share a single path, laid out
+/// like the coset of a FRI layer.
+///
+/// For example, with a degree of 2^10, a blowup factor of 4, 3 queries and the folding sequence
+/// `[0, 2, 2]`, a group of 8 columns over 2^10 and a group of 4 columns over 2^8 are combined two
+/// layers apart and add 264 elements:
+/// * the first group enters at layer 0 (domain 2^10, factor 1) and opens 1 row per query:
+///   3 * 10 * 4 = 120 path and 3 * 1 * 8 = 24 leaf elements
+/// * the second group enters at layer 2 (domain 2^8, factor 4) and opens 4 rows per query:
+///   3 * 6 * 4 = 72 path and 3 * 4 * 4 = 48 leaf elements, its tree holding 2^6 leaves of 4 rows
+///
+/// ```
+/// use fri_dynamic_folding_scheme::batch::{estimate_injection_elements, DegreeGroup};
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let groups = [
+///     DegreeGroup { domain_size: 1 << 10, num_columns: 8 },
+///     DegreeGroup { domain_size: 1 << 8, num_columns: 4 },
+/// ];
+/// let cost_model = CostModel::default();
+/// let seq = [0, 2, 2];
+/// let injections = estimate_injection_elements(1 << 10, 3, &seq, &groups, &cost_model);
+/// assert_eq!(injections, 264);
+/// assert_eq!(estimate_proof_size(1 << 10, 4, 3, &seq, &cost_model).unwrap() + injections, 638);
+///
+/// // folding from 2^9 straight to 2^7 skips the second group's domain, so it enters at layer 3
+/// // (domain 2^7, factor 4) and opens 8 rows per query, from a tree of 2^5 leaves.
+/// let skipping = estimate_injection_elements(1 << 10, 3, &[0, 1, 2, 2], &groups, &cost_model);
+/// assert_eq!(skipping, 144 + 3 * 5 * 4 + 3 * 8 * 4);
+///
+/// // the optimizer prices the same openings.
+/// let (size, _) = fri_dynamic_folding_scheme::optimized_schedule::optimal_batched_folding_strategy(
+///     1 << 10,
+///     4,
+///     3,
+///     &groups,
+///     &Default::default(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// )
+/// .unwrap();
+/// assert!(size <= 638);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved, i.e. the size of the largest domain
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `degree_groups` - The degree groups combined into the codeword
+/// * `cost_model` - The cost model used to price the openings
+///
+/// # Returns
+/// * `num_elements` - The estimated number of elements added by the degree groups
+///
+/// # Panics
+/// * If a group's domain is smaller than the remainder domain
+pub fn estimate_injection_elements(
+    degree: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    degree_groups: &[DegreeGroup],
+    cost_model: &CostModel,
+) -> usize {
+    degree_groups
+        .iter()
+        .map(|group| {
+            let (_, rows) = injection_layer(degree, folding_seq, group.domain_size);
+            path_elements(num_queries, group.domain_size, rows, cost_model)
+                + num_queries * rows * group.num_columns
+        })
+        .sum()
+}
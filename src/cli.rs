@@ -0,0 +1,807 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use fri_dynamic_folding_scheme::batch::DegreeGroup;
+use fri_dynamic_folding_scheme::cost_model::{
+    CosetEvalMethod, CosetLayout, CostModel, DomainLayout, InversionBatching, PathModel,
+    QueryValueTransmission, RemainderStrategy,
+};
+use fri_dynamic_folding_scheme::objective::Objective;
+use fri_dynamic_folding_scheme::prelude::{
+    Schedule, BABYBEAR_QUARTIC, GOLDILOCKS_QUADRATIC, POSEIDON_256,
+};
+use fri_dynamic_folding_scheme::soundness::challenge_field_bits;
+#[cfg(feature = "winterfell")]
+use fri_dynamic_folding_scheme::winterfell::{parse_proof_options_debug, ParsedProofOptions};
+
+/// The format in which the optimal schedule is emitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Emit {
+    /// The human-readable comparison of schedules.
+    #[default]
+    Text,
+    /// A CSV table of the optimal and uniform schedules and their sizes.
+    Csv,
+    /// A `pgfplots` figure of the optimal schedule.
+    Tikz,
+    /// A gnuplot script plotting the optimal schedule.
+    Gnuplot,
+    /// A Markdown section on the optimal schedule for design documents, see the `markdown`
+    /// module. The `table` subcommand and other tables are rendered as Markdown tables.
+    Markdown,
+    /// The full analysis as one JSON object, see `Analysis::to_json`.
+    Json,
+}
+
+/// The subcommand to run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// The full report on the optimal schedule, in the format chosen with `--emit`.
+    #[default]
+    Report,
+    /// A table comparing the optimal schedule with every uniform one.
+    Table,
+    /// The aggregate size of the proofs listed in a file, see [`parse_aggregate_rows`].
+    Aggregate(PathBuf),
+    /// One JSON request answered with one JSON response, see the `stdio` module.
+    StdioJson,
+    /// The requests of `StdioJson` answered over HTTP at the given address, see the `http`
+    /// module.
+    #[cfg(feature = "serve")]
+    Serve(String),
+}
+
+/// One parameter set of the `aggregate` subcommand, with the number of proofs produced with it.
+#[derive(Debug)]
+pub(crate) struct AggregateRow {
+    /// The degree of the polynomial to be proved.
+    pub(crate) degree: usize,
+    /// The blowup factor used in the FRI protocol.
+    pub(crate) blowup_factor: usize,
+    /// The number of queries used in the FRI protocol.
+    pub(crate) num_queries: usize,
+    /// The number of proofs produced with these parameters.
+    pub(crate) count: usize,
+    /// The folding schedule of the proofs, or `None` if they use the optimal one.
+    pub(crate) schedule: Option<Vec<usize>>,
+}
+
+/// The options accepted on the command line.
+#[derive(Debug)]
+pub(crate) struct Options {
+    /// The subcommand to run.
+    pub(crate) command: Command,
+    /// The degree of the polynomial to be proved.
+    pub(crate) degree: usize,
+    /// The blowup factor used in the FRI protocol.
+    pub(crate) blowup_factor: usize,
+    /// The number of queries used in the FRI protocol.
+    pub(crate) num_queries: usize,
+    /// The maximum degree of the remainder polynomial used by the simple schedules.
+    pub(crate) remainder_max_degree: usize,
+    /// The cost model used to price every schedule.
+    pub(crate) cost_model: CostModel,
+    /// Layers whose folding factor (in bits) is forced, keyed by layer index.
+    pub(crate) pinned_layers: HashMap<usize, usize>,
+    /// Groups of columns batched into the proof, each committed in its own tree.
+    pub(crate) degree_groups: Vec<DegreeGroup>,
+    /// Whether to print statistics about the optimizer's search.
+    pub(crate) print_stats: bool,
+    /// Whether an inconsistent cost model is an error rather than a warning.
+    pub(crate) strict: bool,
+    /// The prover memory budget in bytes, if the optimizer should respect one.
+    pub(crate) max_prover_memory: Option<usize>,
+    /// The verifier memory budget in bytes, if the optimizer should respect one.
+    pub(crate) max_verifier_memory: Option<usize>,
+    /// The smallest folding factor in bits the prover supports, if the optimizer should respect
+    /// one.
+    pub(crate) min_folding_bits: Option<usize>,
+    /// The exact remainder degree the optimizer should fold down to, if one is required.
+    pub(crate) target_remainder_degree: Option<usize>,
+    /// How long the optimizer may search before settling for the best schedule found, if its
+    /// search is bounded.
+    pub(crate) time_budget: Option<Duration>,
+    /// The output format.
+    pub(crate) emit: Emit,
+    /// The size in bits of the field the folding challenges are drawn from.
+    pub(crate) field_bits: u32,
+    /// The bits of security the folding challenges may erode before the report warns.
+    pub(crate) max_field_erosion: f64,
+    /// Whether to bump the extension to the smallest one within `max_field_erosion` and report
+    /// the size it costs.
+    pub(crate) auto_extension: bool,
+    /// The number of independent proofs to report the aggregate size of.
+    pub(crate) instances: usize,
+    /// The objectives to optimize in priority order, if the lexicographic optimum is requested.
+    pub(crate) objectives: Option<Vec<Objective>>,
+    /// The presets to find a schedule robust across, with their names, if one is requested.
+    pub(crate) robust_presets: Option<Vec<(String, CostModel)>>,
+    /// The number of random query sets to compare the heuristic against exact sizes on, if any.
+    pub(crate) error_bound_trials: Option<usize>,
+    /// Whether to break down what each refinement of the cost model saves.
+    pub(crate) print_waterfall: bool,
+    /// Whether to report the degrees at which adjacent uniform folding factors swap places.
+    pub(crate) print_crossovers: bool,
+    /// The number of queries each verification tier checks on top of the previous ones, if the
+    /// proof is checked in tiers.
+    pub(crate) query_tiers: Option<Vec<usize>>,
+    /// The number of queries of every layer, if the proof is priced for a query plan.
+    pub(crate) query_plan: Option<Vec<usize>>,
+    /// The factors the degree may grow by, if the deployed schedule's sensitivity to them is
+    /// requested.
+    pub(crate) degree_growths: Option<Vec<usize>>,
+    /// The security level, the prover's hash rate and the grinding time ceiling, if the tradeoff
+    /// between grinding and proof size is requested.
+    pub(crate) grinding: Option<(u32, f64, Duration)>,
+    /// The bits of security to add and the prover's hash rate, if the levers that add them are
+    /// to be compared.
+    pub(crate) security_increase: Option<(u32, f64)>,
+    /// A folding schedule supplied by the user to be sized alongside the optimal one.
+    pub(crate) schedule: Option<Vec<usize>>,
+    /// The Winterfell proof options the parameters were read from, if any.
+    #[cfg(feature = "winterfell")]
+    pub(crate) winterfell_options: Option<ParsedProofOptions>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::default(),
+            degree: 1 << 25,
+            blowup_factor: 8,
+            num_queries: 27,
+            remainder_max_degree: 64,
+            cost_model: CostModel::default(),
+            pinned_layers: HashMap::new(),
+            degree_groups: Vec::new(),
+            print_stats: false,
+            strict: false,
+            max_prover_memory: None,
+            max_verifier_memory: None,
+            min_folding_bits: None,
+            target_remainder_degree: None,
+            time_budget: None,
+            emit: Emit::default(),
+            field_bits: 128,
+            max_field_erosion: 1.0,
+            auto_extension: false,
+            instances: 1,
+            objectives: None,
+            error_bound_trials: None,
+            print_waterfall: false,
+            print_crossovers: false,
+            query_tiers: None,
+            query_plan: None,
+            degree_growths: None,
+            grinding: None,
+            security_increase: None,
+            robust_presets: None,
+            schedule: None,
+            #[cfg(feature = "winterfell")]
+            winterfell_options: None,
+        }
+    }
+}
+
+/// Parses the command line arguments (without the program name) into [`Options`]. The arguments
+/// may start with a subcommand:
+/// * `table` prints a comparison table of the optimal and the uniform schedules instead of the
+///   full report.
+/// * `aggregate <file>` prints the aggregate size of the proofs listed in a file, see
+///   [`parse_aggregate_rows`]. Only the cost model flags apply to it.
+///
+/// Supported flags:
+/// * `--degree <n>` - The degree of the polynomial to be proved; must be a power of 2
+/// * `--blowup <n>` - The blowup factor; must be a power of 2
+/// * `--queries <n>` - The number of queries
+/// * `--remainder-max-degree <n>` - The maximum remainder degree of the simple schedules
+/// * `--preset <goldilocks-quadratic|babybear-quartic|poseidon-256>` - The field and hash the
+///   proof is over
+/// * `--robust <preset,...>` - Also reports the schedule with the smallest worst-case regret
+///   across the given presets, each with the accounting flags applied
+/// * `--coset-layout <packed|split>` - The layout of coset values in the Merkle trees
+/// * `--path-model <naive|ideal>` - How much the authentication paths of the queries share
+/// * `--domain-layout <bit-reversed|natural>` - The order in which evaluations are committed, used
+///   when sizing exact proofs
+/// * `--query-values <all|omit-folded>` - Whether the value the verifier folded from the
+///   previous layer is sent again
+/// * `--remainder-strategy <sent|in-last-layer>` - Whether the remainder is sent or interpolated
+///   from the openings of the last layer, which needs at least as many queries as it has
+///   coefficients
+/// * `--coset-eval <barycentric|horner|naive>` - How the verifier folds each queried coset
+/// * `--inversion-batching <none|per-coset|per-layer>` - How the verifier batches the field
+///   inversions of each layer
+/// * `--shared-first-layer` - The first layer reuses the trace commitment, so its root is not
+///   counted
+/// * `--semantics-version <n>` - The version of the accounting rules to price schedules under
+/// * `--exclude-initial-layer` - The initial layer is opened outside of the proof, so its paths
+///   and leaves are not counted
+/// * `--exclude <component,...>` - Leaves components another tool accounts for out of the size,
+///   each one of `layer0-paths`, `layer0-leaves` or `remainder`; they are reported apart
+/// * `--trace-columns <n>` - The initial layer is a batched commitment to `n` trace columns, so
+///   each of its opened values is `n` base field elements
+/// * `--winterfell-layout` - The leaves of the initial layer group the values of the first fold,
+///   as Winterfell commits its first layer
+/// * `--remainder-verify-cost <n>` - Makes the optimizer charge `n` proof elements for every
+///   remainder element the verifier checks, on top of its size
+/// * `--pin <layer>=<bits>` - Forces the folding factor of a layer; may be repeated
+/// * `--degree-group <domain>=<columns>` - Batches a group of columns over a smaller domain into
+///   the proof; may be repeated
+/// * `--stats` - Prints statistics about the optimizer's search
+/// * `--strict` - Rejects a cost model that breaks a consistency rule instead of warning about it,
+///   see `CostModel::validate`
+/// * `--stdio-json` - Reads one JSON request from standard input and writes one JSON response to
+///   standard output instead of reporting, see the `stdio` module
+/// * `--serve <address>` - Answers the requests of `--stdio-json` over HTTP at `address`, e.g.
+///   `127.0.0.1:9000`, until stopped, see the `http` module; only with the `serve` feature
+/// * `--max-prover-memory <bytes>` - Restricts the optimizer to schedules within a prover memory
+///   budget
+/// * `--max-verifier-memory <bytes>` - Restricts the optimizer to schedules within a verifier
+///   memory budget
+/// * `--min-folding-bits <n>` - Restricts the optimizer to layers folding by at least `n` bits
+/// * `--target-remainder-degree <n>` - Restricts the optimizer to schedules whose remainder has
+///   exactly degree `n`
+/// * `--time-budget <secs>` - Stops the optimizer's search after `secs` seconds, reporting the best
+///   schedule found as possibly suboptimal if it did not finish
+/// * `--emit <text|csv|tikz|gnuplot|markdown|json>` - The output format
+/// * `--instances <n>` - Also reports the aggregate size of `n` independent proofs
+/// * `--lexicographic <objective,...>` - Also reports the schedule minimizing the objectives in
+///   priority order, each one of `size`, `weighted-size`, `hashes`, `field-ops`, `inversions`,
+///   `verifier-memory`, `prover-memory`, `roots`, `deepest-path` or `security=<bits>`
+/// * `--error-bound <trials>` - Also compares the estimated size of the optimal schedule with
+///   exact sizes for `trials` random query sets
+/// * `--query-tiers <n,...>` - Also reports the bytes each verification tier needs, where every
+///   tier checks the given number of queries on top of the previous tiers; the tiers must add up
+///   to the number of queries
+/// * `--query-plan <n,...>` - Also reports the size and the security of the supplied schedule,
+///   or else the optimal one, when each layer is queried the given number of times, starting
+///   with the initial layer; security is reported by the plan's per-layer accounting and by the
+///   conservative one
+/// * `--waterfall` - Also reports the size saved by each refinement enabled in the cost model,
+///   starting from the naive accounting
+/// * `--crossovers` - Also reports the degrees up to `--degree` at which adjacent uniform folding
+///   factors swap places as the smaller proof, each with its best remainder bound; the `table`
+///   subcommand reports them below its table
+/// * `--degree-growth <n,...>` - Also reports how the supplied schedule, or else the optimal one,
+///   compares with the optimum once the degree has grown by each of the given powers of 2, e.g.
+///   `1,2,4,8`
+/// * `--grinding <security-bits>,<hashes-per-sec>,<max-secs>` - Also reports the smallest proof at
+///   the given conjectured security level for every level of grinding the prover can afford
+///   within `max-secs` seconds at the given hash rate, with the queries each level needs
+/// * `--security-increase <bits>,<hashes-per-sec>` - Also reports the proof size and the prover's
+///   work when the conjectured security is raised by `bits` with more queries, with as many
+///   grinding bits at the given hash rate, or with a doubled blowup factor
+/// * `--schedule <bits,...>` - Sizes a folding schedule given as comma-separated folding bits, or
+///   as folding factors starting with the initial layer's factor of 1
+/// * `--field-bits <n>` - The size in bits of the field the folding challenges are drawn from;
+///   defaults to the extension field of the preset, e.g. 124 bits for `babybear-quartic`
+/// * `--max-field-erosion <bits>` - Warns when the folding challenges cost more than `bits` of
+///   the security the queries give; defaults to 1 bit, where both errors are equal
+/// * `--auto-extension` - Also reports the smallest extension, no smaller than the cost model's,
+///   that keeps the folding challenges within `--max-field-erosion`, and the size the larger
+///   extension adds to the optimal proof; the base field is the challenge field of
+///   `--field-bits` divided by the extension degree
+/// * `--from-winterfell-debug <options>` - Reads the blowup factor, the queries, the remainder
+///   degree and the field extension from the `Debug` print of a Winterfell `ProofOptions`, over
+///   the domain given by `--degree`; only with the `winterfell` feature
+pub(crate) fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.peekable();
+    let mut options = Options::default();
+    let mut field_bits_given = false;
+    #[cfg(feature = "winterfell")]
+    let mut winterfell_debug = None;
+    if args.next_if(|arg| arg == "table").is_some() {
+        options.command = Command::Table;
+    } else if let Some(flag) = args.next_if(|arg| arg == "aggregate") {
+        options.command = Command::Aggregate(next_value(&flag, &mut args)?.into());
+    }
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--degree" => options.degree = parse_power_of_two(&flag, &mut args)?,
+            "--blowup" => options.blowup_factor = parse_power_of_two(&flag, &mut args)?,
+            "--queries" => options.num_queries = parse_number(&flag, &mut args)?,
+            "--remainder-max-degree" => {
+                options.remainder_max_degree = parse_number(&flag, &mut args)?
+            }
+            "--preset" => {
+                let name = next_value(&flag, &mut args)?;
+                let preset = parse_preset(&name)?;
+                options.cost_model = with_knobs(preset, &options.cost_model);
+                if !field_bits_given {
+                    options.field_bits = challenge_field_bits(
+                        preset_base_field_bits(&name),
+                        preset.extension_degree,
+                    );
+                }
+            }
+            "--robust" => {
+                let presets = next_value(&flag, &mut args)?
+                    .split(',')
+                    .map(|name| Ok((name.trim().to_string(), parse_preset(name.trim())?)))
+                    .collect::<Result<_, String>>()?;
+                options.robust_presets = Some(presets);
+            }
+            "--coset-layout" => {
+                options.cost_model.coset_layout = match next_value(&flag, &mut args)?.as_str() {
+                    "packed" => CosetLayout::PackedLeaf,
+                    "split" => CosetLayout::SplitLeaves,
+                    other => return Err(format!("unknown coset layout `{other}`")),
+                };
+            }
+            "--path-model" => {
+                options.cost_model.path_model = match next_value(&flag, &mut args)?.as_str() {
+                    "naive" => PathModel::Naive,
+                    "ideal" => PathModel::IdealCompression,
+                    other => return Err(format!("unknown path model `{other}`")),
+                };
+            }
+            "--domain-layout" => {
+                options.cost_model.domain_layout = match next_value(&flag, &mut args)?.as_str() {
+                    "bit-reversed" => DomainLayout::BitReversed,
+                    "natural" => DomainLayout::Natural,
+                    other => return Err(format!("unknown domain layout `{other}`")),
+                };
+            }
+            "--query-values" => {
+                options.cost_model.query_values = match next_value(&flag, &mut args)?.as_str() {
+                    "all" => QueryValueTransmission::AllValues,
+                    "omit-folded" => QueryValueTransmission::OmitFoldedValue,
+                    other => return Err(format!("unknown query value transmission `{other}`")),
+                };
+            }
+            "--remainder-strategy" => {
+                options.cost_model.remainder_strategy = match next_value(&flag, &mut args)?.as_str()
+                {
+                    "sent" => RemainderStrategy::Sent,
+                    "in-last-layer" => RemainderStrategy::InLastLayer,
+                    other => return Err(format!("unknown remainder strategy `{other}`")),
+                };
+            }
+            "--coset-eval" => {
+                options.cost_model.coset_eval = match next_value(&flag, &mut args)?.as_str() {
+                    "barycentric" => CosetEvalMethod::Barycentric,
+                    "horner" => CosetEvalMethod::Horner,
+                    "naive" => CosetEvalMethod::Naive,
+                    other => return Err(format!("unknown coset evaluation method `{other}`")),
+                };
+            }
+            "--inversion-batching" => {
+                options.cost_model.inversion_batching = match next_value(&flag, &mut args)?.as_str()
+                {
+                    "none" => InversionBatching::Unbatched,
+                    "per-coset" => InversionBatching::PerCoset,
+                    "per-layer" => InversionBatching::PerLayer,
+                    other => return Err(format!("unknown inversion batching `{other}`")),
+                };
+            }
+            "--pin" => {
+                let value = next_value(&flag, &mut args)?;
+                let (layer, bits) = value
+                    .split_once('=')
+                    .and_then(|(layer, bits)| Some((layer.parse().ok()?, bits.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid pin `{value}`, expected `<layer>=<bits>`"))?;
+                options.pinned_layers.insert(layer, bits);
+            }
+            "--degree-group" => {
+                let value = next_value(&flag, &mut args)?;
+                let (domain_size, num_columns) = value
+                    .split_once('=')
+                    .and_then(|(domain, columns)| {
+                        Some((domain.parse().ok()?, columns.parse().ok()?))
+                    })
+                    .filter(|(domain, _): &(usize, usize)| domain.is_power_of_two())
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid degree group `{value}`, expected `<domain>=<columns>` with a \
+                             power of 2 domain"
+                        )
+                    })?;
+                options.degree_groups.push(DegreeGroup {
+                    domain_size,
+                    num_columns,
+                });
+            }
+            "--shared-first-layer" => options.cost_model.first_layer_shares_trace_commitment = true,
+            "--exclude-initial-layer" => {
+                let exclude = &mut options.cost_model.exclude;
+                exclude.layer0_paths = true;
+                exclude.layer0_leaves = true;
+            }
+            "--exclude" => {
+                for component in next_value(&flag, &mut args)?.split(',') {
+                    let exclude = &mut options.cost_model.exclude;
+                    match component.trim() {
+                        "layer0-paths" => exclude.layer0_paths = true,
+                        "layer0-leaves" => exclude.layer0_leaves = true,
+                        "remainder" => exclude.remainder = true,
+                        other => return Err(format!("unknown proof component `{other}`")),
+                    }
+                }
+            }
+            "--winterfell-layout" => options.cost_model.winterfell_layout = true,
+            "--trace-columns" => {
+                options.cost_model.initial_layer_columns = Some(parse_number(&flag, &mut args)?)
+            }
+            "--remainder-verify-cost" => {
+                options.cost_model.remainder_verify_cost_per_element =
+                    parse_number(&flag, &mut args)?
+            }
+            "--stats" => options.print_stats = true,
+            "--strict" => options.strict = true,
+            "--stdio-json" => options.command = Command::StdioJson,
+            #[cfg(feature = "serve")]
+            "--serve" => options.command = Command::Serve(next_value(&flag, &mut args)?),
+            "--max-prover-memory" => {
+                options.max_prover_memory = Some(parse_number(&flag, &mut args)?)
+            }
+            "--max-verifier-memory" => {
+                options.max_verifier_memory = Some(parse_number(&flag, &mut args)?)
+            }
+            "--min-folding-bits" => {
+                options.min_folding_bits = Some(parse_number(&flag, &mut args)?)
+            }
+            "--target-remainder-degree" => {
+                options.target_remainder_degree = Some(parse_number(&flag, &mut args)?)
+            }
+            "--time-budget" => {
+                let value = next_value(&flag, &mut args)?;
+                let budget = value
+                    .parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(|| format!("invalid value `{value}` for `{flag}`"))?;
+                options.time_budget = Some(budget);
+            }
+            "--error-bound" => options.error_bound_trials = Some(parse_number(&flag, &mut args)?),
+            "--waterfall" => options.print_waterfall = true,
+            "--crossovers" => options.print_crossovers = true,
+            "--auto-extension" => options.auto_extension = true,
+            "--max-field-erosion" => {
+                let value = next_value(&flag, &mut args)?;
+                options.max_field_erosion = value
+                    .parse()
+                    .ok()
+                    .filter(|&bits: &f64| bits >= 0.0)
+                    .ok_or_else(|| format!("invalid value `{value}` for `{flag}`"))?;
+            }
+            "--query-tiers" => {
+                let value = next_value(&flag, &mut args)?;
+                let tiers = value
+                    .split(',')
+                    .map(|queries| queries.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid query tiers `{value}`"))?;
+                options.query_tiers = Some(tiers);
+            }
+            "--query-plan" => {
+                let value = next_value(&flag, &mut args)?;
+                let plan = value
+                    .split(',')
+                    .map(|queries| queries.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid query plan `{value}`"))?;
+                options.query_plan = Some(plan);
+            }
+            "--degree-growth" => {
+                let value = next_value(&flag, &mut args)?;
+                let growths = value
+                    .split(',')
+                    .map(|growth| growth.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid degree growths `{value}`"))?;
+                options.degree_growths = Some(growths);
+            }
+            "--grinding" => {
+                let value = next_value(&flag, &mut args)?;
+                let invalid = || format!("invalid grinding tradeoff `{value}`");
+                let mut parts = value.split(',').map(str::trim);
+                let (Some(security_bits), Some(hash_rate), Some(max_secs), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(invalid());
+                };
+                let security_bits = security_bits.parse().map_err(|_| invalid())?;
+                let hash_rate = hash_rate
+                    .parse()
+                    .ok()
+                    .filter(|&rate: &f64| rate > 0.0)
+                    .ok_or_else(invalid)?;
+                let ceiling = max_secs
+                    .parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(invalid)?;
+                options.grinding = Some((security_bits, hash_rate, ceiling));
+            }
+            "--security-increase" => {
+                let value = next_value(&flag, &mut args)?;
+                let invalid = || format!("invalid security increase `{value}`");
+                let Some((extra_bits, hash_rate)) = value.split_once(',') else {
+                    return Err(invalid());
+                };
+                let extra_bits = extra_bits.trim().parse().map_err(|_| invalid())?;
+                let hash_rate = hash_rate
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|&rate: &f64| rate > 0.0)
+                    .ok_or_else(invalid)?;
+                options.security_increase = Some((extra_bits, hash_rate));
+            }
+            "--instances" => options.instances = parse_number(&flag, &mut args)?,
+            "--schedule" => {
+                let value = next_value(&flag, &mut args)?;
+                let schedule = value
+                    .parse::<Schedule>()
+                    .and_then(|schedule| schedule.canonicalize())
+                    .map_err(|err| format!("invalid folding schedule `{value}`: {err}"))?;
+                options.schedule = Some(schedule.into_entries());
+            }
+            "--lexicographic" => {
+                let value = next_value(&flag, &mut args)?;
+                let objectives = value
+                    .split(',')
+                    .map(|name| parse_objective(name.trim()))
+                    .collect::<Result<_, _>>()?;
+                options.objectives = Some(objectives);
+            }
+            "--semantics-version" => {
+                let value = next_value(&flag, &mut args)?;
+                options.cost_model.semantics_version = value
+                    .parse()
+                    .map_err(|_| format!("invalid value `{value}` for `{flag}`"))?;
+                options
+                    .cost_model
+                    .check_semantics_version()
+                    .map_err(|err| err.to_string())?;
+            }
+            "--field-bits" => {
+                let value = next_value(&flag, &mut args)?;
+                options.field_bits = value
+                    .parse()
+                    .map_err(|_| format!("invalid value `{value}` for `{flag}`"))?;
+                field_bits_given = true;
+            }
+            "--emit" => {
+                options.emit = match next_value(&flag, &mut args)?.as_str() {
+                    "text" => Emit::Text,
+                    "csv" => Emit::Csv,
+                    "tikz" => Emit::Tikz,
+                    "gnuplot" => Emit::Gnuplot,
+                    "markdown" => Emit::Markdown,
+                    "json" => Emit::Json,
+                    other => return Err(format!("unknown output format `{other}`")),
+                };
+            }
+            #[cfg(feature = "winterfell")]
+            "--from-winterfell-debug" => winterfell_debug = Some(next_value(&flag, &mut args)?),
+            _ => return Err(format!("unknown argument `{flag}`")),
+        }
+    }
+    // the domain size may be given after the Winterfell options, so they are read last.
+    #[cfg(feature = "winterfell")]
+    if let Some(debug) = winterfell_debug {
+        let parsed =
+            parse_proof_options_debug(&debug, options.degree).map_err(|err| err.to_string())?;
+        for (name, value) in [
+            ("blowup factor", parsed.options.blowup_factor),
+            ("folding factor", parsed.options.folding_factor),
+        ] {
+            if !value.is_power_of_two() {
+                return Err(format!("the Winterfell {name} {value} is not a power of 2"));
+            }
+        }
+        options.blowup_factor = parsed.options.blowup_factor;
+        options.num_queries = parsed.options.num_queries;
+        options.remainder_max_degree = parsed.options.remainder_max_degree;
+        options.cost_model.extension_degree = parsed.options.cost_model.extension_degree;
+        options.winterfell_options = Some(parsed);
+    }
+    // security targets are checked against the field given by `--field-bits`, wherever it appears.
+    for objective in options.objectives.iter_mut().flatten() {
+        if let Objective::SecurityShortfall { field_bits, .. } = objective {
+            *field_bits = options.field_bits;
+        }
+    }
+    if options.degree < options.blowup_factor {
+        return Err(format!(
+            "the degree {} must be at least the blowup factor {}",
+            options.degree, options.blowup_factor
+        ));
+    }
+    if !options.degree_groups.is_empty() && options.max_prover_memory.is_some() {
+        return Err("`--degree-group` cannot be combined with `--max-prover-memory`".to_string());
+    }
+    if options.max_verifier_memory.is_some()
+        && (options.max_prover_memory.is_some() || !options.degree_groups.is_empty())
+    {
+        return Err(
+            "`--max-verifier-memory` cannot be combined with `--max-prover-memory` or \
+             `--degree-group`"
+                .to_string(),
+        );
+    }
+    if options.target_remainder_degree.is_some()
+        && (options.min_folding_bits.is_some()
+            || options.max_prover_memory.is_some()
+            || options.max_verifier_memory.is_some()
+            || !options.degree_groups.is_empty()
+            || !options.pinned_layers.is_empty())
+    {
+        return Err(
+            "`--target-remainder-degree` cannot be combined with `--min-folding-bits`, memory \
+             budgets, `--degree-group` or `--pin`"
+                .to_string(),
+        );
+    }
+    if options.min_folding_bits.is_some()
+        && (options.max_prover_memory.is_some()
+            || options.max_verifier_memory.is_some()
+            || !options.degree_groups.is_empty()
+            || !options.pinned_layers.is_empty())
+    {
+        return Err(
+            "`--min-folding-bits` cannot be combined with memory budgets, `--degree-group` or \
+             `--pin`"
+                .to_string(),
+        );
+    }
+    if options.time_budget.is_some()
+        && (options.min_folding_bits.is_some()
+            || options.target_remainder_degree.is_some()
+            || options.max_prover_memory.is_some()
+            || options.max_verifier_memory.is_some()
+            || !options.degree_groups.is_empty()
+            || !options.pinned_layers.is_empty())
+    {
+        return Err(
+            "`--time-budget` cannot be combined with `--min-folding-bits`, \
+             `--target-remainder-degree`, memory budgets, `--degree-group` or `--pin`"
+                .to_string(),
+        );
+    }
+    Ok(options)
+}
+
+/// Parses the contents of an `aggregate` file. Every line lists one parameter set as
+/// `<degree> <blowup> <queries> <count> [<bits,...>]`, where `count` is the number of proofs and
+/// the optional schedule overrides the optimal one. Blank lines and lines starting with `#` are
+/// ignored.
+pub(crate) fn parse_aggregate_rows(contents: &str) -> Result<Vec<AggregateRow>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let invalid = || format!("invalid aggregate row {}: `{line}`", i + 1);
+            let fields: Vec<_> = line.split_whitespace().collect();
+            if !(4..=5).contains(&fields.len()) {
+                return Err(invalid());
+            }
+            let numbers = fields[..4]
+                .iter()
+                .map(|field| field.parse())
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|_| invalid())?;
+            if !numbers[0].is_power_of_two()
+                || !numbers[1].is_power_of_two()
+                || numbers[0] < numbers[1]
+            {
+                return Err(invalid());
+            }
+            let schedule = fields
+                .get(4)
+                .map(|schedule| {
+                    schedule
+                        .split(',')
+                        .map(|bits| bits.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid())
+                })
+                .transpose()?;
+            Ok(AggregateRow {
+                degree: numbers[0],
+                blowup_factor: numbers[1],
+                num_queries: numbers[2],
+                count: numbers[3],
+                schedule,
+            })
+        })
+        .collect()
+}
+
+/// Parses the name of an objective. The field size of a security target is left at 0 until all
+/// arguments have been parsed.
+fn parse_objective(name: &str) -> Result<Objective, String> {
+    let objective = match name {
+        "size" => Objective::ProofSize,
+        "weighted-size" => Objective::WeightedProofSize,
+        "hashes" => Objective::VerifierHashes,
+        "field-ops" => Objective::VerifierFieldOps,
+        "inversions" => Objective::VerifierInversions,
+        "verifier-memory" => Objective::VerifierMemory,
+        "prover-memory" => Objective::ProverMemory,
+        "roots" => Objective::CommitmentRoots,
+        "deepest-path" => Objective::DeepestPath,
+        _ => {
+            let target_bits = name
+                .strip_prefix("security=")
+                .and_then(|bits| bits.parse().ok())
+                .ok_or_else(|| format!("unknown objective `{name}`"))?;
+            Objective::SecurityShortfall {
+                target_bits,
+                field_bits: 0,
+            }
+        }
+    };
+    Ok(objective)
+}
+
+/// Returns the cost model preset called `name`.
+pub(crate) fn parse_preset(name: &str) -> Result<CostModel, String> {
+    match name {
+        "goldilocks-quadratic" => Ok(GOLDILOCKS_QUADRATIC),
+        "babybear-quartic" => Ok(BABYBEAR_QUARTIC),
+        "poseidon-256" => Ok(POSEIDON_256),
+        other => Err(format!("unknown preset `{other}`")),
+    }
+}
+
+/// Returns the size in bits of the base field of the preset named `name`, which must be accepted
+/// by [`parse_preset`].
+fn preset_base_field_bits(name: &str) -> u32 {
+    match name {
+        "goldilocks-quadratic" => 64,
+        "babybear-quartic" => 31,
+        "poseidon-256" => 254,
+        other => unreachable!("unknown preset `{other}`"),
+    }
+}
+
+/// Returns `preset` with the accounting knobs of `knobs`, so that the flags setting them apply
+/// whichever preset is chosen.
+pub(crate) fn with_knobs(preset: CostModel, knobs: &CostModel) -> CostModel {
+    CostModel {
+        coset_layout: knobs.coset_layout,
+        path_model: knobs.path_model,
+        domain_layout: knobs.domain_layout,
+        query_values: knobs.query_values,
+        remainder_strategy: knobs.remainder_strategy,
+        coset_eval: knobs.coset_eval,
+        inversion_batching: knobs.inversion_batching,
+        first_layer_shares_trace_commitment: knobs.first_layer_shares_trace_commitment,
+        initial_layer_columns: knobs.initial_layer_columns,
+        winterfell_layout: knobs.winterfell_layout,
+        exclude: knobs.exclude,
+        remainder_verify_cost_per_element: knobs.remainder_verify_cost_per_element,
+        semantics_version: knobs.semantics_version,
+        ..preset
+    }
+}
+
+/// Returns the value following `flag`, or an error if the arguments are exhausted.
+fn next_value(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for `{flag}`"))
+}
+
+/// Parses the value following `flag` as a number.
+fn parse_number(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<usize, String> {
+    let value = next_value(flag, args)?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{flag}`"))
+}
+
+/// Parses the value following `flag` as a power of 2.
+fn parse_power_of_two(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<usize, String> {
+    let value = parse_number(flag, args)?;
+    if !value.is_power_of_two() {
+        return Err(format!("`{flag}` must be a power of 2, got {value}"));
+    }
+    Ok(value)
+}
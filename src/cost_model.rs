@@ -0,0 +1,1185 @@
+use std::fmt;
+
+use super::defaults::GOLDILOCKS_QUADRATIC;
+use super::error::{FoldingError, Warning};
+
+/// The version of the accounting rules the estimator implements. It is bumped whenever the size
+/// the estimator charges for an existing cost model changes, so that numbers produced under an
+/// older version are never silently reinterpreted.
+///
+/// The default parameters are pinned for every supported version:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::cost_model::SUPPORTED_SEMANTICS_VERSIONS;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// for &version in SUPPORTED_SEMANTICS_VERSIONS {
+///     let cost_model = CostModel { semantics_version: version, ..CostModel::default() };
+///     let (size, schedule) = optimal_folding_strategy(
+///         1 << 25,
+///         8,
+///         27,
+///         vec![0],
+///         &HashMap::new(),
+///         &cost_model,
+///         &mut SearchStats::default(),
+///     )
+///     .unwrap();
+///     let pinned = match version {
+///         2 => (12622, vec![0, 4, 4, 4, 3]),
+///         _ => unreachable!("semantics version {version} has no pinned results"),
+///     };
+///     assert_eq!((size, schedule), pinned);
+/// }
+/// ```
+pub const SEMANTICS_VERSION: u32 = 2;
+
+/// The semantics versions the estimator can still reproduce.
+pub const SUPPORTED_SEMANTICS_VERSIONS: &[u32] = &[SEMANTICS_VERSION];
+
+/// The semantics versions the estimator no longer reproduces, with what the next version changed,
+/// so that a cost model written against one is rejected with what to migrate.
+pub(crate) const RETIRED_SEMANTICS_VERSIONS: &[(u32, &str)] = &[(
+    1,
+    "packed coset leaves were charged a path over the whole domain, version 2 charges the path \
+     above the coset as for split leaves, which shrinks every folded layer",
+)];
+
+/// Describes how the `factor` sibling values of a folding coset are laid out in a layer's Merkle
+/// tree. Either way a query is authenticated by the path above its coset, so the layout only
+/// changes the number of hashes the verifier has to compute, not the estimated size of the proof.
+///
+/// The layouts cannot differ in size as long as the values of a coset are adjacent in the tree:
+/// split leaves then fill a subtree of height `log2(factor)` whose nodes the verifier recomputes,
+/// which leaves exactly the path above a packed leaf to send. The heuristic estimator charges
+/// every query in full whatever the [`DomainLayout`], so its sizes are the same for both. Only in
+/// natural order, where the values of a coset are `domain_size / factor` apart and share no
+/// subtree, does every split leaf need its own path, which
+/// [`exact_proof_size`](super::exact_size::exact_proof_size) charges.
+///
+/// For one query in a domain of 2^10 folded by 4, the tree of split leaves spans the whole domain.
+/// The four leaves differ in their top two bits, so the eight levels below are not shared and
+/// each leaf needs eight digests of its own. A packed leaf or a bit-reversed coset needs eight for
+/// all four values:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::DomainLayout;
+/// use fri_dynamic_folding_scheme::exact_size::exact_proof_size;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let size = |domain_layout, coset_layout| {
+///     let cost_model = CostModel { domain_layout, coset_layout, ..CostModel::default() };
+///     exact_proof_size(1 << 10, 4, &[5], &[0, 2], &cost_model)
+/// };
+/// let packed = size(DomainLayout::BitReversed, CosetLayout::PackedLeaf);
+/// assert_eq!(packed, estimate_proof_size(1 << 10, 4, 1, &[0, 2], &CostModel::default()).unwrap());
+/// assert_eq!(size(DomainLayout::BitReversed, CosetLayout::SplitLeaves), packed);
+/// assert_eq!(size(DomainLayout::Natural, CosetLayout::PackedLeaf), packed);
+/// let digest_elements = CostModel::default().digest_elements;
+/// assert_eq!(size(DomainLayout::Natural, CosetLayout::SplitLeaves), packed + 3 * 8 * digest_elements);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CosetLayout {
+    /// All `factor` values of a coset live in a single Merkle leaf. Each query opens one leaf of
+    /// `factor` elements together with a single authentication path.
+    #[default]
+    PackedLeaf,
+    /// Each of the `factor` values of a coset lives in its own leaf, and the leaves of a coset sit
+    /// under a common subtree of height `log2(factor)`. The verifier needs `factor` short paths,
+    /// but every node inside the coset subtree can be recomputed from the opened values, so only
+    /// the path above the subtree has to be sent.
+    SplitLeaves,
+}
+
+impl CosetLayout {
+    /// Returns the number of digests sent per query for a layer with the given domain size and
+    /// folding factor.
+    ///
+    /// * `PackedLeaf` charges `log2(domain_size) - log2(factor)` digests: the tree has one leaf
+    ///   per coset, so it is `log2(factor)` levels shallower than the domain is wide.
+    /// * `SplitLeaves` charges `log2(domain_size) - log2(factor)` digests too: the `log2(factor)`
+    ///   levels inside the coset subtree are reconstructed by the verifier from the opened values.
+    ///
+    /// Both layouts send the same path; they differ in the hashes the verifier computes, see
+    /// [`CosetLayout::verifier_hashes`].
+    pub fn path_digests(&self, domain_size: usize, factor: usize) -> usize {
+        let depth = domain_size.ilog2() as usize;
+        match self {
+            CosetLayout::PackedLeaf | CosetLayout::SplitLeaves => depth - factor.ilog2() as usize,
+        }
+    }
+
+    /// Returns the number of hashes the verifier computes per query to authenticate one coset of
+    /// `factor` values in a layer with the given domain size.
+    ///
+    /// * `PackedLeaf` hashes the leaf once and then compresses `log2(domain_size) - log2(factor)`
+    ///   times along the path.
+    /// * `SplitLeaves` hashes each of the `factor` leaves, compresses `factor - 1` times to rebuild
+    ///   the coset subtree, and then compresses `log2(domain_size) - log2(factor)` times along the
+    ///   rest of the path.
+    pub fn verifier_hashes(&self, domain_size: usize, factor: usize) -> usize {
+        let path = self.path_digests(domain_size, factor);
+        match self {
+            CosetLayout::PackedLeaf => 1 + path,
+            CosetLayout::SplitLeaves => factor + (factor - 1) + path,
+        }
+    }
+}
+
+/// Describes how much of the authentication paths of a layer's queries is deduplicated before it
+/// is sent. Sharing only ever removes digests, so the model does not affect the opened values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathModel {
+    /// Every query carries its own full authentication path, as in a prover without batched
+    /// openings.
+    #[default]
+    Naive,
+    /// The queries share their paths as much as any set of queries could: they are assumed to
+    /// open adjacent leaves, so a node is sent only when the verifier cannot recompute it. This
+    /// is the floor a batched-opening implementation can reach for the given query count.
+    IdealCompression,
+}
+
+impl PathModel {
+    /// Returns the number of digests sent for `num_queries` authentication paths in a tree of the
+    /// given depth, where `depth` is the per-query path length returned by
+    /// [`CosetLayout::path_digests`].
+    ///
+    /// * `Naive` charges `num_queries * depth` digests.
+    /// * `IdealCompression` opens the `num_queries` leftmost leaves. Walking up the tree, the
+    ///   known nodes of each level are again the leftmost ones, and a sibling digest is needed
+    ///   exactly on the levels where their number is odd.
+    pub fn path_digests(&self, depth: usize, num_queries: usize) -> usize {
+        match self {
+            PathModel::Naive => num_queries * depth,
+            PathModel::IdealCompression => {
+                // there are only 2^depth distinct leaves to open.
+                let mut known = num_queries.min(1 << depth);
+                let mut digests = 0;
+                for _ in 0..depth {
+                    digests += known % 2;
+                    known = known.div_ceil(2);
+                }
+                digests
+            }
+        }
+    }
+}
+
+/// The order in which the evaluations of a layer are committed. The order decides which queried
+/// positions share subtrees of the layer's Merkle tree, so it only matters when openings are
+/// deduplicated, as in [`exact_proof_size`](super::exact_size::exact_proof_size); the heuristic
+/// estimator charges every query in full either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DomainLayout {
+    /// Evaluations are committed in bit-reversed order. The values of a folding coset are
+    /// adjacent, so they fill one packed leaf or one subtree, and the cosets that fold into the
+    /// same position of the next layer sit next to each other.
+    #[default]
+    BitReversed,
+    /// Evaluations are committed in natural order. A packed leaf gathers the values of its coset,
+    /// which are spread across the domain, and split coset leaves share no subtree.
+    Natural,
+}
+
+/// Which values of a queried coset are sent in the proof. All of them are hashed into the coset's
+/// leaf or subtree either way, so the choice only changes the opened values, never the paths.
+///
+/// For example, with a degree of 2^10, a blowup factor of 4, 3 queries and the folding sequence
+/// `[0, 2, 2]`, omitting the folded values saves one value of each query in layers 1 and 2:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::QueryValueTransmission;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let every_value = CostModel::default();
+/// let omitted = CostModel {
+///     query_values: QueryValueTransmission::OmitFoldedValue,
+///     ..every_value
+/// };
+/// assert_eq!(estimate_proof_size(1 << 10, 4, 3, &[0, 2, 2], &every_value).unwrap(), 374);
+/// assert_eq!(estimate_proof_size(1 << 10, 4, 3, &[0, 2, 2], &omitted).unwrap(), 374 - 2 * 3 * 2);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueryValueTransmission {
+    /// Every value of a queried coset is sent at every layer, including the one the verifier
+    /// could compute itself. This is the accounting the estimator has always used.
+    #[default]
+    AllValues,
+    /// In every layer after the first, the value at the query's own position is the one the
+    /// verifier obtained by folding the previous layer, so only the other `factor - 1` values of
+    /// the coset are sent and the verifier fills in the last one before hashing the leaf.
+    OmitFoldedValue,
+}
+
+impl QueryValueTransmission {
+    /// Returns the number of values sent per query for a coset of `factor` values in the given
+    /// layer of the folding sequence.
+    pub fn sent_values(&self, layer: usize, factor: usize) -> usize {
+        match self {
+            QueryValueTransmission::OmitFoldedValue if layer > 0 => factor - 1,
+            _ => factor,
+        }
+    }
+}
+
+/// How the remainder polynomial reaches the verifier.
+///
+/// Instead of sending the remainder, a prover may leave it committed in the last layer's Merkle
+/// tree: every query opens the last layer and the verifier folds it into one evaluation of the
+/// remainder, so that the evaluations of all queries determine the remainder. This saves the
+/// remainder's elements but only works if there are at least as many queries as the remainder
+/// has coefficients, assuming the queried positions are distinct. The remainder is evaluated over
+/// the domain the last layer folds into, so it never spans more than the last layer's domain.
+///
+/// At the default parameters the optimal schedule stops at a remainder of degree 2^7, more than
+/// 27 queries can interpolate, so the optimizer folds one more layer when the remainder is left
+/// in the last layer:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::RemainderStrategy;
+/// use fri_dynamic_folding_scheme::optimized_schedule::check_remainder_strategy;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let in_last_layer = CostModel {
+///     remainder_strategy: RemainderStrategy::InLastLayer,
+///     ..CostModel::default()
+/// };
+/// let schedule = [0, 4, 4, 4, 3];
+/// assert_eq!(
+///     check_remainder_strategy(1 << 25, 8, 27, &schedule, &in_last_layer),
+///     Err(FoldingError::RemainderNotInterpolable { remainder_degree: 128, num_queries: 27 })
+/// );
+/// ```
+///
+/// A sent remainder is never padded: its `domain_size / blowup_factor` coefficients are a power
+/// of two for every schedule, as the degree and the blowup factor are, so a verifier that needs a
+/// power-of-two length for its inverse NTT gets one as is. The cost model has no option to round
+/// the remainder up for that reason, as it would price every schedule the same either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RemainderStrategy {
+    /// The remainder's coefficients are sent in the proof.
+    #[default]
+    Sent,
+    /// The remainder is interpolated from the openings of the last layer and costs nothing.
+    InLastLayer,
+}
+
+impl RemainderStrategy {
+    /// Returns whether `num_queries` queries let the verifier learn a remainder of
+    /// `remainder_degree` coefficients.
+    pub fn is_feasible(&self, remainder_degree: usize, num_queries: usize) -> bool {
+        match self {
+            RemainderStrategy::Sent => true,
+            RemainderStrategy::InLastLayer => remainder_degree <= num_queries,
+        }
+    }
+}
+
+/// The method the verifier uses to compute the folded value of a queried coset, i.e. to evaluate
+/// the polynomial interpolating the `factor` coset values at the folding challenge. The method
+/// determines the number of field multiplications per queried coset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CosetEvalMethod {
+    /// Barycentric evaluation with precomputed weights: `2 * factor` multiplications.
+    #[default]
+    Barycentric,
+    /// Interpolation with an inverse FFT followed by Horner evaluation:
+    /// `factor * log2(factor) + factor` multiplications.
+    Horner,
+    /// Lagrange interpolation without any precomputation: `factor^2` multiplications.
+    Naive,
+}
+
+impl CosetEvalMethod {
+    /// Returns the number of field multiplications needed to fold one queried coset of `factor`
+    /// values. Unfolded layers (`factor == 1`) need none.
+    pub fn field_ops(&self, factor: usize) -> usize {
+        if factor == 1 {
+            return 0;
+        }
+        match self {
+            CosetEvalMethod::Barycentric => 2 * factor,
+            CosetEvalMethod::Horner => factor * factor.ilog2() as usize + factor,
+            CosetEvalMethod::Naive => factor * factor,
+        }
+    }
+
+    /// Returns the number of field inversions needed to fold one queried coset of `factor`
+    /// values, before any batching. Unfolded layers (`factor == 1`) need none.
+    ///
+    /// * `Barycentric` inverts the distance from the challenge to every coset point.
+    /// * `Horner` inverts the coset offset once, to shift the interpolated coefficients.
+    /// * `Naive` inverts the distance from the challenge to every coset point, as its Lagrange
+    ///   denominators are fixed by the domain.
+    pub fn inversions(&self, factor: usize) -> usize {
+        if factor == 1 {
+            return 0;
+        }
+        match self {
+            CosetEvalMethod::Barycentric | CosetEvalMethod::Naive => factor,
+            CosetEvalMethod::Horner => 1,
+        }
+    }
+}
+
+/// How the verifier batches the field inversions of a layer with Montgomery's trick, which
+/// replaces a batch of `m` inversions by one inversion and `3 * (m - 1)` multiplications.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InversionBatching {
+    /// Every inversion is computed on its own.
+    #[default]
+    Unbatched,
+    /// The inversions of each queried coset form one batch.
+    PerCoset,
+    /// The inversions of all queried cosets of a layer form one batch.
+    PerLayer,
+}
+
+impl InversionBatching {
+    /// Returns the number of inversions and of additional multiplications for `num_batches`
+    /// batches of `batch_size` inversions each, as they would be computed without batching.
+    fn batch(&self, num_batches: usize, batch_size: usize) -> (usize, usize) {
+        let (num_batches, batch_size) = match self {
+            InversionBatching::Unbatched => return (num_batches * batch_size, 0),
+            InversionBatching::PerCoset => (num_batches, batch_size),
+            InversionBatching::PerLayer => (1, num_batches * batch_size),
+        };
+        if batch_size == 0 {
+            return (0, 0);
+        }
+        (num_batches, num_batches * 3 * (batch_size - 1))
+    }
+
+    /// Returns the number of inversions and of additional multiplications the verifier needs to
+    /// fold `num_queries` cosets of `factor` values evaluated with `coset_eval`.
+    pub fn layer_inversions(
+        &self,
+        num_queries: usize,
+        factor: usize,
+        coset_eval: CosetEvalMethod,
+    ) -> (usize, usize) {
+        self.batch(num_queries, coset_eval.inversions(factor))
+    }
+}
+
+/// Components of a FRI proof that another tool already accounts for, such as a spreadsheet sizing
+/// the STARK around the FRI proof, and that are left out of the size so that combined totals do
+/// not count them twice. The excluded elements are still computed and reported apart, see
+/// [`ProofSizeBreakdown`](super::report::ProofSizeBreakdown).
+///
+/// Whatever is excluded, and however the initial layer is committed, the proof and the excluded
+/// elements add up to the full proof:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::Exclusions;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let seq = [0, 4, 3, 3];
+/// for initial_layer_columns in [None, Some(80)] {
+///     for first_layer_shares_trace_commitment in [false, true] {
+///         let full = CostModel {
+///             initial_layer_columns,
+///             first_layer_shares_trace_commitment,
+///             ..CostModel::default()
+///         };
+///         for mask in 0..8 {
+///             let exclude = Exclusions {
+///                 layer0_paths: mask & 1 != 0,
+///                 layer0_leaves: mask & 2 != 0,
+///                 remainder: mask & 4 != 0,
+///             };
+///             let cost_model = CostModel { exclude, ..full };
+///             let breakdown = ProofSizeBreakdown::new(1 << 20, 8, 27, &seq, &cost_model);
+///             assert_eq!(
+///                 breakdown.total_elements(),
+///                 estimate_proof_size(1 << 20, 8, 27, &seq, &cost_model).unwrap()
+///             );
+///             assert_eq!(
+///                 breakdown.total_elements() + breakdown.excluded.total_elements(),
+///                 estimate_proof_size(1 << 20, 8, 27, &seq, &full).unwrap()
+///             );
+///         }
+///
+///         // excluding the initial layer takes off exactly its paths and leaves.
+///         let initial_layer = ProofSizeBreakdown::new(1 << 20, 8, 27, &seq, &full).layers[0].clone();
+///         let cost_model = CostModel { exclude: Exclusions::INITIAL_LAYER, ..full };
+///         assert_eq!(
+///             estimate_proof_size(1 << 20, 8, 27, &seq, &cost_model).unwrap(),
+///             estimate_proof_size(1 << 20, 8, 27, &seq, &full).unwrap() - initial_layer.total_elements()
+///         );
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exclusions {
+    /// The Merkle paths of the initial layer.
+    pub layer0_paths: bool,
+    /// The opened values of the initial layer.
+    pub layer0_leaves: bool,
+    /// The remainder polynomial. Since the remainder then costs nothing, the optimal schedule
+    /// folds as little as possible.
+    pub remainder: bool,
+}
+
+impl Exclusions {
+    /// Excludes the paths and leaves of the initial layer, the first entry of the folding
+    /// sequence, whose openings some recursion setups check in an outer proof instead. The layer
+    /// then only serves the degree bookkeeping. This is independent of the cost model's
+    /// `first_layer_shares_trace_commitment`, which only concerns the layer's root.
+    ///
+    /// The initial layer is never folded, so unless `winterfell_layout` ties its leaves to the
+    /// first fold, its cost is the same for every schedule: excluding it lowers every size by the
+    /// same amount and leaves the optimal schedule unchanged. Under `winterfell_layout` a larger
+    /// first fold widens the initial layer's leaves, and excluding them can change the optimum:
+    /// at a degree of 2^25, a blowup factor of 8 and 27 queries it goes from `[0, 3, 4, 4, 3]` to
+    /// `[0, 4, 4, 4, 3]`. The excluded elements stay in the breakdown, see
+    /// [`ProofSizeBreakdown::excluded`](crate::report::ProofSizeBreakdown::excluded).
+    pub const INITIAL_LAYER: Exclusions = Exclusions {
+        layer0_paths: true,
+        layer0_leaves: true,
+        remainder: false,
+    };
+}
+
+/// Knobs of the cost model used to price a FRI proof. Sizes are counted in base field elements.
+/// Every cost model records the [`SEMANTICS_VERSION`] it was written against.
+/// The default cost model reproduces the accounting of the original estimator, see
+/// [`GOLDILOCKS_QUADRATIC`].
+///
+/// The query positions are never charged, in any encoding: the verifier derives them from the
+/// transcript, so a proof does not carry them. A serializer that sent them anyway would add the
+/// same number of bits to every schedule of the same domain and queries, which leaves every
+/// optimum as it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostModel {
+    /// The layout of coset values in each layer's Merkle tree.
+    pub coset_layout: CosetLayout,
+    /// How much the authentication paths of a layer's queries share.
+    pub path_model: PathModel,
+    /// The order in which each layer's evaluations are committed.
+    pub domain_layout: DomainLayout,
+    /// Which values of a queried coset are sent in the proof.
+    pub query_values: QueryValueTransmission,
+    /// How the remainder polynomial reaches the verifier.
+    pub remainder_strategy: RemainderStrategy,
+    /// The method the verifier uses to fold each queried coset.
+    pub coset_eval: CosetEvalMethod,
+    /// How the verifier batches the field inversions of each layer.
+    pub inversion_batching: InversionBatching,
+    /// Whether the first layer of the folding sequence reuses an oracle the prover has already
+    /// committed to, such as the composition polynomial in DEEP-ALI, instead of a fresh
+    /// commitment. Its root is then not counted again, while its query openings still are.
+    pub first_layer_shares_trace_commitment: bool,
+    /// The number of columns the initial layer commits to in each leaf when it is a batched
+    /// commitment, such as the trace of a STARK, rather than a single polynomial. Each opened
+    /// value of the layer is then one base field element per column. `None` prices the initial
+    /// layer like every other layer.
+    pub initial_layer_columns: Option<usize>,
+    /// Whether the leaves of the initial layer group the values the first fold combines, as
+    /// Winterfell commits its first FRI layer, instead of holding one value each. A first fold by
+    /// 16 then opens 16 values of the initial layer per query over a tree 4 levels shallower, so
+    /// the cost of the initial layer depends on the first folding factor, see
+    /// [`CostModel::leaf_bits`].
+    pub winterfell_layout: bool,
+    /// The components left out of the size because another tool accounts for them or because
+    /// they are checked outside of the proof, see [`Exclusions::INITIAL_LAYER`].
+    pub exclude: Exclusions,
+    /// The cost, in proof elements, the optimizer charges for every element of the remainder the
+    /// verifier checks directly, on top of the element itself. Checking a larger remainder takes
+    /// the verifier more work, so a positive weight makes the optimizer fold deeper. The weight
+    /// never changes the estimated size of a proof, see
+    /// [`remainder_check_cost`](super::verifier_cost::remainder_check_cost).
+    pub remainder_verify_cost_per_element: usize,
+    /// The number of base field elements in a hash digest.
+    pub digest_elements: usize,
+    /// The number of base field elements in each element of the field FRI layers are over.
+    pub extension_degree: usize,
+    /// The size of a base field element in bytes.
+    pub element_bytes: usize,
+    /// The version of the accounting rules the cost model is priced under.
+    pub semantics_version: u32,
+}
+
+impl CostModel {
+    /// Returns the number of base field elements in one committed value of a layer: one element
+    /// per column for a batched initial layer, and one extension field element otherwise.
+    pub fn value_elements(&self, layer: usize) -> usize {
+        match self.initial_layer_columns {
+            Some(columns) if layer == 0 => columns,
+            _ => self.extension_degree,
+        }
+    }
+
+    /// Returns the number of values, in bits, each leaf of a layer's Merkle tree groups: the
+    /// layer's folding factor, except for the unfolded initial layer under `winterfell_layout`,
+    /// whose leaves group the values of the first fold, `folding_seq[1]`.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let seq = [0, 4, 3, 3];
+    /// let winterfell = CostModel { winterfell_layout: true, ..CostModel::default() };
+    /// assert_eq!(CostModel::default().leaf_bits(&seq, 0), 0);
+    /// assert_eq!(winterfell.leaf_bits(&seq, 0), 4);
+    /// assert_eq!(winterfell.leaf_bits(&seq, 1), 4);
+    /// assert_eq!(winterfell.leaf_bits(&[0], 0), 0);
+    /// ```
+    ///
+    /// # Panics
+    /// * If `layer` is not a layer of `folding_seq`
+    pub fn leaf_bits(&self, folding_seq: &[usize], layer: usize) -> usize {
+        match folding_seq.get(1) {
+            Some(&first_fold) if layer == 0 && self.winterfell_layout => first_fold,
+            _ => folding_seq[layer],
+        }
+    }
+
+    /// Returns whether the Merkle paths of a layer are counted in the size of the proof.
+    pub fn counts_paths(&self, layer: usize) -> bool {
+        layer > 0 || !self.exclude.layer0_paths
+    }
+
+    /// Returns whether the opened values of a layer are counted in the size of the proof.
+    pub fn counts_leaves(&self, layer: usize) -> bool {
+        layer > 0 || !self.exclude.layer0_leaves
+    }
+
+    /// Returns the consistency rules the cost model breaks, in the order they are listed in
+    /// [`CostModelViolation`]. Such a cost model still prices every schedule, but the prices
+    /// describe no proof a prover could produce. Every shipped preset follows every rule.
+    ///
+    /// The optimizers record the violations among the warnings of their [`SearchStats`], and
+    /// [`analyze`] rejects them in strict mode, see [`AnalysisOptions::strict`].
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::cost_model::CostModelViolation;
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let cost_model = CostModel { digest_elements: 0, element_bytes: 0, ..CostModel::default() };
+    /// assert_eq!(
+    ///     cost_model.validate(),
+    ///     [CostModelViolation::EmptyDigest, CostModelViolation::EmptyElement]
+    /// );
+    /// ```
+    ///
+    /// [`SearchStats`]: super::stats::SearchStats
+    /// [`analyze`]: super::pipeline::analyze
+    /// [`AnalysisOptions::strict`]: super::pipeline::AnalysisOptions::strict
+    pub fn validate(&self) -> Vec<CostModelViolation> {
+        let mut violations = Vec::new();
+        // paths and roots are counted in digests, so an empty digest makes commitments free.
+        if self.digest_elements == 0 {
+            violations.push(CostModelViolation::EmptyDigest);
+        }
+        // every opened value and the remainder are counted in extension field elements.
+        if self.extension_degree == 0 {
+            violations.push(CostModelViolation::EmptyValue);
+        }
+        // sizes in bytes are counted in base field elements.
+        if self.element_bytes == 0 {
+            violations.push(CostModelViolation::EmptyElement);
+        }
+        // a batched initial layer opens one element per column, so it needs at least one.
+        if self.initial_layer_columns == Some(0) {
+            violations.push(CostModelViolation::EmptyInitialLayer);
+        }
+        // Horner evaluation inverts once per coset, and a batch of one saves nothing.
+        if self.coset_eval == CosetEvalMethod::Horner
+            && self.inversion_batching == InversionBatching::PerCoset
+        {
+            violations.push(CostModelViolation::SingleInversionBatches);
+        }
+        // a remainder interpolated from the last layer is never sent, so there is none to leave
+        // out.
+        if self.exclude.remainder && self.remainder_strategy == RemainderStrategy::InLastLayer {
+            violations.push(CostModelViolation::ExcludedUnsentRemainder);
+        }
+        violations
+    }
+
+    /// Returns the violations of [`Self::validate`] as warnings.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.validate()
+            .into_iter()
+            .map(Warning::InconsistentCostModel)
+            .collect()
+    }
+
+    /// Checks that the cost model breaks no consistency rule, see [`Self::validate`].
+    ///
+    /// # Errors
+    /// * If [`Self::validate`] reports any violation, with all of them
+    pub fn check_consistency(&self) -> Result<(), FoldingError> {
+        let violations = self.validate();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(FoldingError::InconsistentCostModel { violations })
+        }
+    }
+
+    /// Checks that the estimator still implements the accounting rules the cost model was
+    /// written against. The error for a retired version says what changed since:
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let cost_model = CostModel { semantics_version: 1, ..CostModel::default() };
+    /// let error = cost_model.check_semantics_version().unwrap_err();
+    /// assert_eq!(error, FoldingError::UnsupportedSemanticsVersion { version: 1 });
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "semantics version 1 no longer supported: packed coset leaves were charged a path over \
+    ///      the whole domain, version 2 charges the path above the coset as for split leaves, \
+    ///      which shrinks every folded layer; rerun it under version 2 to migrate"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// * If `semantics_version` is not one of [`SUPPORTED_SEMANTICS_VERSIONS`]
+    pub fn check_semantics_version(&self) -> Result<(), FoldingError> {
+        if SUPPORTED_SEMANTICS_VERSIONS.contains(&self.semantics_version) {
+            Ok(())
+        } else {
+            Err(FoldingError::UnsupportedSemanticsVersion {
+                version: self.semantics_version,
+            })
+        }
+    }
+}
+
+/// A consistency rule a cost model breaks, see [`CostModel::validate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CostModelViolation {
+    /// `digest_elements` is 0, so Merkle paths and roots cost nothing.
+    EmptyDigest,
+    /// `extension_degree` is 0, so opened values and the remainder cost nothing.
+    EmptyValue,
+    /// `element_bytes` is 0, so every proof takes 0 bytes.
+    EmptyElement,
+    /// `initial_layer_columns` is `Some(0)`, so the initial layer commits to no column.
+    EmptyInitialLayer,
+    /// `inversion_batching` batches per coset while `coset_eval` is Horner evaluation, which
+    /// inverts once per coset, so there is nothing to batch.
+    SingleInversionBatches,
+    /// `exclude.remainder` leaves out a remainder the `InLastLayer` strategy never sends.
+    ExcludedUnsentRemainder,
+}
+
+impl fmt::Display for CostModelViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostModelViolation::EmptyDigest => write!(f, "digests hold no field element"),
+            CostModelViolation::EmptyValue => {
+                write!(f, "values of the FRI layers hold no base field element")
+            }
+            CostModelViolation::EmptyElement => write!(f, "field elements take no byte"),
+            CostModelViolation::EmptyInitialLayer => {
+                write!(f, "the batched initial layer commits to no column")
+            }
+            CostModelViolation::SingleInversionBatches => write!(
+                f,
+                "Horner evaluation inverts once per coset, so per-coset batching has nothing to \
+                 batch"
+            ),
+            CostModelViolation::ExcludedUnsentRemainder => write!(
+                f,
+                "the remainder is interpolated from the last layer, so there is no sent remainder \
+                 to exclude"
+            ),
+        }
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        GOLDILOCKS_QUADRATIC
+    }
+}
+
+/// Returns every preset, every preset with one toggle away from its default, and every preset
+/// with all of them, for tests that must hold under any cost model.
+#[cfg(test)]
+pub(crate) fn toggled_cost_models() -> Vec<CostModel> {
+    use crate::defaults::{BABYBEAR_QUARTIC, POSEIDON_256};
+
+    let toggles: [fn(&mut CostModel); 16] = [
+        |model| model.coset_layout = CosetLayout::SplitLeaves,
+        |model| model.path_model = PathModel::IdealCompression,
+        |model| model.domain_layout = DomainLayout::Natural,
+        |model| model.query_values = QueryValueTransmission::OmitFoldedValue,
+        |model| model.remainder_strategy = RemainderStrategy::InLastLayer,
+        |model| model.coset_eval = CosetEvalMethod::Horner,
+        |model| model.coset_eval = CosetEvalMethod::Naive,
+        |model| model.inversion_batching = InversionBatching::PerCoset,
+        |model| model.inversion_batching = InversionBatching::PerLayer,
+        |model| model.first_layer_shares_trace_commitment = true,
+        |model| model.initial_layer_columns = Some(80),
+        |model| model.winterfell_layout = true,
+        |model| model.exclude.layer0_paths = true,
+        |model| model.exclude.layer0_leaves = true,
+        |model| model.exclude.remainder = true,
+        |model| model.remainder_verify_cost_per_element = 16,
+    ];
+    let mut models = Vec::new();
+    for preset in [GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256] {
+        models.push(preset);
+        let mut all = preset;
+        for toggle in toggles {
+            let mut model = preset;
+            toggle(&mut model);
+            models.push(model);
+            toggle(&mut all);
+        }
+        models.push(all);
+    }
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::defaults::{BABYBEAR_QUARTIC, GOLDILOCKS_QUADRATIC, POSEIDON_256};
+    use crate::exact_size::exact_proof_size;
+    use crate::optimized_schedule::{
+        check_remainder_strategy, estimate_proof_size_unchecked, for_each_schedule,
+        is_remainder_feasible, optimal_folding_strategy, optimal_with_max_folding_factor,
+        remainder_coefficient_elements,
+    };
+    use crate::params::FriParams;
+    use crate::pipeline::{analyze, AnalysisOptions};
+    use crate::report::ProofSizeBreakdown;
+    use crate::stats::SearchStats;
+
+    /// Asserts that `cost_model` breaks exactly `violation`, and that strict checking rejects it.
+    fn assert_breaks(cost_model: CostModel, violation: CostModelViolation) {
+        assert_eq!(cost_model.validate(), [violation]);
+        assert_eq!(
+            cost_model.check_consistency(),
+            Err(FoldingError::InconsistentCostModel {
+                violations: vec![violation]
+            })
+        );
+    }
+
+    #[test]
+    fn presets_follow_every_rule() {
+        for preset in [GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256] {
+            assert_eq!(preset.validate(), []);
+            assert_eq!(preset.check_consistency(), Ok(()));
+            assert_eq!(preset.warnings(), []);
+        }
+    }
+
+    #[test]
+    fn empty_digest_is_a_violation() {
+        let cost_model = CostModel {
+            digest_elements: 0,
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::EmptyDigest);
+    }
+
+    #[test]
+    fn empty_value_is_a_violation() {
+        let cost_model = CostModel {
+            extension_degree: 0,
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::EmptyValue);
+    }
+
+    #[test]
+    fn empty_element_is_a_violation() {
+        let cost_model = CostModel {
+            element_bytes: 0,
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::EmptyElement);
+    }
+
+    #[test]
+    fn empty_initial_layer_is_a_violation() {
+        let cost_model = CostModel {
+            initial_layer_columns: Some(0),
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::EmptyInitialLayer);
+
+        // a single column is a batch like any other.
+        let cost_model = CostModel {
+            initial_layer_columns: Some(1),
+            ..CostModel::default()
+        };
+        assert_eq!(cost_model.validate(), []);
+    }
+
+    #[test]
+    fn per_coset_batches_of_horner_are_a_violation() {
+        let cost_model = CostModel {
+            coset_eval: CosetEvalMethod::Horner,
+            inversion_batching: InversionBatching::PerCoset,
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::SingleInversionBatches);
+
+        // batching the whole layer still saves inversions.
+        let cost_model = CostModel {
+            inversion_batching: InversionBatching::PerLayer,
+            ..cost_model
+        };
+        assert_eq!(cost_model.validate(), []);
+    }
+
+    #[test]
+    fn excluded_unsent_remainder_is_a_violation() {
+        let exclude = Exclusions {
+            remainder: true,
+            ..Exclusions::default()
+        };
+        let cost_model = CostModel {
+            remainder_strategy: RemainderStrategy::InLastLayer,
+            exclude,
+            ..CostModel::default()
+        };
+        assert_breaks(cost_model, CostModelViolation::ExcludedUnsentRemainder);
+
+        // a sent remainder may be left out.
+        let cost_model = CostModel {
+            remainder_strategy: RemainderStrategy::Sent,
+            ..cost_model
+        };
+        assert_eq!(cost_model.validate(), []);
+    }
+
+    #[test]
+    fn every_violation_is_reported_as_a_warning() {
+        let cost_model = CostModel {
+            digest_elements: 0,
+            element_bytes: 0,
+            ..CostModel::default()
+        };
+        assert_eq!(
+            cost_model.warnings(),
+            [
+                Warning::InconsistentCostModel(CostModelViolation::EmptyDigest),
+                Warning::InconsistentCostModel(CostModelViolation::EmptyElement),
+            ]
+        );
+    }
+
+    #[test]
+    fn searches_record_the_violations() {
+        let cost_model = CostModel {
+            digest_elements: 0,
+            ..CostModel::default()
+        };
+        let mut stats = SearchStats::default();
+        optimal_with_max_folding_factor(1 << 10, 4, 3, 2, &cost_model, &mut stats).unwrap();
+        assert_eq!(stats.warnings, cost_model.warnings());
+    }
+
+    #[test]
+    fn strict_analysis_rejects_violations() {
+        let params = FriParams {
+            degree: 1 << 10,
+            blowup_factor: 4,
+            num_queries: 3,
+            cost_model: CostModel {
+                element_bytes: 0,
+                ..CostModel::default()
+            },
+        };
+        let lenient = analyze(&params, &AnalysisOptions::default()).unwrap();
+        assert_eq!(lenient.warnings, params.cost_model.warnings());
+
+        let strict = AnalysisOptions {
+            strict: true,
+            ..AnalysisOptions::default()
+        };
+        assert_eq!(
+            analyze(&params, &strict).err(),
+            params.cost_model.check_consistency().err()
+        );
+    }
+
+    fn optimal(
+        degree: usize,
+        num_queries: usize,
+        cost_model: &CostModel,
+    ) -> Result<(usize, Vec<usize>), FoldingError> {
+        optimal_folding_strategy(
+            degree,
+            8,
+            num_queries,
+            vec![0],
+            &HashMap::new(),
+            cost_model,
+            &mut SearchStats::default(),
+        )
+    }
+
+    #[test]
+    fn remainder_in_the_last_layer_is_not_sent() {
+        let sent = CostModel::default();
+        let in_last_layer = CostModel {
+            remainder_strategy: RemainderStrategy::InLastLayer,
+            ..sent
+        };
+        // the remainder of [0, 4, 4] at 2^16 has degree 2^5, 2 elements per coefficient.
+        let size =
+            |cost_model| estimate_proof_size_unchecked(1 << 16, 8, 32, &[0, 4, 4], cost_model);
+        assert_eq!(size(&in_last_layer), size(&sent) - 32 * 2);
+        assert_eq!(
+            check_remainder_strategy(1 << 16, 8, 32, &[0, 4, 4], &in_last_layer),
+            Ok(())
+        );
+        assert_eq!(
+            check_remainder_strategy(1 << 16, 8, 31, &[0, 4, 4], &in_last_layer),
+            Err(FoldingError::RemainderNotInterpolable {
+                remainder_degree: 32,
+                num_queries: 31
+            })
+        );
+    }
+
+    #[test]
+    fn excluding_the_initial_layer_can_change_the_optimum() {
+        let excluded = |full: CostModel| CostModel {
+            exclude: Exclusions::INITIAL_LAYER,
+            ..full
+        };
+
+        // the initial layer costs the same for every schedule, so only the size moves.
+        let full = CostModel::default();
+        let (full_size, schedule) = optimal(1 << 25, 27, &full).unwrap();
+        let initial_layer =
+            ProofSizeBreakdown::new(1 << 25, 8, 27, &schedule, &full).layers[0].total_elements();
+        assert_eq!(
+            optimal(1 << 25, 27, &excluded(full)),
+            Ok((full_size - initial_layer, schedule))
+        );
+
+        // under the Winterfell layout the initial leaves grow with the first fold, which the
+        // optimum avoids until they are opened elsewhere.
+        let full = CostModel {
+            winterfell_layout: true,
+            ..CostModel::default()
+        };
+        assert_eq!(
+            optimal(1 << 25, 27, &full),
+            Ok((12932, vec![0, 3, 4, 4, 3]))
+        );
+        let (size, schedule) = optimal(1 << 25, 27, &excluded(full)).unwrap();
+        assert_eq!((size, &schedule[..]), (9868, &[0, 4, 4, 4, 3][..]));
+
+        // the excluded elements are reported apart, and add up to the full size.
+        let breakdown = ProofSizeBreakdown::new(1 << 25, 8, 27, &schedule, &excluded(full));
+        assert_eq!(breakdown.total_elements(), size);
+        assert_eq!(breakdown.layers[0].total_elements(), 0);
+        assert_eq!(breakdown.excluded.remainder_elements, 0);
+        assert_eq!(
+            size + breakdown.excluded.total_elements(),
+            estimate_proof_size_unchecked(1 << 25, 8, 27, &schedule, &full)
+        );
+        assert!(size + breakdown.excluded.total_elements() > 12932);
+    }
+
+    #[test]
+    fn sent_remainders_need_no_padding_to_a_power_of_two() {
+        let cost_model = CostModel::default();
+        for blowup_factor in [2, 8, 32] {
+            for_each_schedule(1 << 16, blowup_factor, &mut |seq| {
+                let folded = 1 << 16 >> seq.iter().sum::<usize>();
+                let coefficients =
+                    remainder_coefficient_elements(folded, blowup_factor, &cost_model)
+                        / cost_model.extension_degree;
+                assert!(coefficients.is_power_of_two(), "{seq:?}");
+            });
+        }
+    }
+
+    // the optimum of an interpolated remainder is the smallest schedule the queries can
+    // interpolate, which at the default parameters folds one layer more.
+    #[test]
+    fn optimizer_folds_until_the_queries_interpolate() {
+        let sent = CostModel::default();
+        let in_last_layer = CostModel {
+            remainder_strategy: RemainderStrategy::InLastLayer,
+            ..sent
+        };
+        assert_eq!(
+            optimal(1 << 25, 27, &sent),
+            Ok((12622, vec![0, 4, 4, 4, 3]))
+        );
+        let (size, later) = optimal(1 << 25, 27, &in_last_layer).unwrap();
+        assert_eq!(later, [0, 4, 4, 4, 3, 3]);
+        // the later stop leaves a remainder of degree 16, which is not sent.
+        assert_eq!(
+            size,
+            estimate_proof_size_unchecked(1 << 25, 8, 27, &later, &sent) - 16 * 2
+        );
+
+        let mut feasible = Vec::new();
+        for_each_schedule(1 << 16, 8, &mut |seq| {
+            if is_remainder_feasible(1 << 16, 8, 4, seq, &in_last_layer) {
+                let size = estimate_proof_size_unchecked(1 << 16, 8, 4, seq, &in_last_layer);
+                feasible.push((size, seq.to_vec()));
+            }
+        });
+        let smallest = feasible.into_iter().map(|(size, _)| size).min();
+        assert_eq!(
+            optimal(1 << 16, 4, &in_last_layer)
+                .ok()
+                .map(|(size, _)| size),
+            smallest
+        );
+    }
+
+    #[test]
+    fn no_queries_interpolate_no_remainder() {
+        let in_last_layer = CostModel {
+            remainder_strategy: RemainderStrategy::InLastLayer,
+            ..CostModel::default()
+        };
+        assert_eq!(
+            optimal(1 << 16, 0, &in_last_layer),
+            Err(FoldingError::NoInterpolableRemainder { num_queries: 0 })
+        );
+    }
+
+    // over the default domain of 2^25, a first fold by 4 or 16 makes each of the 27 queries
+    // open 4 or 16 values of 2 elements in the initial layer instead of 1, over a path 2 or 4
+    // digests shorter than the 25 of one value per leaf, whichever the coset layout.
+    #[test]
+    fn winterfell_initial_layer_by_hand() {
+        let initial_layer = |seq: &[usize], cost_model: &CostModel| {
+            let layer = &ProofSizeBreakdown::new(1 << 25, 8, 27, seq, cost_model).layers[0];
+            (layer.path_elements, layer.leaf_elements)
+        };
+        let winterfell = CostModel {
+            winterfell_layout: true,
+            ..CostModel::default()
+        };
+        let split = CostModel {
+            coset_layout: CosetLayout::SplitLeaves,
+            ..winterfell
+        };
+        assert_eq!(
+            initial_layer(&[0, 2, 4, 4, 4], &CostModel::default()),
+            (27 * 25 * 4, 27 * 2)
+        );
+        for cost_model in [winterfell, split] {
+            assert_eq!(
+                initial_layer(&[0, 2, 4, 4, 4], &cost_model),
+                (27 * 23 * 4, 27 * 4 * 2)
+            );
+            assert_eq!(
+                initial_layer(&[0, 4, 4, 4], &cost_model),
+                (27 * 21 * 4, 27 * 16 * 2)
+            );
+        }
+    }
+
+    // the initial layer costs more the more the first layer folds, so the optimum folds less
+    // first.
+    #[test]
+    fn winterfell_layout_folds_less_first() {
+        let winterfell = CostModel {
+            winterfell_layout: true,
+            ..CostModel::default()
+        };
+        assert_eq!(
+            optimal(1 << 25, 27, &CostModel::default()),
+            Ok((12622, vec![0, 4, 4, 4, 3]))
+        );
+        assert_eq!(
+            optimal(1 << 25, 27, &winterfell),
+            Ok((12932, vec![0, 3, 4, 4, 3]))
+        );
+
+        // the optimizer prices the first fold with the initial layer it packs.
+        let mut smallest = usize::MAX;
+        for_each_schedule(1 << 14, 8, &mut |seq| {
+            smallest = smallest.min(estimate_proof_size_unchecked(
+                1 << 14,
+                8,
+                64,
+                seq,
+                &winterfell,
+            ));
+        });
+        assert_eq!(optimal(1 << 14, 64, &winterfell).unwrap().0, smallest);
+    }
+
+    #[test]
+    fn packed_leaf_path_stops_at_the_coset() {
+        // a domain of 2^10 in cosets of 4 is a tree of 2^8 leaves, in cosets of 16 one of 2^6.
+        assert_eq!(CosetLayout::PackedLeaf.path_digests(1 << 10, 1), 10);
+        assert_eq!(CosetLayout::PackedLeaf.path_digests(1 << 10, 4), 8);
+        assert_eq!(CosetLayout::PackedLeaf.path_digests(1 << 10, 16), 6);
+        for factor in [1, 2, 4, 8, 16] {
+            assert_eq!(
+                CosetLayout::PackedLeaf.path_digests(1 << 10, factor),
+                CosetLayout::SplitLeaves.path_digests(1 << 10, factor)
+            );
+        }
+    }
+
+    #[test]
+    fn layouts_differ_in_verifier_hashes_only() {
+        // one leaf hash and the path, against every leaf, the coset subtree and the path.
+        assert_eq!(CosetLayout::PackedLeaf.verifier_hashes(1 << 10, 4), 1 + 8);
+        assert_eq!(CosetLayout::PackedLeaf.verifier_hashes(1 << 10, 16), 1 + 6);
+        assert_eq!(
+            CosetLayout::SplitLeaves.verifier_hashes(1 << 10, 4),
+            4 + 3 + 8
+        );
+        assert_eq!(
+            CosetLayout::SplitLeaves.verifier_hashes(1 << 10, 16),
+            16 + 15 + 6
+        );
+    }
+
+    #[test]
+    fn packed_leaf_size_by_hand() {
+        let cost_model = CostModel::default();
+        // layer 0: 3 * 10 * 4 path and 3 * 1 * 2 leaf elements.
+        let initial_layer = 3 * 10 * 4 + 3 * 2;
+
+        // fold by 4: a tree of 2^8 leaves of 4 values, and 2^8 / 4 coefficients left.
+        assert_eq!(
+            estimate_proof_size_unchecked(1 << 10, 4, 3, &[0, 2], &cost_model),
+            initial_layer + (3 * 8 * 4 + 3 * 4 * 2) + 64 * 2
+        );
+        // fold by 16: a tree of 2^6 leaves of 16 values, and 2^6 / 4 coefficients left.
+        assert_eq!(
+            estimate_proof_size_unchecked(1 << 10, 4, 3, &[0, 4], &cost_model),
+            initial_layer + (3 * 6 * 4 + 3 * 16 * 2) + 16 * 2
+        );
+    }
+
+    #[test]
+    fn packed_leaf_size_matches_the_exact_sizer_for_one_query() {
+        // a single query shares no digest, so the heuristic is exact.
+        for seq in [[0, 2], [0, 4]] {
+            for position in [0, 5, 1023] {
+                for domain_layout in [DomainLayout::BitReversed, DomainLayout::Natural] {
+                    let cost_model = CostModel {
+                        domain_layout,
+                        ..CostModel::default()
+                    };
+                    assert_eq!(
+                        exact_proof_size(1 << 10, 4, &[position], &seq, &cost_model),
+                        estimate_proof_size_unchecked(1 << 10, 4, 1, &seq, &cost_model)
+                    );
+                }
+            }
+        }
+    }
+}
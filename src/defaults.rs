@@ -0,0 +1,94 @@
+//! The default cost model constants and presets for common field and hash combinations.
+
+use super::cost_model::{
+    CosetEvalMethod, CosetLayout, CostModel, DomainLayout, Exclusions, InversionBatching,
+    PathModel, QueryValueTransmission, RemainderStrategy, SEMANTICS_VERSION,
+};
+
+/// The number of base field elements in a hash digest by default: a 256-bit digest over a 64-bit
+/// field.
+pub(crate) const ELEMENTS_IN_HASH_OUTPUT: usize = 4;
+
+/// The number of base field elements in each extension field element by default: FRI layers and
+/// the remainder are over a quadratic extension.
+pub(crate) const FE_IN_EACH_ELEMENTS: usize = 2;
+
+/// The size of a base field element in bytes by default: a 64-bit field.
+pub(crate) const ELEMENT_SIZE: usize = 8;
+
+/// The 64-bit Goldilocks field (`p = 2^64 - 2^32 + 1`) with FRI layers over its quadratic
+/// extension, committed with a 256-bit hash (e.g. Blake3 or Rescue-Prime) whose digest is 4 field
+/// elements. This is the configuration the estimator has always assumed.
+pub const GOLDILOCKS_QUADRATIC: CostModel = CostModel {
+    coset_layout: CosetLayout::PackedLeaf,
+    path_model: PathModel::Naive,
+    domain_layout: DomainLayout::BitReversed,
+    query_values: QueryValueTransmission::AllValues,
+    remainder_strategy: RemainderStrategy::Sent,
+    coset_eval: CosetEvalMethod::Barycentric,
+    inversion_batching: InversionBatching::Unbatched,
+    first_layer_shares_trace_commitment: false,
+    initial_layer_columns: None,
+    winterfell_layout: false,
+    exclude: Exclusions {
+        layer0_paths: false,
+        layer0_leaves: false,
+        remainder: false,
+    },
+    remainder_verify_cost_per_element: 0,
+    digest_elements: ELEMENTS_IN_HASH_OUTPUT,
+    extension_degree: FE_IN_EACH_ELEMENTS,
+    element_bytes: ELEMENT_SIZE,
+    semantics_version: SEMANTICS_VERSION,
+};
+
+/// The 31-bit BabyBear field (`p = 15 * 2^27 + 1`), stored in 4 bytes, with FRI layers over its
+/// quartic extension, committed with Poseidon2 over BabyBear whose digest is 8 field elements.
+pub const BABYBEAR_QUARTIC: CostModel = CostModel {
+    coset_layout: CosetLayout::PackedLeaf,
+    path_model: PathModel::Naive,
+    domain_layout: DomainLayout::BitReversed,
+    query_values: QueryValueTransmission::AllValues,
+    remainder_strategy: RemainderStrategy::Sent,
+    coset_eval: CosetEvalMethod::Barycentric,
+    inversion_batching: InversionBatching::Unbatched,
+    first_layer_shares_trace_commitment: false,
+    initial_layer_columns: None,
+    winterfell_layout: false,
+    exclude: Exclusions {
+        layer0_paths: false,
+        layer0_leaves: false,
+        remainder: false,
+    },
+    remainder_verify_cost_per_element: 0,
+    digest_elements: 8,
+    extension_degree: 4,
+    element_bytes: 4,
+    semantics_version: SEMANTICS_VERSION,
+};
+
+/// The 254-bit BN254 scalar field, stored in 32 bytes, committed with Poseidon over the same field
+/// whose digest is a single field element. The field is large enough that no extension is needed,
+/// which is the usual setup for FRI proofs verified inside a SNARK.
+pub const POSEIDON_256: CostModel = CostModel {
+    coset_layout: CosetLayout::PackedLeaf,
+    path_model: PathModel::Naive,
+    domain_layout: DomainLayout::BitReversed,
+    query_values: QueryValueTransmission::AllValues,
+    remainder_strategy: RemainderStrategy::Sent,
+    coset_eval: CosetEvalMethod::Barycentric,
+    inversion_batching: InversionBatching::Unbatched,
+    first_layer_shares_trace_commitment: false,
+    initial_layer_columns: None,
+    winterfell_layout: false,
+    exclude: Exclusions {
+        layer0_paths: false,
+        layer0_leaves: false,
+        remainder: false,
+    },
+    remainder_verify_cost_per_element: 0,
+    digest_elements: 1,
+    extension_degree: 1,
+    element_bytes: 32,
+    semantics_version: SEMANTICS_VERSION,
+};
@@ -0,0 +1,315 @@
+use std::fmt;
+
+use super::cost_model::{CostModelViolation, RETIRED_SEMANTICS_VERSIONS, SEMANTICS_VERSION};
+use super::optimized_schedule::CapNotice;
+
+/// Errors returned when a folding strategy cannot be computed for the requested constraints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FoldingError {
+    /// A parameter that must be a power of 2 is not.
+    NotPowerOfTwo {
+        parameter: &'static str,
+        value: usize,
+    },
+    /// The degree is smaller than the blowup factor, leaving no polynomial to prove.
+    DegreeBelowBlowup { degree: usize, blowup_factor: usize },
+    /// A pinned layer disagrees with the folding sequence it is meant to extend.
+    PinConflict {
+        layer: usize,
+        bits: usize,
+        expected: usize,
+    },
+    /// A layer after the first is pinned to a folding factor of 1.
+    TrivialFold { layer: usize },
+    /// The pinned layers fold the polynomial further than its degree allows.
+    OverFolding {
+        layer: usize,
+        bits: usize,
+        available_bits: usize,
+    },
+    /// A folding schedule does not start with the unfolded initial layer.
+    MissingInitialLayer,
+    /// A folding schedule contains an unfolded layer after a folded one.
+    UnfoldedInteriorLayer { layer: usize },
+    /// A folding schedule folds the polynomial further than its degree allows.
+    ScheduleOverFolding {
+        folded_bits: usize,
+        available_bits: usize,
+    },
+    /// An entry of a folding schedule written down as text is not a number.
+    InvalidScheduleEntry { entry: String },
+    /// No folding sequence fits within the prover memory budget.
+    ProverMemoryExceeded { max_prover_bytes: usize },
+    /// No folding sequence fits within the verifier memory budget.
+    VerifierMemoryExceeded { max_verifier_bytes: usize },
+    /// The minimum folding factor is trivial or above the largest factor the optimizer considers.
+    InvalidMinFoldingFactor {
+        min_folding_bits: usize,
+        max_folding_bits: usize,
+    },
+    /// The maximum folding factor is trivial.
+    InvalidMaxFoldingFactor,
+    /// No folding sequence lands exactly on the target remainder degree: it is not a power of 2
+    /// no larger than the degree of the polynomial.
+    UnreachableRemainderDegree {
+        target_remainder_degree: usize,
+        poly_degree: usize,
+    },
+    /// Even the smallest proof is larger than the target size.
+    TargetSizeUnreachable { target_bytes: usize },
+    /// No blowup factor and folding sequence fit within the verifier gas budget.
+    GasBudgetExceeded { gas_budget: usize },
+    /// A cost model was written against accounting rules the estimator no longer implements.
+    UnsupportedSemanticsVersion { version: u32 },
+    /// A cost model breaks consistency rules, see
+    /// [`CostModel::validate`](super::cost_model::CostModel::validate).
+    InconsistentCostModel { violations: Vec<CostModelViolation> },
+    /// The remainder a folding schedule leaves has more coefficients than the verifier can
+    /// interpolate from the queries.
+    RemainderNotInterpolable {
+        remainder_degree: usize,
+        num_queries: usize,
+    },
+    /// No folding schedule the search considers leaves a remainder the verifier can interpolate
+    /// from the queries.
+    NoInterpolableRemainder { num_queries: usize },
+    /// The security target is reached without any queries.
+    NoQueriesNeeded,
+    /// A degree group's domain is larger than the degree or smaller than the blowup factor.
+    DegreeGroupOutOfRange {
+        domain_size: usize,
+        blowup_factor: usize,
+        degree: usize,
+    },
+    /// The query tiers are empty, contain an empty tier, or do not add up to the number of
+    /// queries.
+    QueryTiersMismatch {
+        tiers_total: usize,
+        num_queries: usize,
+    },
+    /// A query plan does not give one query count per layer of the folding schedule.
+    QueryPlanLengthMismatch {
+        plan_layers: usize,
+        schedule_layers: usize,
+    },
+    /// A query plan queries a layer more often than the layer before it, whose queries are the
+    /// only ones a layer can continue.
+    IncreasingQueryPlan { layer: usize },
+    /// No extension of the base field up to the largest one considered keeps the folding
+    /// challenges from eroding security by more than the tolerated bits.
+    FieldErosionUnreachable { max_extension_degree: usize },
+    /// A STARK is described by an empty list of trace commitment rounds.
+    NoCommitmentRounds,
+    /// An estimate, such as the prover memory, does not fit in 64 bits.
+    ArithmeticOverflow { quantity: &'static str },
+    /// The `Debug` print of Winterfell proof options lacks required fields.
+    #[cfg(feature = "winterfell")]
+    IncompleteWinterfellOptions { missing: Vec<&'static str> },
+}
+
+impl fmt::Display for FoldingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FoldingError::NotPowerOfTwo { parameter, value } => {
+                write!(f, "the {parameter} {value} is not a power of 2")
+            }
+            FoldingError::DegreeBelowBlowup {
+                degree,
+                blowup_factor,
+            } => write!(
+                f,
+                "the degree {degree} is smaller than the blowup factor {blowup_factor}"
+            ),
+            FoldingError::PinConflict {
+                layer,
+                bits,
+                expected,
+            } => write!(
+                f,
+                "layer {layer} is pinned to {bits} folding bits but the folding sequence already \
+                 uses {expected}"
+            ),
+            FoldingError::TrivialFold { layer } => {
+                write!(f, "layer {layer} is pinned to a folding factor of 1")
+            }
+            FoldingError::OverFolding {
+                layer,
+                bits,
+                available_bits,
+            } => write!(
+                f,
+                "pinning layer {layer} to {bits} folding bits folds more than the {available_bits} \
+                 bits the polynomial degree allows"
+            ),
+            FoldingError::MissingInitialLayer => {
+                write!(f, "the folding schedule must start with an unfolded layer")
+            }
+            FoldingError::UnfoldedInteriorLayer { layer } => write!(
+                f,
+                "layer {layer} does not fold, but only the leading layers may skip folding"
+            ),
+            FoldingError::ScheduleOverFolding {
+                folded_bits,
+                available_bits,
+            } => write!(
+                f,
+                "the folding schedule folds {folded_bits} bits but the polynomial degree only \
+                 allows {available_bits}"
+            ),
+            FoldingError::InvalidScheduleEntry { entry } => {
+                write!(f, "the folding schedule entry `{entry}` is not a number")
+            }
+            FoldingError::ProverMemoryExceeded { max_prover_bytes } => write!(
+                f,
+                "no folding schedule fits within {max_prover_bytes} bytes of prover memory"
+            ),
+            FoldingError::VerifierMemoryExceeded { max_verifier_bytes } => write!(
+                f,
+                "no folding schedule fits within {max_verifier_bytes} bytes of verifier memory"
+            ),
+            FoldingError::InvalidMinFoldingFactor {
+                min_folding_bits,
+                max_folding_bits,
+            } => write!(
+                f,
+                "the minimum folding factor of {min_folding_bits} bits is not between 1 and \
+                 {max_folding_bits} bits"
+            ),
+            FoldingError::InvalidMaxFoldingFactor => {
+                write!(f, "the maximum folding factor must be at least 1 bit")
+            }
+            FoldingError::UnreachableRemainderDegree {
+                target_remainder_degree,
+                poly_degree,
+            } => write!(
+                f,
+                "no folding schedule folds a polynomial of degree {poly_degree} to a remainder of \
+                 degree {target_remainder_degree}"
+            ),
+            FoldingError::TargetSizeUnreachable { target_bytes } => {
+                write!(f, "no folding schedule fits within {target_bytes} bytes")
+            }
+            FoldingError::GasBudgetExceeded { gas_budget } => write!(
+                f,
+                "no blowup factor and folding schedule fit within {gas_budget} gas of verification"
+            ),
+            FoldingError::UnsupportedSemanticsVersion { version } => {
+                write!(f, "semantics version {version} no longer supported")?;
+                match RETIRED_SEMANTICS_VERSIONS
+                    .iter()
+                    .find(|(v, _)| v == version)
+                {
+                    Some((_, change)) => write!(
+                        f,
+                        ": {change}; rerun it under version {SEMANTICS_VERSION} to migrate"
+                    ),
+                    None => Ok(()),
+                }
+            }
+            FoldingError::InconsistentCostModel { violations } => {
+                let violations: Vec<_> = violations.iter().map(ToString::to_string).collect();
+                write!(f, "inconsistent cost model: {}", violations.join("; "))
+            }
+            FoldingError::RemainderNotInterpolable {
+                remainder_degree,
+                num_queries,
+            } => write!(
+                f,
+                "a remainder of degree {remainder_degree} cannot be interpolated from the last \
+                 layer with {num_queries} queries"
+            ),
+            FoldingError::NoInterpolableRemainder { num_queries } => write!(
+                f,
+                "no folding schedule leaves a remainder that can be interpolated from the last \
+                 layer with {num_queries} queries"
+            ),
+            FoldingError::NoQueriesNeeded => {
+                write!(f, "the security target is reached without any queries")
+            }
+            FoldingError::DegreeGroupOutOfRange {
+                domain_size,
+                blowup_factor,
+                degree,
+            } => write!(
+                f,
+                "a degree group over a domain of size {domain_size} must lie between the blowup \
+                 factor {blowup_factor} and the degree {degree}"
+            ),
+            FoldingError::QueryTiersMismatch {
+                tiers_total,
+                num_queries,
+            } => write!(
+                f,
+                "the query tiers must be non-empty and add up to the {num_queries} queries, not \
+                 {tiers_total}"
+            ),
+            FoldingError::QueryPlanLengthMismatch {
+                plan_layers,
+                schedule_layers,
+            } => write!(
+                f,
+                "the query plan gives {plan_layers} query counts for a folding schedule of \
+                 {schedule_layers} layers"
+            ),
+            FoldingError::IncreasingQueryPlan { layer } => write!(
+                f,
+                "the query plan queries layer {layer} more often than the layer before it"
+            ),
+            FoldingError::FieldErosionUnreachable {
+                max_extension_degree,
+            } => write!(
+                f,
+                "no extension of degree up to {max_extension_degree} keeps the folding challenges \
+                 within the tolerated erosion of security"
+            ),
+            FoldingError::NoCommitmentRounds => {
+                write!(f, "the trace must be committed in at least one round")
+            }
+            FoldingError::ArithmeticOverflow { quantity } => {
+                write!(f, "the {quantity} does not fit in 64 bits")
+            }
+            #[cfg(feature = "winterfell")]
+            FoldingError::IncompleteWinterfellOptions { missing } => {
+                let (last, rest) = missing.split_last().expect("at least one field is missing");
+                write!(f, "the Winterfell proof options lack ")?;
+                if !rest.is_empty() {
+                    write!(f, "{} and ", rest.join(", "))?;
+                }
+                write!(f, "{last}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FoldingError {}
+
+/// A finding that does not keep a folding strategy from being computed, but that its user should
+/// know about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// The cost model breaks a consistency rule, see
+    /// [`CostModel::validate`](super::cost_model::CostModel::validate).
+    InconsistentCostModel(CostModelViolation),
+    /// The cap on the folding factor keeps the optimizer from a cheaper proof.
+    BindingFoldingCap(CapNotice),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::InconsistentCostModel(violation) => {
+                write!(f, "inconsistent cost model: {violation}")
+            }
+            Warning::BindingFoldingCap(notice) => write!(
+                f,
+                "the folding factor cap of {} binds at layers {:?}: raising it to {} lowers the \
+                 cost by {} with {:?}",
+                1usize << notice.cap_bits,
+                notice.layers,
+                1usize << (notice.cap_bits + 1),
+                notice.saved_cost,
+                notice.raised_schedule
+            ),
+        }
+    }
+}
@@ -0,0 +1,560 @@
+//! Exact proof sizes for concrete query positions, used to measure how far the heuristic of
+//! [`estimate_proof_size`](super::optimized_schedule::estimate_proof_size) is from the size of a
+//! real proof.
+
+use std::collections::BTreeSet;
+
+use super::cost_model::{CosetLayout, CostModel, DomainLayout, QueryValueTransmission};
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::remainder_elements;
+
+/// Computes the exact size of a FRI proof, in field elements, for the given query positions in
+/// the initial domain. Unlike
+/// [`estimate_proof_size`](super::optimized_schedule::estimate_proof_size), nothing is sent
+/// twice: queries landing in the same coset open it once, and a Merkle node is sent only if the
+/// verifier cannot compute it from the opened cosets and the other nodes sent.
+///
+/// A query at position `p` opens, in a layer over a domain of size `n` folded by `factor`, the
+/// coset `c = p mod (n / factor)`. Where its values sit in the layer's tree depends on the cost
+/// model's [`DomainLayout`]:
+/// * In bit-reversed order the coset is one packed leaf, or one subtree of split leaves whose
+///   nodes the verifier computes from the opened values, at index `bitrev(c)` of a tree over
+///   `n / factor` leaves.
+/// * In natural order a packed coset is the leaf at index `c` of a tree over `n / factor` leaves,
+///   while split coset values are the `factor` leaves `c + j * n / factor` of a tree over `n`
+///   leaves, each needing its own path.
+///
+/// Values the verifier derives from the previous layer are not counted when the cost model
+/// omits them, nor are the components it excludes. Merkle roots are not counted.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `positions` - The query positions in the initial domain
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the size of digests and elements
+///
+/// # Returns
+/// * `num_elements` - The exact number of elements in the proof
+pub fn exact_proof_size(
+    degree: usize,
+    blowup_factor: usize,
+    positions: &[usize],
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    let mut num_elements = 0;
+    let remainder = walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // the leaves group the values of a coset, which is the layer's own unless the initial
+            // layer is laid out for the first fold.
+            let factor = layer.leaf_factor();
+            let num_cosets = layer.domain_size / factor;
+
+            // an excluded initial layer is opened outside of the proof.
+            let cosets: BTreeSet<_> = positions.iter().map(|p| p % num_cosets).collect();
+            if layer.counts_leaves {
+                let mut values = cosets.len() * factor;
+                if cost_model.query_values == QueryValueTransmission::OmitFoldedValue
+                    && layer.layer > 0
+                {
+                    // the values at the queried positions were folded from the previous layer.
+                    let queried: BTreeSet<_> =
+                        positions.iter().map(|p| p % layer.domain_size).collect();
+                    values -= queried.len();
+                }
+                num_elements += values * layer.value_elements;
+            }
+            if layer.counts_paths {
+                let coset_bits = num_cosets.ilog2();
+                let (leaves, depth) = match (cost_model.domain_layout, cost_model.coset_layout) {
+                    (DomainLayout::BitReversed, _) => (
+                        cosets.iter().map(|&c| bit_reverse(c, coset_bits)).collect(),
+                        coset_bits,
+                    ),
+                    (DomainLayout::Natural, CosetLayout::PackedLeaf) => (cosets, coset_bits),
+                    (DomainLayout::Natural, CosetLayout::SplitLeaves) => (
+                        cosets
+                            .iter()
+                            .flat_map(|&c| (0..factor).map(move |j| c + j * num_cosets))
+                            .collect(),
+                        layer.tree_leaves.ilog2(),
+                    ),
+                };
+                num_elements +=
+                    sibling_digests(leaves, depth as usize) * cost_model.digest_elements;
+            }
+        },
+    );
+
+    if !cost_model.exclude.remainder {
+        num_elements += remainder_elements(remainder.domain_size, blowup_factor, cost_model);
+    }
+    num_elements
+}
+
+/// Returns the number of digests needed to authenticate the given leaves of a Merkle tree of the
+/// given depth: on every level, a node is sent when it is the sibling of a known node and not
+/// known itself.
+fn sibling_digests(leaves: BTreeSet<usize>, depth: usize) -> usize {
+    let mut known = leaves;
+    let mut digests = 0;
+    for _ in 0..depth {
+        digests += known
+            .iter()
+            .filter(|&&node| !known.contains(&(node ^ 1)))
+            .count();
+        known = known.iter().map(|node| node / 2).collect();
+    }
+    digests
+}
+
+/// Reverses the lowest `bits` bits of `index`.
+fn bit_reverse(index: usize, bits: u32) -> usize {
+    if bits == 0 {
+        return 0;
+    }
+    index.reverse_bits() >> (usize::BITS - bits)
+}
+
+/// The number of leaves up to which the expectations of [`expected_sibling_digests`] and
+/// [`sibling_digest_bracket`] are computed in exact arithmetic by default.
+#[cfg(feature = "testing")]
+pub const EXACT_EXPECTATION_MAX_LEAVES: usize = 1 << 16;
+
+/// Computes the expected number of digests needed to authenticate `num_queries` leaves drawn
+/// uniformly and independently from a Merkle tree of the given depth, as the exact sizer counts
+/// them for every set of positions. A node is sent when no query falls below it but one falls
+/// below its sibling, which for a subtree of `s` of the `n` leaves happens with probability
+/// `(1 - s/n)^q - (1 - 2s/n)^q`, the same for the `n / s` nodes of a level.
+///
+/// This is the ground truth any analytic model of shared authentication paths under random
+/// queries is checked against. Floating point loses the difference of both powers where it
+/// matters most, in small trees opened by many queries, so up to
+/// [`EXACT_EXPECTATION_MAX_LEAVES`] leaves the expectation is summed exactly, every term being a
+/// fraction over a power of 2, and only the sum is converted to a float.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::exact_size::expected_sibling_digests;
+///
+/// // 2 queries of a tree of 2 leaves need the other leaf's digest when they land on the same one.
+/// assert_eq!(expected_sibling_digests(1, 2), 0.5);
+/// ```
+///
+/// # Arguments
+/// * `depth` - The depth of the tree, which has `2^depth` leaves
+/// * `num_queries` - The number of uniformly random leaves opened, possibly repeated
+#[cfg(feature = "testing")]
+pub fn expected_sibling_digests(depth: usize, num_queries: usize) -> f64 {
+    match sibling_digest_expectation(depth, num_queries, EXACT_EXPECTATION_MAX_LEAVES) {
+        Expectation::Exact(dyadic) => dyadic.to_f64(),
+        Expectation::Approximate(digests) => digests,
+    }
+}
+
+/// The number of digests authenticating the queries of one tree, see [`sibling_digest_bracket`]:
+/// the expectation under random queries between the floor and the ceiling of any set of queries.
+#[cfg(feature = "testing")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SiblingDigestBracket {
+    /// The digests of queries sharing their paths as much as possible, as
+    /// [`PathModel::IdealCompression`](super::cost_model::PathModel::IdealCompression) charges.
+    pub lower: usize,
+    /// The expected digests under uniformly random queries, rounded up.
+    pub expected: usize,
+    /// The digests of queries sharing nothing, as
+    /// [`PathModel::Naive`](super::cost_model::PathModel::Naive) charges.
+    pub upper: usize,
+}
+
+/// Brackets the expected number of digests authenticating `num_queries` random leaves of a tree
+/// of the given depth, see [`expected_sibling_digests`], between the digests of the queries
+/// sharing their paths as much and as little as possible. The expectation is rounded up, so that
+/// the reported count is conservative.
+///
+/// Up to `exact_max_leaves` leaves the expectation is exact before it is rounded up, so no
+/// rounding error can push it past either end of the bracket `lower <= expected <= upper`. Above
+/// it the expectation is a float, and a rounding error past either end is clamped back.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::exact_size::{sibling_digest_bracket, EXACT_EXPECTATION_MAX_LEAVES};
+///
+/// // a tree of 2 leaves opened by 2 queries: one digest whenever both land on the same leaf.
+/// let bracket = sibling_digest_bracket(1, 2, EXACT_EXPECTATION_MAX_LEAVES);
+/// assert_eq!((bracket.lower, bracket.expected, bracket.upper), (0, 1, 2));
+/// ```
+///
+/// # Arguments
+/// * `depth` - The depth of the tree, which has `2^depth` leaves
+/// * `num_queries` - The number of uniformly random leaves opened, possibly repeated
+/// * `exact_max_leaves` - The number of leaves up to which the expectation is computed exactly,
+///   e.g. [`EXACT_EXPECTATION_MAX_LEAVES`]
+///
+/// # Returns
+/// * `bracket` - The least, expected and most digests
+#[cfg(feature = "testing")]
+pub fn sibling_digest_bracket(
+    depth: usize,
+    num_queries: usize,
+    exact_max_leaves: usize,
+) -> SiblingDigestBracket {
+    use super::cost_model::PathModel;
+
+    let lower = PathModel::IdealCompression.path_digests(depth, num_queries);
+    let upper = PathModel::Naive.path_digests(depth, num_queries);
+    let expected = match sibling_digest_expectation(depth, num_queries, exact_max_leaves) {
+        Expectation::Exact(dyadic) => dyadic.ceil(),
+        Expectation::Approximate(digests) => (digests.ceil() as usize).clamp(lower, upper),
+    };
+    SiblingDigestBracket {
+        lower,
+        expected,
+        upper,
+    }
+}
+
+/// An expectation computed exactly or in floating point.
+#[cfg(feature = "testing")]
+enum Expectation {
+    Exact(Dyadic),
+    Approximate(f64),
+}
+
+/// Computes the expectation of [`expected_sibling_digests`], exactly up to `exact_max_leaves`
+/// leaves. With `m = 2^(depth - level)` nodes on a level, the level contributes
+/// `m * ((m - 1)^q - (m - 2)^q) / m^q`, a fraction over `2^((depth - level) * (q - 1))`, so every
+/// level is brought to the denominator of the leaves' level, `2^(depth * (q - 1))`.
+#[cfg(feature = "testing")]
+fn sibling_digest_expectation(
+    depth: usize,
+    num_queries: usize,
+    exact_max_leaves: usize,
+) -> Expectation {
+    if num_queries == 0 {
+        return Expectation::Approximate(0.0);
+    }
+    if 1usize
+        .checked_shl(depth as u32)
+        .is_none_or(|leaves| leaves > exact_max_leaves)
+    {
+        let mut digests = 0.0;
+        for level in 0..depth {
+            let nodes = (1usize << (depth - level)) as f64;
+            let missed = (1.0 - nodes.recip()).powi(num_queries as i32);
+            let both_missed = (1.0 - 2.0 * nodes.recip()).powi(num_queries as i32);
+            digests += nodes * (missed - both_missed);
+        }
+        return Expectation::Approximate(digests);
+    }
+
+    let mut numerator = Vec::new();
+    for level in 0..depth {
+        let nodes = 1u64 << (depth - level);
+        let mut term = power(nodes - 1, num_queries);
+        sub_assign(&mut term, &power(nodes - 2, num_queries));
+        add_shifted(&mut numerator, &term, level * (num_queries - 1));
+    }
+    Expectation::Exact(Dyadic {
+        numerator,
+        shift: depth * (num_queries - 1),
+    })
+}
+
+/// A non-negative fraction over a power of 2.
+#[cfg(feature = "testing")]
+struct Dyadic {
+    /// The numerator, in little-endian 64-bit limbs.
+    numerator: Vec<u64>,
+    /// The exponent of the denominator.
+    shift: usize,
+}
+
+#[cfg(feature = "testing")]
+impl Dyadic {
+    /// Returns the smallest integer no smaller than the fraction.
+    fn ceil(&self) -> usize {
+        let bit = |index: usize| {
+            self.numerator
+                .get(index / 64)
+                .is_some_and(|limb| limb >> (index % 64) & 1 == 1)
+        };
+        let fraction = (0..self.shift.min(64 * self.numerator.len())).any(bit);
+        let integer = (0..usize::BITS as usize)
+            .filter(|&i| bit(self.shift + i))
+            .fold(0usize, |integer, i| integer | 1 << i);
+        integer + fraction as usize
+    }
+
+    /// Returns the fraction as a float, from the top 128 bits of its numerator.
+    fn to_f64(&self) -> f64 {
+        let Some(top) = self.numerator.iter().rposition(|&limb| limb != 0) else {
+            return 0.0;
+        };
+        let below = top.checked_sub(1).map_or(0, |i| self.numerator[i]);
+        let leading = (self.numerator[top] as u128) << 64 | below as u128;
+        // scaled in two steps, so that a tiny fraction does not underflow on the way.
+        let exponent = 64 * top as i64 - 64 - self.shift as i64;
+        let half = 2f64.powi((exponent / 2) as i32);
+        leading as f64 * half * half * 2f64.powi((exponent % 2) as i32)
+    }
+}
+
+/// Returns `base^exponent` in little-endian 64-bit limbs.
+#[cfg(feature = "testing")]
+fn power(base: u64, exponent: usize) -> Vec<u64> {
+    let mut limbs = vec![1];
+    for _ in 0..exponent {
+        let mut carry = 0;
+        for limb in limbs.iter_mut() {
+            let product = *limb as u128 * base as u128 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+        if carry > 0 {
+            limbs.push(carry as u64);
+        }
+    }
+    limbs
+}
+
+/// Subtracts `other` from `limbs`, which is no smaller.
+#[cfg(feature = "testing")]
+fn sub_assign(limbs: &mut [u64], other: &[u64]) {
+    let mut borrow = false;
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let (difference, overflow) = limb.overflowing_sub(other.get(i).copied().unwrap_or(0));
+        let (difference, borrowed) = difference.overflowing_sub(borrow as u64);
+        *limb = difference;
+        borrow = overflow || borrowed;
+    }
+    debug_assert!(!borrow, "the subtrahend must not exceed the minuend");
+}
+
+/// Adds `limbs * 2^shift` to `sum`.
+#[cfg(feature = "testing")]
+fn add_shifted(sum: &mut Vec<u64>, limbs: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    let needed = words + limbs.len() + 2;
+    if sum.len() < needed {
+        sum.resize(needed, 0);
+    }
+    let mut carry = 0u128;
+    for (i, slot) in sum.iter_mut().enumerate().skip(words) {
+        let index = i - words;
+        let low = limbs.get(index).copied().unwrap_or(0);
+        let high = index
+            .checked_sub(1)
+            .and_then(|j| limbs.get(j))
+            .copied()
+            .unwrap_or(0);
+        let shifted = if bits == 0 {
+            low
+        } else {
+            low << bits | high >> (64 - bits)
+        };
+        let total = *slot as u128 + shifted as u128 + carry;
+        *slot = total as u64;
+        carry = total >> 64;
+    }
+}
+
+/// Compares the heuristic of
+/// [`estimate_proof_size`](super::optimized_schedule::estimate_proof_size) against the exact size
+/// of proofs with uniformly random query positions.
+///
+/// Under [`PathModel::Naive`](super::cost_model::PathModel::Naive) the heuristic charges every
+/// query in full, so it never undershoots the exact size; how much it overshoots depends on how
+/// often queries collide in the deeper, narrower layers. Under ideal path compression it is a
+/// lower bound instead, and the ratios fall below 1.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price the proof
+/// * `trials` - The number of random sets of query positions to size exactly
+/// * `seed` - The seed of the query positions, so that results are reproducible
+///
+/// # Returns
+/// * `mean_ratio` - The heuristic estimate divided by the mean exact size
+/// * `worst_ratio` - The largest ratio of the heuristic estimate to a single exact size
+///
+/// # Panics
+/// * If `trials` is 0
+#[cfg(feature = "sim")]
+pub fn heuristic_error_bound(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+    trials: usize,
+    seed: u64,
+) -> (f64, f64) {
+    assert!(trials > 0, "at least one trial is needed");
+    let estimate = super::optimized_schedule::estimate_proof_size_unchecked(
+        degree,
+        blowup_factor,
+        num_queries,
+        folding_seq,
+        cost_model,
+    ) as f64;
+
+    let mut state = seed;
+    let mut total = 0.0;
+    let mut worst_ratio: f64 = 0.0;
+    for _ in 0..trials {
+        let positions: Vec<_> = (0..num_queries)
+            .map(|_| (split_mix(&mut state) % degree as u64) as usize)
+            .collect();
+        let exact =
+            exact_proof_size(degree, blowup_factor, &positions, folding_seq, cost_model) as f64;
+        total += exact;
+        worst_ratio = worst_ratio.max(estimate / exact);
+    }
+
+    (estimate / (total / trials as f64), worst_ratio)
+}
+
+/// Advances a SplitMix64 generator and returns its next output.
+#[cfg(feature = "sim")]
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::cost_model::Exclusions;
+
+    /// Returns the exact number of digests authenticating the positions of a tree of the given
+    /// depth, one element per digest.
+    fn digests(depth: usize, positions: &[usize]) -> f64 {
+        // only the paths of the unfolded layer count.
+        let paths_only = CostModel {
+            digest_elements: 1,
+            exclude: Exclusions {
+                layer0_paths: false,
+                layer0_leaves: true,
+                remainder: true,
+            },
+            ..CostModel::default()
+        };
+        exact_proof_size(1 << depth, 1, positions, &[0], &paths_only) as f64
+    }
+
+    #[test]
+    fn expectation_is_the_mean_over_every_tuple_of_positions() {
+        for depth in 1..=4 {
+            for num_queries in 1..=3 {
+                let n = 1usize << depth;
+                let tuples = n.pow(num_queries as u32);
+                let total: f64 = (0..tuples)
+                    .map(|tuple| {
+                        let positions: Vec<_> = (0..num_queries)
+                            .map(|i| tuple / n.pow(i as u32) % n)
+                            .collect();
+                        digests(depth, &positions)
+                    })
+                    .sum();
+                let expected = expected_sibling_digests(depth, num_queries);
+                assert!(
+                    (total / tuples as f64 - expected).abs() < 1e-9,
+                    "depth {depth}, {num_queries} queries"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn expectation_is_the_mean_over_seeded_random_positions() {
+        let mut state = 1u64;
+        for (depth, num_queries) in [(6, 4), (8, 27), (10, 27), (12, 27), (12, 100)] {
+            let trials = 2000;
+            let total: f64 = (0..trials)
+                .map(|_| {
+                    let positions: Vec<_> = (0..num_queries)
+                        .map(|_| {
+                            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                            (state >> 33) as usize % (1 << depth)
+                        })
+                        .collect();
+                    digests(depth, &positions)
+                })
+                .sum();
+            let expected = expected_sibling_digests(depth, num_queries);
+            assert!(
+                (total / trials as f64 / expected - 1.0).abs() < 0.01,
+                "depth {depth}, {num_queries} queries"
+            );
+        }
+    }
+
+    #[test]
+    fn exact_and_float_expectations_agree_where_floats_are_accurate() {
+        for (depth, num_queries) in [(4, 2), (8, 27), (12, 27), (16, 100)] {
+            let exact =
+                sibling_digest_expectation(depth, num_queries, EXACT_EXPECTATION_MAX_LEAVES);
+            let float = sibling_digest_expectation(depth, num_queries, 0);
+            let (Expectation::Exact(exact), Expectation::Approximate(float)) = (exact, float)
+            else {
+                panic!("depth {depth} must be computed exactly, and in floating point without it");
+            };
+            assert!((exact.to_f64() / float - 1.0).abs() < 1e-9);
+        }
+        assert_eq!(expected_sibling_digests(10, 0), 0.0);
+        // a single leaf has no siblings.
+        assert_eq!(expected_sibling_digests(0, 27), 0.0);
+    }
+
+    #[test]
+    fn bracket_holds_exactly_on_small_trees() {
+        // however many queries open them.
+        for depth in 0..=10 {
+            for num_queries in (0..=64).chain([100, 1000]) {
+                let bracket =
+                    sibling_digest_bracket(depth, num_queries, EXACT_EXPECTATION_MAX_LEAVES);
+                assert!(bracket.lower <= bracket.expected && bracket.expected <= bracket.upper);
+                let expected = expected_sibling_digests(depth, num_queries);
+                assert!(
+                    expected <= bracket.expected as f64 && bracket.expected as f64 - 1.0 < expected,
+                    "depth {depth}, {num_queries} queries"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn float_bracket_is_clamped() {
+        for depth in [0, 1, 4, 12, 20] {
+            for num_queries in [0, 1, 27, 1000] {
+                let bracket = sibling_digest_bracket(depth, num_queries, 0);
+                assert!(bracket.lower <= bracket.expected && bracket.expected <= bracket.upper);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_expectation_keeps_vanishing_probabilities() {
+        // 5000 queries all land on one of 2 leaves with probability 2^-4999, which a float loses
+        // and the exact expectation keeps, rounded up.
+        assert_eq!(
+            sibling_digest_bracket(1, 5000, EXACT_EXPECTATION_MAX_LEAVES).expected,
+            1
+        );
+        assert_eq!(sibling_digest_bracket(1, 5000, 0).expected, 0);
+
+        // both paths agree where floating point is accurate.
+        let exact = sibling_digest_bracket(12, 27, EXACT_EXPECTATION_MAX_LEAVES);
+        assert_eq!(sibling_digest_bracket(12, 27, 0), exact);
+    }
+}
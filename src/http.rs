@@ -0,0 +1,274 @@
+//! The `--serve <address>` mode: the requests of the `stdio` module answered over HTTP, so that
+//! several tools can share one long-running process instead of starting one per request.
+//!
+//! * `POST /optimize`, `POST /estimate` and `POST /sweep` take the JSON request of the `stdio`
+//!   module for the command the path names, which the body may leave out. They answer with
+//!   `200 OK` and the same response, or `400 Bad Request` and its error response when the request
+//!   is malformed or cannot be served.
+//! * `GET /healthz` answers with `200 OK` and `{"status":"ok"}`.
+//!
+//! Any other path is answered with `404 Not Found`, another method with
+//! `405 Method Not Allowed`, a request line and headers over [`MAX_HEADER_BYTES`] with
+//! `431 Request Header Fields Too Large`, and a body over [`MAX_BODY_BYTES`] with
+//! `413 Content Too Large`, each with an error response. The command-line flags other than
+//! `--serve` do not apply: every request names its own parameters and preset.
+//!
+//! Connections are served by a fixed pool of worker threads, one per available core, and closed
+//! after one response, so requests run in parallel while a flood of connections waits in a bounded
+//! queue instead of spawning threads without end. A connection has [`READ_TIMEOUT`] in all to
+//! send its request. The workers share the warm schedule cache of the `stdio` module, which
+//! serializes access to it, so a schedule optimized for one request answers the next ones asking
+//! for it.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::stdio;
+
+/// The largest request body served, in bytes.
+pub(crate) const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// The largest request line and headers served, in bytes.
+pub(crate) const MAX_HEADER_BYTES: usize = 8 << 10;
+
+/// How long a connection may take to send its whole request before it is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a connection may take to send what is left of a request that was answered early.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How many accepted connections may wait for a worker, per worker.
+const QUEUED_CONNECTIONS_PER_WORKER: usize = 16;
+
+/// The commands the `POST` endpoints serve, see the `stdio` module.
+const COMMANDS: [&str; 3] = ["optimize", "estimate", "sweep"];
+
+/// A parsed HTTP request: its method, its path without the query string, and its body.
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+/// The status and JSON body of an HTTP response.
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Response {
+            status: 200,
+            reason: "OK",
+            body,
+        }
+    }
+
+    fn error(status: u16, reason: &'static str, message: &str) -> Self {
+        Response {
+            status,
+            reason,
+            body: stdio::error_response(message),
+        }
+    }
+}
+
+/// Binds to `address` and serves requests until the process is stopped. The address actually
+/// bound is printed first, so that a port of 0 lets the system pick a free one.
+///
+/// # Errors
+/// * If the address cannot be bound
+pub(crate) fn run(address: &str) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(address).map_err(|err| format!("cannot bind to `{address}`: {err}"))?;
+    let local = listener
+        .local_addr()
+        .map_err(|err| format!("cannot read the bound address: {err}"))?;
+    println!("listening on http://{local}");
+    io::stdout()
+        .flush()
+        .map_err(|err| format!("cannot write to standard output: {err}"))?;
+
+    let workers = thread::available_parallelism().map_or(4, NonZeroUsize::get);
+    let (sender, receiver) =
+        mpsc::sync_channel::<TcpStream>(workers * QUEUED_CONNECTIONS_PER_WORKER);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            // the lock is released as soon as a connection is taken off the queue.
+            let stream = match receiver.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            let Ok(stream) = stream else {
+                return;
+            };
+            if let Err(err) = handle(stream) {
+                eprintln!("warning: cannot answer a connection: {err}");
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            // blocks while every worker is busy and the queue is full.
+            Ok(stream) => sender
+                .send(stream)
+                .map_err(|_| "every worker has stopped".to_string())?,
+            Err(err) => eprintln!("warning: cannot accept a connection: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// A connection that fails every read once its deadline has passed, so that the timeout bounds
+/// the whole request rather than each read.
+struct DeadlineStream<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the request took too long",
+            ));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads one request from the connection, answers it and closes the connection.
+fn handle(mut stream: TcpStream) -> io::Result<()> {
+    let connection = DeadlineStream {
+        stream: &stream,
+        deadline: Instant::now() + READ_TIMEOUT,
+    };
+    let response = match read_request(&mut BufReader::new(connection)) {
+        Ok(request) => route(&request),
+        Err(response) => response,
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.reason,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()?;
+
+    // a request rejected before it was read in full leaves bytes unread, and closing over them
+    // would reset the connection before the client reads the response, so they are drained.
+    stream.shutdown(Shutdown::Write)?;
+    stream.set_read_timeout(Some(DRAIN_TIMEOUT))?;
+    // the response is sent, so a client still sending when the drain gives up is no error.
+    let _ = io::copy(&mut (&stream).take(MAX_BODY_BYTES as u64), &mut io::sink());
+    Ok(())
+}
+
+/// Answers a request by its method and path.
+fn route(request: &Request) -> Response {
+    let endpoint = request.path.trim_start_matches('/');
+    match (request.method.as_str(), endpoint) {
+        ("GET", "healthz") => Response::ok("{\"status\":\"ok\"}".to_string()),
+        ("POST", command) if COMMANDS.contains(&command) => {
+            match stdio::serve_command(&request.body, Some(command)) {
+                Ok(response) => Response::ok(response),
+                Err(err) => Response::error(400, "Bad Request", &err),
+            }
+        }
+        (method, endpoint) if endpoint == "healthz" || COMMANDS.contains(&endpoint) => {
+            Response::error(
+                405,
+                "Method Not Allowed",
+                &format!("`{}` does not accept {method}", request.path),
+            )
+        }
+        _ => Response::error(
+            404,
+            "Not Found",
+            &format!("no endpoint at `{}`", request.path),
+        ),
+    }
+}
+
+/// Reads the request line, the headers and the body of a request. A request that cannot be read
+/// is answered with the returned error response.
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let bad_request = |message: &str| Response::error(400, "Bad Request", message);
+    let mut line = String::new();
+    let mut header_bytes = 0;
+    let mut read_line = |line: &mut String| {
+        line.clear();
+        let limit = (MAX_HEADER_BYTES - header_bytes) as u64;
+        let read = reader
+            .take(limit)
+            .read_line(line)
+            .map_err(|err| bad_request(&format!("cannot read the request: {err}")))?;
+        header_bytes += read;
+        if header_bytes == MAX_HEADER_BYTES && !line.ends_with('\n') {
+            return Err(Response::error(
+                431,
+                "Request Header Fields Too Large",
+                &format!("the request line and headers exceed {MAX_HEADER_BYTES} bytes"),
+            ));
+        }
+        Ok(read)
+    };
+
+    read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(bad_request("malformed request line"));
+    };
+    let method = method.to_string();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        if read_line(&mut line)? == 0 {
+            return Err(bad_request("the headers end before an empty line"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(bad_request(&format!("malformed header `{header}`")));
+        };
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .trim()
+                .parse()
+                .map_err(|_| bad_request(&format!("invalid content length `{}`", value.trim())))?;
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(Response::error(
+            413,
+            "Content Too Large",
+            &format!("the body of {content_length} bytes exceeds {MAX_BODY_BYTES} bytes"),
+        ));
+    }
+
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| bad_request(&format!("cannot read the body: {err}")))?;
+    let body = String::from_utf8(body).map_err(|_| bad_request("the body is not UTF-8"))?;
+    Ok(Request { method, path, body })
+}
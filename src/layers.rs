@@ -0,0 +1,234 @@
+//! The walk over the layers of a folding schedule that every per-layer metric is computed from.
+//!
+//! The size of a proof, the work of its verifier and prover and the trees it commits to all
+//! depend on the same facts about each layer: the domain it is committed over, how its values
+//! are grouped into leaves and how deep its authentication paths are. [`walk_schedule`] derives
+//! these facts once, in one place, and hands them to a [`LayerVisitor`] computing its own metric,
+//! so that no metric derives a domain or a path of its own.
+
+use super::cost_model::{CosetLayout, CostModel};
+use super::optimized_schedule::{leaf_elements, paths_of_depth_elements};
+
+/// What every metric needs to know about one layer of a folding schedule, as derived by
+/// [`walk_schedule`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LayerFacts {
+    /// The index of the layer in the folding schedule, 0 for the initial layer.
+    pub(crate) layer: usize,
+    /// The size of the domain the layer is committed over.
+    pub(crate) domain_size: usize,
+    /// The folding factor of the layer, in bits.
+    pub(crate) folding_bits: usize,
+    /// The number of values grouped into a leaf, in bits, see [`CostModel::leaf_bits`].
+    pub(crate) leaf_bits: usize,
+    /// The number of digests in the authentication path of one query.
+    pub(crate) path_depth: usize,
+    /// The number of leaves of the layer's Merkle tree.
+    pub(crate) tree_leaves: usize,
+    /// The number of field elements in each value of the layer.
+    pub(crate) value_elements: usize,
+    /// Whether the layer's paths are counted in the proof, see [`CostModel::counts_paths`].
+    pub(crate) counts_paths: bool,
+    /// Whether the layer's values are counted in the proof, see [`CostModel::counts_leaves`].
+    pub(crate) counts_leaves: bool,
+}
+
+impl LayerFacts {
+    /// Returns the folding factor of the layer.
+    pub(crate) fn factor(&self) -> usize {
+        1 << self.folding_bits
+    }
+
+    /// Returns the number of values a query opens in the layer, the values of its leaf group.
+    pub(crate) fn leaf_factor(&self) -> usize {
+        1 << self.leaf_bits
+    }
+
+    /// Returns the size of the domain of the next layer, or of the remainder after the last one.
+    pub(crate) fn folded_domain_size(&self) -> usize {
+        self.domain_size >> self.folding_bits
+    }
+
+    /// Returns the number of elements in the Merkle paths of the layer over `num_queries`
+    /// queries, counted or not.
+    pub(crate) fn path_elements(&self, num_queries: usize, cost_model: &CostModel) -> usize {
+        paths_of_depth_elements(num_queries, self.path_depth, cost_model)
+    }
+
+    /// Returns the number of opened elements of the layer over `num_queries` queries, counted or
+    /// not.
+    pub(crate) fn leaf_elements(&self, num_queries: usize, cost_model: &CostModel) -> usize {
+        leaf_elements(num_queries, self.layer, self.leaf_factor(), cost_model)
+    }
+}
+
+/// What metrics need to know about the remainder a folding schedule leaves, as derived by
+/// [`walk_schedule`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RemainderFacts {
+    /// The size of the domain of the last layer's folded codeword.
+    pub(crate) domain_size: usize,
+    /// The degree of the remainder polynomial.
+    pub(crate) degree: usize,
+}
+
+/// A metric computed layer by layer by [`walk_schedule`]. Any closure taking a layer's facts is
+/// a visitor that ignores the remainder.
+pub(crate) trait LayerVisitor {
+    /// Visits one layer, in folding order.
+    fn visit_layer(&mut self, layer: &LayerFacts);
+
+    /// Visits the remainder, after every layer.
+    fn visit_remainder(&mut self, _remainder: &RemainderFacts) {}
+}
+
+impl<F: FnMut(&LayerFacts)> LayerVisitor for F {
+    fn visit_layer(&mut self, layer: &LayerFacts) {
+        self(layer)
+    }
+}
+
+/// Walks the layers of a folding schedule, deriving the facts of every layer and of the
+/// remainder once and handing them to the visitor. The remainder's facts are also returned, for
+/// visitors that are closures.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+/// * `visitor` - The metric to hand every layer and the remainder to
+///
+/// # Returns
+/// * `remainder` - The facts of the remainder
+#[inline]
+pub(crate) fn walk_schedule<V: LayerVisitor + ?Sized>(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+    visitor: &mut V,
+) -> RemainderFacts {
+    let mut domain_size = degree;
+    for (layer, &folding_bits) in folding_seq.iter().enumerate() {
+        let leaf_bits = cost_model.leaf_bits(folding_seq, layer);
+        let tree_leaves = match cost_model.coset_layout {
+            CosetLayout::PackedLeaf => domain_size >> leaf_bits,
+            CosetLayout::SplitLeaves => domain_size,
+        };
+        let facts = LayerFacts {
+            layer,
+            domain_size,
+            folding_bits,
+            leaf_bits,
+            path_depth: cost_model
+                .coset_layout
+                .path_digests(domain_size, 1 << leaf_bits),
+            tree_leaves,
+            value_elements: cost_model.value_elements(layer),
+            counts_paths: cost_model.counts_paths(layer),
+            counts_leaves: cost_model.counts_leaves(layer),
+        };
+        visitor.visit_layer(&facts);
+        domain_size = facts.folded_domain_size();
+    }
+
+    let remainder = RemainderFacts {
+        domain_size,
+        degree: domain_size / blowup_factor,
+    };
+    visitor.visit_remainder(&remainder);
+    remainder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        layers: Vec<LayerFacts>,
+        remainders: Vec<RemainderFacts>,
+    }
+
+    impl LayerVisitor for Counter {
+        fn visit_layer(&mut self, layer: &LayerFacts) {
+            self.layers.push(*layer);
+        }
+
+        fn visit_remainder(&mut self, remainder: &RemainderFacts) {
+            self.remainders.push(*remainder);
+        }
+    }
+
+    // the domains of the default optimum shrink by their folding factors. Under packed leaves the
+    // tree has one leaf per coset, and every path is as deep as the tree.
+    #[test]
+    fn walk_visits_every_layer_and_the_remainder() {
+        let mut counter = Counter::default();
+        let schedule = [0, 4, 4, 4, 3];
+        let remainder = walk_schedule(1 << 25, 8, &schedule, &CostModel::default(), &mut counter);
+
+        let layer = |layer: usize, domain_bits: usize, folding_bits: usize| LayerFacts {
+            layer,
+            domain_size: 1 << domain_bits,
+            folding_bits,
+            leaf_bits: folding_bits,
+            path_depth: domain_bits - folding_bits,
+            tree_leaves: 1 << (domain_bits - folding_bits),
+            value_elements: 2,
+            counts_paths: true,
+            counts_leaves: true,
+        };
+        assert_eq!(
+            counter.layers,
+            [
+                layer(0, 25, 0),
+                layer(1, 25, 4),
+                layer(2, 21, 4),
+                layer(3, 17, 4),
+                layer(4, 13, 3)
+            ]
+        );
+        let expected = RemainderFacts {
+            domain_size: 1 << 10,
+            degree: 1 << 7,
+        };
+        assert_eq!(counter.remainders, [expected]);
+        assert_eq!(remainder, expected);
+    }
+
+    #[test]
+    fn closure_visits_the_winterfell_leaves() {
+        let winterfell = CostModel {
+            winterfell_layout: true,
+            ..CostModel::default()
+        };
+        let mut leaf_bits = Vec::new();
+        walk_schedule(
+            1 << 25,
+            8,
+            &[0, 4, 4, 4, 3],
+            &winterfell,
+            &mut |layer: &LayerFacts| leaf_bits.push(layer.leaf_bits),
+        );
+        assert_eq!(leaf_bits, [4, 4, 4, 4, 3]);
+    }
+
+    #[test]
+    fn split_leaves_give_a_leaf_per_value() {
+        let cost_model = CostModel {
+            coset_layout: CosetLayout::SplitLeaves,
+            ..CostModel::default()
+        };
+        let mut leaves = Vec::new();
+        walk_schedule(
+            1 << 16,
+            8,
+            &[0, 4, 3],
+            &cost_model,
+            &mut |layer: &LayerFacts| leaves.push(layer.tree_leaves),
+        );
+        assert_eq!(leaves, [1 << 16, 1 << 16, 1 << 12]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Finds the folding strategy that minimizes the size of a FRI proof.
+//!
+//! Given the degree of the polynomial to be proved, the blowup factor and the number of queries,
+//! the crate estimates the size of a FRI proof for any folding schedule, searches for the schedule
+//! yielding the smallest estimate, and reports related costs such as verifier hashes, memory and
+//! soundness. The [`prelude`] brings the commonly used types and functions into scope.
+//!
+//! # Features
+//!
+//! The estimator and the optimizers need no feature and depend on `std` alone. Everything else
+//! is opt-in, and no feature changes a number the core computes:
+//! * `cli` - The command-line tool; implies `json` and `sim`.
+//! * `json` - Serialization of analyses, see `pipeline::Analysis::to_json`.
+//! * `sim` - Monte Carlo simulation of proofs with random query positions, see
+//!   `exact_size::heuristic_error_bound`.
+//! * `serve` - An HTTP service for the command-line tool's JSON requests; implies `cli`.
+//! * `winterfell` - Calibration against proofs measured with Winterfell.
+//! * `testing` - Ground truths for downstream test suites, such as exact expectations over small
+//!   trees.
+//!
+//! Both extremes are checked with `cargo test --no-default-features` and
+//! `cargo test --all-features`: the doctests of the core run under each.
+//!
+//! # Reproducibility
+//!
+//! Schedules, sizes and their [`schedule_id`](report::schedule_id) fingerprints are the same on
+//! every target, whatever the width of `usize`, e.g. on `wasm32` or 32-bit ARM as on `x86_64`:
+//! * Proof sizes and every cost the optimizers minimize are computed in integer arithmetic only,
+//!   and ties between schedules are broken deterministically.
+//! * Estimates that grow with the size of the domain rather than with the number of queries, such
+//!   as the prover memory, are counted in `u64` with checked arithmetic, and the degrees a
+//!   function derives from its parameters are checked to fit in a `usize`. Either failing is
+//!   reported as [`FoldingError::ArithmeticOverflow`](prelude::FoldingError::ArithmeticOverflow)
+//!   rather than wrapping. Prover memory in bytes is the first estimate to exceed 32 bits, from
+//!   domains of about 2^26 under the default cost model.
+//! * Fingerprints hash every value as 8 little-endian bytes.
+//! * Seeded randomness, as in the simulations of the `sim` feature, uses 64-bit generators.
+//!
+//! Soundness estimates are floating-point logarithms, which may differ in the last bits between
+//! platform math libraries, so the objectives comparing them against a target, such as
+//! [`Objective::SecurityShortfall`](objective::Objective::SecurityShortfall), are only
+//! reproducible away from the target.
+//!
+//! The fingerprints of a few optimal schedules, pinned so that a test run on any target checks
+//! them, e.g. with `cargo test --doc --target wasm32-wasip1` under a `wasmtime` runner:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use fri_dynamic_folding_scheme::prelude::*;
+//! use fri_dynamic_folding_scheme::report::schedule_id;
+//!
+//! for (degree, blowup_factor, num_queries, cost_model, elements, id) in [
+//!     (1 << 25, 8, 27, CostModel::default(), 12622, 0xf1af_be4a_dffd_3c3b),
+//!     (1 << 20, 4, 40, BABYBEAR_QUARTIC, 24736, 0x22f4_084c_ddf0_6e9b),
+//!     (1 << 16, 2, 100, POSEIDON_256, 6112, 0x50bf_5921_0096_f418),
+//!     (1 << 24, 16, 20, GOLDILOCKS_QUADRATIC, 8232, 0x2bfc_50ae_9b9a_1b23),
+//! ] {
+//!     let (size, schedule) = optimal_folding_strategy(
+//!         degree,
+//!         blowup_factor,
+//!         num_queries,
+//!         vec![0],
+//!         &HashMap::new(),
+//!         &cost_model,
+//!         &mut SearchStats::default(),
+//!     )
+//!     .unwrap();
+//!     let report = FoldingReport::new(degree, blowup_factor, num_queries, schedule, &cost_model);
+//!     assert_eq!((size, schedule_id(&report)), (elements, id));
+//! }
+//! ```
+
+pub mod analysis;
+pub mod batch;
+pub mod cost_model;
+mod defaults;
+mod error;
+pub mod exact_size;
+mod layers;
+#[doc(hidden)]
+pub mod markdown;
+pub mod objective;
+pub mod optimized_schedule;
+pub mod params;
+pub mod pipeline;
+#[doc(hidden)]
+pub mod plot;
+pub mod provenance;
+pub mod prover_cost;
+mod recommend;
+pub mod report;
+mod schedule;
+pub mod simple_schedule;
+pub mod soundness;
+mod stats;
+#[doc(hidden)]
+pub mod table;
+pub mod verifier_cost;
+#[cfg(feature = "winterfell")]
+pub mod winterfell;
+
+/// The types and functions needed for common use of the crate.
+///
+/// Finding the optimal folding schedule for a polynomial of degree 2^16 with a blowup factor of 8
+/// and 27 queries:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let (size, schedule) = optimal_folding_strategy(
+///     1 << 16,
+///     8,
+///     27,
+///     vec![0],
+///     &HashMap::new(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(schedule[0], 0);
+/// assert_eq!(size, estimate_proof_size(1 << 16, 8, 27, &schedule, &cost_model).unwrap());
+/// ```
+///
+/// Sizing a given schedule and inspecting the contribution of each layer:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let schedule = vec![0, 2, 2];
+/// validate_schedule(1 << 10, 4, &schedule).unwrap();
+///
+/// let report = FoldingReport::new(1 << 10, 4, 3, schedule, &GOLDILOCKS_QUADRATIC);
+/// assert_eq!(report.breakdown.layers.len(), 3);
+/// assert_eq!(report.total_elements(), 374);
+/// assert_eq!(size_in_bytes(report.total_elements(), &report.cost_model), 2992);
+/// ```
+///
+/// Letting the crate pick every parameter for a goal:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let report = recommend(Goal::MinimalSize { degree: 1 << 16, security_bits: 100 }).unwrap();
+/// assert_eq!(report.cost_model, GOLDILOCKS_QUADRATIC);
+/// assert_eq!(report.degree, (1 << 16) * report.blowup_factor);
+/// ```
+pub mod prelude {
+    pub use crate::cost_model::{CosetEvalMethod, CosetLayout, CostModel, PathModel};
+    pub use crate::defaults::{BABYBEAR_QUARTIC, GOLDILOCKS_QUADRATIC, POSEIDON_256};
+    pub use crate::error::{FoldingError, Warning};
+    pub use crate::optimized_schedule::{
+        baseline_size, estimate_proof_size, optimal_folding_strategy, size_in_bytes,
+        validate_parameters, validate_schedule,
+    };
+    pub use crate::params::FriParams;
+    pub use crate::pipeline::{analyze, Analysis, AnalysisOptions};
+    pub use crate::recommend::{recommend, Goal};
+    pub use crate::report::{
+        ComponentBreakdown, FoldingReport, LayerBreakdown, ProofSizeBreakdown, TreeShape,
+    };
+    pub use crate::schedule::Schedule;
+    pub use crate::simple_schedule::simple_schedule;
+    pub use crate::soundness::{fri_soundness, SoundnessBreakdown};
+    pub use crate::stats::SearchStats;
+}
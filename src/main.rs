@@ -1,32 +1,878 @@
-mod optimized_schedule;
-mod simple_schedule;
+mod cli;
+#[cfg(feature = "serve")]
+mod http;
+mod stdio;
+
+use fri_dynamic_folding_scheme::cost_model::{CostModel, RemainderStrategy};
+use fri_dynamic_folding_scheme::params::FriParams;
+use fri_dynamic_folding_scheme::prelude::{Schedule, SearchStats, Warning};
+#[cfg(feature = "winterfell")]
+use fri_dynamic_folding_scheme::simple_schedule;
+use fri_dynamic_folding_scheme::table::ReportTable;
+use fri_dynamic_folding_scheme::{
+    analysis, markdown, objective, optimized_schedule, pipeline, plot, report, soundness,
+};
 
 fn main() {
-    let (degree, blowup_factor, num_queries, remainder_max_degree) = (1 << 25, 8, 27, 64);
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    };
+    let cost_model = options.cost_model;
+    if options.strict {
+        if let Err(err) = cost_model.check_consistency() {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    }
+    for violation in cost_model.validate() {
+        eprintln!("warning: inconsistent cost model: {violation}");
+    }
+
+    if options.command == cli::Command::StdioJson {
+        let mut request = String::new();
+        let served = std::io::Read::read_to_string(&mut std::io::stdin(), &mut request)
+            .map_err(|err| format!("cannot read the request: {err}"))
+            .and_then(|_| stdio::serve(&request));
+        match served {
+            Ok(response) => println!("{response}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                println!("{}", stdio::error_response(&err));
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    #[cfg(feature = "serve")]
+    if let cli::Command::Serve(address) = &options.command {
+        if let Err(err) = http::run(address) {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let cli::Command::Aggregate(path) = &options.command {
+        if let Err(err) = print_aggregate(path, &cost_model, options.emit) {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let (degree, blowup_factor, num_queries, remainder_max_degree) = (
+        options.degree,
+        options.blowup_factor,
+        options.num_queries,
+        options.remainder_max_degree,
+    );
 
-    let (opt_size, opt_schedule) =
-        optimized_schedule::optimal_folding_strategy(degree, blowup_factor, num_queries, vec![0]);
+    // the text and JSON reports are the only ones with the sensitivity and the extra sections.
+    let full_report = options.command == cli::Command::Report
+        && matches!(options.emit, cli::Emit::Text | cli::Emit::Json);
+    let analysis_options = pipeline::AnalysisOptions {
+        remainder_max_degree,
+        field_bits: options.field_bits,
+        pinned_layers: options.pinned_layers.clone(),
+        degree_groups: options.degree_groups.clone(),
+        max_prover_memory: options.max_prover_memory,
+        max_verifier_memory: options.max_verifier_memory,
+        min_folding_bits: options.min_folding_bits,
+        target_remainder_degree: options.target_remainder_degree,
+        time_budget: options.time_budget,
+        sensitivity: full_report,
+        error_bound_trials: options.error_bound_trials.filter(|_| full_report),
+        robust_cost_models: options
+            .robust_presets
+            .iter()
+            .flatten()
+            .filter(|_| full_report)
+            .map(|(_, preset)| cli::with_knobs(*preset, &cost_model))
+            .collect(),
+        strict: options.strict,
+    };
+    let params = FriParams {
+        degree,
+        blowup_factor,
+        num_queries,
+        cost_model,
+    };
+    let analysis = match pipeline::analyze(&params, &analysis_options) {
+        Ok(analysis) => analysis,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    };
+    let report = &analysis.report;
+    let opt_schedule = &report.schedule;
+    let opt_size = report.total_elements();
+    let (greedy_size, greedy_schedule) = &analysis.greedy;
 
+    if options.command == cli::Command::Table {
+        print_table(&analysis, options.emit);
+        if options.print_crossovers {
+            print_crossovers(&options, &cost_model);
+        }
+        return;
+    }
+
+    match options.emit {
+        cli::Emit::Text => {}
+        cli::Emit::Json => {
+            println!("{}", analysis.to_json());
+            return;
+        }
+        cli::Emit::Csv => {
+            println!("strategy,schedule,size_elements,size_bytes,semantics_version");
+            print_csv_row("baseline", &[0], analysis.baseline, &cost_model);
+            print_csv_row("optimal", opt_schedule, opt_size, &cost_model);
+            print_csv_row("greedy", greedy_schedule, *greedy_size, &cost_model);
+            for comparison in &analysis.comparisons {
+                let (size, schedule) = &comparison.uniform;
+                let factor = 1 << comparison.folding_bits;
+                print_csv_row(&format!("uniform-{factor}"), schedule, *size, &cost_model);
+            }
+            for comparison in &analysis.comparisons {
+                let (size, schedule) = &comparison.tuned_final_round;
+                let factor = 1 << comparison.folding_bits;
+                print_csv_row(
+                    &format!("tuned-final-{factor}"),
+                    schedule,
+                    *size,
+                    &cost_model,
+                );
+            }
+            for comparison in &analysis.comparisons {
+                let (size, schedule) = &comparison.best_head;
+                let factor = 1 << comparison.folding_bits;
+                print_csv_row(&format!("best-head-{factor}"), schedule, *size, &cost_model);
+            }
+            return;
+        }
+        cli::Emit::Markdown => {
+            let reproduce = std::iter::once(env!("CARGO_PKG_NAME").to_string())
+                .chain(std::env::args().skip(1))
+                .collect::<Vec<_>>()
+                .join(" ");
+            print!(
+                "{}",
+                markdown::to_markdown(report, options.field_bits, &reproduce)
+            );
+            return;
+        }
+        cli::Emit::Tikz | cli::Emit::Gnuplot => {
+            if options.emit == cli::Emit::Tikz {
+                print!("{}", plot::to_tikz(report));
+            } else {
+                print!("{}", plot::to_gnuplot(report));
+            }
+            return;
+        }
+    }
+
+    #[cfg(feature = "winterfell")]
+    if let Some(parsed) = &options.winterfell_options {
+        println!(
+            "Winterfell proof options: found {}",
+            parsed.found.join(", ")
+        );
+        if !parsed.assumed.is_empty() {
+            println!("  assumed the default {}", parsed.assumed.join(", "));
+        }
+        let folding_bits = parsed.options.folding_factor.ilog2() as usize;
+        let (winterfell_size, winterfell_schedule) = match simple_schedule::simple_schedule(
+            degree,
+            blowup_factor,
+            num_queries,
+            remainder_max_degree,
+            folding_bits,
+            &cost_model,
+        ) {
+            Ok(simple) => simple,
+            Err(err) => {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        };
+        println!(
+            "Winterfell's folding schedule {:?} takes {} kBs; the {} grinding bits only add a \
+             nonce, which is not counted",
+            winterfell_schedule,
+            optimized_schedule::size_in_bytes(winterfell_size, &cost_model) / 1024,
+            parsed.grinding_bits
+        );
+    }
     println!(
         "The optimal size {} kBs and optimal folding schedule {:?}",
-        optimized_schedule::size_in_bytes(opt_size) / 1024,
+        optimized_schedule::size_in_bytes(opt_size, &cost_model) / 1024,
         opt_schedule
     );
+    println!("  schedule ID {:016x}", report::schedule_id(report));
+    if cost_model.remainder_strategy == RemainderStrategy::InLastLayer {
+        println!(
+            "  the remainder of degree {} is interpolated from the last layer's openings of {} \
+             queries",
+            (degree / blowup_factor) >> opt_schedule.iter().sum::<usize>(),
+            num_queries
+        );
+    }
+    if analysis.optimality == optimized_schedule::Optimality::TimedOut {
+        println!(
+            "  possibly suboptimal: the search ran out of its {:?} time budget",
+            options.time_budget.unwrap()
+        );
+    }
+    if cost_model.remainder_verify_cost_per_element > 0 {
+        println!(
+            "  optimized together with a remainder check costing {} elements",
+            analysis.remainder_check_cost
+        );
+    }
+    if options.print_stats {
+        println!("Search statistics: {}", analysis.stats);
+    }
+    println!(
+        "The proof opens {} field elements ({:?} per layer and {} in the remainder) for the \
+         optimal folding schedule",
+        report.breakdown.total_elements(),
+        report.breakdown.layer_elements(),
+        report.breakdown.remainder_elements
+    );
+    println!("The optimal folding schedule layer by layer: {report:#}");
+    let excluded = report.breakdown.excluded;
+    if excluded.total_elements() > 0 {
+        println!(
+            "Excluded (informational): {} path, {} leaf and {} remainder elements ({} bytes) are \
+             accounted for elsewhere",
+            excluded.path_elements,
+            excluded.leaf_elements,
+            excluded.remainder_elements,
+            optimized_schedule::size_in_bytes(excluded.total_elements(), &cost_model)
+        );
+    }
+    println!(
+        "The proof commits to {} Merkle roots for the optimal folding schedule",
+        report.commitment_roots
+    );
+    println!(
+        "The verifier computes {} hashes for the optimal folding schedule",
+        report.verifier_hashes
+    );
+    println!(
+        "The verifier performs {} field multiplications for the optimal folding schedule",
+        report.verifier_field_ops
+    );
+    println!(
+        "The verifier performs {} field inversions ({:?} per layer) for the optimal folding \
+         schedule",
+        report.verifier_inversions.iter().sum::<usize>(),
+        report.verifier_inversions
+    );
+    println!(
+        "The verifier needs {} bytes of working memory for the optimal folding schedule",
+        report.verifier_memory
+    );
+    println!(
+        "The prover needs {} MBs of memory for the optimal folding schedule",
+        analysis.prover_memory / (1024 * 1024)
+    );
+    println!(
+        "The prover commits to {:.0} times as many elements as the proof holds for the optimal \
+         folding schedule",
+        analysis.commitment_ratio
+    );
+    println!(
+        "The prover absorbs {} field elements into hashes for the optimal folding schedule",
+        analysis.hash_input_elements
+    );
 
-    for i in 1..=4 {
-        let (size, schedule) = simple_schedule::simple_schedule(
+    if options.instances > 1 {
+        let aggregate = report::aggregate_size(
             degree,
             blowup_factor,
             num_queries,
-            remainder_max_degree,
-            i,
+            options.instances,
+            &cost_model,
+        );
+        println!(
+            "{} independent proofs take {} kBs in total",
+            aggregate.instances,
+            optimized_schedule::size_in_bytes(aggregate.total_elements(), &cost_model) / 1024
+        );
+    }
+
+    if let Some(query_tiers) = &options.query_tiers {
+        let tier_bytes = report.query_tier_bytes(query_tiers).unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        println!("The verification tiers of the optimal folding schedule:");
+        let mut checked = 0;
+        for (queries, bytes) in query_tiers.iter().zip(tier_bytes) {
+            checked += queries;
+            println!(
+                "  Verifying {checked} of the {num_queries} queries takes the first {bytes} bytes \
+                 of the proof"
+            );
+        }
+    }
+
+    if let Some(schedule) = &options.schedule {
+        let checked = optimized_schedule::validate_schedule(degree, blowup_factor, schedule)
+            .and_then(|()| {
+                optimized_schedule::check_remainder_strategy(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    schedule,
+                    &cost_model,
+                )
+            })
+            .and_then(|()| {
+                optimized_schedule::estimate_proof_size(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    schedule,
+                    &cost_model,
+                )
+            });
+        let size = match checked {
+            Ok(size) => size,
+            Err(err) => {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        };
+        println!(
+            "The supplied size {} kBs and folding schedule {:?}",
+            optimized_schedule::size_in_bytes(size, &cost_model) / 1024,
+            schedule
+        );
+        if Schedule::new(schedule.clone()) == Schedule::new(opt_schedule.clone()) {
+            println!("  the supplied folding schedule is the optimal one");
+        }
+    }
+
+    if let Some(query_plan) = &options.query_plan {
+        let planned = options.schedule.as_ref().unwrap_or(opt_schedule);
+        let priced = optimized_schedule::estimate_proof_size_with_query_plan(
+            degree,
+            blowup_factor,
+            query_plan,
+            planned,
+            &cost_model,
+        )
+        .and_then(|size| {
+            // the remainder is interpolated from the queries that reach the last layer.
+            let last_queries = query_plan.last().copied().unwrap_or(0);
+            optimized_schedule::check_remainder_strategy(
+                degree,
+                blowup_factor,
+                last_queries,
+                planned,
+                &cost_model,
+            )?;
+            let soundness = soundness::query_plan_soundness(
+                degree,
+                blowup_factor,
+                query_plan,
+                planned,
+                options.field_bits,
+            )?;
+            Ok((size, soundness))
+        });
+        let (size, plan_soundness) = priced.unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        println!(
+            "The query plan {query_plan:?} for folding schedule {planned:?} takes {} kBs",
+            optimized_schedule::size_in_bytes(size, &cost_model) / 1024
+        );
+        println!(
+            "  {:.1} bits of security by the per-layer accounting, {:.1} bits by the conservative \
+             one",
+            plan_soundness.conjectured.security_bits(),
+            plan_soundness.conservative.security_bits()
+        );
+    }
+
+    if let Some(growths) = &options.degree_growths {
+        let deployed = options.schedule.as_ref().unwrap_or(opt_schedule);
+        let sensitivity = analysis::degree_growth_sensitivity(
+            degree,
+            blowup_factor,
+            num_queries,
+            deployed,
+            growths,
+            &cost_model,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        println!("The folding schedule {deployed:?} as the degree grows:");
+        print_report_table(&sensitivity.to_table(), options.emit);
+    }
+
+    if let Some((security_bits, hash_rate, ceiling)) = options.grinding {
+        let tradeoff = analysis::optimal_with_grinding(
+            degree,
+            blowup_factor,
+            security_bits,
+            hash_rate,
+            ceiling,
+            &cost_model,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        println!(
+            "The smallest proof at {security_bits} bits of security for every level of grinding \
+             within {ceiling:?}:"
+        );
+        print_report_table(&tradeoff.to_table(), options.emit);
+        let best = tradeoff.best();
+        println!(
+            "  smallest with {} grinding bits and {} queries",
+            best.grinding_bits, best.report.num_queries
+        );
+    }
+
+    if let Some((extra_bits, hash_rate)) = options.security_increase {
+        let increase = analysis::security_increase_options(
+            degree,
+            blowup_factor,
+            num_queries,
+            extra_bits,
+            hash_rate,
+            &cost_model,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        println!("The proof with {extra_bits} more bits of security by each lever:");
+        print_report_table(&increase.to_table(), options.emit);
+        let cheaper = if increase.queries_cheaper() {
+            analysis::SecurityLever::Queries
+        } else {
+            analysis::SecurityLever::Blowup
+        };
+        println!("  smallest without grinding by {cheaper}");
+    }
+
+    if let Some((mean_ratio, worst_ratio)) = analysis.error_bound {
+        println!(
+            "The estimated size is {mean_ratio:.3} times the mean exact size over {} random query \
+             sets (worst case {worst_ratio:.3})",
+            options.error_bound_trials.unwrap()
+        );
+    }
+
+    if options.print_waterfall {
+        let waterfall = analysis::refinement_waterfall(
+            degree,
+            blowup_factor,
+            num_queries,
+            opt_schedule,
+            &cost_model,
+        );
+        println!("The size saved by each refinement for the optimal folding schedule:");
+        print_report_table(&waterfall.to_table(), options.emit);
+    }
+
+    if options.print_crossovers {
+        print_crossovers(&options, &cost_model);
+    }
+
+    if let Some(objectives) = &options.objectives {
+        let (values, schedule) = objective::optimal_lexicographic(
+            degree,
+            blowup_factor,
+            num_queries,
+            objectives,
+            &cost_model,
+        );
+        println!(
+            "The lexicographic optimum for {objectives:?} is {values:?} with folding schedule \
+             {schedule:?}"
+        );
+    }
+
+    if let (Some(presets), Some(robust)) = (&options.robust_presets, &analysis.robust) {
+        println!(
+            "The folding schedule {:?} has the smallest worst-case regret ({:.1}%) across the \
+             presets",
+            robust.schedule,
+            robust.regret * 100.0
+        );
+        for (((name, _), model), (size, optimum)) in presets
+            .iter()
+            .zip(&analysis_options.robust_cost_models)
+            .zip(robust.sizes.iter().zip(&robust.optima))
+        {
+            println!(
+                "  {name}: size {} kBs against the optimal {} kBs",
+                optimized_schedule::size_in_bytes(*size, model) / 1024,
+                optimized_schedule::size_in_bytes(*optimum, model) / 1024
+            );
+        }
+    }
+
+    // the cost model's violations were reported before the search.
+    for warning in &analysis.warnings {
+        if let Warning::BindingFoldingCap(notice) = warning {
+            println!(
+                "  Notice: the folding factor cap of {} binds at layers {:?}; raising it to {} \
+                 would lower the cost by {} with {:?} (~{} KiB)",
+                1 << notice.cap_bits,
+                notice.layers,
+                1 << (notice.cap_bits + 1),
+                notice.saved_cost,
+                notice.raised_schedule,
+                optimized_schedule::size_in_bytes(notice.raised_size, &cost_model) / 1024
+            );
+        }
+    }
+
+    if let Some(band) = &analysis.query_band {
+        match band.max_queries {
+            Some(max) => println!(
+                "The optimal folding schedule is optimal for {}-{} queries",
+                band.min_queries, max
+            ),
+            None => println!(
+                "The optimal folding schedule is optimal for {} or more queries",
+                band.min_queries
+            ),
+        }
+
+        // warn when a change of 25% in the number of queries would change the schedule.
+        if let Some(below) = band
+            .below
+            .as_ref()
+            .filter(|_| 4 * band.min_queries > 3 * num_queries)
+        {
+            println!(
+                "  Notice: with {} queries the optimal folding schedule becomes {below:?}",
+                band.min_queries - 1
+            );
+        }
+        if let Some(above) = band.above.as_ref().filter(|_| {
+            band.max_queries
+                .is_some_and(|max| 4 * max < 5 * num_queries)
+        }) {
+            println!(
+                "  Notice: with {} queries the optimal folding schedule becomes {above:?}",
+                band.max_queries.unwrap() + 1
+            );
+        }
+    }
+
+    let soundness = &analysis.soundness;
+    println!(
+        "The optimal folding schedule achieves {:.1} bits of security (commit phase error 2^{:.1}, \
+         query phase error 2^{:.1})",
+        soundness.security_bits(),
+        soundness.commit_phase_error.log2(),
+        soundness.query_phase_error.log2()
+    );
+    let erosion = soundness::FieldErosion {
+        extension_degree: cost_model.extension_degree,
+        soundness: *soundness,
+    };
+    if erosion.exceeds(options.max_field_erosion) {
+        println!(
+            "  Warning: the {}-bit field the challenges are drawn from erodes the security of the \
+             queries by {:.1} bits, more than the {} bits tolerated",
+            options.field_bits,
+            erosion.erosion_bits(),
+            options.max_field_erosion
         );
+    }
+    if options.auto_extension {
+        // the challenge field is the extension of the cost model over the base field.
+        let base_field_bits = options.field_bits / cost_model.extension_degree.max(1) as u32;
+        let bump = soundness::auto_extension(
+            degree,
+            blowup_factor,
+            num_queries,
+            opt_schedule,
+            base_field_bits,
+            options.max_field_erosion,
+            &cost_model,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        if bump.is_bumped() {
+            println!(
+                "  Auto extension: the extension of degree {} keeps the erosion within {} bits \
+                 ({:.1} bits of security), growing the proof from {} to {} kBs (+{} elements, \
+                 {:+.1}%)",
+                bump.bumped.extension_degree,
+                options.max_field_erosion,
+                bump.bumped.soundness.security_bits(),
+                optimized_schedule::size_in_bytes(bump.original_size, &cost_model) / 1024,
+                optimized_schedule::size_in_bytes(bump.bumped_size, &cost_model) / 1024,
+                bump.size_penalty(),
+                100.0 * (bump.bumped_size as f64 / bump.original_size as f64 - 1.0)
+            );
+        } else {
+            println!(
+                "  Auto extension: the extension of degree {} already keeps the erosion within {} \
+                 bits",
+                bump.original.extension_degree, options.max_field_erosion
+            );
+        }
+    }
 
+    println!(
+        "Without folding the proof takes {} kBs, of which the optimal folding schedule takes \
+         {:.2}%",
+        optimized_schedule::size_in_bytes(analysis.baseline, &cost_model) / 1024,
+        100.0 * opt_size as f64 / analysis.baseline as f64
+    );
+    println!(
+        "The greedy schedule size {} kBs ({:+.1}% against the optimal size) and folding sequence \
+         {:?}",
+        optimized_schedule::size_in_bytes(*greedy_size, &cost_model) / 1024,
+        100.0 * (*greedy_size as f64 / opt_size as f64 - 1.0),
+        greedy_schedule
+    );
+
+    let mut all_collapse = true;
+    for comparison in &analysis.comparisons {
+        let (size, schedule) = &comparison.uniform;
+        let (tuned_size, tuned_schedule) = &comparison.tuned_final_round;
+        let (head_size, head_schedule) = &comparison.best_head;
         println!(
             "Folding factor {} size {} kBs and folding sequence {:?}",
-            1 << i,
-            optimized_schedule::size_in_bytes(size) / 1024,
+            1 << comparison.folding_bits,
+            optimized_schedule::size_in_bytes(*size, &cost_model) / 1024,
             schedule
         );
+        if tuned_schedule != schedule {
+            println!(
+                "  with a tuned final round size {} kBs (saves {} bytes) and folding sequence {:?}",
+                optimized_schedule::size_in_bytes(*tuned_size, &cost_model) / 1024,
+                optimized_schedule::size_in_bytes(size - tuned_size, &cost_model),
+                tuned_schedule
+            );
+        }
+        if head_schedule != schedule {
+            println!(
+                "  with the best head round size {} kBs and folding sequence {:?}",
+                optimized_schedule::size_in_bytes(*head_size, &cost_model) / 1024,
+                head_schedule
+            );
+        }
+        all_collapse &= schedule == opt_schedule;
+    }
+
+    if all_collapse {
+        println!(
+            "All strategies collapse to the same folding schedule {opt_schedule:?} for this degree"
+        );
+    }
+}
+
+/// Prints one row of the CSV output. The schedule is written as space-separated folding bits.
+fn print_csv_row(strategy: &str, schedule: &[usize], size: usize, cost_model: &CostModel) {
+    let schedule: Vec<_> = schedule.iter().map(|bits| bits.to_string()).collect();
+    println!(
+        "{strategy},{},{size},{},{}",
+        schedule.join(" "),
+        optimized_schedule::size_in_bytes(size, cost_model),
+        cost_model.semantics_version
+    );
+}
+
+/// Prints the degrees up to the requested one at which adjacent uniform folding factors swap
+/// places as the smaller proof.
+fn print_crossovers(options: &cli::Options, cost_model: &CostModel) {
+    let crossovers = analysis::uniform_crossovers(
+        options.degree,
+        options.blowup_factor,
+        options.num_queries,
+        cost_model,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    });
+    println!(
+        "The degrees from 2^{} to 2^{} at which adjacent uniform folding factors swap places, \
+         each with its best remainder bound:",
+        crossovers.min_degree.ilog2(),
+        crossovers.max_degree.ilog2()
+    );
+    print_report_table(&crossovers.to_table(), options.emit);
+}
+
+/// Prints the comparison of the optimal schedule with the baseline that does not fold, the greedy
+/// schedule and the uniform schedules as an aligned table, with every size as a percentage of the
+/// baseline. The smallest schedule is marked with a `*`.
+fn print_table(analysis: &pipeline::Analysis, emit: cli::Emit) {
+    let report = &analysis.report;
+    let (greedy_size, greedy_schedule) = &analysis.greedy;
+    let unfolded = vec![0];
+    let mut rows = vec![
+        (
+            "baseline".to_string(),
+            "-".to_string(),
+            analysis.baseline,
+            &unfolded,
+        ),
+        (
+            "optimal".to_string(),
+            "-".to_string(),
+            report.total_elements(),
+            &report.schedule,
+        ),
+        (
+            "greedy".to_string(),
+            "-".to_string(),
+            *greedy_size,
+            greedy_schedule,
+        ),
+    ];
+    for comparison in &analysis.comparisons {
+        let (size, schedule) = &comparison.uniform;
+        rows.push((
+            "uniform".to_string(),
+            (1 << comparison.folding_bits).to_string(),
+            *size,
+            schedule,
+        ));
+    }
+    let smallest = rows.iter().map(|(_, _, size, _)| *size).min();
+
+    let mut table = ReportTable::new([
+        "strategy",
+        "factor",
+        "size (kB)",
+        "of baseline",
+        "schedule",
+        "smallest",
+    ]);
+    for (strategy, factor, size, schedule) in rows {
+        table.push_row([
+            strategy,
+            factor,
+            (optimized_schedule::size_in_bytes(size, &report.cost_model) / 1024).to_string(),
+            format!("{:.2}%", 100.0 * size as f64 / analysis.baseline as f64),
+            format!("{schedule:?}"),
+            if Some(size) == smallest { "*" } else { "" }.to_string(),
+        ]);
+    }
+    print_report_table(&table, emit);
+}
+
+/// Prints the aggregate size of the proofs listed in the file at `path`, per parameter set and in
+/// total, split by component. Rows without a schedule use the optimal one.
+fn print_aggregate(
+    path: &std::path::Path,
+    cost_model: &CostModel,
+    emit: cli::Emit,
+) -> Result<(), String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| format!("cannot read `{}`: {err}", path.display()))?;
+    let rows = cli::parse_aggregate_rows(&contents)?;
+
+    let bytes = |elements: usize| optimized_schedule::size_in_bytes(elements, cost_model);
+    let cells = |components: &report::ComponentBreakdown| {
+        let total = bytes(components.total_elements());
+        [
+            bytes(components.path_elements).to_string(),
+            bytes(components.leaf_elements).to_string(),
+            bytes(components.remainder_elements).to_string(),
+            total.to_string(),
+            format!("{:.3}", total as f64 / (1u64 << 30) as f64),
+        ]
+    };
+
+    let mut table = ReportTable::new([
+        "degree",
+        "blowup",
+        "queries",
+        "schedule",
+        "count",
+        "path (B)",
+        "leaf (B)",
+        "remainder (B)",
+        "total (B)",
+        "total (GiB)",
+    ]);
+    let mut aggregate = Vec::with_capacity(rows.len());
+    for row in rows {
+        let schedule = match row.schedule {
+            Some(schedule) => {
+                optimized_schedule::validate_schedule(row.degree, row.blowup_factor, &schedule)
+                    .map_err(|err| err.to_string())?;
+                schedule
+            }
+            None => {
+                optimized_schedule::optimal_folding_strategy(
+                    row.degree,
+                    row.blowup_factor,
+                    row.num_queries,
+                    vec![0],
+                    &Default::default(),
+                    cost_model,
+                    &mut SearchStats::default(),
+                )
+                .map_err(|err| err.to_string())?
+                .1
+            }
+        };
+        let components = report::ProofSizeBreakdown::new(
+            row.degree,
+            row.blowup_factor,
+            row.num_queries,
+            &schedule,
+            cost_model,
+        )
+        .components()
+            * row.count;
+
+        let mut line = vec![
+            row.degree.to_string(),
+            row.blowup_factor.to_string(),
+            row.num_queries.to_string(),
+            format!("{schedule:?}"),
+            row.count.to_string(),
+        ];
+        line.extend(cells(&components));
+        table.push_row(line);
+        aggregate.push(components);
+    }
+
+    let total: report::ComponentBreakdown = aggregate.into_iter().sum();
+    let mut line = vec![String::from("total")];
+    line.resize(5, String::new());
+    line.extend(cells(&total));
+    table.push_row(line);
+
+    print_report_table(&table, emit);
+    Ok(())
+}
+
+/// Prints `table` as Markdown if that is the output format, and as aligned plain text otherwise.
+fn print_report_table(table: &ReportTable, emit: cli::Emit) {
+    if emit == cli::Emit::Markdown {
+        print!("{}", table.to_markdown());
+    } else {
+        print!("{}", table.to_plain());
     }
 }
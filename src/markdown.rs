@@ -0,0 +1,190 @@
+//! Renders a sized folding schedule as a Markdown section, ready to be pasted into a design
+//! document. The section is laid out from a [`FoldingReport`], the same data the JSON responses
+//! are built from, so the two cannot disagree.
+
+use std::fmt::Write;
+
+use super::optimized_schedule::size_in_bytes;
+use super::report::FoldingReport;
+use super::soundness::fri_soundness;
+use super::table::ReportTable;
+
+/// Renders `report` as a Markdown section: a table of the parameters, the schedule in bits and
+/// as folding factors, a table of the size of every layer and of the remainder with its share of
+/// the proof, a security summary for folding challenges drawn from a field of `field_bits` bits,
+/// and the command line `reproduce` that produced it. Columns are always in the same order, and
+/// no line ends in whitespace.
+///
+/// The section for the default parameters, with the fences of the last block escaped:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::markdown::to_markdown;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let report = FoldingReport::new(1 << 25, 8, 27, vec![0, 4, 4, 4, 3], &CostModel::default());
+/// assert_eq!(
+///     to_markdown(&report, 128, "fri-dynamic-folding-scheme"),
+///     "## FRI folding schedule
+///
+/// #### Parameters
+///
+/// | parameter | value |
+/// | --- | --- |
+/// | degree | 2^25 |
+/// | blowup factor | 8 |
+/// | queries | 27 |
+/// | field element bytes | 8 |
+/// | extension degree | 2 |
+/// | digest elements | 4 |
+/// | semantics version | 2 |
+///
+/// #### Schedule
+///
+/// - Folding bits: `[0, 4, 4, 4, 3]`
+/// - Folding factors: `[1, 16, 16, 16, 8]`
+///
+/// #### Proof size
+///
+/// 12622 field elements, 100976 bytes.
+///
+/// | component | domain | factor | path elements | leaf elements | bytes | share (%) |
+/// | --- | --- | --- | --- | --- | --- | --- |
+/// | layer 0 | 2^25 | 1 | 2700 | 54 | 22032 | 21.8 |
+/// | layer 1 | 2^25 | 16 | 2268 | 864 | 25056 | 24.8 |
+/// | layer 2 | 2^21 | 16 | 1836 | 864 | 21600 | 21.4 |
+/// | layer 3 | 2^17 | 16 | 1404 | 864 | 18144 | 18.0 |
+/// | layer 4 | 2^13 | 8 | 1080 | 432 | 12096 | 12.0 |
+/// | remainder | 2^10 |  |  | 256 | 2048 | 2.0 |
+/// | total |  |  |  |  | 100976 | 100.0 |
+///
+/// #### Security
+///
+/// - 81.0 bits of security with folding challenges from a 128-bit field
+/// - Commit phase error 2^-99.0, query phase error 2^-81.0
+///
+/// #### Reproduce
+///
+/// \x60\x60\x60sh
+/// fri-dynamic-folding-scheme
+/// \x60\x60\x60
+/// "
+/// );
+/// ```
+pub fn to_markdown(report: &FoldingReport, field_bits: u32, reproduce: &str) -> String {
+    let cost_model = &report.cost_model;
+    let bytes = |elements| size_in_bytes(elements, cost_model);
+
+    let mut parameters = ReportTable::new(["parameter", "value"]);
+    parameters.push_row(["degree".to_string(), pow2(report.degree)]);
+    parameters.push_row([
+        "blowup factor".to_string(),
+        report.blowup_factor.to_string(),
+    ]);
+    parameters.push_row(["queries".to_string(), report.num_queries.to_string()]);
+    parameters.push_row([
+        "field element bytes".to_string(),
+        cost_model.element_bytes.to_string(),
+    ]);
+    parameters.push_row([
+        "extension degree".to_string(),
+        cost_model.extension_degree.to_string(),
+    ]);
+    parameters.push_row([
+        "digest elements".to_string(),
+        cost_model.digest_elements.to_string(),
+    ]);
+    parameters.push_row([
+        "semantics version".to_string(),
+        cost_model.semantics_version.to_string(),
+    ]);
+
+    let factors: Vec<usize> = report.schedule.iter().map(|bits| 1 << bits).collect();
+
+    let total = report.breakdown.total_elements();
+    let share = |elements: usize| format!("{:.1}", 100.0 * elements as f64 / total as f64);
+    let mut sizes = ReportTable::new([
+        "component",
+        "domain",
+        "factor",
+        "path elements",
+        "leaf elements",
+        "bytes",
+        "share (%)",
+    ]);
+    for (layer, info) in report.breakdown.layers.iter().enumerate() {
+        sizes.push_row([
+            format!("layer {layer}"),
+            pow2(info.domain_size),
+            (1usize << info.folding_bits).to_string(),
+            info.path_elements.to_string(),
+            info.leaf_elements.to_string(),
+            bytes(info.total_elements()).to_string(),
+            share(info.total_elements()),
+        ]);
+    }
+    sizes.push_row([
+        "remainder".to_string(),
+        pow2(report.breakdown.remainder_domain_size),
+        String::new(),
+        String::new(),
+        report.breakdown.remainder_elements.to_string(),
+        bytes(report.breakdown.remainder_elements).to_string(),
+        share(report.breakdown.remainder_elements),
+    ]);
+    sizes.push_row([
+        "total".to_string(),
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+        bytes(total).to_string(),
+        share(total),
+    ]);
+
+    let soundness = fri_soundness(
+        report.degree,
+        report.blowup_factor,
+        report.num_queries,
+        &report.schedule,
+        field_bits,
+    );
+
+    let mut markdown = String::new();
+    writeln!(markdown, "## FRI folding schedule\n").unwrap();
+    writeln!(markdown, "### Parameters\n").unwrap();
+    writeln!(markdown, "{}", parameters.to_markdown()).unwrap();
+    writeln!(markdown, "### Schedule\n").unwrap();
+    writeln!(markdown, "- Folding bits: `{:?}`", report.schedule).unwrap();
+    writeln!(markdown, "- Folding factors: `{factors:?}`\n").unwrap();
+    writeln!(markdown, "### Proof size\n").unwrap();
+    writeln!(
+        markdown,
+        "{} field elements, {} bytes.\n",
+        total,
+        bytes(total)
+    )
+    .unwrap();
+    writeln!(markdown, "{}", sizes.to_markdown()).unwrap();
+    writeln!(markdown, "### Security\n").unwrap();
+    writeln!(
+        markdown,
+        "- {:.1} bits of security with folding challenges from a {field_bits}-bit field",
+        soundness.security_bits()
+    )
+    .unwrap();
+    writeln!(
+        markdown,
+        "- Commit phase error 2^{:.1}, query phase error 2^{:.1}\n",
+        soundness.commit_phase_error.log2(),
+        soundness.query_phase_error.log2()
+    )
+    .unwrap();
+    writeln!(markdown, "### Reproduce\n").unwrap();
+    writeln!(markdown, "```sh\n{reproduce}\n```").unwrap();
+    markdown
+}
+
+/// Formats a power of 2 as `2^k`.
+fn pow2(n: usize) -> String {
+    format!("2^{}", n.ilog2())
+}
@@ -0,0 +1,372 @@
+//! Optimizing several incommensurable objectives in strict priority order.
+
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::optimized_schedule::{
+    estimate_proof_size_unchecked, for_each_schedule, is_remainder_feasible, size_in_bytes,
+    validate_parameters,
+};
+use super::prover_cost::estimate_prover_memory;
+use super::report::FoldingReport;
+use super::soundness::fri_soundness;
+use super::verifier_cost::{
+    count_commitment_roots, deepest_path, estimate_verifier_field_ops, estimate_verifier_hashes,
+    estimate_verifier_inversions, estimate_verifier_memory, remainder_check_cost,
+};
+
+/// A quantity a folding schedule is ranked by. Every objective is minimized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// The estimated proof size in field elements.
+    ProofSize,
+    /// The estimated proof size plus the cost of the verifier's check of the remainder, see
+    /// [`remainder_check_cost`], which is what the optimizer minimizes.
+    WeightedProofSize,
+    /// The number of hashes the verifier computes.
+    VerifierHashes,
+    /// The number of field multiplications the verifier performs.
+    VerifierFieldOps,
+    /// The number of field inversions the verifier performs.
+    VerifierInversions,
+    /// The peak verifier memory in bytes.
+    VerifierMemory,
+    /// The peak prover memory in bytes, or `usize::MAX` if it does not fit in a `usize`.
+    ProverMemory,
+    /// The number of Merkle roots in the proof.
+    CommitmentRoots,
+    /// The number of digests in the longest authentication path of a single query.
+    DeepestPath,
+    /// Whether the schedule falls short of `target_bits` bits of security with folding challenges
+    /// drawn from a field of `field_bits` bits: 0 if it meets the target and 1 otherwise, so that
+    /// every schedule meeting the target ranks equally.
+    SecurityShortfall { target_bits: u32, field_bits: u32 },
+}
+
+impl Objective {
+    /// Returns the value of the objective for a folding schedule.
+    ///
+    /// # Arguments
+    /// * `degree` - The degree of the polynomial to be proved
+    /// * `blowup_factor` - The blowup factor used in the FRI protocol
+    /// * `num_queries` - The number of queries used in the FRI protocol
+    /// * `folding_seq` - The folding sequence
+    /// * `cost_model` - The cost model used to price the schedule
+    pub fn value(
+        &self,
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        folding_seq: &[usize],
+        cost_model: &CostModel,
+    ) -> usize {
+        match *self {
+            Objective::ProofSize => estimate_proof_size_unchecked(
+                degree,
+                blowup_factor,
+                num_queries,
+                folding_seq,
+                cost_model,
+            ),
+            Objective::WeightedProofSize => {
+                estimate_proof_size_unchecked(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    folding_seq,
+                    cost_model,
+                ) + remainder_check_cost(degree, blowup_factor, folding_seq, cost_model)
+            }
+            Objective::VerifierHashes => {
+                estimate_verifier_hashes(degree, num_queries, folding_seq, cost_model)
+            }
+            Objective::VerifierFieldOps => estimate_verifier_field_ops(
+                degree,
+                blowup_factor,
+                num_queries,
+                folding_seq,
+                cost_model,
+            ),
+            Objective::VerifierInversions => {
+                estimate_verifier_inversions(num_queries, folding_seq, cost_model)
+                    .iter()
+                    .sum()
+            }
+            Objective::VerifierMemory => {
+                estimate_verifier_memory(degree, blowup_factor, folding_seq, cost_model)
+            }
+            // memory that does not fit in a `usize` ranks after every schedule whose memory does.
+            Objective::ProverMemory => {
+                estimate_prover_memory(degree, blowup_factor, folding_seq, cost_model)
+                    .ok()
+                    .and_then(|memory| usize::try_from(memory).ok())
+                    .unwrap_or(usize::MAX)
+            }
+            Objective::CommitmentRoots => count_commitment_roots(folding_seq, cost_model),
+            Objective::DeepestPath => deepest_path(degree, folding_seq, cost_model),
+            Objective::SecurityShortfall {
+                target_bits,
+                field_bits,
+            } => {
+                let soundness =
+                    fri_soundness(degree, blowup_factor, num_queries, folding_seq, field_bits);
+                (soundness.security_bits() < target_bits as f64) as usize
+            }
+        }
+    }
+}
+
+/// Computes the folding strategy that minimizes `objectives` in priority order: among all
+/// schedules the optimizer considers, those minimizing the first objective are kept, among them
+/// those minimizing the second, and so on. Schedules that tie on every objective are broken in
+/// favor of the lexicographically smallest schedule.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `objectives` - The objectives, most important first
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `values` - The value of every objective for the optimal schedule, in the order given
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Panics
+/// * If the cost model interpolates the remainder from the last layer, see
+///   [`RemainderStrategy`](super::cost_model::RemainderStrategy), and there are no queries
+pub fn optimal_lexicographic(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    objectives: &[Objective],
+    cost_model: &CostModel,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut optimum: Option<(Vec<usize>, Vec<usize>)> = None;
+    for_each_schedule(degree, blowup_factor, &mut |seq| {
+        if !is_remainder_feasible(degree, blowup_factor, num_queries, seq, cost_model) {
+            return;
+        }
+        let values: Vec<_> = objectives
+            .iter()
+            .map(|objective| objective.value(degree, blowup_factor, num_queries, seq, cost_model))
+            .collect();
+
+        // vectors compare lexicographically.
+        if optimum
+            .as_ref()
+            .is_none_or(|(optimal_values, _)| values < *optimal_values)
+        {
+            optimum = Some((values, seq.to_vec()));
+        }
+    });
+    optimum.expect("folding down to degree 1 leaves a feasible remainder given any query")
+}
+
+/// Computes the folding strategy with the shortest longest authentication path, see
+/// [`deepest_path`], which bounds the circuit of a recursive verifier that unrolls its path
+/// checks. Folding less only ever moves work into the remainder, so only schedules folding the
+/// polynomial down to at most `remainder_max_degree` are considered, or down to degree 1 for a
+/// bound of 0. Schedules with equally deep paths are ranked by proof size.
+///
+/// Only a first committed layer that is folded can have a shorter path, so the objective matters
+/// when the initial layer is checked outside of the proof:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::Exclusions;
+/// use fri_dynamic_folding_scheme::objective::optimal_minimizing_deepest_path;
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::verifier_cost::deepest_path;
+///
+/// let included = CostModel { coset_layout: CosetLayout::SplitLeaves, ..CostModel::default() };
+/// let excluded = CostModel { exclude: Exclusions::INITIAL_LAYER, ..included };
+///
+/// // the unfolded initial layer is always the deepest, so the smallest proof wins.
+/// let (_, schedule) = optimal_minimizing_deepest_path(1 << 25, 8, 27, 64, &included).unwrap();
+/// assert_eq!(deepest_path(1 << 25, &schedule, &included), 25);
+///
+/// // without it, the first folded layer folds by as much as it can.
+/// let (_, schedule) = optimal_minimizing_deepest_path(1 << 25, 8, 27, 64, &excluded).unwrap();
+/// assert_eq!(schedule[1], 4);
+/// assert_eq!(deepest_path(1 << 25, &schedule, &excluded), 21);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `remainder_max_degree` - The maximum degree of the remainder polynomial
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `optimal_size` - The proof size of the optimal schedule in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's remainder strategy cannot interpolate any remainder within the bound
+pub fn optimal_minimizing_deepest_path(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    remainder_max_degree: usize,
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    let mut optimum: Option<((usize, usize), Vec<usize>)> = None;
+    for_each_schedule(degree, blowup_factor, &mut |seq| {
+        let remainder_degree = (degree / blowup_factor) >> seq.iter().sum::<usize>();
+        if remainder_degree > remainder_max_degree.max(1)
+            || !is_remainder_feasible(degree, blowup_factor, num_queries, seq, cost_model)
+        {
+            return;
+        }
+
+        // tuples compare lexicographically.
+        let values = (
+            deepest_path(degree, seq, cost_model),
+            estimate_proof_size_unchecked(degree, blowup_factor, num_queries, seq, cost_model),
+        );
+        if optimum
+            .as_ref()
+            .is_none_or(|(optimal_values, _)| values < *optimal_values)
+        {
+            optimum = Some((values, seq.to_vec()));
+        }
+    });
+    // folding down to degree 1 always meets the remainder bound, so only the remainder strategy
+    // can rule out every schedule.
+    let ((_, size), schedule) =
+        optimum.ok_or(FoldingError::NoInterpolableRemainder { num_queries })?;
+    Ok((size, schedule))
+}
+
+/// Finds the folding schedule whose proof comes closest to `target_bytes` without exceeding it,
+/// among all schedules the optimizer considers, for a proof that has to fill a fixed budget
+/// rather than be as small as possible. Schedules of equal size are broken in favor of the
+/// lexicographically smallest one.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::objective::closest_to_target_size;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let report = closest_to_target_size(1 << 20, 8, 27, 150_000, &cost_model).unwrap();
+/// let bytes = size_in_bytes(report.total_elements(), &cost_model);
+/// assert!(bytes <= 150_000 && bytes > 140_000);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `target_bytes` - The size in bytes the proof may not exceed
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `report` - The report of the schedule closest to the target
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If even the smallest proof is larger than `target_bytes`
+pub fn closest_to_target_size(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    target_bytes: usize,
+    cost_model: &CostModel,
+) -> Result<FoldingReport, FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    let mut closest: Option<(usize, Vec<usize>)> = None;
+    for_each_schedule(degree, blowup_factor, &mut |seq| {
+        if !is_remainder_feasible(degree, blowup_factor, num_queries, seq, cost_model) {
+            return;
+        }
+        let size =
+            estimate_proof_size_unchecked(degree, blowup_factor, num_queries, seq, cost_model);
+        if size_in_bytes(size, cost_model) <= target_bytes
+            && closest
+                .as_ref()
+                .is_none_or(|(closest_size, _)| size > *closest_size)
+        {
+            closest = Some((size, seq.to_vec()));
+        }
+    });
+    let (_, schedule) = closest.ok_or(FoldingError::TargetSizeUnreachable { target_bytes })?;
+    Ok(FoldingReport::new(
+        degree,
+        blowup_factor,
+        num_queries,
+        schedule,
+        cost_model,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the size of every schedule the optimizer considers at 2^10, a blowup factor of 4
+    /// and 3 queries, in enumeration order.
+    fn every_size(cost_model: &CostModel) -> Vec<(usize, Vec<usize>)> {
+        let mut sizes = Vec::new();
+        for_each_schedule(1 << 10, 4, &mut |seq| {
+            sizes.push((
+                estimate_proof_size_unchecked(1 << 10, 4, 3, seq, cost_model),
+                seq.to_vec(),
+            ));
+        });
+        sizes
+    }
+
+    #[test]
+    fn closest_is_the_largest_proof_within_the_target() {
+        let cost_model = CostModel::default();
+        let sizes = every_size(&cost_model);
+        for &(size, _) in &sizes {
+            let bytes = size_in_bytes(size, &cost_model);
+            for target_bytes in [bytes, bytes + 1] {
+                let report =
+                    closest_to_target_size(1 << 10, 4, 3, target_bytes, &cost_model).unwrap();
+                let fitting = sizes
+                    .iter()
+                    .map(|&(size, _)| size)
+                    .filter(|&size| size_in_bytes(size, &cost_model) <= target_bytes)
+                    .max();
+                assert_eq!(Some(report.total_elements()), fitting, "{target_bytes}");
+            }
+        }
+    }
+
+    #[test]
+    fn closest_breaks_ties_lexicographically() {
+        let cost_model = CostModel::default();
+        let sizes = every_size(&cost_model);
+        let (size, _) = sizes
+            .iter()
+            .find(|(size, _)| sizes.iter().filter(|(other, _)| other == size).count() > 1)
+            .unwrap();
+        let smallest = sizes
+            .iter()
+            .filter(|(other, _)| other == size)
+            .map(|(_, seq)| seq)
+            .min()
+            .unwrap();
+        let target_bytes = size_in_bytes(*size, &cost_model);
+        let report = closest_to_target_size(1 << 10, 4, 3, target_bytes, &cost_model).unwrap();
+        assert_eq!(&report.schedule, smallest);
+    }
+
+    #[test]
+    fn target_below_the_optimum_is_unreachable() {
+        let cost_model = CostModel::default();
+        let optimum = every_size(&cost_model)
+            .into_iter()
+            .map(|(size, _)| size)
+            .min();
+        let target_bytes = size_in_bytes(optimum.unwrap(), &cost_model) - 1;
+        assert_eq!(
+            closest_to_target_size(1 << 10, 4, 3, target_bytes, &cost_model).err(),
+            Some(FoldingError::TargetSizeUnreachable { target_bytes })
+        );
+    }
+}
@@ -1,126 +1,2076 @@
-const ELEMENTS_IN_HASH_OUTPUT: usize = 4;
-const FE_IN_EACH_ELEMENTS: usize = 2;
-const ELEMENT_SIZE: usize = 8; // in bytes
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::batch::{estimate_injection_elements, DegreeGroup};
+use super::cost_model::{CostModel, PathModel, RemainderStrategy};
+use super::error::{FoldingError, Warning};
+use super::layers::{walk_schedule, LayerFacts};
+use super::prover_cost::estimate_prover_memory;
+use super::simple_schedule::greedy_schedule;
+use super::stats::{search_space_size, SearchStats};
+use super::verifier_cost::{estimate_verifier_memory, remainder_check_cost};
+
+/// The largest folding factor, in bits, the optimizer lets a layer fold by unless told otherwise,
+/// see [`optimal_with_max_folding_factor`].
+pub const MAX_FOLDING_BITS: usize = 4;
 
 /// Computes the optimal folding strategy for a FRI proof. The function uses a heuristic to estimate
 /// the proof size in terms of field elements. It then iteratively explores different folding strategies
 /// to identify the one yielding the smallest estimated proof size.
 ///
+/// When no fold pays for its own layer, the optimal schedule is the unfolded first layer alone,
+/// the base case of the search. With a degree of 16, a blowup factor of 4 and 27 queries, the
+/// proof then holds the paths and leaves of the first layer and the remainder of degree 4:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let (size, schedule) = optimal_folding_strategy(
+///     16,
+///     4,
+///     27,
+///     vec![0],
+///     &HashMap::new(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(schedule, vec![0]);
+/// // 27 paths of 4 digests of 4 elements, 27 leaves of 2 elements, and 4 coefficients of 2.
+/// assert_eq!(size, 27 * 4 * 4 + 27 * 2 + 4 * 2);
+/// assert_eq!(size, estimate_proof_size(16, 4, 27, &[0], &cost_model).unwrap());
+///
+/// // without any degree to fold, the search stops at the first layer too.
+/// let (_, schedule) = optimal_folding_strategy(
+///     8,
+///     8,
+///     27,
+///     vec![0],
+///     &HashMap::new(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(schedule, vec![0]);
+///
+/// // the search never starts from an empty schedule.
+/// let empty = optimal_folding_strategy(
+///     16,
+///     4,
+///     27,
+///     vec![],
+///     &HashMap::new(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// );
+/// assert_eq!(empty, Err(FoldingError::MissingInitialLayer));
+/// ```
+///
 /// # Arguments
 /// * `degree` - The degree of the polynomial to be proved
 /// * `blowup_factor` - The blowup factor used in the FRI protocol
 /// * `num_queries` - The number of queries used in the FRI protocol
 /// * `current_folding_seq` - The current folding sequence. This is used to recursively explore different
-/// folding strategies. The default value is an vector with a single element, 0(`bits`)), which corresponds to
-/// no folding. We include first FRI layer into the FRI proof without any folding. The folding factors
-/// are represented as in the form of bits. For example, a folding factor of 4 is represented as 2.
+///   folding strategies. The default value is an vector with a single element, 0(`bits`)), which corresponds to
+///   no folding. We include first FRI layer into the FRI proof without any folding. The folding factors
+///   are represented as in the form of bits. For example, a folding factor of 4 is represented as 2.
+/// * `pinned_layers` - Layers whose folding factor is forced, mapping the layer index (the position
+///   in the folding sequence) to the folding factor in bits. All other layers are optimized freely.
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
 ///
 /// # Returns
 /// * `optimal_proof` - The estimated proof size in terms of field elements
 /// * `optimal_sequences` - The optimal folding sequence
 ///
-/// # Panics
-/// * If the degree is not a power of 2
-/// * If the blowup factor is not a power of 2
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If `current_folding_seq` does not start with the unfolded first layer
+/// * If a pinned layer contradicts `current_folding_seq`
+/// * If a layer after the first is pinned to a folding factor of 1 (0 bits)
+/// * If the pinned layers would fold the polynomial below degree 1
 ///
-/// TODO: This function can be optimized further by memoizing the results. This is left as a future
-/// optimization.
-pub(crate) fn optimal_folding_strategy(
+/// The search memoizes nothing: every sequence it visits is priced in full, so the layers of a
+/// prefix shared by many sequences are priced again for each of them, and nothing is kept from
+/// one call to the next. Callers repeating the same searches cache their optima themselves, as the
+/// binary does for the requests it serves.
+pub fn optimal_folding_strategy(
     degree: usize,
     blowup_factor: usize,
     num_queries: usize,
     current_folding_seq: Vec<usize>,
-) -> (usize, Vec<usize>) {
-    // The degree and blowup factor must be powers of 2.
-    debug_assert!(degree.is_power_of_two());
-    debug_assert!(blowup_factor.is_power_of_two());
+    pinned_layers: &HashMap<usize, usize>,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    if current_folding_seq.first() != Some(&0) {
+        return Err(FoldingError::MissingInitialLayer);
+    }
+    validate_pinned_layers(degree, blowup_factor, &current_folding_seq, pinned_layers)?;
+
+    let start = Instant::now();
+    let remaining_bits =
+        (degree / blowup_factor).ilog2() as usize - current_folding_seq.iter().sum::<usize>();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(remaining_bits, MAX_FOLDING_BITS),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    // Validation guarantees that folding every free layer by 2 honors all pins, so the search
+    // always finds at least one schedule.
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits: MAX_FOLDING_BITS,
+        pinned_layers,
+        degree_groups: &[],
+        cost_model,
+        admissible: &|_| true,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    let prefix_len = current_folding_seq.len();
+    let optimum = search.explore_feasible(
+        current_folding_seq,
+        stats,
+        "validated pins always admit a schedule",
+    )?;
+
+    // Unfolded layers never reduce the proof size, so the search must not emit any beyond the
+    // ones it was given.
+    debug_assert!(optimum.1[prefix_len..].iter().all(|&bits| bits > 0));
+    stats.elapsed = start.elapsed();
+    Ok(optimum)
+}
+
+/// Computes the folding strategy yielding the smallest estimated proof size among those whose
+/// estimated peak prover memory does not exceed `max_prover_bytes`.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `max_prover_bytes` - The prover memory budget in bytes
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If no folding sequence fits within `max_prover_bytes`
+pub fn optimal_under_prover_memory(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    max_prover_bytes: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    optimal_admissible_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        cost_model,
+        stats,
+        &|folding_seq| {
+            estimate_prover_memory(degree, blowup_factor, folding_seq, cost_model)
+                .is_ok_and(|memory| memory <= max_prover_bytes as u64)
+        },
+    )
+    .ok_or(FoldingError::ProverMemoryExceeded { max_prover_bytes })
+}
+
+/// Computes the folding strategy yielding the smallest estimated proof size among those whose
+/// estimated peak verifier memory, see [`estimate_verifier_memory`], does not exceed
+/// `max_verifier_bytes`.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `max_verifier_bytes` - The verifier memory budget in bytes
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If no folding sequence fits within `max_verifier_bytes`
+pub fn optimal_under_verifier_memory(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    max_verifier_bytes: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    optimal_admissible_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        cost_model,
+        stats,
+        &|folding_seq| {
+            estimate_verifier_memory(degree, blowup_factor, folding_seq, cost_model)
+                <= max_verifier_bytes
+        },
+    )
+    .ok_or(FoldingError::VerifierMemoryExceeded { max_verifier_bytes })
+}
+
+/// Computes the optimal folding strategy for a FRI proof like [`optimal_folding_strategy`], but
+/// for a prover that only supports folding factors of at least `min_folding_bits` bits: layers
+/// folding by less are never considered. The unfolded first layer is exempt, and the sequence
+/// that stops without folding always satisfies the floor.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::optimized_schedule::optimal_with_min_folding_factor;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// for min_folding_bits in 1..=4 {
+///     let (_, schedule) = optimal_with_min_folding_factor(
+///         1 << 25,
+///         8,
+///         27,
+///         min_folding_bits,
+///         &CostModel::default(),
+///         &mut SearchStats::default(),
+///     )
+///     .unwrap();
+///     assert!(schedule[1..].iter().all(|&bits| bits >= min_folding_bits));
+/// }
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `min_folding_bits` - The smallest folding factor, in bits, a layer after the first may use
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If `min_folding_bits` is 0 or larger than the largest folding factor the optimizer considers
+pub fn optimal_with_min_folding_factor(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    min_folding_bits: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    if !(1..=MAX_FOLDING_BITS).contains(&min_folding_bits) {
+        return Err(FoldingError::InvalidMinFoldingFactor {
+            min_folding_bits,
+            max_folding_bits: MAX_FOLDING_BITS,
+        });
+    }
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            MAX_FOLDING_BITS,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits,
+        max_folding_bits: MAX_FOLDING_BITS,
+        pinned_layers: &HashMap::new(),
+        degree_groups: &[],
+        cost_model,
+        admissible: &|_| true,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    let optimum =
+        search.explore_feasible(vec![0], stats, "the unfolded schedule is always admissible")?;
+    stats.elapsed = start.elapsed();
+    Ok(optimum)
+}
+
+/// Computes the optimal folding strategy for a FRI proof like [`optimal_folding_strategy`], but
+/// with layers folding by at most `max_folding_bits` bits instead of [`MAX_FOLDING_BITS`]. The cap
+/// may be raised above the usual one, see [`optimal_probing_folding_cap`].
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `max_folding_bits` - The largest folding factor, in bits, a layer may use
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If `max_folding_bits` is 0
+pub fn optimal_with_max_folding_factor(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    max_folding_bits: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    if max_folding_bits == 0 {
+        return Err(FoldingError::InvalidMaxFoldingFactor);
+    }
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            max_folding_bits,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits,
+        pinned_layers: &HashMap::new(),
+        degree_groups: &[],
+        cost_model,
+        admissible: &|_| true,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    let optimum =
+        search.explore_feasible(vec![0], stats, "the unfolded schedule is always admissible")?;
+    stats.elapsed = start.elapsed();
+    Ok(optimum)
+}
+
+/// Whether a search bounded in time visited every folding sequence, see
+/// [`optimal_within_time_budget`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Optimality {
+    /// The search finished, so the schedule is optimal.
+    Exact,
+    /// The search ran out of time, so the schedule is the best one found and possibly
+    /// suboptimal.
+    TimedOut,
+}
+
+/// Computes the optimal folding strategy for a FRI proof like [`optimal_folding_strategy`], but
+/// stops searching once `time_budget` has elapsed. A search that runs out of time returns the
+/// smaller of the best schedule it visited and the [`greedy_schedule`], marked as
+/// [`Optimality::TimedOut`]. Either way the schedule is complete and sized by
+/// [`estimate_proof_size`], though which one a timed out search returns depends on how far it got.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::time::Duration;
+///
+/// use fri_dynamic_folding_scheme::optimized_schedule::{optimal_within_time_budget, Optimality};
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let mut stats = SearchStats::default();
+///
+/// // a generous budget lets a small search finish.
+/// let (size, schedule, optimality) =
+///     optimal_within_time_budget(1 << 20, 8, 27, Duration::from_secs(10), &cost_model, &mut stats)
+///         .unwrap();
+/// assert_eq!(optimality, Optimality::Exact);
+/// let optimum =
+///     optimal_folding_strategy(1 << 20, 8, 27, vec![0], &HashMap::new(), &cost_model, &mut stats);
+/// assert_eq!(optimum, Ok((size, schedule)));
+///
+/// // a search over 2^30 cannot finish without any time, but still returns a valid schedule.
+/// let (size, schedule, optimality) =
+///     optimal_within_time_budget(1 << 30, 2, 27, Duration::ZERO, &cost_model, &mut stats).unwrap();
+/// assert_eq!(optimality, Optimality::TimedOut);
+/// assert_eq!(validate_schedule(1 << 30, 2, &schedule), Ok(()));
+/// assert_eq!(size, estimate_proof_size(1 << 30, 2, 27, &schedule, &cost_model).unwrap());
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `time_budget` - How long the search may run
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence, or the best one found in time
+/// * `optimality` - Whether the search finished
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+pub fn optimal_within_time_budget(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    time_budget: Duration,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>, Optimality), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            MAX_FOLDING_BITS,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    // A budget too large to add to the clock never runs out.
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits: MAX_FOLDING_BITS,
+        pinned_layers: &HashMap::new(),
+        degree_groups: &[],
+        cost_model,
+        admissible: &|_| true,
+        deadline: start.checked_add(time_budget),
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    // The unfolded schedule is visited before the first deadline check, but the remainder
+    // strategy may rule it out.
+    let mut optimum = search.explore(vec![0], stats).map(|(_, schedule)| {
+        let cost = search.terminal_cost(&schedule);
+        (
+            cost.expect("the search only returns feasible schedules"),
+            schedule,
+        )
+    });
+    let optimality = if search.timed_out.get() {
+        // The greedy schedule is feasible whenever any schedule is.
+        let (_, greedy) = greedy_schedule(degree, blowup_factor, num_queries, cost_model)?;
+        if let Some(cost) = search.terminal_cost(&greedy) {
+            if optimum
+                .as_ref()
+                .is_none_or(|(optimal_cost, _)| cost < *optimal_cost)
+            {
+                optimum = Some((cost, greedy));
+            }
+        }
+        Optimality::TimedOut
+    } else {
+        Optimality::Exact
+    };
+    let ((_, size), schedule) =
+        optimum.ok_or(FoldingError::NoInterpolableRemainder { num_queries })?;
+    stats.elapsed = start.elapsed();
+    Ok((size, schedule, optimality))
+}
+
+/// A cap on the folding factor that keeps the optimizer from a cheaper proof, see
+/// [`optimal_probing_folding_cap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapNotice {
+    /// The cap, in bits.
+    pub cap_bits: usize,
+    /// The layers of the capped optimum folding by exactly the cap.
+    pub layers: Vec<usize>,
+    /// The optimal folding sequence with the cap raised by one bit.
+    pub raised_schedule: Vec<usize>,
+    /// The estimated proof size of the raised optimum, in field elements.
+    pub raised_size: usize,
+    /// How much raising the cap lowers the cost the optimizer minimizes, the proof size plus the
+    /// verifier's check of the remainder, see [`remainder_check_cost`].
+    pub saved_cost: usize,
+}
+
+/// Computes the optimal folding strategy like [`optimal_with_max_folding_factor`], and records in
+/// a [`Warning::BindingFoldingCap`] among the warnings of its [`SearchStats`] whether the cap of
+/// `max_folding_bits` bits binds: whether letting a layer fold by one bit more lowers the cost the
+/// search minimizes. The search visits the sequences folding by one bit more alongside the others,
+/// but ranks them apart, so its optimum is the capped one, and its statistics count both.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::optimized_schedule::{
+///     optimal_probing_folding_cap, optimal_with_max_folding_factor, MAX_FOLDING_BITS,
+/// };
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let probe = |cap| {
+///     let mut stats = SearchStats::default();
+///     let optimum = optimal_probing_folding_cap(1 << 25, 8, 27, cap, &cost_model, &mut stats);
+///     let notice = stats.warnings.into_iter().find_map(|warning| match warning {
+///         Warning::BindingFoldingCap(notice) => Some(notice),
+///         _ => None,
+///     });
+///     (optimum.unwrap(), notice)
+/// };
+/// let optimum = |cap| {
+///     let mut stats = SearchStats::default();
+///     optimal_with_max_folding_factor(1 << 25, 8, 27, cap, &cost_model, &mut stats).unwrap()
+/// };
+///
+/// // the usual cap binds: folding the first layer by 32 saves a layer.
+/// let ((_, capped), notice) = probe(MAX_FOLDING_BITS);
+/// assert_eq!(capped, vec![0, 4, 4, 4, 3]);
+/// let notice = notice.unwrap();
+/// assert_eq!(notice.layers, vec![1, 2, 3]);
+/// assert_eq!((notice.raised_size, notice.raised_schedule), optimum(MAX_FOLDING_BITS + 1));
+/// assert_eq!(notice.saved_cost, 12622 - 12418);
+///
+/// // a cap of 32 does not bind.
+/// assert_eq!(probe(5), (optimum(5), None));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `max_folding_bits` - The largest folding factor, in bits, a layer may use
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search, and whether the cap binds
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence under the cap
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+/// * If `max_folding_bits` is 0
+pub fn optimal_probing_folding_cap(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    max_folding_bits: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    if max_folding_bits == 0 {
+        return Err(FoldingError::InvalidMaxFoldingFactor);
+    }
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            max_folding_bits + 1,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits,
+        pinned_layers: &HashMap::new(),
+        degree_groups: &[],
+        cost_model,
+        admissible: &|_| true,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: true,
+    };
+    let optimum =
+        search.explore_feasible(vec![0], stats, "the unfolded schedule is always admissible")?;
+    stats.elapsed = start.elapsed();
+    Ok(optimum)
+}
+
+/// Computes the optimal folding strategy for a FRI proof like [`optimal_folding_strategy`], but
+/// only among the folding sequences whose remainder has exactly `target_remainder_degree`, for a
+/// verifier whose final polynomial check has a fixed size. The remainder degree of a sequence is
+/// the polynomial degree `degree / blowup_factor` divided by every folding factor.
+///
+/// The unconstrained search prices stopping at every degree it reaches with the same terminal
+/// cost, so constraining it to the degree its optimum stops at finds that optimum again:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::optimized_schedule::optimal_with_target_remainder_degree;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let optimal = |target| {
+///     let mut stats = SearchStats::default();
+///     optimal_with_target_remainder_degree(1 << 25, 8, 27, target, &cost_model, &mut stats)
+/// };
+///
+/// // [0, 4, 4, 4, 3] folds 2^22 down to 2^7.
+/// assert_eq!(optimal(128), Ok((12622, vec![0, 4, 4, 4, 3])));
+/// assert_eq!(optimal(1).unwrap().1.iter().sum::<usize>(), 22);
+///
+/// // degree 100 is not a power of 2, and 2^23 is above the polynomial degree.
+/// assert!(matches!(optimal(100), Err(FoldingError::UnreachableRemainderDegree { .. })));
+/// assert!(matches!(optimal(1 << 23), Err(FoldingError::UnreachableRemainderDegree { .. })));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `target_remainder_degree` - The exact degree of the remainder polynomial
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If `target_remainder_degree` is not a power of 2 no larger than `degree / blowup_factor`
+/// * If the cost model's remainder strategy cannot interpolate a remainder of
+///   `target_remainder_degree`
+pub fn optimal_with_target_remainder_degree(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    target_remainder_degree: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    let poly_degree = degree / blowup_factor;
+    if !target_remainder_degree.is_power_of_two() || target_remainder_degree > poly_degree {
+        return Err(FoldingError::UnreachableRemainderDegree {
+            target_remainder_degree,
+            poly_degree,
+        });
+    }
+
+    if !cost_model
+        .remainder_strategy
+        .is_feasible(target_remainder_degree, num_queries)
+    {
+        return Err(FoldingError::RemainderNotInterpolable {
+            remainder_degree: target_remainder_degree,
+            num_queries,
+        });
+    }
+
+    // Any number of bits splits into folds of 1 to `MAX_FOLDING_BITS` bits.
+    Ok(optimal_admissible_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        cost_model,
+        stats,
+        &|folding_seq| poly_degree >> folding_seq.iter().sum::<usize>() == target_remainder_degree,
+    )
+    .expect("a power of 2 below the polynomial degree is always reachable"))
+}
+
+/// Searches every folding sequence without pins and returns the smallest one `admissible`
+/// accepts, or `None` if it accepts none.
+pub(crate) fn optimal_admissible_strategy(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+    admissible: &dyn Fn(&[usize]) -> bool,
+) -> Option<(usize, Vec<usize>)> {
+    // Every caller validates the degree and blowup factor.
+    debug_assert!(validate_parameters(degree, blowup_factor).is_ok());
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            MAX_FOLDING_BITS,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits: MAX_FOLDING_BITS,
+        pinned_layers: &HashMap::new(),
+        degree_groups: &[],
+        cost_model,
+        admissible,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    let optimum = search.explore(vec![0], stats);
+    stats.elapsed = start.elapsed();
+    optimum
+}
+
+/// Computes the optimal folding strategy for a FRI proof that batches columns of several degrees.
+/// Every degree group is committed in its own tree and combined into the codeword at the first
+/// layer whose domain is no larger than the group's, and the size of its openings is added to the
+/// proof, see [`estimate_injection_elements`]. Only folding sequences whose remainder domain is
+/// no larger than every group's domain are considered, so that every group is combined.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved, i.e. the size of the largest domain
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `degree_groups` - The degree groups combined into the codeword
+/// * `pinned_layers` - Layers whose folding factor is forced, mapping the layer index to the
+///   folding factor in bits
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+/// * `stats` - Receives statistics about the search
+///
+/// # Returns
+/// * `optimal_proof` - The estimated proof size in terms of field elements, including the
+///   openings of the degree groups
+/// * `optimal_sequences` - The optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If a degree group's domain is larger than `degree` or smaller than `blowup_factor`
+/// * If the pinned layers cannot be honored, as for [`optimal_folding_strategy`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder the search may stop at
+pub fn optimal_batched_folding_strategy(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    degree_groups: &[DegreeGroup],
+    pinned_layers: &HashMap<usize, usize>,
+    cost_model: &CostModel,
+    stats: &mut SearchStats,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    if let Some(group) = degree_groups
+        .iter()
+        .find(|group| group.domain_size > degree || group.domain_size < blowup_factor)
+    {
+        return Err(FoldingError::DegreeGroupOutOfRange {
+            domain_size: group.domain_size,
+            blowup_factor,
+            degree,
+        });
+    }
+    validate_pinned_layers(degree, blowup_factor, &[0], pinned_layers)?;
+    cost_model.check_semantics_version()?;
+
+    let start = Instant::now();
+    *stats = SearchStats {
+        theoretical_nodes: search_space_size(
+            (degree / blowup_factor).ilog2() as usize,
+            MAX_FOLDING_BITS,
+        ),
+        warnings: cost_model.warnings(),
+        ..SearchStats::default()
+    };
+
+    // every group must be combined before the remainder is sent.
+    let min_domain_size = degree_groups
+        .iter()
+        .map(|group| group.domain_size)
+        .min()
+        .unwrap_or(degree);
+    let search = Search {
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits: 1,
+        max_folding_bits: MAX_FOLDING_BITS,
+        pinned_layers,
+        degree_groups,
+        cost_model,
+        admissible: &|folding_seq| degree >> folding_seq.iter().sum::<usize>() <= min_domain_size,
+        deadline: None,
+        timed_out: Cell::new(false),
+        probe_raised_cap: false,
+    };
+    // Folding all the way down to the blowup factor after the last pin combines every group.
+    let optimum = search.explore_feasible(
+        vec![0],
+        stats,
+        "validated groups and pins always admit a schedule",
+    )?;
+    stats.elapsed = start.elapsed();
+    Ok(optimum)
+}
+
+/// The inputs shared by every node of a search over folding sequences.
+struct Search<'a> {
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    /// The smallest folding factor, in bits, an unpinned layer may use.
+    min_folding_bits: usize,
+    /// The largest folding factor, in bits, an unpinned layer may use.
+    max_folding_bits: usize,
+    /// Layers whose folding factor is forced.
+    pinned_layers: &'a HashMap<usize, usize>,
+    /// The degree groups whose openings are added to every folding sequence.
+    degree_groups: &'a [DegreeGroup],
+    cost_model: &'a CostModel,
+    /// Decides whether a complete folding sequence satisfies the constraints of the search.
+    admissible: &'a dyn Fn(&[usize]) -> bool,
+    /// The time after which the search stops exploring and keeps the best sequence found so far,
+    /// if it has one.
+    deadline: Option<Instant>,
+    /// Whether the search ran past its deadline before exploring every sequence.
+    timed_out: Cell<bool>,
+    /// Whether unpinned layers may also fold by one bit more than `max_folding_bits`, in
+    /// sequences that are only ranked among themselves, to find out whether the cap binds.
+    probe_raised_cap: bool,
+}
+
+/// The cost and size of the best sequence a subtree of the search found, with the sequence.
+type Optimum = Option<((usize, usize), SequenceRef)>;
+
+/// A folding sequence stored as its last folding factor and a pointer to the sequence before it.
+/// Extending a sequence, or keeping it as the best one found, takes constant time however long it
+/// is, and sequences sharing a prefix share its layers.
+struct SharedSequence {
+    bits: usize,
+    parent: SequenceRef,
+}
+
+/// A possibly empty [`SharedSequence`].
+type SequenceRef = Option<Rc<SharedSequence>>;
+
+impl SharedSequence {
+    /// Returns `sequence` extended by a layer folding by `bits`.
+    fn push(sequence: &SequenceRef, bits: usize) -> SequenceRef {
+        Some(Rc::new(SharedSequence {
+            bits,
+            parent: sequence.clone(),
+        }))
+    }
+
+    /// Returns the folding factors of `sequence`, first layer first.
+    fn to_vec(sequence: &SequenceRef) -> Vec<usize> {
+        let mut folding_seq = Vec::new();
+        let mut layer = sequence.as_deref();
+        while let Some(SharedSequence { bits, parent }) = layer {
+            folding_seq.push(*bits);
+            layer = parent.as_deref();
+        }
+        folding_seq.reverse();
+        folding_seq
+    }
+}
+
+impl Search<'_> {
+    /// Runs [`Self::explore`] for a search whose constraints admit some schedule, so that it only
+    /// fails if no remainder it can stop at is feasible under the cost model's remainder strategy.
+    /// `invariant` says why the constraints admit a schedule.
+    fn explore_feasible(
+        &self,
+        current_folding_seq: Vec<usize>,
+        stats: &mut SearchStats,
+        invariant: &str,
+    ) -> Result<(usize, Vec<usize>), FoldingError> {
+        match self.explore(current_folding_seq, stats) {
+            Some(optimum) => Ok(optimum),
+            None if self.cost_model.remainder_strategy != RemainderStrategy::Sent => {
+                Err(FoldingError::NoInterpolableRemainder {
+                    num_queries: self.num_queries,
+                })
+            }
+            None => panic!("{invariant}"),
+        }
+    }
+
+    /// Explores every folding sequence extending `current_folding_seq` that honors the pinned
+    /// layers and is admissible, returning the smallest one, or `None` if there is no such
+    /// sequence. A search probing the raised cap records in `stats` whether the cap binds.
+    fn explore(
+        &self,
+        mut current_folding_seq: Vec<usize>,
+        stats: &mut SearchStats,
+    ) -> Option<(usize, Vec<usize>)> {
+        let prefix = current_folding_seq.iter().fold(None, |sequence, &bits| {
+            SharedSequence::push(&sequence, bits)
+        });
+        let (optimum, raised) = self.explore_from(&mut current_folding_seq, prefix, false, stats);
+        let ((cost, size), optimum) = optimum?;
+        let optimum = SharedSequence::to_vec(&optimum);
+
+        // The search must price its optimum exactly as the estimator does, so that it is compared
+        // with other schedules on equal terms.
+        debug_assert!(
+            !self.degree_groups.is_empty()
+                || size
+                    == estimate_proof_size_unchecked(
+                        self.degree,
+                        self.blowup_factor,
+                        self.num_queries,
+                        &optimum,
+                        self.cost_model,
+                    )
+        );
+
+        // the raised optimum ranges over the capped sequences as well, so it is never worse.
+        if let Some(((raised_cost, raised_size), raised)) = raised {
+            if raised_cost < cost {
+                stats.warnings.push(Warning::BindingFoldingCap(CapNotice {
+                    cap_bits: self.max_folding_bits,
+                    layers: (1..optimum.len())
+                        .filter(|&layer| optimum[layer] == self.max_folding_bits)
+                        .collect(),
+                    raised_schedule: SharedSequence::to_vec(&raised),
+                    raised_size,
+                    saved_cost: cost - raised_cost,
+                }));
+            }
+        }
+        Some((size, optimum))
+    }
+
+    /// Recursively explores every folding sequence extending `current_folding_seq`, which
+    /// `sequence` holds as well. Every layer folds by at least 1 bit, so the recursion is never
+    /// deeper than `log2(degree / blowup_factor)` calls, i.e. less than 64.
+    ///
+    /// Only a layer with a choice of factors recurses: while a single factor is possible, the
+    /// sequence is extended in place, and once none is, the best sequence found is returned.
+    /// Every layer pushed onto `current_folding_seq` is popped again before returning, so a
+    /// visited sequence is never copied, and only the winner is turned into a `Vec` at the end.
+    ///
+    /// Sequences are ranked by their cost and then by their size, see [`Self::terminal_cost`].
+    /// Returns the best sequence within the cap, and, if the search probes the raised cap, the
+    /// best sequence of all, `over_cap` telling whether the sequence so far already exceeds the
+    /// cap.
+    fn explore_from(
+        &self,
+        current_folding_seq: &mut Vec<usize>,
+        mut sequence: SequenceRef,
+        over_cap: bool,
+        stats: &mut SearchStats,
+    ) -> (Optimum, Optimum) {
+        stats.calls += 1;
+
+        let prefix_len = current_folding_seq.len();
+        let mut optimum = None;
+        let mut raised = None;
+        let candidates = loop {
+            stats.nodes_explored += 1;
+            if let Some(cost) = self.terminal_cost(current_folding_seq) {
+                if !over_cap {
+                    keep_smaller(&mut optimum, Some((cost, sequence.clone())));
+                }
+                if self.probe_raised_cap {
+                    keep_smaller(&mut raised, Some((cost, sequence.clone())));
+                }
+            }
 
-    let mut optimal_sequences = current_folding_seq.clone();
-    let mut optimal_proof =
-        estimate_proof_size(degree, blowup_factor, num_queries, &optimal_sequences);
+            match self.candidates(current_folding_seq) {
+                Some(candidates) if candidates.start() != candidates.end() => break candidates,
+                Some(candidates) => {
+                    current_folding_seq.push(*candidates.start());
+                    sequence = SharedSequence::push(&sequence, *candidates.start());
+                }
+                None => {
+                    current_folding_seq.truncate(prefix_len);
+                    return (optimum, raised);
+                }
+            }
+        };
 
-    // The current layer degree is the degree of the polynomial at the current layer. This is
-    // initialized to the degree of the polynomial to be proved. At each layer, the degree is
-    // divided by the folding factor.
-    let folding_sum = (1 << current_folding_seq.iter().sum::<usize>()) as usize;
-    let current_layer_degree: usize = degree / folding_sum;
+        for factor in candidates {
+            if self.out_of_time() {
+                break;
+            }
+            current_folding_seq.push(factor);
 
-    // The maximum folding factor is the largest power of 2 that divides the current layer degree.
-    // This is capped at 4.
-    let max_folding_factor = ((current_layer_degree / blowup_factor).ilog2() as usize).min(4);
+            // The size of the proof is the sum of the size of the proof at the current layer and
+            // the size of the proof at the next layer.
+            let next = SharedSequence::push(&sequence, factor);
+            let over_cap = over_cap || factor > self.max_folding_bits;
+            let (explored, explored_raised) =
+                self.explore_from(current_folding_seq, next, over_cap, stats);
+            current_folding_seq.pop();
 
-    for factor in 1..=max_folding_factor {
-        let mut sequences_this_layer = current_folding_seq.clone();
-        sequences_this_layer.push(factor);
+            // If the cost of the proof is smaller than the current optimal cost, update the
+            // optimal cost and the optimal folding sequence.
+            keep_smaller(&mut optimum, explored);
+            keep_smaller(&mut raised, explored_raised);
+        }
+        current_folding_seq.truncate(prefix_len);
+        (optimum, raised)
+    }
+
+    /// Returns whether the search has run past its deadline. The clock is read before every
+    /// branch, and once the deadline has passed, the search unwinds without reading it again.
+    fn out_of_time(&self) -> bool {
+        if let Some(deadline) = self.deadline {
+            if !self.timed_out.get() && Instant::now() >= deadline {
+                self.timed_out.set(true);
+            }
+        }
+        self.timed_out.get()
+    }
+
+    /// Returns the cost and the size of the proof if the sequence stops at `current_folding_seq`,
+    /// or `None` if it may not stop there, including when the cost model's remainder strategy
+    /// cannot learn the remainder left there. The cost adds the verifier's check of the remainder,
+    /// see [`remainder_check_cost`], to the size.
+    fn terminal_cost(&self, current_folding_seq: &[usize]) -> Option<(usize, usize)> {
+        // The sequence may only stop here if no pinned layer remains ahead of it.
+        let next_layer = current_folding_seq.len();
+        if self.pinned_layers.keys().any(|&layer| layer >= next_layer)
+            || !(self.admissible)(current_folding_seq)
+            || !is_remainder_feasible(
+                self.degree,
+                self.blowup_factor,
+                self.num_queries,
+                current_folding_seq,
+                self.cost_model,
+            )
+        {
+            return None;
+        }
+        let size = estimate_proof_size_unchecked(
+            self.degree,
+            self.blowup_factor,
+            self.num_queries,
+            current_folding_seq,
+            self.cost_model,
+        ) + estimate_injection_elements(
+            self.degree,
+            self.num_queries,
+            current_folding_seq,
+            self.degree_groups,
+            self.cost_model,
+        );
+        let check_cost = remainder_check_cost(
+            self.degree,
+            self.blowup_factor,
+            current_folding_seq,
+            self.cost_model,
+        );
+        Some((size + check_cost, size))
+    }
+
+    /// Returns the folding factors, in bits, the layer after `current_folding_seq` may fold by,
+    /// or `None` if the sequence cannot be extended.
+    fn candidates(&self, current_folding_seq: &[usize]) -> Option<RangeInclusive<usize>> {
+        // The current layer degree is the degree of the polynomial at the current layer. This is
+        // initialized to the degree of the polynomial to be proved. At each layer, the degree is
+        // divided by the folding factor.
+        let current_layer_degree = self.degree >> current_folding_seq.iter().sum::<usize>();
+
+        // The maximum folding factor is the largest power of 2 that divides the current layer
+        // degree. This is capped at the search's maximum, and must not fall below the minimum.
+        let available_bits = (current_layer_degree / self.blowup_factor).ilog2() as usize;
+        let max_folding_bits = self.max_folding_bits + self.probe_raised_cap as usize;
+        let max_folding_factor = available_bits.min(max_folding_bits);
+
+        // A pinned layer only admits its forced factor, provided it does not over-fold.
+        match self.pinned_layers.get(&current_folding_seq.len()) {
+            Some(&bits) if bits <= available_bits => Some(bits..=bits),
+            Some(_) => None,
+            None => Some(self.min_folding_bits..=max_folding_factor)
+                .filter(|candidates| !candidates.is_empty()),
+        }
+    }
+}
+
+/// Replaces `optimum` with `candidate` if the candidate costs less, ties keeping the sequence found
+/// first.
+fn keep_smaller(optimum: &mut Optimum, candidate: Optimum) {
+    let Some((cost, _)) = &candidate else {
+        return;
+    };
+    // tuples compare lexicographically.
+    if optimum
+        .as_ref()
+        .is_none_or(|(optimal_cost, _)| cost < optimal_cost)
+    {
+        *optimum = candidate;
+    }
+}
+
+/// Calls `visit` with every folding sequence the optimizer considers: the unfolded first layer
+/// followed by any number of layers folding by between 1 and `MAX_FOLDING_BITS` bits, without
+/// folding the polynomial below degree 1.
+pub(crate) fn for_each_schedule(
+    degree: usize,
+    blowup_factor: usize,
+    visit: &mut impl FnMut(&[usize]),
+) {
+    fn extend(seq: &mut Vec<usize>, remaining_bits: usize, visit: &mut impl FnMut(&[usize])) {
+        visit(seq);
+        for bits in 1..=remaining_bits.min(MAX_FOLDING_BITS) {
+            seq.push(bits);
+            extend(seq, remaining_bits - bits, visit);
+            seq.pop();
+        }
+    }
+
+    let available_bits = (degree / blowup_factor).ilog2() as usize;
+    extend(&mut vec![0], available_bits, visit);
+}
+
+/// Checks that `pinned_layers` can be honored by some folding sequence extending
+/// `current_folding_seq`, i.e. that they agree with the sequence so far, never pin a trivial fold
+/// after the first layer, and leave enough degree to fold every unpinned layer in between by 2.
+fn validate_pinned_layers(
+    degree: usize,
+    blowup_factor: usize,
+    current_folding_seq: &[usize],
+    pinned_layers: &HashMap<usize, usize>,
+) -> Result<(), FoldingError> {
+    let mut layers: Vec<_> = pinned_layers.iter().map(|(&l, &b)| (l, b)).collect();
+    layers.sort_unstable();
 
-        // The size of the proof is the sum of the size of the proof at the current layer and the
-        // size of the proof at the next layer.
-        let (size, sequences_layer) =
-            optimal_folding_strategy(degree, blowup_factor, num_queries, sequences_this_layer);
+    let available_bits = (degree / blowup_factor).ilog2() as usize;
+    let mut folded_bits: usize = current_folding_seq.iter().sum();
+    let mut next_layer = current_folding_seq.len();
+    for (layer, bits) in layers {
+        if let Some(&expected) = current_folding_seq.get(layer) {
+            if expected != bits {
+                return Err(FoldingError::PinConflict {
+                    layer,
+                    bits,
+                    expected,
+                });
+            }
+            continue;
+        }
+        if bits == 0 {
+            return Err(FoldingError::TrivialFold { layer });
+        }
 
-        // If the size of the proof is smaller than the current optimal proof size, update the
-        // optimal proof size and the optimal folding sequence.
-        if size < optimal_proof {
-            optimal_proof = size;
-            optimal_sequences = sequences_layer;
+        // every unpinned layer before this one folds by at least 2.
+        folded_bits += layer - next_layer + bits;
+        next_layer = layer + 1;
+        if folded_bits > available_bits {
+            return Err(FoldingError::OverFolding {
+                layer,
+                bits,
+                available_bits,
+            });
         }
     }
-    (optimal_proof, optimal_sequences)
+    Ok(())
+}
+
+/// Checks that the degree and blowup factor of a FRI proof are powers of 2 and that the degree is
+/// at least the blowup factor, so that the polynomial has a degree of at least 1. Every entry
+/// point returning a [`Result`] runs this check, in release builds as in debug ones, and those
+/// that cannot return an error warn about invalid parameters instead, see
+/// [`simple_schedule`](super::simple_schedule::simple_schedule).
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// assert!(validate_parameters(1 << 10, 4).is_ok());
+/// assert_eq!(
+///     validate_parameters(3 << 10, 4),
+///     Err(FoldingError::NotPowerOfTwo { parameter: "degree", value: 3 << 10 })
+/// );
+/// assert_eq!(
+///     validate_parameters(2, 4),
+///     Err(FoldingError::DegreeBelowBlowup { degree: 2, blowup_factor: 4 })
+/// );
+/// ```
+///
+/// # Errors
+/// * If the degree or the blowup factor is not a power of 2
+/// * If the degree is smaller than the blowup factor
+pub fn validate_parameters(degree: usize, blowup_factor: usize) -> Result<(), FoldingError> {
+    if !degree.is_power_of_two() {
+        return Err(FoldingError::NotPowerOfTwo {
+            parameter: "degree",
+            value: degree,
+        });
+    }
+    if !blowup_factor.is_power_of_two() {
+        return Err(FoldingError::NotPowerOfTwo {
+            parameter: "blowup factor",
+            value: blowup_factor,
+        });
+    }
+    if degree < blowup_factor {
+        return Err(FoldingError::DegreeBelowBlowup {
+            degree,
+            blowup_factor,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that `folding_seq` is a well-formed folding schedule for a polynomial of the given degree:
+/// it starts with at least one unfolded layer (`0` bits), every layer after the leading unfolded
+/// ones folds, and the layers together do not fold the polynomial below degree 1.
+///
+/// Several leading unfolded layers are allowed to match configurations (such as StarkWare's
+/// `fri_step = 0` entries) that commit to the same codeword more than once before the first fold.
+///
+/// The degree and blowup factor are checked by [`validate_parameters`] first.
+pub fn validate_schedule(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+) -> Result<(), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    if folding_seq.first() != Some(&0) {
+        return Err(FoldingError::MissingInitialLayer);
+    }
+    let leading_zeros = folding_seq.iter().take_while(|&&bits| bits == 0).count();
+    if let Some(layer) = (leading_zeros..folding_seq.len()).find(|&l| folding_seq[l] == 0) {
+        return Err(FoldingError::UnfoldedInteriorLayer { layer });
+    }
+
+    let available_bits = (degree / blowup_factor).ilog2() as usize;
+    let folded_bits = folding_seq.iter().sum();
+    if folded_bits > available_bits {
+        return Err(FoldingError::ScheduleOverFolding {
+            folded_bits,
+            available_bits,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the cost model's remainder strategy lets the verifier learn the remainder
+/// `folding_seq` leaves, see [`RemainderStrategy`]. A sent remainder always can be, while one
+/// interpolated from the last layer needs as many queries as it has coefficients.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model whose remainder strategy is checked
+///
+/// # Errors
+/// * If the remainder has more coefficients than the verifier can interpolate from the queries
+pub fn check_remainder_strategy(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Result<(), FoldingError> {
+    if is_remainder_feasible(degree, blowup_factor, num_queries, folding_seq, cost_model) {
+        Ok(())
+    } else {
+        Err(FoldingError::RemainderNotInterpolable {
+            remainder_degree: (degree / blowup_factor) >> folding_seq.iter().sum::<usize>(),
+            num_queries,
+        })
+    }
+}
+
+/// Returns whether the cost model's remainder strategy lets the verifier learn the remainder
+/// `folding_seq` leaves, see [`check_remainder_strategy`].
+pub(crate) fn is_remainder_feasible(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> bool {
+    let remainder_degree = (degree / blowup_factor) >> folding_seq.iter().sum::<usize>();
+    cost_model
+        .remainder_strategy
+        .is_feasible(remainder_degree, num_queries)
 }
 
 /// Estimates the size of the FRI proof for a given folding strategy in terms of
 /// field elements. This function provides an estimation based on a heuristic and
 /// may not yield exact values, especially due to potential Merkle path compressions.
 ///
+/// Every entry of the folding sequence is a committed layer. An unfolded layer (`0` bits) is
+/// priced like any other: one Merkle path and one opened element per query. Whether the opened
+/// values include the one the verifier derives from the previous layer is set by the cost
+/// model's [`QueryValueTransmission`](super::cost_model::QueryValueTransmission). The Merkle roots of
+/// the layers are not charged; they are counted separately by
+/// [`count_commitment_roots`](super::verifier_cost::count_commitment_roots), which also honors a
+/// first layer shared with the trace commitment. When the cost model excludes the initial layer,
+/// its paths and leaves are not charged either, but it still takes part in the folding.
+///
+/// The first entry is the initial layer, which the cost model prices apart (its width, its
+/// exclusion, its shared root) and which is never folded: like the optimizer, the estimator
+/// expects the sequence to start with `0`. A sequence folding its first layer would price a
+/// different protocol than the optimizer searches, so it is rejected by [`validate_schedule`],
+/// which the estimator checks first, and the optimizer refuses it as a prefix:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let size = estimate_proof_size(1 << 10, 4, 3, &[2, 2], &cost_model);
+/// assert_eq!(size, Err(FoldingError::MissingInitialLayer));
+/// let optimum = optimal_folding_strategy(
+///     1 << 10,
+///     4,
+///     3,
+///     vec![2],
+///     &HashMap::new(),
+///     &cost_model,
+///     &mut SearchStats::default(),
+/// );
+/// assert_eq!(optimum, Err(FoldingError::MissingInitialLayer));
+/// ```
+///
+/// For example, with a degree of 2^10, a blowup factor of 4, 3 queries and the folding sequence
+/// `[0, 2, 2]`, the proof holds 374 elements:
+/// * layer 0 (domain 2^10, factor 1): 3 * 10 * 4 = 120 path and 3 * 1 * 2 = 6 leaf elements
+/// * layer 1 (domain 2^10, factor 4): 3 * 8 * 4 = 96 path and 3 * 4 * 2 = 24 leaf elements
+/// * layer 2 (domain 2^8, factor 4): 3 * 6 * 4 = 72 path and 3 * 4 * 2 = 24 leaf elements
+/// * remainder (domain 2^6): 2^6 / 4 * 2 = 32 elements
+///
 /// # Arguments
 /// * `degree` - The degree of the polynomial to be proved
 /// * `blowup_factor` - The blowup factor used in the FRI protocol
 /// * `num_queries` - The number of queries used in the FRI protocol
 /// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price each layer
 ///
 /// # Returns
 /// * `num_elements` - The estimated proof size in terms of field elements
-pub(crate) fn estimate_proof_size(
+///
+/// # Errors
+/// * If the folding sequence, the degree or the blowup factor is rejected by
+///   [`validate_schedule`]
+pub fn estimate_proof_size(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Result<usize, FoldingError> {
+    validate_schedule(degree, blowup_factor, folding_seq)?;
+    Ok(estimate_proof_size_unchecked(
+        degree,
+        blowup_factor,
+        num_queries,
+        folding_seq,
+        cost_model,
+    ))
+}
+
+/// Estimates the size of the FRI proof like [`estimate_proof_size`], for callers that have
+/// validated the parameters and the folding sequence already, such as the search, which prices
+/// every node it explores.
+pub(crate) fn estimate_proof_size_unchecked(
     degree: usize,
     blowup_factor: usize,
     num_queries: usize,
-    folding_seq: &Vec<usize>,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
 ) -> usize {
-    // The current layer degree is the degree of the polynomial at the current layer. This is
-    // initialized to the degree of the polynomial to be proved. At each layer, the degree is
-    // divided by the folding factor.
-    let mut current_layer_degree = degree;
+    debug_assert_eq!(
+        folding_seq.first(),
+        Some(&0),
+        "the folding sequence {folding_seq:?} must start with the unfolded initial layer"
+    );
+
+    estimate_layers(
+        degree,
+        blowup_factor,
+        |_| num_queries,
+        folding_seq,
+        cost_model,
+    )
+}
+
+/// Estimates the size of the proof that does not fold at all: the initial layer is committed and
+/// opened at the queries, and the whole polynomial is the remainder. This is the `[0]` schedule,
+/// a baseline that shows non-specialists what folding saves. Its remainder is sent as
+/// `poly_degree * extension_degree` elements, unless the cost
+/// model interpolates the remainder from the queries and they are enough to do so.
+///
+/// With a degree of 2^10, a blowup factor of 4 and 3 queries, the baseline opens the initial layer
+/// as the proof of 374 elements in [`estimate_proof_size`] does, `3 * 10 * 4 + 3 * 1 * 2`, and
+/// sends the 2^8 coefficients of the polynomial:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::RemainderStrategy;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let coefficients = CostModel::default();
+/// assert_eq!(baseline_size(1 << 10, 4, 3, &coefficients), 126 + 256 * 2);
+/// assert_eq!(baseline_size(1 << 10, 4, 3, &coefficients), estimate_proof_size(1 << 10, 4, 3, &[0], &coefficients).unwrap());
+///
+/// // too few queries to interpolate the polynomial from its evaluations, so it is sent.
+/// let evaluations =
+///     CostModel { remainder_strategy: RemainderStrategy::InLastLayer, ..CostModel::default() };
+/// assert_eq!(baseline_size(1 << 10, 4, 3, &evaluations), 126 + 256 * 2);
+///
+/// // with 256 queries it is interpolated and costs nothing.
+/// let interpolated = baseline_size(1 << 10, 4, 256, &evaluations);
+/// assert_eq!(interpolated, 256 * 10 * 4 + 256 * 2);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `cost_model` - The cost model used to price the proof
+///
+/// # Returns
+/// * `num_elements` - The estimated size of the unfolded proof in terms of field elements
+pub fn baseline_size(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_model: &CostModel,
+) -> usize {
+    let unfolded = [0];
+    let cost_model =
+        if is_remainder_feasible(degree, blowup_factor, num_queries, &unfolded, cost_model) {
+            *cost_model
+        } else {
+            CostModel {
+                remainder_strategy: RemainderStrategy::Sent,
+                ..*cost_model
+            }
+        };
+    estimate_proof_size_unchecked(degree, blowup_factor, num_queries, &unfolded, &cost_model)
+}
+
+/// Checks that a query plan gives every layer of the folding schedule its own number of queries,
+/// and that no layer is queried more often than the layer before it: a query of a layer continues
+/// one of the previous layer, checking that the coset it opens folds to the value it opens next.
+///
+/// # Arguments
+/// * `query_plan` - The number of queries of every layer, starting with the initial layer
+/// * `folding_seq` - The folding sequence
+///
+/// # Errors
+/// * If the query plan and the folding sequence differ in length
+/// * If a layer has more queries than the layer before it
+pub fn validate_query_plan(
+    query_plan: &[usize],
+    folding_seq: &[usize],
+) -> Result<(), FoldingError> {
+    if query_plan.len() != folding_seq.len() {
+        return Err(FoldingError::QueryPlanLengthMismatch {
+            plan_layers: query_plan.len(),
+            schedule_layers: folding_seq.len(),
+        });
+    }
+    if let Some(layer) = (1..query_plan.len()).find(|&l| query_plan[l] > query_plan[l - 1]) {
+        return Err(FoldingError::IncreasingQueryPlan { layer });
+    }
+    Ok(())
+}
 
+/// Estimates the size of the FRI proof like [`estimate_proof_size`], but with the number of
+/// queries of every layer given by a query plan, as in designs that stop some queries before the
+/// deeper layers. Every per-query term of a layer, its paths and its opened values, is charged
+/// for the layer's own queries, while the remainder is charged once as before. A plan querying
+/// every layer equally prices the proof as [`estimate_proof_size`] does.
+///
+/// With the folding sequence `[0, 2, 2]` of [`estimate_proof_size`], querying the last layer
+/// twice instead of three times saves the path and the leaf of one query there, 24 and 8
+/// elements:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::optimized_schedule::estimate_proof_size_with_query_plan;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let size = |plan: &[usize]| {
+///     estimate_proof_size_with_query_plan(1 << 10, 4, plan, &[0, 2, 2], &GOLDILOCKS_QUADRATIC)
+/// };
+/// assert_eq!(size(&[3, 3, 3]), Ok(374));
+/// assert_eq!(size(&[3, 3, 2]), Ok(374 - 6 * 4 - 4 * 2));
+///
+/// assert_eq!(
+///     size(&[3, 3]),
+///     Err(FoldingError::QueryPlanLengthMismatch { plan_layers: 2, schedule_layers: 3 })
+/// );
+/// assert_eq!(size(&[3, 2, 3]), Err(FoldingError::IncreasingQueryPlan { layer: 2 }));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `query_plan` - The number of queries of every layer, starting with the initial layer
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price each layer
+///
+/// # Returns
+/// * `num_elements` - The estimated proof size in terms of field elements
+///
+/// # Errors
+/// * If the query plan is rejected by [`validate_query_plan`]
+/// * If the folding sequence is rejected by [`validate_schedule`]
+pub fn estimate_proof_size_with_query_plan(
+    degree: usize,
+    blowup_factor: usize,
+    query_plan: &[usize],
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Result<usize, FoldingError> {
+    validate_schedule(degree, blowup_factor, folding_seq)?;
+    validate_query_plan(query_plan, folding_seq)?;
+    Ok(estimate_layers(
+        degree,
+        blowup_factor,
+        |layer| query_plan[layer],
+        folding_seq,
+        cost_model,
+    ))
+}
+
+/// Sums the elements of every layer and of the remainder, with the queries of each layer given
+/// by `layer_queries`.
+fn estimate_layers(
+    degree: usize,
+    blowup_factor: usize,
+    layer_queries: impl Fn(usize) -> usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
     // The number of elements in the proof is the sum of the number of elements in the Merkle
     // path, the number of elements in the remainder polynomial, and the number of elements in
     // nodes.
     let mut num_elements = 0;
-    for folding_factors_bits in folding_seq {
-        // computing the factor as 2^folding_factors_bits.
-        let factor = (1 << folding_factors_bits) as usize;
+    let remainder = walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            let num_queries = layer_queries(layer.layer);
 
-        // number of elements in the Merkle path. No compression is assumed.
-        num_elements +=
-            num_queries * current_layer_degree.ilog2() as usize * ELEMENTS_IN_HASH_OUTPUT;
+            // an excluded initial layer is opened outside of the proof.
+            if layer.counts_paths {
+                num_elements += layer.path_elements(num_queries, cost_model);
+            }
+            if layer.counts_leaves {
+                num_elements += layer.leaf_elements(num_queries, cost_model);
+            }
+        },
+    );
 
-        // count neighboring elements. Neighboring field elements are hashed together
-        // to form a node.
-        num_elements += num_queries * factor * FE_IN_EACH_ELEMENTS;
+    if !cost_model.exclude.remainder {
+        num_elements += remainder_elements(remainder.domain_size, blowup_factor, cost_model);
+    }
+    num_elements
+}
 
-        // update the current layer degree.
-        current_layer_degree /= factor;
+/// Splits the estimated size of a proof into the elements that do not depend on the number of
+/// queries, i.e. the remainder, and the elements every query adds. Under [`PathModel::Naive`]
+/// the size is affine in the number of queries, so the split is exact for every query count:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::optimized_schedule::proof_size_coefficients;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let (fixed, per_query) =
+///     proof_size_coefficients(1 << 25, 8, &[0, 4, 4, 3, 3], &cost_model).unwrap();
+/// for num_queries in [0, 1, 27, 100, 1000] {
+///     assert_eq!(
+///         fixed + num_queries * per_query,
+///         estimate_proof_size(1 << 25, 8, num_queries, &[0, 4, 4, 3, 3], &cost_model).unwrap()
+///     );
+/// }
+///
+/// // shared paths grow sublinearly in the number of queries.
+/// let shared = CostModel { path_model: PathModel::IdealCompression, ..cost_model };
+/// assert_eq!(proof_size_coefficients(1 << 25, 8, &[0, 4, 4, 3, 3], &shared), None);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price each layer
+///
+/// # Returns
+/// * `coefficients` - The fixed elements and the elements per query, or `None` if the cost model
+///   shares paths between queries so that the size is not affine in their number
+pub fn proof_size_coefficients(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Option<(usize, usize)> {
+    if cost_model.path_model != PathModel::Naive {
+        return None;
+    }
+    let fixed = estimate_proof_size_unchecked(degree, blowup_factor, 0, folding_seq, cost_model);
+    let per_query =
+        estimate_proof_size_unchecked(degree, blowup_factor, 1, folding_seq, cost_model) - fixed;
+    Some((fixed, per_query))
+}
+
+/// Computes the optimal folding strategy for every query count of `queries`, as
+/// [`optimal_folding_strategy`] would for each of them. Under [`PathModel::Naive`] the size of
+/// every schedule is affine in the number of queries, see [`proof_size_coefficients`], so the
+/// schedules are enumerated once and every query count is priced from their coefficients;
+/// otherwise the optimizer runs once per query count. Either way the results, ties included, are
+/// those of the optimizer.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::optimized_schedule::optimal_for_query_counts;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let sweep = optimal_for_query_counts(1 << 20, 8, &[8, 27, 80], &cost_model).unwrap();
+/// for (&num_queries, optimum) in [8, 27, 80].iter().zip(&sweep) {
+///     let single = optimal_folding_strategy(
+///         1 << 20,
+///         8,
+///         num_queries,
+///         vec![0],
+///         &HashMap::new(),
+///         &cost_model,
+///         &mut SearchStats::default(),
+///     );
+///     assert_eq!(Ok(optimum), single.as_ref());
+/// }
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `queries` - The query counts to optimize for
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+///
+/// # Returns
+/// * `optima` - For every query count, in the order given, the estimated proof size and the
+///   optimal folding sequence
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's semantics version is no longer supported
+/// * If the cost model's remainder strategy cannot interpolate any remainder for one of the
+///   query counts
+pub fn optimal_for_query_counts(
+    degree: usize,
+    blowup_factor: usize,
+    queries: &[usize],
+    cost_model: &CostModel,
+) -> Result<Vec<(usize, Vec<usize>)>, FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    cost_model.check_semantics_version()?;
+    if cost_model.path_model != PathModel::Naive {
+        return queries
+            .iter()
+            .map(|&num_queries| {
+                optimal_folding_strategy(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    vec![0],
+                    &HashMap::new(),
+                    cost_model,
+                    &mut SearchStats::default(),
+                )
+            })
+            .collect();
     }
 
-    // remainder polynomial in coefficient form (orginal form has degree *
-    // blowup_factor)
-    let remainder_poly_degree: usize = current_layer_degree / blowup_factor;
+    // the optimizer visits schedules in this order and only replaces its optimum by a strictly
+    // better one, ranked by cost and then by size, so the same rule finds the same schedules.
+    let mut optima = vec![None; queries.len()];
+    for_each_schedule(degree, blowup_factor, &mut |seq| {
+        let (fixed, per_query) = proof_size_coefficients(degree, blowup_factor, seq, cost_model)
+            .expect("naive paths are affine in the number of queries");
+        let check_cost = remainder_check_cost(degree, blowup_factor, seq, cost_model);
+        for (optimum, &num_queries) in optima.iter_mut().zip(queries) {
+            if !is_remainder_feasible(degree, blowup_factor, num_queries, seq, cost_model) {
+                continue;
+            }
+            let size = fixed + num_queries * per_query;
+            let cost = (size + check_cost, size);
+            if optimum
+                .as_ref()
+                .is_none_or(|(optimal_cost, _)| cost < *optimal_cost)
+            {
+                *optimum = Some((cost, seq.to_vec()));
+            }
+        }
+    });
+    optima
+        .into_iter()
+        .zip(queries)
+        .map(|(optimum, &num_queries)| {
+            // the unfolded schedule is always visited, so only the remainder strategy can leave
+            // a query count without a schedule.
+            let ((_, size), schedule) =
+                optimum.ok_or(FoldingError::NoInterpolableRemainder { num_queries })?;
+            Ok((size, schedule))
+        })
+        .collect()
+}
 
-    // number of elements in the remainder polynomial.
-    num_elements += remainder_poly_degree * FE_IN_EACH_ELEMENTS;
-    num_elements
+/// Computes the number of elements in the Merkle paths of one layer, over all queries. The number
+/// of digests depends on how the coset values are laid out in the tree and on how much the paths
+/// of different queries are assumed to share.
+pub(crate) fn path_elements(
+    num_queries: usize,
+    layer_degree: usize,
+    factor: usize,
+    cost_model: &CostModel,
+) -> usize {
+    let depth = cost_model.coset_layout.path_digests(layer_degree, factor);
+    paths_of_depth_elements(num_queries, depth, cost_model)
+}
+
+/// Computes the number of elements in the Merkle paths of one layer, over all queries, from the
+/// number of digests in the path of a single query.
+pub(crate) fn paths_of_depth_elements(
+    num_queries: usize,
+    depth: usize,
+    cost_model: &CostModel,
+) -> usize {
+    cost_model.path_model.path_digests(depth, num_queries) * cost_model.digest_elements
+}
+
+/// Computes the number of opened elements of one layer, over all queries. Neighboring field
+/// elements are hashed together to form a node, so each query opens `factor` of them, of which
+/// the verifier may already know one. A batched initial layer opens one element per column for
+/// each of them.
+pub(crate) fn leaf_elements(
+    num_queries: usize,
+    layer: usize,
+    factor: usize,
+    cost_model: &CostModel,
+) -> usize {
+    num_queries
+        * cost_model.query_values.sent_values(layer, factor)
+        * cost_model.value_elements(layer)
+}
+
+/// Computes the number of elements the remainder polynomial takes up in the proof: its
+/// coefficients, see [`remainder_coefficient_elements`], or none if the cost model's remainder
+/// strategy interpolates it from the last layer.
+pub(crate) fn remainder_elements(
+    layer_degree: usize,
+    blowup_factor: usize,
+    cost_model: &CostModel,
+) -> usize {
+    match cost_model.remainder_strategy {
+        RemainderStrategy::Sent => {
+            remainder_coefficient_elements(layer_degree, blowup_factor, cost_model)
+        }
+        RemainderStrategy::InLastLayer => 0,
+    }
+}
+
+/// Computes the number of elements in the remainder polynomial in coefficient form (the
+/// evaluated form over the last layer's domain has degree * blowup_factor elements). The number
+/// of coefficients is a power of two and never padded, see [`RemainderStrategy`].
+pub(crate) fn remainder_coefficient_elements(
+    layer_degree: usize,
+    blowup_factor: usize,
+    cost_model: &CostModel,
+) -> usize {
+    layer_degree / blowup_factor * cost_model.extension_degree
 }
 
 /// Computes the size of the FRI proof in bytes for a given folding strategy.
-pub(crate) fn size_in_bytes(num_elements: usize) -> usize {
-    num_elements * ELEMENT_SIZE
+pub fn size_in_bytes(num_elements: usize, cost_model: &CostModel) -> usize {
+    num_elements * cost_model.element_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost_model::toggled_cost_models;
+    use crate::simple_schedule::simple_schedule;
+
+    // Under the default cost model, a digest is 4 elements and a value 2. A layer over a domain
+    // of 2^d folding by 2^f opens, per query, a path of d - f digests and a leaf of 2^f values.
+    // The remainder left on a domain of 2^r sends 2^r / blowup_factor values.
+    #[test]
+    fn sizes_by_hand() {
+        let size = |degree, blowup_factor, num_queries, folding_seq: &[usize]| {
+            estimate_proof_size(
+                degree,
+                blowup_factor,
+                num_queries,
+                folding_seq,
+                &CostModel::default(),
+            )
+            .unwrap()
+        };
+
+        // 3 * (10 * 4 + 2) + 3 * (8 * 4 + 4 * 2) + 3 * (6 * 4 + 4 * 2) + 2^6 / 4 * 2
+        assert_eq!(size(1 << 10, 4, 3, &[0, 2, 2]), 126 + 120 + 96 + 32);
+        // a single layer: 1 * (4 * 4 + 2) + 2^4 / 4 * 2
+        assert_eq!(size(1 << 4, 4, 1, &[0]), 18 + 8);
+        // mixed factors: 5 * (12 * 4 + 2) + 5 * (9 * 4 + 8 * 2) + 5 * (8 * 4 + 2 * 2)
+        // + 5 * (6 * 4 + 4 * 2) + 2^6 / 8 * 2
+        assert_eq!(
+            size(1 << 12, 8, 5, &[0, 3, 1, 2]),
+            250 + 260 + 180 + 160 + 16
+        );
+        // a remainder of degree 1: 2 * (6 * 4 + 2) + 2 * (4 * 4 + 4 * 2) + 2 * (2 * 4 + 4 * 2)
+        // + 2^2 / 4 * 2
+        assert_eq!(size(1 << 6, 4, 2, &[0, 2, 2]), 52 + 48 + 32 + 2);
+        // the default optimum: 27 * (25 * 4 + 2) + 27 * (21 * 4 + 16 * 2)
+        // + 27 * (17 * 4 + 16 * 2) + 27 * (13 * 4 + 16 * 2) + 27 * (10 * 4 + 8 * 2) + 2^10 / 8 * 2
+        assert_eq!(
+            size(1 << 25, 8, 27, &[0, 4, 4, 4, 3]),
+            2754 + 3132 + 2700 + 2268 + 1512 + 256
+        );
+    }
+
+    // the optimizer and the uniform schedules must price their schedules as the estimator does,
+    // or they carry pricing logic of their own a new toggle could miss.
+    #[test]
+    fn strategies_price_like_the_estimator_under_every_toggle() {
+        let (degree, blowup_factor, num_queries) = (1 << 10, 4, 27);
+        for cost_model in toggled_cost_models() {
+            let mut stats = SearchStats::default();
+            let optimum = optimal_folding_strategy(
+                degree,
+                blowup_factor,
+                num_queries,
+                vec![0],
+                &HashMap::new(),
+                &cost_model,
+                &mut stats,
+            )
+            .unwrap();
+            let mut priced = vec![optimum];
+            for bits in 1..=MAX_FOLDING_BITS {
+                priced.push(
+                    simple_schedule(degree, blowup_factor, num_queries, 3, bits, &cost_model)
+                        .unwrap(),
+                );
+            }
+            for (size, schedule) in priced {
+                let estimate =
+                    estimate_proof_size(degree, blowup_factor, num_queries, &schedule, &cost_model);
+                assert_eq!(Ok(size), estimate, "{schedule:?} {cost_model:?}");
+            }
+        }
+    }
+
+    // at the usual cap of 2^4 the optimum folds by the cap thrice; one bit more lets it fold by
+    // 2^5 once and drop a layer: 2754 + 27 * (20 * 4 + 32 * 2) + 27 * (16 * 4 + 16 * 2)
+    // + 27 * (12 * 4 + 16 * 2) + 2^12 / 8 * 2.
+    #[test]
+    fn optimum_at_the_cap_boundary() {
+        let optimal = |cap| {
+            let mut stats = SearchStats::default();
+            optimal_with_max_folding_factor(1 << 25, 8, 27, cap, &CostModel::default(), &mut stats)
+                .unwrap()
+        };
+        assert_eq!(optimal(MAX_FOLDING_BITS), (12622, vec![0, 4, 4, 4, 3]));
+        assert_eq!(
+            optimal(MAX_FOLDING_BITS + 1),
+            (2754 + 3888 + 2592 + 2160 + 1024, vec![0, 5, 4, 4])
+        );
+    }
+
+    // with 1 bit to fold, folding costs a layer of 1 * (2 * 4 + 2 * 2) to halve a remainder of
+    // 2^3 / 4 * 2, so the optimum is the initial layer alone: 1 * (3 * 4 + 2) + 4.
+    #[test]
+    fn single_layer_optimum() {
+        let mut stats = SearchStats::default();
+        let optimum = optimal_folding_strategy(
+            1 << 3,
+            4,
+            1,
+            vec![0],
+            &HashMap::new(),
+            &CostModel::default(),
+            &mut stats,
+        );
+        assert_eq!(optimum, Ok((18, vec![0])));
+        assert_eq!(
+            estimate_proof_size(1 << 3, 4, 1, &[0, 1], &CostModel::default()),
+            Ok(14 + 12 + 2)
+        );
+    }
+
+    // folding 2^8 to a remainder of degree 1 with 3 queries: after the initial layer of
+    // 3 * (8 * 4 + 2) = 102, two folds by 2^3 cost 3 * (5 * 4 + 8 * 2) + 3 * (2 * 4 + 8 * 2)
+    // = 180, against 192 for 2^4 then 2^2 and 216 for three folds by 2^2. The remainder is
+    // 2^2 / 4 * 2 = 2.
+    #[test]
+    fn remainder_of_degree_one_by_hand() {
+        let mut stats = SearchStats::default();
+        let optimum = optimal_with_target_remainder_degree(
+            1 << 8,
+            4,
+            3,
+            1,
+            &CostModel::default(),
+            &mut stats,
+        );
+        assert_eq!(optimum, Ok((102 + 180 + 2, vec![0, 3, 3])));
+        let size = |folding_seq: &[usize]| {
+            estimate_proof_size(1 << 8, 4, 3, folding_seq, &CostModel::default()).unwrap()
+        };
+        assert_eq!(size(&[0, 4, 2]), 102 + 192 + 2);
+        assert_eq!(size(&[0, 2, 2, 2]), 102 + 216 + 2);
+    }
+
+    #[test]
+    fn estimate_rejects_invalid_input() {
+        let cost_model = CostModel::default();
+        let estimate = |degree, blowup_factor, seq: &[usize]| {
+            estimate_proof_size(degree, blowup_factor, 3, seq, &cost_model)
+        };
+        assert!(matches!(
+            estimate(1000, 4, &[0, 2]),
+            Err(FoldingError::NotPowerOfTwo {
+                parameter: "degree",
+                ..
+            })
+        ));
+        assert!(matches!(
+            estimate(1 << 10, 3, &[0, 2]),
+            Err(FoldingError::NotPowerOfTwo {
+                parameter: "blowup factor",
+                ..
+            })
+        ));
+        assert!(matches!(
+            estimate(2, 4, &[0]),
+            Err(FoldingError::DegreeBelowBlowup { .. })
+        ));
+        assert_eq!(
+            estimate(1 << 10, 4, &[]),
+            Err(FoldingError::MissingInitialLayer)
+        );
+        assert!(matches!(
+            estimate(1 << 10, 4, &[0, 4, 4, 2]),
+            Err(FoldingError::ScheduleOverFolding { .. })
+        ));
+        assert_eq!(estimate(1 << 10, 4, &[0, 2, 2]), Ok(374));
+    }
+
+    #[test]
+    fn probed_cap_matches_two_searches() {
+        let cost_model = CostModel {
+            remainder_verify_cost_per_element: 3,
+            ..CostModel::default()
+        };
+        let cost = |seq: &[usize], degree, blowup_factor, num_queries| {
+            estimate_proof_size_unchecked(degree, blowup_factor, num_queries, seq, &cost_model)
+                + remainder_check_cost(degree, blowup_factor, seq, &cost_model)
+        };
+        for (degree, blowup_factor, num_queries) in [(1 << 12, 4, 3), (1 << 16, 8, 27)] {
+            for cap in 1..=4 {
+                let capped = |cap| {
+                    let mut stats = SearchStats::default();
+                    optimal_with_max_folding_factor(
+                        degree,
+                        blowup_factor,
+                        num_queries,
+                        cap,
+                        &cost_model,
+                        &mut stats,
+                    )
+                    .unwrap()
+                };
+                let mut stats = SearchStats::default();
+                let probed = optimal_probing_folding_cap(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    cap,
+                    &cost_model,
+                    &mut stats,
+                )
+                .unwrap();
+                assert_eq!(probed, capped(cap));
+
+                let (_, optimum) = capped(cap);
+                let (raised_size, raised) = capped(cap + 1);
+                let optimum_cost = cost(&optimum, degree, blowup_factor, num_queries);
+                let raised_cost = cost(&raised, degree, blowup_factor, num_queries);
+                let expected = (raised_cost < optimum_cost).then(|| CapNotice {
+                    cap_bits: cap,
+                    layers: (1..optimum.len())
+                        .filter(|&layer| optimum[layer] == cap)
+                        .collect(),
+                    raised_schedule: raised,
+                    raised_size,
+                    saved_cost: optimum_cost - raised_cost,
+                });
+                let expected: Vec<_> = expected
+                    .into_iter()
+                    .map(Warning::BindingFoldingCap)
+                    .collect();
+                assert_eq!(stats.warnings, expected, "{degree} {cap}");
+            }
+        }
+    }
+
+    // the unchecked estimator only asserts its input in debug builds, so the public entry points
+    // must reject the same input on their own once the assertions are compiled out.
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn release_estimate_rejects_a_folded_initial_layer() {
+        let cost_model = CostModel::default();
+        let size = estimate_proof_size(1 << 10, 4, 3, &[2, 2], &cost_model);
+        assert_eq!(size, Err(FoldingError::MissingInitialLayer));
+    }
 }
@@ -0,0 +1,352 @@
+//! FRI parameters stated in terms of the trace a STARK commits to, rather than of an abstract
+//! polynomial degree.
+
+use std::collections::HashMap;
+
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::optimized_schedule::{leaf_elements, optimal_folding_strategy, validate_parameters};
+use super::report::FoldingReport;
+use super::stats::SearchStats;
+
+/// One round in which a STARK commits to trace columns, such as the main trace or, after
+/// randomness has been drawn from it, the auxiliary trace of a randomized AIR. Every round is its
+/// own Merkle commitment over the LDE domain, opened at the same query positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommitmentRound {
+    /// The number of columns committed in the round.
+    pub num_columns: usize,
+    /// The number of base field elements in each value of a column: 1 for columns over the base
+    /// field, the extension degree for columns over an extension.
+    pub column_elements: usize,
+}
+
+/// How the trace commitment rounds are laid out in the initial layer of the FRI proof: each round
+/// in a Merkle tree of its own, or every round in one tree whose leaves concatenate the rows of
+/// all rounds, as Winterfell can merge the main and auxiliary trace segments into one commitment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Layer0Layout {
+    /// Every round is committed in its own tree and opened with its own path per query, see
+    /// [`FriParams::later_round_openings`].
+    SeparateTrees(Vec<CommitmentRound>),
+    /// The rounds share one tree and are opened with one path per query, each leaf holding a row
+    /// of every round.
+    MergedLeaf(Vec<CommitmentRound>),
+}
+
+impl Layer0Layout {
+    /// Returns the commitment rounds in the order they are committed.
+    pub fn rounds(&self) -> &[CommitmentRound] {
+        match self {
+            Layer0Layout::SeparateTrees(rounds) | Layer0Layout::MergedLeaf(rounds) => rounds,
+        }
+    }
+
+    /// Returns the number of base field elements in a leaf of the initial layer: a row of the
+    /// first round for separate trees, and a row of every round for a merged leaf.
+    pub fn leaf_width(&self) -> usize {
+        let width = |round: &CommitmentRound| round.num_columns * round.column_elements;
+        match self {
+            Layer0Layout::SeparateTrees(rounds) => rounds.first().map_or(0, width),
+            Layer0Layout::MergedLeaf(rounds) => rounds.iter().map(width).sum(),
+        }
+    }
+}
+
+/// The openings of the trace commitment rounds after the first, which the FRI proof does not
+/// account for, see [`FriParams::later_round_openings`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundOpenings {
+    /// The number of elements in the Merkle paths of the rounds, over all queries.
+    pub path_elements: usize,
+    /// The number of opened elements of the rounds, over all queries.
+    pub leaf_elements: usize,
+    /// The number of Merkle roots the rounds commit to.
+    pub roots: usize,
+}
+
+impl RoundOpenings {
+    /// Returns the number of elements the rounds add to the proof.
+    pub fn total_elements(&self) -> usize {
+        self.path_elements + self.leaf_elements
+    }
+}
+
+/// The parameters of a FRI proof: the size of the domain the initial layer is committed over, the
+/// blowup factor, the number of queries, and the cost model the proof is priced with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriParams {
+    /// The size of the LDE domain, which the rest of the crate calls the degree.
+    pub degree: usize,
+    /// The blowup factor used in the FRI protocol.
+    pub blowup_factor: usize,
+    /// The number of queries used in the FRI protocol.
+    pub num_queries: usize,
+    /// The cost model the proof is priced with.
+    pub cost_model: CostModel,
+}
+
+impl FriParams {
+    /// Derives the FRI parameters of a STARK whose trace has `trace_length` rows and
+    /// `num_columns` columns. The trace is extended to a domain `blowup_factor` times as large,
+    /// and the initial layer commits to all of its columns in each leaf, so each of its opened
+    /// values is `num_columns` base field elements.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::params::FriParams;
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let params = FriParams::from_trace(1 << 20, 80, 8, 27, &GOLDILOCKS_QUADRATIC).unwrap();
+    /// assert_eq!(params.degree, 1 << 23);
+    ///
+    /// // the width of the initial layer does not change which schedule is optimal.
+    /// let batched = params.optimal_report().unwrap();
+    /// let single = FriParams { cost_model: GOLDILOCKS_QUADRATIC, ..params };
+    /// let single = single.optimal_report().unwrap();
+    /// assert_eq!(batched.schedule, single.schedule);
+    /// assert_eq!(
+    ///     batched.total_elements() - single.total_elements(),
+    ///     27 * (80 - GOLDILOCKS_QUADRATIC.extension_degree)
+    /// );
+    /// ```
+    ///
+    /// # Arguments
+    /// * `trace_length` - The number of rows of the trace
+    /// * `num_columns` - The number of columns of the trace
+    /// * `blowup_factor` - The blowup factor used in the FRI protocol
+    /// * `num_queries` - The number of queries used in the FRI protocol
+    /// * `cost_model` - The cost model the proof is priced with, whose initial layer is replaced
+    ///   by the batched trace commitment
+    ///
+    /// # Errors
+    /// * If `trace_length` is not a power of 2
+    /// * If the evaluation domain does not fit in a `usize`
+    /// * If the resulting degree or the blowup factor is rejected by [`validate_parameters`]
+    pub fn from_trace(
+        trace_length: usize,
+        num_columns: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        cost_model: &CostModel,
+    ) -> Result<Self, FoldingError> {
+        if !trace_length.is_power_of_two() {
+            return Err(FoldingError::NotPowerOfTwo {
+                parameter: "trace length",
+                value: trace_length,
+            });
+        }
+        let degree =
+            trace_length
+                .checked_mul(blowup_factor)
+                .ok_or(FoldingError::ArithmeticOverflow {
+                    quantity: "evaluation domain",
+                })?;
+        validate_parameters(degree, blowup_factor)?;
+        Ok(FriParams {
+            degree,
+            blowup_factor,
+            num_queries,
+            cost_model: CostModel {
+                initial_layer_columns: Some(num_columns),
+                ..*cost_model
+            },
+        })
+    }
+
+    /// Derives the FRI parameters of a STARK whose trace of `trace_length` rows is committed in
+    /// several `rounds`, as in a randomized AIR whose auxiliary trace is committed after the main
+    /// one. The first round becomes the initial layer of the FRI proof, as with
+    /// [`Self::from_trace`], and the openings of the others are sized by
+    /// [`Self::later_round_openings`], to be added to the size of the FRI proof. The FRI layers
+    /// stay the same, so the rounds do not change which schedule is optimal.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::params::{CommitmentRound, FriParams};
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// // a main trace of 80 base field columns and 8 auxiliary columns over the extension.
+    /// let rounds = [
+    ///     CommitmentRound { num_columns: 80, column_elements: 1 },
+    ///     CommitmentRound { num_columns: 8, column_elements: 2 },
+    /// ];
+    /// let params = FriParams::from_trace_rounds(1 << 20, &rounds, 8, 27, &GOLDILOCKS_QUADRATIC)
+    ///     .unwrap();
+    /// let single = FriParams::from_trace(1 << 20, 80, 8, 27, &GOLDILOCKS_QUADRATIC).unwrap();
+    /// assert_eq!(params, single);
+    ///
+    /// // the auxiliary round opens 16 elements and a path over the domain of 2^23 per query.
+    /// let openings = params.later_round_openings(&rounds);
+    /// assert_eq!(openings.leaf_elements, 27 * 16);
+    /// assert_eq!(openings.path_elements, 27 * 23 * GOLDILOCKS_QUADRATIC.digest_elements);
+    /// assert_eq!(openings.roots, 1);
+    ///
+    /// ```
+    ///
+    /// # Arguments
+    /// * `trace_length` - The number of rows of the trace
+    /// * `rounds` - The commitment rounds in the order they are committed
+    /// * `blowup_factor` - The blowup factor used in the FRI protocol
+    /// * `num_queries` - The number of queries used in the FRI protocol
+    /// * `cost_model` - The cost model the proof is priced with, whose initial layer is replaced
+    ///   by the first commitment round
+    ///
+    /// # Errors
+    /// * If `rounds` is empty
+    /// * On the parameters [`Self::from_trace`] rejects
+    pub fn from_trace_rounds(
+        trace_length: usize,
+        rounds: &[CommitmentRound],
+        blowup_factor: usize,
+        num_queries: usize,
+        cost_model: &CostModel,
+    ) -> Result<Self, FoldingError> {
+        let first = rounds.first().ok_or(FoldingError::NoCommitmentRounds)?;
+        Self::from_trace(
+            trace_length,
+            first.num_columns * first.column_elements,
+            blowup_factor,
+            num_queries,
+            cost_model,
+        )
+    }
+
+    /// Derives the FRI parameters of a STARK whose trace of `trace_length` rows is committed in
+    /// rounds laid out as `layout`. The initial layer of the FRI proof is the first round's tree
+    /// for separate trees, as with [`Self::from_trace_rounds`], and the merged tree of every round
+    /// for a merged leaf. The openings outside of the FRI proof are sized by
+    /// [`Self::layer0_openings`].
+    ///
+    /// Unless the leaves of the initial layer group the values of the first fold, as under
+    /// `winterfell_layout`, both layouts open the same elements, so merging the rounds saves
+    /// exactly the paths of the trees it removes, whatever the path model, and nothing when the
+    /// initial layer's paths are left out of the proof:
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::cost_model::Exclusions;
+    /// use fri_dynamic_folding_scheme::params::{CommitmentRound, FriParams, Layer0Layout};
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// // a main trace of 80 base field columns and 8 auxiliary columns over the extension.
+    /// let rounds = vec![
+    ///     CommitmentRound { num_columns: 80, column_elements: 1 },
+    ///     CommitmentRound { num_columns: 8, column_elements: 2 },
+    /// ];
+    /// let total = |layout: &Layer0Layout, cost_model: &CostModel| {
+    ///     let params = FriParams::from_layer0_layout(1 << 20, layout, 8, 27, cost_model).unwrap();
+    ///     let schedule = [0, 4, 4, 4, 4];
+    ///     estimate_proof_size(params.degree, 8, 27, &schedule, &params.cost_model).unwrap()
+    ///         + params.layer0_openings(layout).total_elements()
+    /// };
+    /// let separate = Layer0Layout::SeparateTrees(rounds.clone());
+    /// let merged = Layer0Layout::MergedLeaf(rounds);
+    /// assert_eq!(merged.leaf_width(), 96);
+    ///
+    /// // one path of 23 digests of 4 elements per query over the LDE domain of 2^23.
+    /// let path_cost = 23 * GOLDILOCKS_QUADRATIC.digest_elements;
+    /// let naive = GOLDILOCKS_QUADRATIC;
+    /// assert_eq!(total(&separate, &naive) - total(&merged, &naive), 27 * path_cost);
+    ///
+    /// let compressed = CostModel { path_model: PathModel::IdealCompression, ..naive };
+    /// assert_eq!(
+    ///     total(&separate, &compressed) - total(&merged, &compressed),
+    ///     PathModel::IdealCompression.path_digests(23, 27) * naive.digest_elements
+    /// );
+    ///
+    /// let no_paths = CostModel {
+    ///     exclude: Exclusions { layer0_paths: true, ..naive.exclude },
+    ///     ..naive
+    /// };
+    /// assert_eq!(total(&separate, &no_paths), total(&merged, &no_paths));
+    /// ```
+    ///
+    /// # Arguments
+    /// * `trace_length` - The number of rows of the trace
+    /// * `layout` - The commitment rounds and how they are laid out
+    /// * `blowup_factor` - The blowup factor used in the FRI protocol
+    /// * `num_queries` - The number of queries used in the FRI protocol
+    /// * `cost_model` - The cost model the proof is priced with, whose initial layer is replaced
+    ///   by the layout's tree
+    ///
+    /// # Errors
+    /// * If the layout has no rounds
+    /// * On the parameters [`Self::from_trace`] rejects
+    pub fn from_layer0_layout(
+        trace_length: usize,
+        layout: &Layer0Layout,
+        blowup_factor: usize,
+        num_queries: usize,
+        cost_model: &CostModel,
+    ) -> Result<Self, FoldingError> {
+        if layout.rounds().is_empty() {
+            return Err(FoldingError::NoCommitmentRounds);
+        }
+        Self::from_trace(
+            trace_length,
+            layout.leaf_width(),
+            blowup_factor,
+            num_queries,
+            cost_model,
+        )
+    }
+
+    /// Sizes the openings of the trace commitment rounds that the FRI proof of
+    /// [`Self::from_layer0_layout`] leaves out: those of every round after the first for separate
+    /// trees, see [`Self::later_round_openings`], and none for a merged leaf, whose single tree is
+    /// the initial layer.
+    pub fn layer0_openings(&self, layout: &Layer0Layout) -> RoundOpenings {
+        match layout {
+            Layer0Layout::SeparateTrees(rounds) => self.later_round_openings(rounds),
+            Layer0Layout::MergedLeaf(_) => RoundOpenings::default(),
+        }
+    }
+
+    /// Sizes the openings of every commitment round after the first, which the FRI proof of
+    /// [`Self::from_trace_rounds`] leaves out. Each round opens one row of its columns per query,
+    /// with a path through a tree over the whole LDE domain, and is counted like the initial
+    /// layer it is opened alongside: the cost model's path model applies, and the components the
+    /// initial layer leaves out of the proof are left out of every round.
+    pub fn later_round_openings(&self, rounds: &[CommitmentRound]) -> RoundOpenings {
+        let cost_model = &self.cost_model;
+        let mut openings = RoundOpenings::default();
+        for round in rounds.iter().skip(1) {
+            if cost_model.counts_paths(0) {
+                let depth = cost_model.coset_layout.path_digests(self.degree, 1);
+                openings.path_elements +=
+                    cost_model.path_model.path_digests(depth, self.num_queries)
+                        * cost_model.digest_elements;
+            }
+            if cost_model.counts_leaves(0) {
+                let round_model = CostModel {
+                    initial_layer_columns: Some(round.num_columns * round.column_elements),
+                    ..*cost_model
+                };
+                openings.leaf_elements += leaf_elements(self.num_queries, 0, 1, &round_model);
+            }
+            openings.roots += 1;
+        }
+        openings
+    }
+
+    /// Computes the optimal folding schedule for these parameters and reports its costs.
+    ///
+    /// # Errors
+    /// * If the degree or blowup factor is rejected by [`validate_parameters`]
+    pub fn optimal_report(&self) -> Result<FoldingReport, FoldingError> {
+        let (_, schedule) = optimal_folding_strategy(
+            self.degree,
+            self.blowup_factor,
+            self.num_queries,
+            vec![0],
+            &HashMap::new(),
+            &self.cost_model,
+            &mut SearchStats::default(),
+        )?;
+        Ok(FoldingReport::new(
+            self.degree,
+            self.blowup_factor,
+            self.num_queries,
+            schedule,
+            &self.cost_model,
+        ))
+    }
+}
@@ -0,0 +1,506 @@
+//! The full analysis of a FRI proof behind the command-line report, computed in one call, so that
+//! integrators get the same numbers as the binary without orchestrating the estimators themselves.
+
+use std::collections::HashMap;
+#[cfg(feature = "json")]
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::analysis::{min_regret_schedule, query_sensitivity_band, QueryBand, RobustSchedule};
+use super::batch::DegreeGroup;
+use super::cost_model::{CostModel, PathModel};
+use super::error::{FoldingError, Warning};
+#[cfg(feature = "sim")]
+use super::exact_size::heuristic_error_bound;
+#[cfg(feature = "json")]
+use super::optimized_schedule::size_in_bytes;
+use super::optimized_schedule::{
+    baseline_size, optimal_batched_folding_strategy, optimal_folding_strategy,
+    optimal_probing_folding_cap, optimal_under_prover_memory, optimal_under_verifier_memory,
+    optimal_with_min_folding_factor, optimal_with_target_remainder_degree,
+    optimal_within_time_budget, Optimality, MAX_FOLDING_BITS,
+};
+use super::params::FriParams;
+use super::prover_cost::{
+    commitment_to_proof_ratio, estimate_prover_memory, total_hash_input_elements,
+};
+#[cfg(feature = "json")]
+use super::report::schedule_id;
+use super::report::{comparison_factors, FoldingReport};
+use super::simple_schedule::{
+    greedy_schedule, simple_schedule, simple_schedule_with_best_head,
+    simple_schedule_with_tuned_final_round,
+};
+use super::soundness::{fri_soundness, SoundnessBreakdown};
+use super::stats::SearchStats;
+use super::verifier_cost::remainder_check_cost;
+
+/// What [`analyze`] searches under and which of its expensive sections it computes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisOptions {
+    /// The largest remainder degree of the uniform schedules the optimum is compared against.
+    pub remainder_max_degree: usize,
+    /// The size in bits of the field the folding challenges are drawn from.
+    pub field_bits: u32,
+    /// Layers whose folding factor is forced, as in [`optimal_folding_strategy`].
+    pub pinned_layers: HashMap<usize, usize>,
+    /// The polynomials of lower degree batched into the proof, see
+    /// [`optimal_batched_folding_strategy`].
+    pub degree_groups: Vec<DegreeGroup>,
+    /// The prover memory budget in bytes, see [`optimal_under_prover_memory`].
+    pub max_prover_memory: Option<usize>,
+    /// The verifier memory budget in bytes, see [`optimal_under_verifier_memory`].
+    pub max_verifier_memory: Option<usize>,
+    /// The smallest folding factor in bits, see [`optimal_with_min_folding_factor`].
+    pub min_folding_bits: Option<usize>,
+    /// The degree the remainder must be folded to, see
+    /// [`optimal_with_target_remainder_degree`].
+    pub target_remainder_degree: Option<usize>,
+    /// The time the search may take, see [`optimal_within_time_budget`].
+    pub time_budget: Option<Duration>,
+    /// Whether to compute the range of query counts the optimum stays optimal for and whether
+    /// the folding factor cap binds, which take about as long as the search itself.
+    pub sensitivity: bool,
+    /// The number of random query sets to compare the estimate with exact sizes on, if any.
+    #[cfg(feature = "sim")]
+    pub error_bound_trials: Option<usize>,
+    /// The cost models to find a schedule robust across, if any.
+    pub robust_cost_models: Vec<CostModel>,
+    /// Whether a cost model breaking a consistency rule is an error, see
+    /// [`CostModel::check_consistency`], rather than a warning of the analysis.
+    pub strict: bool,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            remainder_max_degree: 64,
+            field_bits: 128,
+            pinned_layers: HashMap::new(),
+            degree_groups: Vec::new(),
+            max_prover_memory: None,
+            max_verifier_memory: None,
+            min_folding_bits: None,
+            target_remainder_degree: None,
+            time_budget: None,
+            sensitivity: true,
+            #[cfg(feature = "sim")]
+            error_bound_trials: None,
+            robust_cost_models: Vec::new(),
+            strict: false,
+        }
+    }
+}
+
+impl AnalysisOptions {
+    /// Returns whether the search is the unconstrained, unbatched one, capped at the usual
+    /// folding factor alone.
+    pub fn is_unconstrained(&self) -> bool {
+        self.max_prover_memory.is_none()
+            && self.max_verifier_memory.is_none()
+            && self.min_folding_bits.is_none()
+            && self.target_remainder_degree.is_none()
+            && self.pinned_layers.is_empty()
+            && self.degree_groups.is_empty()
+    }
+}
+
+/// A uniform folding schedule the optimum is compared against, together with its variants with
+/// a tuned final round and with the best head round. Each schedule comes with its size in field
+/// elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformComparison {
+    /// The folding factor of every layer, in bits.
+    pub folding_bits: usize,
+    /// The uniform schedule, see [`simple_schedule`].
+    pub uniform: (usize, Vec<usize>),
+    /// The uniform schedule with a tuned final round, see
+    /// [`simple_schedule_with_tuned_final_round`].
+    pub tuned_final_round: (usize, Vec<usize>),
+    /// The uniform schedule with the best head round, see [`simple_schedule_with_best_head`].
+    pub best_head: (usize, Vec<usize>),
+}
+
+/// Everything the command-line report states about the optimal folding schedule of a FRI proof,
+/// see [`analyze`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// The report of the optimal folding schedule, with its breakdown and verifier costs.
+    pub report: FoldingReport,
+    /// Whether the search visited every folding sequence.
+    pub optimality: Optimality,
+    /// The statistics of the search.
+    pub stats: SearchStats,
+    /// The cost of the verifier's check of the remainder the optimizer weighed with the size.
+    pub remainder_check_cost: usize,
+    /// The size of the proof that does not fold at all, see [`baseline_size`].
+    pub baseline: usize,
+    /// The greedy schedule and its size, see [`greedy_schedule`].
+    pub greedy: (usize, Vec<usize>),
+    /// The uniform schedules, one per factor of [`comparison_factors`].
+    pub comparisons: Vec<UniformComparison>,
+    /// The soundness of the optimal schedule over the field of `field_bits` bits.
+    pub soundness: SoundnessBreakdown,
+    /// The prover's memory in bytes.
+    pub prover_memory: u64,
+    /// The number of elements the prover commits to per element of the proof.
+    pub commitment_ratio: f64,
+    /// The number of elements the prover absorbs into hashes.
+    pub hash_input_elements: u64,
+    /// The range of query counts the optimum stays optimal for, if sensitivity was requested,
+    /// the search unconstrained and the size the only cost of unshared paths.
+    pub query_band: Option<QueryBand>,
+    /// The mean and worst ratio of the estimate to exact sizes, if trials were requested.
+    #[cfg(feature = "sim")]
+    pub error_bound: Option<(f64, f64)>,
+    /// The schedule robust across the requested cost models, if any.
+    pub robust: Option<RobustSchedule>,
+    /// The consistency rules the cost model breaks, and the folding factor cap if it binds,
+    /// which is only probed if sensitivity was requested and the search is unconstrained and not
+    /// bounded in time, see [`optimal_probing_folding_cap`].
+    pub warnings: Vec<Warning>,
+}
+
+/// Runs the full analysis of the command-line report: searches for the optimal folding schedule
+/// under the constraints of `options`, compares it with the greedy and uniform schedules, and
+/// reports its size, verifier and prover costs, soundness and the sections `options` requests.
+///
+/// The search is the one the most specific constraint calls for, in this order: the prover or
+/// verifier memory budget, the target remainder degree, the minimum folding factor, the degree
+/// groups and the time budget, and otherwise the unconstrained search with the pinned layers.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let params = FriParams {
+///     degree: 1 << 20,
+///     blowup_factor: 8,
+///     num_queries: 27,
+///     cost_model: CostModel::default(),
+/// };
+/// let analysis = analyze(&params, &AnalysisOptions::default()).unwrap();
+/// assert_eq!(analysis.report.schedule, vec![0, 4, 4]);
+/// assert_eq!(analysis.greedy.1, analysis.report.schedule);
+/// assert_eq!(analysis.baseline, baseline_size(1 << 20, 8, 27, &CostModel::default()));
+/// assert!(analysis.comparisons.iter().all(|c| c.uniform.0 >= analysis.report.total_elements()));
+/// assert!(analysis.warnings.is_empty());
+/// ```
+///
+/// # Errors
+/// * If the options are strict and the cost model breaks a consistency rule, see
+///   [`CostModel::check_consistency`]
+/// * If the search the options call for fails, see the errors of each search
+/// * If the prover memory or hash input of the optimal schedule does not fit in a `u64`
+pub fn analyze(params: &FriParams, options: &AnalysisOptions) -> Result<Analysis, FoldingError> {
+    let FriParams {
+        degree,
+        blowup_factor,
+        num_queries,
+        ref cost_model,
+    } = *params;
+    if options.strict {
+        cost_model.check_consistency()?;
+    }
+    let mut stats = SearchStats::default();
+    let mut optimality = Optimality::Exact;
+    let sensitivity = options.sensitivity && options.is_unconstrained();
+    let (_, schedule) = match (options.max_prover_memory, options.max_verifier_memory) {
+        (Some(max_prover_bytes), _) => optimal_under_prover_memory(
+            degree,
+            blowup_factor,
+            num_queries,
+            max_prover_bytes,
+            cost_model,
+            &mut stats,
+        ),
+        (_, Some(max_verifier_bytes)) => optimal_under_verifier_memory(
+            degree,
+            blowup_factor,
+            num_queries,
+            max_verifier_bytes,
+            cost_model,
+            &mut stats,
+        ),
+        _ if options.target_remainder_degree.is_some() => optimal_with_target_remainder_degree(
+            degree,
+            blowup_factor,
+            num_queries,
+            options.target_remainder_degree.unwrap(),
+            cost_model,
+            &mut stats,
+        ),
+        _ if options.min_folding_bits.is_some() => optimal_with_min_folding_factor(
+            degree,
+            blowup_factor,
+            num_queries,
+            options.min_folding_bits.unwrap(),
+            cost_model,
+            &mut stats,
+        ),
+        _ if !options.degree_groups.is_empty() => optimal_batched_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            &options.degree_groups,
+            &options.pinned_layers,
+            cost_model,
+            &mut stats,
+        ),
+        _ if options.time_budget.is_some() => optimal_within_time_budget(
+            degree,
+            blowup_factor,
+            num_queries,
+            options.time_budget.unwrap(),
+            cost_model,
+            &mut stats,
+        )
+        .map(|(size, schedule, exact)| {
+            optimality = exact;
+            (size, schedule)
+        }),
+        _ if sensitivity => optimal_probing_folding_cap(
+            degree,
+            blowup_factor,
+            num_queries,
+            MAX_FOLDING_BITS,
+            cost_model,
+            &mut stats,
+        ),
+        _ => optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            vec![0],
+            &options.pinned_layers,
+            cost_model,
+            &mut stats,
+        ),
+    }?;
+
+    let remainder_max_degree = options.remainder_max_degree;
+    let comparisons = comparison_factors(&schedule)
+        .into_iter()
+        .map(|folding_bits| {
+            Ok(UniformComparison {
+                folding_bits,
+                uniform: simple_schedule(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    remainder_max_degree,
+                    folding_bits,
+                    cost_model,
+                )?,
+                tuned_final_round: simple_schedule_with_tuned_final_round(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    remainder_max_degree,
+                    folding_bits,
+                    cost_model,
+                )?,
+                best_head: simple_schedule_with_best_head(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    remainder_max_degree,
+                    folding_bits,
+                    cost_model,
+                ),
+            })
+        })
+        .collect::<Result<_, FoldingError>>()?;
+    let greedy = greedy_schedule(degree, blowup_factor, num_queries, cost_model)?;
+
+    // the band is only meaningful for the optimum of the proof size under unshared paths.
+    let query_band = (sensitivity
+        && cost_model.path_model == PathModel::Naive
+        && cost_model.remainder_verify_cost_per_element == 0)
+        .then(|| query_sensitivity_band(degree, blowup_factor, num_queries, &schedule, cost_model));
+    #[cfg(feature = "sim")]
+    let error_bound = options
+        .error_bound_trials
+        .filter(|&trials| trials > 0)
+        .map(|trials| {
+            heuristic_error_bound(
+                degree,
+                blowup_factor,
+                num_queries,
+                &schedule,
+                cost_model,
+                trials,
+                0,
+            )
+        });
+    let robust = (!options.robust_cost_models.is_empty())
+        .then(|| {
+            min_regret_schedule(
+                degree,
+                blowup_factor,
+                num_queries,
+                &options.robust_cost_models,
+            )
+        })
+        .transpose()?;
+
+    let warnings = stats.warnings.clone();
+    Ok(Analysis {
+        optimality,
+        stats,
+        remainder_check_cost: remainder_check_cost(degree, blowup_factor, &schedule, cost_model),
+        baseline: baseline_size(degree, blowup_factor, num_queries, cost_model),
+        greedy,
+        comparisons,
+        soundness: fri_soundness(
+            degree,
+            blowup_factor,
+            num_queries,
+            &schedule,
+            options.field_bits,
+        ),
+        prover_memory: estimate_prover_memory(degree, blowup_factor, &schedule, cost_model)?,
+        commitment_ratio: commitment_to_proof_ratio(
+            degree,
+            blowup_factor,
+            num_queries,
+            &schedule,
+            cost_model,
+        ),
+        hash_input_elements: total_hash_input_elements(degree, &schedule, cost_model)?,
+        query_band,
+        #[cfg(feature = "sim")]
+        error_bound,
+        robust,
+        warnings,
+        report: FoldingReport::new(degree, blowup_factor, num_queries, schedule, cost_model),
+    })
+}
+
+#[cfg(feature = "json")]
+impl Analysis {
+    /// Serializes the analysis as one JSON object, with the sections that were not computed left
+    /// out. Sizes are in field elements unless their name says bytes, and the search statistics,
+    /// which vary from run to run, are not part of it.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let params = FriParams {
+    ///     degree: 1 << 20,
+    ///     blowup_factor: 8,
+    ///     num_queries: 27,
+    ///     cost_model: CostModel::default(),
+    /// };
+    /// let analysis = analyze(&params, &AnalysisOptions::default()).unwrap();
+    ///
+    /// // the serialized analysis leads with the parameters and the optimum.
+    /// assert!(analysis.to_json().starts_with(
+    ///     "{\"degree\":1048576,\"blowup\":8,\"queries\":27,\"schedule\":[0,4,4],\"size_elements\":"
+    /// ));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let report = &self.report;
+        let cost_model = &report.cost_model;
+        let list = |values: &[usize]| {
+            let values: Vec<_> = values.iter().map(usize::to_string).collect();
+            format!("[{}]", values.join(","))
+        };
+        let sized = |(size, schedule): &(usize, Vec<usize>)| {
+            format!(
+                "{{\"schedule\":{},\"size_elements\":{size},\"size_bytes\":{}}}",
+                list(schedule),
+                size_in_bytes(*size, cost_model)
+            )
+        };
+
+        let mut json = format!(
+            "{{\"degree\":{},\"blowup\":{},\"queries\":{},\"schedule\":{},\"size_elements\":{},\
+             \"size_bytes\":{},\"schedule_id\":\"{:016x}\",\"optimality\":\"{:?}\",\
+             \"remainder_check_cost\":{},\"layer_elements\":{},\"remainder_elements\":{},\
+             \"commitment_roots\":{},\"verifier_hashes\":{},\"verifier_field_ops\":{},\
+             \"verifier_inversions\":{},\"verifier_memory_bytes\":{},\"prover_memory_bytes\":{},\
+             \"commitment_ratio\":{},\"hash_input_elements\":{},\
+             \"soundness\":{{\"security_bits\":{},\"commit_phase_error\":{:e},\
+             \"query_phase_error\":{:e}}},\"baseline\":{},\"greedy\":{}",
+            report.degree,
+            report.blowup_factor,
+            report.num_queries,
+            list(&report.schedule),
+            report.total_elements(),
+            size_in_bytes(report.total_elements(), cost_model),
+            schedule_id(report),
+            self.optimality,
+            self.remainder_check_cost,
+            list(&report.breakdown.layer_elements()),
+            report.breakdown.remainder_elements,
+            report.commitment_roots,
+            report.verifier_hashes,
+            report.verifier_field_ops,
+            list(&report.verifier_inversions),
+            report.verifier_memory,
+            self.prover_memory,
+            self.commitment_ratio,
+            self.hash_input_elements,
+            self.soundness.security_bits(),
+            self.soundness.commit_phase_error,
+            self.soundness.query_phase_error,
+            sized(&(self.baseline, vec![0])),
+            sized(&self.greedy),
+        );
+
+        let comparisons: Vec<_> = self
+            .comparisons
+            .iter()
+            .map(|comparison| {
+                format!(
+                    "{{\"folding_bits\":{},\"uniform\":{},\"tuned_final_round\":{},\
+                     \"best_head\":{}}}",
+                    comparison.folding_bits,
+                    sized(&comparison.uniform),
+                    sized(&comparison.tuned_final_round),
+                    sized(&comparison.best_head)
+                )
+            })
+            .collect();
+        write!(json, ",\"comparisons\":[{}]", comparisons.join(",")).unwrap();
+
+        if let Some(band) = &self.query_band {
+            let max_queries = band
+                .max_queries
+                .map_or("null".to_string(), |max| max.to_string());
+            write!(
+                json,
+                ",\"query_band\":{{\"min_queries\":{},\"max_queries\":{max_queries}}}",
+                band.min_queries
+            )
+            .unwrap();
+        }
+        #[cfg(feature = "sim")]
+        if let Some((mean_ratio, worst_ratio)) = self.error_bound {
+            write!(
+                json,
+                ",\"error_bound\":{{\"mean_ratio\":{mean_ratio},\"worst_ratio\":{worst_ratio}}}"
+            )
+            .unwrap();
+        }
+        if let Some(robust) = &self.robust {
+            write!(
+                json,
+                ",\"robust\":{{\"schedule\":{},\"sizes\":{},\"optima\":{},\"regret\":{}}}",
+                list(&robust.schedule),
+                list(&robust.sizes),
+                list(&robust.optima),
+                robust.regret
+            )
+            .unwrap();
+        }
+        // the warnings are sentences of numbers and lists without characters to escape.
+        let warnings: Vec<_> = self
+            .warnings
+            .iter()
+            .map(|warning| format!("\"{warning}\""))
+            .collect();
+        write!(json, ",\"warnings\":[{}]}}", warnings.join(",")).unwrap();
+        json
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use super::optimized_schedule::size_in_bytes;
+use super::report::FoldingReport;
+
+/// Renders the folding schedule of `report` as a `pgfplots` figure. The figure draws the domain
+/// size of every layer as a staircase, labels each step with the folding factor applied to it, and
+/// shows the proof-size contribution of each layer (and of the remainder) as bars on a secondary
+/// axis.
+pub fn to_tikz(report: &FoldingReport) -> String {
+    let mut steps = String::new();
+    let mut labels = String::new();
+    let mut bars = String::new();
+    for (layer, info) in report.breakdown.layers.iter().enumerate() {
+        let log_domain = info.domain_size.ilog2();
+        write!(steps, " ({layer},{log_domain})").unwrap();
+        writeln!(
+            labels,
+            "    \\node[above] at (axis cs:{layer},{log_domain}) {{$\\times {}$}};",
+            1 << info.folding_bits
+        )
+        .unwrap();
+        write!(bars, " ({layer},{})", kib(report, info.total_elements())).unwrap();
+    }
+    let remainder_layer = report.breakdown.layers.len();
+    write!(
+        steps,
+        " ({remainder_layer},{})",
+        report.breakdown.remainder_domain_size.ilog2()
+    )
+    .unwrap();
+    write!(
+        bars,
+        " ({remainder_layer},{})",
+        kib(report, report.breakdown.remainder_elements)
+    )
+    .unwrap();
+
+    let mut tikz = String::new();
+    tikz.push_str("\\begin{tikzpicture}\n");
+    tikz.push_str(
+        "  \\begin{axis}[xlabel={Layer}, ylabel={Proof size (KiB)}, ybar, bar width=0.4,\n    \
+         axis y line*=right, ymin=0, xtick=data]\n",
+    );
+    writeln!(tikz, "    \\addplot[fill=gray!40] coordinates {{{bars} }};").unwrap();
+    tikz.push_str("  \\end{axis}\n");
+    tikz.push_str(
+        "  \\begin{axis}[ylabel={$\\log_2$ domain size}, axis y line*=left, axis x line=none,\n    \
+         ymin=0]\n",
+    );
+    writeln!(
+        tikz,
+        "    \\addplot[const plot, mark=*] coordinates {{{steps} }};"
+    )
+    .unwrap();
+    tikz.push_str(&labels);
+    tikz.push_str("  \\end{axis}\n");
+    tikz.push_str("\\end{tikzpicture}\n");
+    tikz
+}
+
+/// Renders the folding schedule of `report` as a gnuplot script. The script plots the same data
+/// as [`to_tikz`]: the domain size of every layer as a staircase labelled with the folding factor,
+/// and the proof-size contribution of each layer (and of the remainder) as boxes.
+pub fn to_gnuplot(report: &FoldingReport) -> String {
+    let mut script = String::new();
+    script.push_str("$layers << EOD\n");
+    script.push_str("# layer log2_domain folding_factor size_kib\n");
+    for (layer, info) in report.breakdown.layers.iter().enumerate() {
+        writeln!(
+            script,
+            "{layer} {} {} {}",
+            info.domain_size.ilog2(),
+            1 << info.folding_bits,
+            kib(report, info.total_elements())
+        )
+        .unwrap();
+    }
+    writeln!(
+        script,
+        "{} {} 1 {}",
+        report.breakdown.layers.len(),
+        report.breakdown.remainder_domain_size.ilog2(),
+        kib(report, report.breakdown.remainder_elements)
+    )
+    .unwrap();
+    script.push_str("EOD\n");
+    script.push_str(
+        "set xlabel \"Layer\"\n\
+         set ylabel \"log2 domain size\"\n\
+         set y2label \"Proof size (KiB)\"\n\
+         set ytics nomirror\n\
+         set y2tics\n\
+         set yrange [0:*]\n\
+         set y2range [0:*]\n\
+         set style fill solid 0.4\n\
+         set boxwidth 0.5\n\
+         plot $layers using 1:4 axes x1y2 with boxes title \"proof size\", \\\n     \
+         '' using 1:2 with steps title \"domain size\", \\\n     \
+         '' using 1:2:(sprintf(\"x%d\", $3)) with labels offset 0,1 notitle\n",
+    );
+    script
+}
+
+/// Converts a number of elements to KiB under the report's cost model, rounded to two decimals.
+fn kib(report: &FoldingReport, num_elements: usize) -> f64 {
+    (size_in_bytes(num_elements, &report.cost_model) as f64 / 1024.0 * 100.0).round() / 100.0
+}
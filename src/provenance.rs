@@ -0,0 +1,261 @@
+//! Records how every number of a [`ProofSizeBreakdown`](super::report::ProofSizeBreakdown) is
+//! computed, so that a reviewer can recompute any of them by hand without reading the estimator.
+
+use super::cost_model::{CostModel, PathModel, RemainderStrategy};
+use super::optimized_schedule::{leaf_elements, path_elements, remainder_elements};
+
+/// A formula the estimator prices one component of a proof with. Every formula is a product of
+/// recorded inputs, except for [`Formula::SharedPaths`], which counts digests as described by
+/// [`PathModel::path_digests`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Formula {
+    /// `queries * depth * digest_elements`: every query sends a full authentication path of
+    /// `depth` digests.
+    NaivePaths,
+    /// `shared_digests(depth, queries) * digest_elements`: the queries share their paths as with
+    /// [`PathModel::IdealCompression`].
+    SharedPaths,
+    /// `queries * values * width`: every query opens `values` values of a coset of `factor`, each
+    /// taking up `width` elements.
+    Leaves,
+    /// `coefficients * extension_degree`: the remainder of `coefficients = domain_size / blowup`
+    /// coefficients is sent.
+    SentRemainder,
+    /// `0`: the remainder is interpolated from the last layer's openings.
+    InterpolatedRemainder,
+}
+
+impl Formula {
+    /// Returns the identifier the formula is serialized with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Formula::NaivePaths => "naive_paths",
+            Formula::SharedPaths => "shared_paths",
+            Formula::Leaves => "leaves",
+            Formula::SentRemainder => "sent_remainder",
+            Formula::InterpolatedRemainder => "interpolated_remainder",
+        }
+    }
+}
+
+/// How one component of a breakdown was computed: the formula, its inputs and the result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// The layer the component belongs to, or `None` for the remainder.
+    pub layer: Option<usize>,
+    /// The formula the component is priced with.
+    pub formula: Formula,
+    /// The named inputs of the formula, including some that only explain where another input
+    /// comes from, such as the `factor` a path's `depth` depends on.
+    pub inputs: Vec<(&'static str, usize)>,
+    /// The computed number of elements, before any exclusion.
+    pub value: usize,
+    /// Whether the cost model counts the component in the size of the proof. An excluded
+    /// component is set aside in the breakdown, see
+    /// [`ProofSizeBreakdown::excluded`](super::report::ProofSizeBreakdown::excluded).
+    pub counted: bool,
+}
+
+impl Provenance {
+    /// Returns the input named `name`.
+    ///
+    /// # Panics
+    /// * If the formula has no such input
+    pub fn input(&self, name: &str) -> usize {
+        self.inputs
+            .iter()
+            .find(|(input, _)| *input == name)
+            .map(|&(_, value)| value)
+            .unwrap_or_else(|| panic!("{} has no input `{name}`", self.formula.name()))
+    }
+
+    /// Recomputes the value from the recorded inputs alone.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let breakdown = ProofSizeBreakdown::with_provenance(1 << 25, 8, 27, &[0, 4, 4], &POSEIDON_256);
+    /// for record in &breakdown.provenance {
+    ///     assert_eq!(record.recompute(), record.value);
+    /// }
+    /// ```
+    pub fn recompute(&self) -> usize {
+        match self.formula {
+            Formula::NaivePaths => {
+                self.input("queries") * self.input("depth") * self.input("digest_elements")
+            }
+            Formula::SharedPaths => {
+                PathModel::IdealCompression.path_digests(self.input("depth"), self.input("queries"))
+                    * self.input("digest_elements")
+            }
+            Formula::Leaves => self.input("queries") * self.input("values") * self.input("width"),
+            Formula::SentRemainder => self.input("coefficients") * self.input("extension_degree"),
+            Formula::InterpolatedRemainder => 0,
+        }
+    }
+}
+
+/// Records the pricing of the Merkle paths of one layer, as priced by the estimator.
+pub(crate) fn path_provenance(
+    num_queries: usize,
+    layer: usize,
+    layer_degree: usize,
+    factor: usize,
+    cost_model: &CostModel,
+) -> Provenance {
+    let depth = cost_model.coset_layout.path_digests(layer_degree, factor);
+    let formula = match cost_model.path_model {
+        PathModel::Naive => Formula::NaivePaths,
+        PathModel::IdealCompression => Formula::SharedPaths,
+    };
+    // the path stops at the coset, `log2(factor)` levels below the domain's width.
+    let inputs = vec![
+        ("queries", num_queries),
+        ("domain_size", layer_degree),
+        ("factor", factor),
+        ("depth", depth),
+        ("digest_elements", cost_model.digest_elements),
+    ];
+    Provenance {
+        layer: Some(layer),
+        formula,
+        inputs,
+        value: path_elements(num_queries, layer_degree, factor, cost_model),
+        counted: cost_model.counts_paths(layer),
+    }
+}
+
+/// Records the pricing of the opened values of one layer, as priced by the estimator.
+pub(crate) fn leaf_provenance(
+    num_queries: usize,
+    layer: usize,
+    factor: usize,
+    cost_model: &CostModel,
+) -> Provenance {
+    let values = cost_model.query_values.sent_values(layer, factor);
+    let width = cost_model.value_elements(layer);
+    Provenance {
+        layer: Some(layer),
+        formula: Formula::Leaves,
+        inputs: vec![
+            ("queries", num_queries),
+            ("factor", factor),
+            ("values", values),
+            ("width", width),
+        ],
+        value: leaf_elements(num_queries, layer, factor, cost_model),
+        counted: cost_model.counts_leaves(layer),
+    }
+}
+
+/// Records the pricing of the remainder, as priced by the estimator.
+pub(crate) fn remainder_provenance(
+    layer_degree: usize,
+    blowup_factor: usize,
+    cost_model: &CostModel,
+) -> Provenance {
+    let mut inputs = vec![("domain_size", layer_degree), ("blowup", blowup_factor)];
+    let formula = match cost_model.remainder_strategy {
+        RemainderStrategy::Sent => {
+            let coefficients = layer_degree / blowup_factor;
+            inputs.extend([
+                ("coefficients", coefficients),
+                ("extension_degree", cost_model.extension_degree),
+            ]);
+            Formula::SentRemainder
+        }
+        RemainderStrategy::InLastLayer => Formula::InterpolatedRemainder,
+    };
+    Provenance {
+        layer: None,
+        formula,
+        inputs,
+        value: remainder_elements(layer_degree, blowup_factor, cost_model),
+        counted: !cost_model.exclude.remainder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost_model::toggled_cost_models;
+    use crate::optimized_schedule::estimate_proof_size_unchecked;
+    use crate::report::ProofSizeBreakdown;
+
+    const SCHEDULES: [&[usize]; 4] = [&[0], &[0, 4, 4, 3, 3], &[0, 1, 2, 3], &[0, 5, 4, 4]];
+
+    #[test]
+    fn every_record_recomputes_from_its_inputs() {
+        for cost_model in toggled_cost_models() {
+            for seq in SCHEDULES {
+                let breakdown =
+                    ProofSizeBreakdown::with_provenance(1 << 25, 8, 300, seq, &cost_model);
+                // two records per layer, and one for the remainder.
+                assert_eq!(breakdown.provenance.len(), 2 * seq.len() + 1);
+                for record in &breakdown.provenance {
+                    assert_eq!(
+                        record.recompute(),
+                        record.value,
+                        "{record:?} of {cost_model:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counted_records_add_up_to_the_estimate() {
+        for cost_model in toggled_cost_models() {
+            for seq in SCHEDULES {
+                let breakdown =
+                    ProofSizeBreakdown::with_provenance(1 << 25, 8, 300, seq, &cost_model);
+                let counted: usize = breakdown
+                    .provenance
+                    .iter()
+                    .filter(|record| record.counted)
+                    .map(|record| record.value)
+                    .sum();
+                assert_eq!(
+                    counted,
+                    estimate_proof_size_unchecked(1 << 25, 8, 300, seq, &cost_model),
+                    "{seq:?} under {cost_model:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn formulas_follow_the_cost_model() {
+        let seq = [0, 4, 4];
+        for path_model in [PathModel::Naive, PathModel::IdealCompression] {
+            for remainder_strategy in [RemainderStrategy::Sent, RemainderStrategy::InLastLayer] {
+                let cost_model = CostModel {
+                    path_model,
+                    remainder_strategy,
+                    ..CostModel::default()
+                };
+                let breakdown =
+                    ProofSizeBreakdown::with_provenance(1 << 16, 8, 32, &seq, &cost_model);
+                let formulas: Vec<_> = breakdown.provenance.iter().map(|r| r.formula).collect();
+                let paths = match path_model {
+                    PathModel::Naive => Formula::NaivePaths,
+                    PathModel::IdealCompression => Formula::SharedPaths,
+                };
+                let remainder = match remainder_strategy {
+                    RemainderStrategy::Sent => Formula::SentRemainder,
+                    RemainderStrategy::InLastLayer => Formula::InterpolatedRemainder,
+                };
+                assert!(formulas.contains(&paths) && formulas.contains(&Formula::Leaves));
+                assert_eq!(formulas.last(), Some(&remainder));
+                assert_eq!(breakdown.provenance.last().unwrap().layer, None);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "leaves has no input `depth`")]
+    fn missing_input_panics() {
+        let record = leaf_provenance(27, 0, 1, &CostModel::default());
+        record.input("depth");
+    }
+}
@@ -0,0 +1,201 @@
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::estimate_proof_size_unchecked;
+
+/// Estimates the peak memory of a prover, in bytes, for a given folding strategy. The prover has
+/// to answer queries against every layer after the commit phase, so the evaluations and the
+/// Merkle tree of every committed layer are assumed to stay live until the end of the proof,
+/// together with the remainder polynomial.
+///
+/// A layer over a domain of size `n` folded by `factor` holds `n` evaluations and a Merkle tree
+/// of `2 * leaves - 1` digests, where `leaves` is `n / factor` for packed cosets and `n` for split
+/// cosets.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+///
+/// Memory is counted in `u64` whatever the width of `usize`, since it exceeds 32 bits from domains
+/// of about 2^26 under the default cost model.
+///
+/// # Returns
+/// * `memory` - The estimated peak prover memory in bytes
+///
+/// # Errors
+/// * If the memory does not fit in a `u64`
+pub fn estimate_prover_memory(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Result<u64, FoldingError> {
+    let mut num_elements = Some(0u64);
+    let remainder = walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // the evaluations of the layer.
+            num_elements =
+                checked_add_product(num_elements, layer.domain_size, layer.value_elements);
+
+            // the Merkle tree committing to the evaluations.
+            let (leaves, digest) = (layer.tree_leaves, cost_model.digest_elements);
+            num_elements = checked_add_product(num_elements, leaves, digest);
+            num_elements = checked_add_product(num_elements, leaves - 1, digest);
+        },
+    );
+
+    // the remainder polynomial in coefficient form.
+    num_elements = checked_add_product(num_elements, remainder.degree, cost_model.extension_degree);
+
+    num_elements
+        .and_then(|elements| elements.checked_mul(cost_model.element_bytes as u64))
+        .ok_or(FoldingError::ArithmeticOverflow {
+            quantity: "prover memory",
+        })
+}
+
+/// Counts the field elements the prover absorbs into hashes while committing to every layer of
+/// the folding sequence. Hashing time grows with the input absorbed rather than with the number
+/// of hash calls, so wide leaves, as with large folding factors, weigh in by their width.
+///
+/// A layer over a domain of size `n` absorbs all of its `n` evaluations into its leaves, and each
+/// of the `leaves - 1` internal nodes of its Merkle tree compresses two digests, where `leaves` is
+/// `n / factor` for packed cosets and `n` for split cosets. The remainder is sent in the clear and
+/// not hashed.
+///
+/// For example, with a degree of 2^10 and the folding sequence `[0, 2, 2]`, the three layers
+/// absorb 2^10, 2^10 and 2^8 evaluations of 2 elements each into trees of 2^10, 2^8 and 2^6
+/// leaves, whose internal nodes compress two digests of 4 elements each:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prover_cost::total_hash_input_elements;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let elements = total_hash_input_elements(1 << 10, &[0, 2, 2], &CostModel::default());
+/// let leaves = (1024 + 1024 + 256) * 2;
+/// let nodes = (1023 + 255 + 63) * 2 * 4;
+/// assert_eq!(elements, Ok(leaves + nodes));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+///
+/// # Returns
+/// * `num_elements` - The number of field elements absorbed into hashes
+///
+/// # Errors
+/// * If the number of elements does not fit in a `u64`
+pub fn total_hash_input_elements(
+    degree: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Result<u64, FoldingError> {
+    // the remainder is not hashed, so the blowup factor, which only sizes it, does not matter.
+    let mut num_elements = Some(0u64);
+    walk_schedule(
+        degree,
+        1,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // every evaluation is absorbed into a leaf.
+            num_elements =
+                checked_add_product(num_elements, layer.domain_size, layer.value_elements);
+
+            // every internal node compresses its two children.
+            let (nodes, digest) = (layer.tree_leaves - 1, cost_model.digest_elements);
+            num_elements = checked_add_product(num_elements, nodes, digest);
+            num_elements = checked_add_product(num_elements, nodes, digest);
+        },
+    );
+    num_elements.ok_or(FoldingError::ArithmeticOverflow {
+        quantity: "hash input",
+    })
+}
+
+/// Computes the ratio of the data the prover commits to, the evaluations of every layer of the
+/// folding sequence, to the size of the proof it ends up sending. A high ratio means the prover
+/// does a lot of work for every element of the proof.
+///
+/// For example, with a degree of 2^10, a blowup factor of 4, 3 queries and the folding sequence
+/// `[0, 2, 2]`, the prover commits to 2^10 + 2^10 + 2^8 evaluations of 2 elements each, 4608
+/// elements against a proof of 374:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prover_cost::commitment_to_proof_ratio;
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let ratio = commitment_to_proof_ratio(1 << 10, 4, 3, &[0, 2, 2], &CostModel::default());
+/// assert_eq!(ratio, 4608.0 / 374.0);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price the proof
+///
+/// # Returns
+/// * `ratio` - The committed elements divided by the proof elements
+pub fn commitment_to_proof_ratio(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> f64 {
+    // a product of two `usize` always fits in a `u128`, and so do their sums over the layers.
+    let mut committed_elements = 0u128;
+    walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            committed_elements += layer.domain_size as u128 * layer.value_elements as u128
+        },
+    );
+
+    let proof_elements =
+        estimate_proof_size_unchecked(degree, blowup_factor, num_queries, folding_seq, cost_model);
+    committed_elements as f64 / proof_elements as f64
+}
+
+/// Adds `count * width` to a running total, or returns `None` once the total no longer fits in a
+/// `u64`.
+fn checked_add_product(total: Option<u64>, count: usize, width: usize) -> Option<u64> {
+    let product = (count as u64).checked_mul(width as u64)?;
+    total?.checked_add(product)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a degree of 2^60 only exists on 64-bit targets.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn prover_memory_overflow_is_an_error() {
+        let cost_model = CostModel::default();
+        let memory = estimate_prover_memory(1 << 60, 8, &[0, 4], &cost_model);
+        assert_eq!(
+            memory,
+            Err(FoldingError::ArithmeticOverflow {
+                quantity: "prover memory"
+            })
+        );
+
+        // the same domain fits when counted in elements rather than bytes.
+        let elements = total_hash_input_elements(1 << 60, &[0, 4], &cost_model).unwrap();
+        assert!(elements > u32::MAX as u64);
+    }
+}
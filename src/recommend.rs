@@ -0,0 +1,160 @@
+//! Parameter recommendations for high-level goals, for users who do not want to pick the FRI
+//! parameters themselves.
+
+use super::analysis::{evaluation_domain, optimal_at_security};
+use super::cost_model::CostModel;
+use super::defaults::{GOLDILOCKS_QUADRATIC, POSEIDON_256};
+use super::error::FoldingError;
+use super::objective::{optimal_lexicographic, Objective};
+use super::optimized_schedule::optimal_admissible_strategy;
+use super::report::FoldingReport;
+use super::soundness::required_queries;
+use super::stats::SearchStats;
+use super::verifier_cost::estimate_verifier_gas;
+
+/// The blowup factors a recommendation chooses from.
+pub(crate) const RECOMMENDED_BLOWUPS: [usize; 4] = [2, 4, 8, 16];
+
+/// What a recommended FRI configuration should be good at. Every goal takes the degree of the
+/// polynomial to be proved before the blowup, and a security level reached with the conjectured
+/// soundness bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// The smallest proof, over Goldilocks with a quadratic extension.
+    MinimalSize { degree: usize, security_bits: u32 },
+    /// The smallest proof an EVM verifier can check within `gas_budget`, see
+    /// [`estimate_verifier_gas`], over Goldilocks with a quadratic extension.
+    MinimalVerifierGas {
+        degree: usize,
+        security_bits: u32,
+        gas_budget: usize,
+    },
+    /// The proof that is cheapest to verify inside a SNARK over BN254: the fewest verifier hashes,
+    /// with ties broken by proof size.
+    RecursionFriendly { degree: usize, security_bits: u32 },
+}
+
+/// Picks a blowup factor, number of queries, cost model and folding schedule for `goal`, trying
+/// every blowup factor from 2 to 16 with the queries it needs for the security level, and
+/// returns the report of the best configuration.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let (degree, security_bits) = (1 << 16, 100);
+/// let smallest = recommend(Goal::MinimalSize { degree, security_bits }).unwrap();
+///
+/// // a budget nothing fits in is an error, and an unlimited one leaves the smallest proof.
+/// let gas_budget = 0;
+/// let goal = Goal::MinimalVerifierGas { degree, security_bits, gas_budget };
+/// assert_eq!(recommend(goal), Err(FoldingError::GasBudgetExceeded { gas_budget }));
+/// let goal = Goal::MinimalVerifierGas { degree, security_bits, gas_budget: usize::MAX };
+/// assert_eq!(recommend(goal).unwrap().total_elements(), smallest.total_elements());
+///
+/// // the degree is checked whatever the goal.
+/// let degree = 1000;
+/// let error = FoldingError::NotPowerOfTwo { parameter: "degree", value: degree };
+/// assert_eq!(recommend(Goal::MinimalSize { degree, security_bits }), Err(error.clone()));
+/// assert_eq!(recommend(Goal::RecursionFriendly { degree, security_bits }), Err(error));
+/// ```
+///
+/// # Errors
+/// * If the degree is not a power of 2, or an evaluation domain does not fit in a `usize`
+/// * If no configuration meets the gas budget of [`Goal::MinimalVerifierGas`]
+pub fn recommend(goal: Goal) -> Result<FoldingReport, FoldingError> {
+    match goal {
+        Goal::MinimalSize {
+            degree,
+            security_bits,
+        } => best_blowup(|blowup_factor| {
+            let report = optimal_at_security(
+                degree,
+                blowup_factor,
+                security_bits,
+                true,
+                &GOLDILOCKS_QUADRATIC,
+            )?;
+            Ok((report.total_elements(), report))
+        }),
+        Goal::MinimalVerifierGas {
+            degree,
+            security_bits,
+            gas_budget,
+        } => RECOMMENDED_BLOWUPS
+            .iter()
+            .map(|&blowup_factor| {
+                let domain_size = evaluation_domain(degree, blowup_factor)?;
+                let num_queries = required_queries(blowup_factor, security_bits, true);
+                let cost_model = &GOLDILOCKS_QUADRATIC;
+                let optimum = optimal_admissible_strategy(
+                    domain_size,
+                    blowup_factor,
+                    num_queries,
+                    cost_model,
+                    &mut SearchStats::default(),
+                    &|folding_seq| {
+                        estimate_verifier_gas(
+                            domain_size,
+                            blowup_factor,
+                            num_queries,
+                            folding_seq,
+                            cost_model,
+                        ) <= gas_budget
+                    },
+                );
+                Ok(optimum.map(|(_, schedule)| {
+                    FoldingReport::new(
+                        domain_size,
+                        blowup_factor,
+                        num_queries,
+                        schedule,
+                        cost_model,
+                    )
+                }))
+            })
+            .collect::<Result<Vec<_>, FoldingError>>()?
+            .into_iter()
+            .flatten()
+            .min_by_key(FoldingReport::total_elements)
+            .ok_or(FoldingError::GasBudgetExceeded { gas_budget }),
+        Goal::RecursionFriendly {
+            degree,
+            security_bits,
+        } => best_blowup(|blowup_factor| {
+            let domain_size = evaluation_domain(degree, blowup_factor)?;
+            let num_queries = required_queries(blowup_factor, security_bits, true);
+            let cost_model: &CostModel = &POSEIDON_256;
+            let (values, schedule) = optimal_lexicographic(
+                domain_size,
+                blowup_factor,
+                num_queries,
+                &[Objective::VerifierHashes, Objective::ProofSize],
+                cost_model,
+            );
+            let report = FoldingReport::new(
+                domain_size,
+                blowup_factor,
+                num_queries,
+                schedule,
+                cost_model,
+            );
+            Ok((values, report))
+        }),
+    }
+}
+
+/// Returns the report `candidate` ranks lowest among the recommended blowup factors, the smallest
+/// blowup factor breaking ties.
+fn best_blowup<K: Ord>(
+    candidate: impl Fn(usize) -> Result<(K, FoldingReport), FoldingError>,
+) -> Result<FoldingReport, FoldingError> {
+    let [first, others @ ..] = RECOMMENDED_BLOWUPS;
+    let mut best = candidate(first)?;
+    for blowup_factor in others {
+        let next = candidate(blowup_factor)?;
+        if next.0 < best.0 {
+            best = next;
+        }
+    }
+    Ok(best.1)
+}
@@ -0,0 +1,809 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Mul};
+
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::{
+    estimate_proof_size_unchecked, optimal_folding_strategy, remainder_elements, size_in_bytes,
+};
+use super::provenance::{leaf_provenance, path_provenance, remainder_provenance, Provenance};
+use super::schedule::Schedule;
+use super::stats::SearchStats;
+use super::verifier_cost::{
+    count_commitment_roots, estimate_verifier_field_ops, estimate_verifier_hashes,
+    estimate_verifier_inversions, estimate_verifier_memory,
+};
+
+/// The contribution of a single layer to the size of a FRI proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerBreakdown {
+    /// The size of the domain committed to in this layer.
+    pub domain_size: usize,
+    /// The folding factor applied to this layer, in bits.
+    pub folding_bits: usize,
+    /// The number of values each leaf of this layer's Merkle tree groups, in bits: the folding
+    /// factor, except for the initial layer under the cost model's `winterfell_layout`, see
+    /// [`CostModel::leaf_bits`].
+    pub leaf_bits: usize,
+    /// The number of leaves of this layer's Merkle tree under the cost model the breakdown was
+    /// computed with.
+    pub tree_leaves: usize,
+    /// The number of elements in the Merkle paths of this layer, over all queries.
+    pub path_elements: usize,
+    /// The number of opened elements of this layer, over all queries.
+    pub leaf_elements: usize,
+}
+
+impl LayerBreakdown {
+    /// Returns the number of elements this layer contributes to the proof.
+    pub fn total_elements(&self) -> usize {
+        self.path_elements + self.leaf_elements
+    }
+
+    /// Returns the shape of the Merkle tree this layer is committed with under the cost model the
+    /// breakdown was computed with, which a prover needs to preallocate its digest buffers. Trees are binary and committed
+    /// with a single root.
+    ///
+    /// A coset packed into one leaf gives one leaf per coset, while split leaves give one leaf
+    /// per value. The estimator prices paths from the same layout, see
+    /// [`CosetLayout::path_digests`]: packed leaves send a path as deep as the tree, and split
+    /// leaves the path above the coset subtree, which the verifier rebuilds from the values.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::cost_model::CosetLayout;
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// for coset_layout in [CosetLayout::PackedLeaf, CosetLayout::SplitLeaves] {
+    ///     let cost_model = CostModel { coset_layout, ..CostModel::default() };
+    ///     let breakdown = ProofSizeBreakdown::new(1 << 16, 8, 27, &[0, 4, 3, 3], &cost_model);
+    ///     for layer in &breakdown.layers {
+    ///         let shape = layer.tree_shape();
+    ///         assert_eq!(shape.internal_nodes, shape.leaves - shape.cap_nodes);
+    ///         assert_eq!(shape.leaves, shape.cap_nodes << shape.depth);
+    ///     }
+    /// }
+    ///
+    /// // the second layer packs cosets of 16 values over a domain of 2^16.
+    /// let breakdown = ProofSizeBreakdown::new(1 << 16, 8, 27, &[0, 4, 3, 3], &CostModel::default());
+    /// let shape = breakdown.layers[1].tree_shape();
+    /// assert_eq!((shape.leaves, shape.depth), (1 << 12, 12));
+    /// ```
+    ///
+    /// [`CosetLayout::path_digests`]: super::cost_model::CosetLayout::path_digests
+    pub fn tree_shape(&self) -> TreeShape {
+        let leaves = self.tree_leaves;
+        let cap_nodes = 1;
+        TreeShape {
+            leaves,
+            internal_nodes: leaves - cap_nodes,
+            depth: (leaves / cap_nodes).ilog2() as usize,
+            cap_nodes,
+        }
+    }
+}
+
+/// The shape of the Merkle tree a layer is committed with, see [`LayerBreakdown::tree_shape`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeShape {
+    /// The number of leaves.
+    pub leaves: usize,
+    /// The number of nodes above the leaves, cap nodes included.
+    pub internal_nodes: usize,
+    /// The number of levels from a leaf up to a cap node.
+    pub depth: usize,
+    /// The number of nodes the tree is committed with.
+    pub cap_nodes: usize,
+}
+
+/// The size of a FRI proof split into the contribution of each layer and of the remainder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofSizeBreakdown {
+    /// The contribution of every layer, in folding order.
+    pub layers: Vec<LayerBreakdown>,
+    /// The size of the domain the remainder polynomial is evaluated over.
+    pub remainder_domain_size: usize,
+    /// The number of elements in the remainder polynomial.
+    pub remainder_elements: usize,
+    /// The elements the cost model leaves out of the proof, either because the initial layer is
+    /// opened outside of it or because another tool accounts for them, for information. The
+    /// excluded components in `layers` and `remainder_elements` then contribute nothing.
+    pub excluded: ComponentBreakdown,
+    /// How every component was computed, layer by layer with the paths before the leaves and
+    /// the remainder last, if the breakdown was built with
+    /// [`ProofSizeBreakdown::with_provenance`]; empty otherwise.
+    pub provenance: Vec<Provenance>,
+}
+
+impl ProofSizeBreakdown {
+    /// Computes the per-layer breakdown of the proof size estimated by
+    /// [`estimate_proof_size`](super::optimized_schedule::estimate_proof_size).
+    ///
+    /// # Arguments
+    /// * `degree` - The degree of the polynomial to be proved
+    /// * `blowup_factor` - The blowup factor used in the FRI protocol
+    /// * `num_queries` - The number of queries used in the FRI protocol
+    /// * `folding_seq` - The folding sequence
+    /// * `cost_model` - The cost model used to price each layer
+    pub fn new(
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        folding_seq: &[usize],
+        cost_model: &CostModel,
+    ) -> Self {
+        Self::build(
+            degree,
+            blowup_factor,
+            num_queries,
+            folding_seq,
+            cost_model,
+            false,
+        )
+    }
+
+    /// Computes the same breakdown as [`ProofSizeBreakdown::new`], and records in `provenance`
+    /// the formula, inputs and value of every component, so that an auditor can recompute any
+    /// number by hand, see [`Provenance::recompute`].
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::provenance::Formula;
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let seq = [0, 4, 4, 3, 3];
+    /// let cost_model = CostModel::default();
+    /// let breakdown = ProofSizeBreakdown::with_provenance(1 << 25, 8, 27, &seq, &cost_model);
+    /// assert_eq!(breakdown.layers, ProofSizeBreakdown::new(1 << 25, 8, 27, &seq, &cost_model).layers);
+    ///
+    /// // the paths of the third layer: 27 queries of 17 digests of 4 elements.
+    /// let paths = &breakdown.provenance[4];
+    /// assert_eq!((paths.layer, paths.formula), (Some(2), Formula::NaivePaths));
+    /// assert_eq!((paths.input("depth"), paths.value), (17, 27 * 17 * 4));
+    /// assert_eq!(paths.value, breakdown.layers[2].path_elements);
+    /// ```
+    pub fn with_provenance(
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        folding_seq: &[usize],
+        cost_model: &CostModel,
+    ) -> Self {
+        Self::build(
+            degree,
+            blowup_factor,
+            num_queries,
+            folding_seq,
+            cost_model,
+            true,
+        )
+    }
+
+    /// Computes the breakdown, recording the provenance of every component if `record` is set.
+    fn build(
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        folding_seq: &[usize],
+        cost_model: &CostModel,
+        record: bool,
+    ) -> Self {
+        let mut layers = Vec::with_capacity(folding_seq.len());
+        let mut excluded = ComponentBreakdown::default();
+        let mut provenance = Vec::new();
+        let remainder_facts = walk_schedule(
+            degree,
+            blowup_factor,
+            folding_seq,
+            cost_model,
+            &mut |layer: &LayerFacts| {
+                let mut paths = layer.path_elements(num_queries, cost_model);
+                let mut leaves = layer.leaf_elements(num_queries, cost_model);
+                if record {
+                    provenance.push(path_provenance(
+                        num_queries,
+                        layer.layer,
+                        layer.domain_size,
+                        layer.leaf_factor(),
+                        cost_model,
+                    ));
+                    provenance.push(leaf_provenance(
+                        num_queries,
+                        layer.layer,
+                        layer.leaf_factor(),
+                        cost_model,
+                    ));
+                }
+
+                // excluded components are set aside rather than dropped.
+                if !layer.counts_paths {
+                    excluded.path_elements += std::mem::take(&mut paths);
+                }
+                if !layer.counts_leaves {
+                    excluded.leaf_elements += std::mem::take(&mut leaves);
+                }
+                layers.push(LayerBreakdown {
+                    domain_size: layer.domain_size,
+                    folding_bits: layer.folding_bits,
+                    leaf_bits: layer.leaf_bits,
+                    tree_leaves: layer.tree_leaves,
+                    path_elements: paths,
+                    leaf_elements: leaves,
+                });
+            },
+        );
+
+        let mut remainder =
+            remainder_elements(remainder_facts.domain_size, blowup_factor, cost_model);
+        if record {
+            provenance.push(remainder_provenance(
+                remainder_facts.domain_size,
+                blowup_factor,
+                cost_model,
+            ));
+        }
+        if cost_model.exclude.remainder {
+            excluded.remainder_elements = std::mem::take(&mut remainder);
+        }
+
+        ProofSizeBreakdown {
+            layers,
+            remainder_domain_size: remainder_facts.domain_size,
+            remainder_elements: remainder,
+            excluded,
+            provenance,
+        }
+    }
+
+    /// Returns the total number of elements in the proof.
+    pub fn total_elements(&self) -> usize {
+        self.layers
+            .iter()
+            .map(LayerBreakdown::total_elements)
+            .sum::<usize>()
+            + self.remainder_elements
+    }
+
+    /// Returns the number of elements every layer contributes to the proof, in folding order.
+    /// These are the field elements a recursive verifier absorbs as witnesses for each layer,
+    /// counted before any conversion to bytes.
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let breakdown = ProofSizeBreakdown::new(1 << 10, 4, 3, &[0, 2, 2], &CostModel::default());
+    /// assert_eq!(breakdown.layer_elements(), [126, 120, 96]);
+    /// assert_eq!(breakdown.remainder_elements, 32);
+    /// ```
+    pub fn layer_elements(&self) -> Vec<usize> {
+        self.layers
+            .iter()
+            .map(LayerBreakdown::total_elements)
+            .collect()
+    }
+
+    /// Collapses the layers into one total per kind of component, so that breakdowns of
+    /// schedules with different numbers of layers can be added up.
+    pub fn components(&self) -> ComponentBreakdown {
+        ComponentBreakdown {
+            path_elements: self.layers.iter().map(|layer| layer.path_elements).sum(),
+            leaf_elements: self.layers.iter().map(|layer| layer.leaf_elements).sum(),
+            remainder_elements: self.remainder_elements,
+        }
+    }
+}
+
+/// The size of one or more FRI proofs split by kind of component, with all layers collapsed.
+/// Component breakdowns add up component by component and scale by a number of proofs, which is
+/// what aggregate bandwidth planning over many proofs needs.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let cost_model = CostModel::default();
+/// let a = ProofSizeBreakdown::new(1 << 10, 4, 3, &[0, 2, 2], &cost_model);
+/// let b = ProofSizeBreakdown::new(1 << 12, 8, 5, &[0, 3], &cost_model);
+/// let (x, y) = (a.components(), b.components());
+///
+/// // collapsing the layers keeps the total.
+/// assert_eq!(x.total_elements(), a.total_elements());
+/// // counts distribute over the sum of different schedules.
+/// assert_eq!((x + y) * 7, x * 7 + y * 7);
+/// assert_eq!((x * 7).total_elements(), 7 * a.total_elements());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentBreakdown {
+    /// The number of elements in the Merkle paths of all layers.
+    pub path_elements: usize,
+    /// The number of opened elements of all layers.
+    pub leaf_elements: usize,
+    /// The number of elements in the remainder polynomials.
+    pub remainder_elements: usize,
+}
+
+impl ComponentBreakdown {
+    /// Returns the total number of elements over all components.
+    pub fn total_elements(&self) -> usize {
+        self.path_elements + self.leaf_elements + self.remainder_elements
+    }
+}
+
+impl Add for ComponentBreakdown {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        ComponentBreakdown {
+            path_elements: self.path_elements + other.path_elements,
+            leaf_elements: self.leaf_elements + other.leaf_elements,
+            remainder_elements: self.remainder_elements + other.remainder_elements,
+        }
+    }
+}
+
+impl Sum for ComponentBreakdown {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(ComponentBreakdown::default(), Add::add)
+    }
+}
+
+impl Mul<usize> for ComponentBreakdown {
+    type Output = Self;
+
+    /// Scales the breakdown to `count` proofs.
+    fn mul(self, count: usize) -> Self {
+        ComponentBreakdown {
+            path_elements: self.path_elements * count,
+            leaf_elements: self.leaf_elements * count,
+            remainder_elements: self.remainder_elements * count,
+        }
+    }
+}
+
+/// The outcome of sizing a FRI proof for a folding schedule, together with the parameters it was
+/// computed for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoldingReport {
+    /// The degree of the polynomial to be proved.
+    pub degree: usize,
+    /// The blowup factor used in the FRI protocol.
+    pub blowup_factor: usize,
+    /// The number of queries used in the FRI protocol.
+    pub num_queries: usize,
+    /// The folding schedule, in bits.
+    pub schedule: Vec<usize>,
+    /// The cost model the proof was sized with.
+    pub cost_model: CostModel,
+    /// The per-layer breakdown of the proof size.
+    pub breakdown: ProofSizeBreakdown,
+    /// The number of Merkle roots in the proof.
+    pub commitment_roots: usize,
+    /// The number of hashes the verifier computes.
+    pub verifier_hashes: usize,
+    /// The number of field multiplications the verifier performs.
+    pub verifier_field_ops: usize,
+    /// The number of field inversions the verifier performs in each layer of the schedule.
+    pub verifier_inversions: Vec<usize>,
+    /// The peak working memory of the verifier in bytes, checking one query at a time.
+    pub verifier_memory: usize,
+    /// The number of independent proofs with these parameters reported together. The breakdown
+    /// and verifier costs are per proof.
+    pub instances: usize,
+}
+
+impl FoldingReport {
+    /// Sizes the FRI proof for `schedule` and collects the result into a report.
+    pub fn new(
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        schedule: Vec<usize>,
+        cost_model: &CostModel,
+    ) -> Self {
+        let breakdown =
+            ProofSizeBreakdown::new(degree, blowup_factor, num_queries, &schedule, cost_model);
+
+        // The breakdown must account for exactly the elements the estimator charges.
+        debug_assert_eq!(
+            breakdown.total_elements(),
+            estimate_proof_size_unchecked(
+                degree,
+                blowup_factor,
+                num_queries,
+                &schedule,
+                cost_model
+            )
+        );
+        let commitment_roots = count_commitment_roots(&schedule, cost_model);
+        let verifier_hashes = estimate_verifier_hashes(degree, num_queries, &schedule, cost_model);
+        let verifier_field_ops =
+            estimate_verifier_field_ops(degree, blowup_factor, num_queries, &schedule, cost_model);
+        let verifier_inversions = estimate_verifier_inversions(num_queries, &schedule, cost_model);
+        let verifier_memory =
+            estimate_verifier_memory(degree, blowup_factor, &schedule, cost_model);
+        FoldingReport {
+            degree,
+            blowup_factor,
+            num_queries,
+            schedule,
+            cost_model: *cost_model,
+            breakdown,
+            commitment_roots,
+            verifier_hashes,
+            verifier_field_ops,
+            verifier_inversions,
+            verifier_memory,
+            instances: 1,
+        }
+    }
+
+    /// Returns the number of elements across all instances. Independent proofs share no element,
+    /// so this is the per-proof size times the number of instances.
+    pub fn total_elements(&self) -> usize {
+        self.breakdown.total_elements() * self.instances
+    }
+
+    /// Returns, for every prefix of `query_tiers`, the bytes of a single proof a verifier needs
+    /// to check as many queries as the prefix holds, for deployments where some verifiers only
+    /// check the first queries. The proof is laid out tier by tier, so each slice holds the
+    /// openings of its queries and the remainder. Queries share paths under the cost model's
+    /// [`PathModel`](super::cost_model::PathModel), and how much they share depends on how
+    /// many there are, so a slice is not proportional to its number of queries.
+    ///
+    /// With ideally compressed paths, 12 and 27 queries need equally many digests in trees
+    /// of 2^5 leaves and more, so the light tier only saves the leaves of the other 15 queries:
+    ///
+    /// ```
+    /// use fri_dynamic_folding_scheme::prelude::*;
+    ///
+    /// let cost_model = CostModel {
+    ///     path_model: PathModel::IdealCompression,
+    ///     ..CostModel::default()
+    /// };
+    /// // every query opens one value of layer 0 and a coset of each later layer, of 2 elements each.
+    /// for (schedule, leaf_elements) in [
+    ///     (vec![0, 2, 2], (1 + 4 + 4) * 2),
+    ///     (vec![0, 2, 3], (1 + 4 + 8) * 2),
+    /// ] {
+    ///     let report = FoldingReport::new(1 << 10, 4, 27, schedule.clone(), &cost_model);
+    ///     let tiers = report.query_tier_bytes(&[12, 15]).unwrap();
+    ///     assert_eq!(tiers[1], size_in_bytes(report.total_elements(), &cost_model));
+    ///     assert_eq!(tiers[1] - tiers[0], size_in_bytes(15 * leaf_elements, &cost_model));
+    ///
+    ///     let light = estimate_proof_size(1 << 10, 4, 12, &schedule, &cost_model).unwrap();
+    ///     assert_eq!(tiers[0], size_in_bytes(light, &cost_model));
+    /// }
+    ///
+    /// let report = FoldingReport::new(1 << 10, 4, 27, vec![0, 2, 2], &cost_model);
+    /// assert!(matches!(
+    ///     report.query_tier_bytes(&[12, 12]),
+    ///     Err(FoldingError::QueryTiersMismatch { tiers_total: 24, num_queries: 27 })
+    /// ));
+    /// ```
+    ///
+    /// # Errors
+    /// * If `query_tiers` is empty, contains an empty tier, or does not add up to the number of
+    ///   queries of the report
+    pub fn query_tier_bytes(&self, query_tiers: &[usize]) -> Result<Vec<usize>, FoldingError> {
+        let tiers_total = query_tiers.iter().sum();
+        if query_tiers.is_empty() || query_tiers.contains(&0) || tiers_total != self.num_queries {
+            return Err(FoldingError::QueryTiersMismatch {
+                tiers_total,
+                num_queries: self.num_queries,
+            });
+        }
+        Ok(query_tiers
+            .iter()
+            .scan(0, |num_queries, tier| {
+                *num_queries += tier;
+                Some(*num_queries)
+            })
+            .map(|num_queries| {
+                let size = estimate_proof_size_unchecked(
+                    self.degree,
+                    self.blowup_factor,
+                    num_queries,
+                    &self.schedule,
+                    &self.cost_model,
+                );
+                size_in_bytes(size, &self.cost_model)
+            })
+            .collect())
+    }
+}
+
+/// Shows the schedule and the number of elements of a single proof. The alternate form `{:#}`
+/// adds a line for every layer, with its folding factor and the domain it commits to and the
+/// degree of its polynomial before and after folding, and a line for the remainder. A layer
+/// folding by a factor of 1, such as the unfolded initial layer, keeps its domain and degree.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let report = FoldingReport::new(1 << 25, 8, 27, vec![0, 4, 4, 4, 3], &CostModel::default());
+/// assert_eq!(report.to_string(), "[0, 4, 4, 4, 3] (12622 elements)");
+/// assert_eq!(
+///     format!("{report:#}"),
+///     "[0, 4, 4, 4, 3] (12622 elements)
+///   layer 0: fold 1, domain 2^25 -> 2^25, poly degree 2^22 -> 2^22
+///   layer 1: fold 16, domain 2^25 -> 2^21, poly degree 2^22 -> 2^18
+///   layer 2: fold 16, domain 2^21 -> 2^17, poly degree 2^18 -> 2^14
+///   layer 3: fold 16, domain 2^17 -> 2^13, poly degree 2^14 -> 2^10
+///   layer 4: fold 8, domain 2^13 -> 2^10, poly degree 2^10 -> 2^7
+///   remainder: domain 2^10, poly degree 2^7"
+/// );
+/// ```
+impl fmt::Display for FoldingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} ({} elements)",
+            self.schedule,
+            self.breakdown.total_elements()
+        )?;
+        if !f.alternate() {
+            return Ok(());
+        }
+
+        let log_blowup = self.blowup_factor.ilog2();
+        for (i, layer) in self.breakdown.layers.iter().enumerate() {
+            let domain_bits = layer.domain_size.ilog2();
+            let folded_bits = domain_bits - layer.folding_bits as u32;
+            write!(
+                f,
+                "\n  layer {i}: fold {}, domain 2^{domain_bits} -> 2^{folded_bits}, poly degree \
+                 2^{} -> 2^{}",
+                1usize << layer.folding_bits,
+                domain_bits - log_blowup,
+                folded_bits - log_blowup
+            )?;
+        }
+        let remainder_bits = self.breakdown.remainder_domain_size.ilog2();
+        write!(f, "\n  remainder: domain 2^{remainder_bits}, ")?;
+        match remainder_bits - log_blowup {
+            0 => write!(f, "constant"),
+            degree_bits => write!(f, "poly degree 2^{degree_bits}"),
+        }
+    }
+}
+
+/// The version of the scheme [`schedule_id`] hashes with. It is part of every hashed input, and
+/// only changes if the scheme does, so that IDs computed under different schemes never collide.
+pub const SCHEDULE_ID_VERSION: u64 = 1;
+
+/// Returns a stable identifier of the parameters and the folding schedule of `report`, i.e. of
+/// everything a prover is configured with, to key caches or tag configurations in logs. The cost
+/// model the schedule was found with is not part of the identifier: two reports share an
+/// identifier exactly when their provers run the same FRI proof.
+///
+/// The identifier is the 64-bit FNV-1a hash of the following values, each encoded as 8
+/// little-endian bytes: [`SCHEDULE_ID_VERSION`], the degree, the blowup factor, the number of
+/// queries, the length of the schedule and every folding factor of the schedule in bits. The
+/// scheme does not depend on the platform or on the version of the crate, so the identifiers
+/// stay comparable over time. The schedule is hashed in its canonical form, see [`Schedule`], so
+/// that every spelling of a schedule has the same identifier:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::report::schedule_id;
+///
+/// let report = FoldingReport::new(1 << 25, 8, 27, vec![0, 4, 4, 3, 3], &CostModel::default());
+/// assert_eq!(schedule_id(&report), 0x60c1_ac8d_23f5_293c);
+///
+/// // the cost model does not matter, every parameter and layer does.
+/// let priced = FoldingReport::new(1 << 25, 8, 27, vec![0, 4, 4, 3, 3], &POSEIDON_256);
+/// assert_eq!(schedule_id(&priced), schedule_id(&report));
+/// let reordered = FoldingReport::new(1 << 25, 8, 27, vec![0, 3, 3, 4, 4], &CostModel::default());
+/// assert_ne!(schedule_id(&reordered), schedule_id(&report));
+/// let queries = FoldingReport::new(1 << 25, 8, 28, vec![0, 4, 4, 3, 3], &CostModel::default());
+/// assert_ne!(schedule_id(&queries), schedule_id(&report));
+///
+/// // semantically equal schedules share their identifier.
+/// for spelling in [vec![0, 4, 4, 3, 3, 0], vec![1, 16, 16, 8, 8]] {
+///     let spelled = FoldingReport {
+///         schedule: spelling,
+///         ..report.clone()
+///     };
+///     assert_eq!(schedule_id(&spelled), schedule_id(&report));
+/// }
+/// ```
+pub fn schedule_id(report: &FoldingReport) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    // a schedule without a canonical form is hashed as written down.
+    let schedule = Schedule::new(report.schedule.clone());
+    let schedule = schedule.canonicalize().unwrap_or(schedule).into_entries();
+    let values = [
+        SCHEDULE_ID_VERSION,
+        report.degree as u64,
+        report.blowup_factor as u64,
+        report.num_queries as u64,
+        schedule.len() as u64,
+    ]
+    .into_iter()
+    .chain(schedule.iter().map(|&bits| bits as u64));
+    values
+        .flat_map(u64::to_le_bytes)
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Computes the optimal folding schedule for one FRI proof and reports the aggregate size of
+/// `n_instances` independent proofs with the same parameters, e.g. one per shard. The proofs are
+/// not batched: each one has its own commitments, openings and remainder, so none of the proof is
+/// shared and the aggregate is `n_instances` times the size of a single proof.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `n_instances` - The number of independent proofs
+/// * `cost_model` - The cost model used to price each candidate folding sequence
+pub fn aggregate_size(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    n_instances: usize,
+    cost_model: &CostModel,
+) -> FoldingReport {
+    let (_, schedule) = optimal_folding_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        vec![0],
+        &HashMap::new(),
+        cost_model,
+        &mut SearchStats::default(),
+    )
+    .expect("an unconstrained search always finds a schedule");
+
+    FoldingReport {
+        instances: n_instances,
+        ..FoldingReport::new(degree, blowup_factor, num_queries, schedule, cost_model)
+    }
+}
+
+/// Returns the folding factors, in bits, of the uniform schedules an optimal schedule should be
+/// compared against: the classic factors 2, 4, 8 and 16, together with every factor the optimal
+/// schedule uses, so that the comparison always brackets the optimum. The factors are sorted and
+/// distinct.
+pub fn comparison_factors(optimal_schedule: &[usize]) -> Vec<usize> {
+    let mut factors: Vec<_> = (1..=4)
+        .chain(optimal_schedule.iter().copied().filter(|&bits| bits > 0))
+        .collect();
+    factors.sort_unstable();
+    factors.dedup();
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost_model::{CosetLayout, PathModel, QueryValueTransmission};
+    use crate::defaults::{BABYBEAR_QUARTIC, GOLDILOCKS_QUADRATIC, POSEIDON_256};
+    use crate::optimized_schedule::estimate_proof_size;
+
+    #[test]
+    fn layers_and_remainder_account_for_every_element() {
+        let seq = [0, 4, 3, 3];
+        for preset in [GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256] {
+            for coset_layout in [CosetLayout::PackedLeaf, CosetLayout::SplitLeaves] {
+                for query_values in [
+                    QueryValueTransmission::AllValues,
+                    QueryValueTransmission::OmitFoldedValue,
+                ] {
+                    let cost_model = CostModel {
+                        coset_layout,
+                        query_values,
+                        ..preset
+                    };
+                    let breakdown = ProofSizeBreakdown::new(1 << 16, 8, 27, &seq, &cost_model);
+                    let layers: usize = breakdown.layer_elements().iter().sum();
+                    let size = estimate_proof_size(1 << 16, 8, 27, &seq, &cost_model).unwrap();
+                    assert_eq!(layers + breakdown.remainder_elements, size);
+
+                    // every component is converted to bytes at the same width.
+                    let components = breakdown.components();
+                    assert_eq!(
+                        size_in_bytes(size, &cost_model),
+                        (components.path_elements
+                            + components.leaf_elements
+                            + components.remainder_elements)
+                            * cost_model.element_bytes
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trace_of_a_constant_remainder() {
+        let constant = FoldingReport::new(16, 4, 27, vec![0, 2], &CostModel::default());
+        assert_eq!(
+            format!("{constant:#}"),
+            "[0, 2] (920 elements)
+  layer 0: fold 1, domain 2^4 -> 2^4, poly degree 2^2 -> 2^2
+  layer 1: fold 4, domain 2^4 -> 2^2, poly degree 2^2 -> 2^0
+  remainder: domain 2^2, constant"
+        );
+    }
+
+    #[test]
+    fn trace_of_the_unfolded_schedule() {
+        let unfolded = FoldingReport::new(16, 4, 27, vec![0], &CostModel::default());
+        assert_eq!(unfolded.to_string(), "[0] (494 elements)");
+        assert_eq!(
+            format!("{unfolded:#}"),
+            "[0] (494 elements)
+  layer 0: fold 1, domain 2^4 -> 2^4, poly degree 2^2 -> 2^2
+  remainder: domain 2^4, poly degree 2^2"
+        );
+    }
+
+    // the trace counts a single proof, whatever the number of instances.
+    #[test]
+    fn trace_of_aggregated_proofs_is_per_proof() {
+        let mut report = FoldingReport::new(16, 4, 27, vec![0], &CostModel::default());
+        report.instances = 3;
+        assert_eq!(report.to_string(), "[0] (494 elements)");
+    }
+
+    // the report, its breakdown and the optimizer must all price a schedule as the estimator
+    // does, or they compare schedules on different terms.
+    #[test]
+    fn reports_price_like_the_estimator_for_every_preset_and_layout() {
+        let (degree, blowup_factor, num_queries) = (1 << 16, 8, 27);
+        for preset in [GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256] {
+            for coset_layout in [CosetLayout::PackedLeaf, CosetLayout::SplitLeaves] {
+                for path_model in [PathModel::Naive, PathModel::IdealCompression] {
+                    for winterfell_layout in [false, true] {
+                        let cost_model = CostModel {
+                            coset_layout,
+                            path_model,
+                            winterfell_layout,
+                            ..preset
+                        };
+                        let (size, optimum) = optimal_folding_strategy(
+                            degree,
+                            blowup_factor,
+                            num_queries,
+                            vec![0],
+                            &HashMap::new(),
+                            &cost_model,
+                            &mut SearchStats::default(),
+                        )
+                        .unwrap();
+                        for schedule in [optimum.clone(), vec![0, 4, 3, 3], vec![0, 1, 2, 3, 4]] {
+                            let estimate = estimate_proof_size(
+                                degree,
+                                blowup_factor,
+                                num_queries,
+                                &schedule,
+                                &cost_model,
+                            )
+                            .unwrap();
+                            let report = FoldingReport::new(
+                                degree,
+                                blowup_factor,
+                                num_queries,
+                                schedule.clone(),
+                                &cost_model,
+                            );
+                            assert_eq!(
+                                report.total_elements(),
+                                estimate,
+                                "{schedule:?} {cost_model:?}"
+                            );
+                            assert_eq!(report.breakdown.total_elements(), estimate);
+                            if schedule == optimum {
+                                assert_eq!(size, estimate, "{cost_model:?}");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Folding schedules that compare by what they fold rather than by how they were written down, so
+//! that schedules from the optimizer, configuration files and users can be checked for drift.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use super::error::FoldingError;
+
+/// A folding schedule as some source wrote it down.
+///
+/// The crate's convention is the folding factor of every layer in bits, starting with the
+/// unfolded initial layer, `0`, as the optimizer returns them. [`Schedule::canonicalize`] brings
+/// other spellings of the same schedule to that form:
+/// * Folding factors rather than bits are recognized by their initial layer, a factor of `1`,
+///   provided every entry is a power of 2: `[1, 16, 8]` is `[0, 4, 3]`.
+/// * Unfolded layers after the last fold fold nothing and are dropped: `[0, 4, 3, 0]` is
+///   `[0, 4, 3]`. Several leading unfolded layers are kept, since each commits to the codeword
+///   again, see [`validate_schedule`](super::optimized_schedule::validate_schedule).
+///
+/// Schedules are equal, and hash alike, when their canonical forms are. A schedule without a
+/// canonical form only equals the schedules written down with the same entries.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+///
+/// let optimal = Schedule::new(vec![0, 4, 4, 4, 3]);
+/// assert_eq!("[0, 4, 4, 4, 3, 0]".parse::<Schedule>().unwrap(), optimal);
+/// assert_eq!("1,16,16,16,8".parse::<Schedule>().unwrap(), optimal);
+/// assert_ne!(Schedule::new(vec![0, 3, 4, 4, 4]), optimal);
+///
+/// // leading unfolded layers are commitments of their own.
+/// assert_ne!(Schedule::new(vec![0, 0, 4, 4, 4, 3]), optimal);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    entries: Vec<usize>,
+}
+
+impl Schedule {
+    /// Returns the schedule with the given entries, in folding bits or in folding factors.
+    pub fn new(entries: Vec<usize>) -> Self {
+        Schedule { entries }
+    }
+
+    /// Returns the entries the schedule was written down with.
+    pub fn entries(&self) -> &[usize] {
+        &self.entries
+    }
+
+    /// Returns the entries the schedule was written down with, which are the folding bits of
+    /// every layer for a canonical schedule.
+    pub fn into_entries(self) -> Vec<usize> {
+        self.entries
+    }
+
+    /// Returns whether the schedule is written down in the crate's convention, i.e. is its own
+    /// canonical form.
+    pub fn is_canonical(&self) -> bool {
+        self.canonicalize()
+            .is_ok_and(|canonical| canonical.entries == self.entries)
+    }
+
+    /// Returns the schedule in the crate's convention: the folding bits of every layer, starting
+    /// with the unfolded initial layer and ending with the last fold, see [`Schedule`]. The
+    /// canonical form of a canonical schedule is the schedule itself.
+    ///
+    /// Whether the schedule fits a given degree is left to
+    /// [`validate_schedule`](super::optimized_schedule::validate_schedule).
+    ///
+    /// # Errors
+    /// * If the schedule is empty or starts with neither `0` bits nor a factor of `1`
+    /// * If a layer after the first fold does not fold, unless no layer after it folds either
+    /// * If the schedule folds by `usize::BITS` bits or more, more than any degree allows
+    pub fn canonicalize(&self) -> Result<Schedule, FoldingError> {
+        let mut bits = match self.entries.first() {
+            Some(0) => self.entries.clone(),
+            Some(1) if self.entries.iter().all(|factor| factor.is_power_of_two()) => self
+                .entries
+                .iter()
+                .map(|factor| factor.trailing_zeros() as usize)
+                .collect(),
+            _ => return Err(FoldingError::MissingInitialLayer),
+        };
+
+        // unfolded layers after the last fold fold nothing, while leading ones are commitments.
+        if let Some(last_fold) = bits.iter().rposition(|&bits| bits > 0) {
+            bits.truncate(last_fold + 1);
+        }
+        let leading_zeros = bits.iter().take_while(|&&bits| bits == 0).count();
+        if let Some(layer) = (leading_zeros..bits.len()).find(|&layer| bits[layer] == 0) {
+            return Err(FoldingError::UnfoldedInteriorLayer { layer });
+        }
+
+        let available_bits = usize::BITS as usize - 1;
+        let folded_bits = bits
+            .iter()
+            .try_fold(0usize, |folded, &bits| folded.checked_add(bits))
+            .unwrap_or(usize::MAX);
+        if folded_bits > available_bits {
+            return Err(FoldingError::ScheduleOverFolding {
+                folded_bits,
+                available_bits,
+            });
+        }
+        Ok(Schedule { entries: bits })
+    }
+}
+
+impl From<Vec<usize>> for Schedule {
+    fn from(entries: Vec<usize>) -> Self {
+        Schedule::new(entries)
+    }
+}
+
+impl PartialEq for Schedule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.canonicalize(), other.canonicalize()) {
+            (Ok(canonical), Ok(other_canonical)) => canonical.entries == other_canonical.entries,
+            _ => self.entries == other.entries,
+        }
+    }
+}
+
+impl Eq for Schedule {}
+
+impl Hash for Schedule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.canonicalize() {
+            Ok(canonical) => canonical.entries.hash(state),
+            Err(_) => self.entries.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.entries)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = FoldingError;
+
+    /// Parses the entries of a schedule separated by commas or whitespace, optionally within
+    /// square brackets, e.g. `0,4,4,3` or `[0, 4, 4, 3]`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let value = value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+            .unwrap_or(value);
+        value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse()
+                    .map_err(|_| FoldingError::InvalidScheduleEntry {
+                        entry: entry.to_string(),
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map(Schedule::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+    use crate::cost_model::CostModel;
+    use crate::report::{schedule_id, FoldingReport};
+
+    /// Returns every schedule of up to 4 layers folding by up to 5 bits each, in bits and in
+    /// factors.
+    fn short_schedules() -> Vec<Schedule> {
+        let mut schedules = Vec::new();
+        for len in 0..=4u32 {
+            for index in 0..6usize.pow(len) {
+                let bits: Vec<_> = (0..len).map(|i| index / 6usize.pow(i) % 6).collect();
+                let factors = bits.iter().map(|&bits| 1 << bits).collect();
+                schedules.extend([Schedule::new(bits), Schedule::new(factors)]);
+            }
+        }
+        schedules
+    }
+
+    fn hash(schedule: &Schedule) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        schedule.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn fingerprint(schedule: &Schedule) -> u64 {
+        let report = FoldingReport::new(1 << 20, 8, 27, vec![0, 4, 4, 4], &CostModel::default());
+        schedule_id(&FoldingReport {
+            schedule: schedule.entries().to_vec(),
+            ..report
+        })
+    }
+
+    #[test]
+    fn canonicalizing_is_idempotent() {
+        for schedule in short_schedules() {
+            if let Ok(canonical) = schedule.canonicalize() {
+                assert!(canonical.is_canonical(), "{schedule}");
+                assert_eq!(canonical.canonicalize(), Ok(canonical.clone()));
+                assert_eq!(canonical, schedule);
+            }
+        }
+    }
+
+    #[test]
+    fn equal_schedules_hash_and_fingerprint_alike() {
+        let schedules = short_schedules();
+        for schedule in &schedules {
+            for other in &schedules {
+                if schedule == other {
+                    assert_eq!(hash(schedule), hash(other), "{schedule} and {other}");
+                    assert_eq!(fingerprint(schedule), fingerprint(other));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spellings_of_one_schedule_are_equal() {
+        let canonical = Schedule::new(vec![0, 4, 3]);
+        for spelling in [vec![0, 4, 3, 0, 0], vec![1, 16, 8], vec![1, 16, 8, 1]] {
+            assert_eq!(
+                Schedule::new(spelling).canonicalize(),
+                Ok(canonical.clone())
+            );
+        }
+        // an entry that is not a power of 2 is not a factor, so the schedule is in bits.
+        assert_eq!(
+            Schedule::new(vec![1, 16, 3]).canonicalize(),
+            Err(FoldingError::MissingInitialLayer)
+        );
+        // a schedule without a canonical form equals its own spelling only.
+        let invalid = Schedule::new(vec![2, 3]);
+        assert_eq!(invalid, Schedule::new(vec![2, 3]));
+        assert_ne!(invalid, Schedule::new(vec![3, 2]));
+    }
+
+    #[test]
+    fn invalid_schedules_have_no_canonical_form() {
+        assert_eq!(
+            Schedule::new(vec![]).canonicalize(),
+            Err(FoldingError::MissingInitialLayer)
+        );
+        assert_eq!(
+            Schedule::new(vec![0, 4, 0, 3]).canonicalize(),
+            Err(FoldingError::UnfoldedInteriorLayer { layer: 2 })
+        );
+        let available_bits = usize::BITS as usize - 1;
+        assert_eq!(
+            Schedule::new(vec![0, 32, 32]).canonicalize(),
+            Err(FoldingError::ScheduleOverFolding {
+                folded_bits: 64,
+                available_bits
+            })
+        );
+        assert_eq!(
+            Schedule::new(vec![0, usize::MAX, 1]).canonicalize(),
+            Err(FoldingError::ScheduleOverFolding {
+                folded_bits: usize::MAX,
+                available_bits
+            })
+        );
+        // leading unfolded layers are commitments of their own.
+        assert!(Schedule::new(vec![0, 0, 4]).is_canonical());
+    }
+
+    #[test]
+    fn parses_every_separator() {
+        let expected = Schedule::new(vec![0, 4, 3]);
+        for value in ["0,4,3", "[0, 4, 3]", " 0 4 3 ", "[0,4 ,3]"] {
+            let parsed: Schedule = value.parse().unwrap();
+            assert_eq!(parsed.entries(), expected.entries(), "{value}");
+        }
+        assert_eq!(
+            "0, four".parse::<Schedule>(),
+            Err(FoldingError::InvalidScheduleEntry {
+                entry: "four".to_string()
+            })
+        );
+    }
+}
@@ -1,39 +1,43 @@
-use super::optimized_schedule::estimate_proof_size;
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::optimized_schedule::{
+    estimate_proof_size_unchecked, is_remainder_feasible, leaf_elements, path_elements,
+    validate_parameters, validate_schedule, MAX_FOLDING_BITS,
+};
 
 /// Computes a schedule for a simple FRI proof. The schedule is a vector of folding factors. The
 /// folding factors are represented as in the form of bits. For example, a folding factor of 4 is
 /// represented as 2. The folding would stop if the degree of the polynomial to be proved is less
 /// than the `remainder_max_degree`. It follows `winterfell` implementation FRI proof generation.
 ///
+/// Folding also stops before a round that would fold the polynomial below degree 1, so small
+/// polynomials (at most `remainder_max_degree`, or smaller than the folding factor) get a schedule
+/// with no folding rounds at all.
+///
 /// # Arguments
 /// * `degree` - The degree of the polynomial to be proved
 /// * `blowup_factor` - The blowup factor used in the FRI protocol
 /// * `num_queries` - The number of queries used in the FRI protocol
 /// * `remainder_max_degree` - The maximum degree of the remainder polynomial
 /// * `folding_factor` - The folding factor used in the FRI protocol
+/// * `cost_model` - The cost model used to price the schedule
 ///
 /// # Returns
 /// * `proof_size` - The estimated proof size in terms of field elements
 /// * `folding_schedule` - The folding schedule
 ///
-/// # Panics
-/// * If the degree is not a power of 2
-/// * If the blowup factor is not a power of 2
-/// * If the folding factor is not a power of 2
-/// * If the remainder poly degree is greater than the degree of the polynomial to be proved
-pub(crate) fn simple_schedule(
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+pub fn simple_schedule(
     degree: usize,
     blowup_factor: usize,
     num_queries: usize,
     remainder_max_degree: usize,
     folding_factor: usize,
-) -> (usize, Vec<usize>) {
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
     // The degree, blowup factor must be powers of 2.
-    debug_assert!(degree.is_power_of_two());
-    debug_assert!(blowup_factor.is_power_of_two());
-
-    // The `remainder_max_degree` must be less than the degree of the polynomial to be proved.
-    debug_assert!(remainder_max_degree <= degree / blowup_factor);
+    validate_parameters(degree, blowup_factor)?;
 
     // The degree of the polynomial to be proved.
     let poly_degree = degree / blowup_factor;
@@ -47,13 +51,327 @@ pub(crate) fn simple_schedule(
     // the folding schedule for subsequent rounds.
     folding_schedule.resize(num_rounds, folding_factor);
 
-    let proof_size = estimate_proof_size(degree, blowup_factor, num_queries, &folding_schedule);
+    let proof_size = estimate_proof_size_unchecked(
+        degree,
+        blowup_factor,
+        num_queries,
+        &folding_schedule,
+        cost_model,
+    );
+
+    Ok((proof_size, folding_schedule))
+}
 
-    (proof_size, folding_schedule)
+/// A schedule of [`simple_schedule`] together with what it achieves, see
+/// [`simple_schedule_result`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimpleScheduleResult {
+    /// The folding schedule.
+    pub schedule: Vec<usize>,
+    /// The estimated proof size in terms of field elements.
+    pub size: usize,
+    /// The degree of the remainder polynomial the schedule folds down to, which can be well below
+    /// the requested `remainder_max_degree`.
+    pub final_remainder_degree: usize,
+    /// The number of folding rounds, not counting the unfolded first layer.
+    pub rounds: usize,
+}
+
+/// Computes the schedule of [`simple_schedule`] and reports the remainder degree it actually
+/// lands on. A prover configured with the achieved degree instead of the requested bound commits
+/// to the same layers, while a looser bound may let it stop folding earlier.
+///
+/// Unless folding stops early because the polynomial is too small for another round, the
+/// achieved degree is within the bound and one more fold of it would not have been needed:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::simple_schedule::simple_schedule_result;
+///
+/// for folding_bits in 1..=4 {
+///     let result =
+///         simple_schedule_result(1 << 25, 8, 27, 64, folding_bits, &CostModel::default()).unwrap();
+///     assert!(result.final_remainder_degree <= 64);
+///     assert!(64 < result.final_remainder_degree << folding_bits);
+///     assert_eq!(result.rounds + 1, result.schedule.len());
+/// }
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `remainder_max_degree` - The maximum degree of the remainder polynomial
+/// * `folding_factor` - The folding factor used in the FRI protocol
+/// * `cost_model` - The cost model used to price the schedule
+///
+/// # Errors
+/// * On the parameters [`simple_schedule`] rejects
+pub fn simple_schedule_result(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    remainder_max_degree: usize,
+    folding_factor: usize,
+    cost_model: &CostModel,
+) -> Result<SimpleScheduleResult, FoldingError> {
+    let (size, schedule) = simple_schedule(
+        degree,
+        blowup_factor,
+        num_queries,
+        remainder_max_degree,
+        folding_factor,
+        cost_model,
+    )?;
+    Ok(SimpleScheduleResult {
+        final_remainder_degree: (degree / blowup_factor) >> schedule.iter().sum::<usize>(),
+        rounds: schedule.len() - 1,
+        schedule,
+        size,
+    })
+}
+
+/// Computes a schedule like [`simple_schedule`], but lets the final round use a smaller folding
+/// factor. A uniform final fold often overshoots `remainder_max_degree` and lands well below it;
+/// folding less in the final round still meets the bound while committing to a wider final layer.
+/// Among the final factors that keep the remainder within the bound, the one yielding the smallest
+/// proof is used, so the result is never larger than the uniform schedule.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `remainder_max_degree` - The maximum degree of the remainder polynomial
+/// * `folding_factor` - The folding factor used in every round but the last
+/// * `cost_model` - The cost model used to price the schedule
+///
+/// # Returns
+/// * `proof_size` - The estimated proof size in terms of field elements
+/// * `folding_schedule` - The folding schedule
+///
+/// # Errors
+/// * On the parameters [`simple_schedule`] rejects
+pub fn simple_schedule_with_tuned_final_round(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    remainder_max_degree: usize,
+    folding_factor: usize,
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    let (mut proof_size, mut folding_schedule) = simple_schedule(
+        degree,
+        blowup_factor,
+        num_queries,
+        remainder_max_degree,
+        folding_factor,
+        cost_model,
+    )?;
+
+    // Without any folding round there is no final round to tune.
+    if folding_schedule.len() < 2 {
+        return Ok((proof_size, folding_schedule));
+    }
+
+    let poly_degree = degree / blowup_factor;
+    let last = folding_schedule.len() - 1;
+    let folded_before_last: usize = folding_schedule[..last].iter().sum();
+    for final_bits in 1..folding_factor {
+        // the final round must still bring the remainder within the bound.
+        if poly_degree >> (folded_before_last + final_bits) > remainder_max_degree {
+            continue;
+        }
+
+        let mut candidate = folding_schedule.clone();
+        candidate[last] = final_bits;
+        let size = estimate_proof_size_unchecked(
+            degree,
+            blowup_factor,
+            num_queries,
+            &candidate,
+            cost_model,
+        );
+        if size < proof_size {
+            proof_size = size;
+            folding_schedule = candidate;
+        }
+    }
+
+    Ok((proof_size, folding_schedule))
+}
+
+/// Computes a schedule like [`simple_schedule`], but with a first folding round of its own size
+/// followed by uniform rounds: `[0, head_bits, body_bits, body_bits, ...]`. A single odd-sized
+/// first fold absorbs a degree that is not a clean power of the body folding factor, which a
+/// uniform prover can implement far more easily than an arbitrary schedule.
+///
+/// The body rounds stop like the rounds of [`simple_schedule`], once the polynomial is within
+/// `remainder_max_degree` or one more round would fold it below degree 1. The head round is
+/// always applied.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `remainder_max_degree` - The maximum degree of the remainder polynomial
+/// * `head_bits` - The folding factor of the first folding round, in bits
+/// * `body_bits` - The folding factor of every later round, in bits
+/// * `cost_model` - The cost model used to price the schedule
+///
+/// # Returns
+/// * `proof_size` - The estimated proof size in terms of field elements
+/// * `folding_schedule` - The folding schedule
+///
+/// # Errors
+/// * If the schedule is rejected by [`validate_schedule`], e.g. because the head round folds the
+///   polynomial below degree 1
+pub fn simple_schedule_with_head(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    remainder_max_degree: usize,
+    head_bits: usize,
+    body_bits: usize,
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    let mut folding_schedule = vec![0, head_bits];
+    validate_schedule(degree, blowup_factor, &folding_schedule)?;
+
+    // the body rounds fold what is left after the head round.
+    let poly_degree = (degree / blowup_factor) >> head_bits;
+    let body_rounds = num_rounds(poly_degree, body_bits, remainder_max_degree) - 1;
+    folding_schedule.resize(2 + body_rounds, body_bits);
+    validate_schedule(degree, blowup_factor, &folding_schedule)?;
+
+    let proof_size = estimate_proof_size_unchecked(
+        degree,
+        blowup_factor,
+        num_queries,
+        &folding_schedule,
+        cost_model,
+    );
+    Ok((proof_size, folding_schedule))
+}
+
+/// Computes the schedule of [`simple_schedule_with_head`] with the best head round for a given body
+/// folding factor, trying every head of 1 up to 4 bits that does not over-fold. Polynomials too
+/// small for any head round get the schedule without folding rounds.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `remainder_max_degree` - The maximum degree of the remainder polynomial
+/// * `body_bits` - The folding factor of every round after the head, in bits
+/// * `cost_model` - The cost model used to price the schedule
+///
+/// # Returns
+/// * `proof_size` - The estimated proof size in terms of field elements
+/// * `folding_schedule` - The folding schedule
+pub fn simple_schedule_with_best_head(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    remainder_max_degree: usize,
+    body_bits: usize,
+    cost_model: &CostModel,
+) -> (usize, Vec<usize>) {
+    (1..=MAX_FOLDING_BITS)
+        .filter_map(|head_bits| {
+            simple_schedule_with_head(
+                degree,
+                blowup_factor,
+                num_queries,
+                remainder_max_degree,
+                head_bits,
+                body_bits,
+                cost_model,
+            )
+            .ok()
+        })
+        .min_by_key(|(proof_size, _)| *proof_size)
+        .unwrap_or_else(|| {
+            let schedule = vec![0];
+            let size = estimate_proof_size_unchecked(
+                degree,
+                blowup_factor,
+                num_queries,
+                &schedule,
+                cost_model,
+            );
+            (size, schedule)
+        })
+}
+
+/// Computes the schedule of [`simple_schedule`] with the best remainder bound for the folding
+/// factor, trying every power of 2 from 1 up to the degree of the polynomial. A uniform schedule
+/// only differs by its number of rounds, so this is the smallest uniform proof for the factor,
+/// which compares factors without favoring the one a fixed `remainder_max_degree` suits. Ties
+/// are broken towards the smaller bound, i.e. towards folding further.
+///
+/// It is never larger than the schedule of any fixed bound:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::simple_schedule::simple_schedule_with_best_remainder;
+///
+/// let cost_model = CostModel::default();
+/// for folding_bits in 1..=4 {
+///     let (size, _) =
+///         simple_schedule_with_best_remainder(1 << 25, 8, 27, folding_bits, &cost_model).unwrap();
+///     for bound in [1, 64, 256, 1 << 22] {
+///         let (uniform, _) = simple_schedule(1 << 25, 8, 27, bound, folding_bits, &cost_model).unwrap();
+///         assert!(size <= uniform);
+///     }
+/// }
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_factor` - The folding factor used in the FRI protocol
+/// * `cost_model` - The cost model used to price the schedule
+///
+/// # Returns
+/// * `proof_size` - The estimated proof size in terms of field elements
+/// * `folding_schedule` - The folding schedule
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+/// * If the cost model's remainder strategy cannot interpolate the remainder of any bound
+pub fn simple_schedule_with_best_remainder(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_factor: usize,
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+    let poly_degree = degree / blowup_factor;
+    (0..=poly_degree.ilog2())
+        .map(|bound_bits| {
+            simple_schedule(
+                degree,
+                blowup_factor,
+                num_queries,
+                1 << bound_bits,
+                folding_factor,
+                cost_model,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|(_, schedule)| {
+            is_remainder_feasible(degree, blowup_factor, num_queries, schedule, cost_model)
+        })
+        .min_by_key(|(proof_size, _)| *proof_size)
+        .ok_or(FoldingError::NoInterpolableRemainder { num_queries })
 }
 
 /// Computes the number of rounds during FRI proof generation. The folding stops when the degree of
-/// the polynomial to be proved is less than the `remainder_max_degree`.
+/// the polynomial to be proved is less than the `remainder_max_degree`, or when one more fold would
+/// fold it below degree 1.
 fn num_rounds(degree: usize, folding_factor: usize, remainder_max_degree: usize) -> usize {
     let mut num_rounds = 1;
     let mut current_degree = degree;
@@ -61,10 +379,197 @@ fn num_rounds(degree: usize, folding_factor: usize, remainder_max_degree: usize)
     // The folding factor in absolute terms.
     let folding_factor = 1 << folding_factor;
 
-    while current_degree > remainder_max_degree {
+    while current_degree > remainder_max_degree && current_degree >= folding_factor {
         current_degree /= folding_factor;
         num_rounds += 1;
     }
 
     num_rounds
 }
+
+/// Computes a folding schedule greedily, one layer at a time: each new layer folds by the factor
+/// whose layer costs the least per bit of degree it removes, and folding stops as soon as adding
+/// that layer would make the proof larger, unless the cost model's remainder strategy cannot
+/// learn the remainder yet. This takes a handful of estimates per layer instead of a search, at
+/// the price of optimality: folding by less than the cheapest factor per bit, or adding a layer
+/// that only pays off together with the next one, is never considered.
+///
+/// Over degrees from 2^10 to 2^24, blowup factors from 2 to 16 and 8 to 128 queries under the
+/// default cost model, the greedy proof is at most 9% larger than the optimal one, as measured by
+/// the stress test. With the default parameters greedy folds by 16 four times, while the
+/// optimizer folds the last layer by 8 only:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::simple_schedule::greedy_schedule;
+///
+/// // the optimum is [0, 4, 4, 4, 3] of 12622 elements.
+/// let greedy = greedy_schedule(1 << 25, 8, 27, &CostModel::default());
+/// assert_eq!(greedy, Ok((12818, vec![0, 4, 4, 4, 4])));
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `cost_model` - The cost model used to price each candidate layer
+///
+/// # Returns
+/// * `proof_size` - The estimated proof size in terms of field elements
+/// * `folding_schedule` - The folding schedule
+///
+/// # Errors
+/// * If the degree or blowup factor is rejected by [`validate_parameters`]
+pub fn greedy_schedule(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_model: &CostModel,
+) -> Result<(usize, Vec<usize>), FoldingError> {
+    validate_parameters(degree, blowup_factor)?;
+
+    let mut folding_schedule = vec![0];
+    let mut proof_size = estimate_proof_size_unchecked(
+        degree,
+        blowup_factor,
+        num_queries,
+        &folding_schedule,
+        cost_model,
+    );
+    let mut layer_degree = degree;
+    loop {
+        let remaining_bits = (layer_degree / blowup_factor).ilog2() as usize;
+        // the cost of the new layer per bit it folds, compared as fractions.
+        let layer = folding_schedule.len();
+        let best_bits = (1..=remaining_bits.min(MAX_FOLDING_BITS)).min_by(|&a, &b| {
+            let cost = |bits: usize| {
+                path_elements(num_queries, layer_degree, 1 << bits, cost_model)
+                    + leaf_elements(num_queries, layer, 1 << bits, cost_model)
+            };
+            (cost(a) * b).cmp(&(cost(b) * a))
+        });
+        let best = best_bits.map(|bits| {
+            folding_schedule.push(bits);
+            let size = estimate_proof_size_unchecked(
+                degree,
+                blowup_factor,
+                num_queries,
+                &folding_schedule,
+                cost_model,
+            );
+            folding_schedule.pop();
+            (size as isize - proof_size as isize, bits, size)
+        });
+
+        // a remainder the verifier cannot learn is folded further, whatever it costs.
+        let feasible = is_remainder_feasible(
+            degree,
+            blowup_factor,
+            num_queries,
+            &folding_schedule,
+            cost_model,
+        );
+        match best {
+            Some((change, bits, size)) if change < 0 || !feasible => {
+                folding_schedule.push(bits);
+                proof_size = size;
+                layer_degree >>= bits;
+            }
+            _ => return Ok((proof_size, folding_schedule)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::optimized_schedule::optimal_folding_strategy;
+    use crate::stats::SearchStats;
+
+    fn optimal(
+        degree: usize,
+        blowup_factor: usize,
+        num_queries: usize,
+        cost_model: &CostModel,
+    ) -> Result<(usize, Vec<usize>), FoldingError> {
+        optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            vec![0],
+            &HashMap::new(),
+            cost_model,
+            &mut SearchStats::default(),
+        )
+    }
+
+    #[test]
+    fn greedy_folds_once_too_far_at_the_default_parameters() {
+        let cost_model = CostModel::default();
+        assert_eq!(
+            greedy_schedule(1 << 25, 8, 27, &cost_model),
+            Ok((12818, vec![0, 4, 4, 4, 4]))
+        );
+        assert_eq!(
+            optimal(1 << 25, 8, 27, &cost_model),
+            Ok((12622, vec![0, 4, 4, 4, 3]))
+        );
+        // for a degree of 2^20 greedy is optimal.
+        assert_eq!(
+            greedy_schedule(1 << 20, 8, 27, &cost_model),
+            optimal(1 << 20, 8, 27, &cost_model)
+        );
+    }
+
+    // the greedy schedule is valid, never beats the optimum, and stays within the documented gap.
+    #[test]
+    fn greedy_is_valid_and_close_to_the_optimum() {
+        let cost_model = CostModel::default();
+        for degree_bits in 10..=16 {
+            for blowup_factor in [2, 4, 8, 16] {
+                for num_queries in [8, 27, 64, 128] {
+                    let degree = 1 << degree_bits;
+                    let (greedy_size, greedy) =
+                        greedy_schedule(degree, blowup_factor, num_queries, &cost_model).unwrap();
+                    let (opt_size, _) =
+                        optimal(degree, blowup_factor, num_queries, &cost_model).unwrap();
+                    let params = format!("2^{degree_bits} {blowup_factor} {num_queries}");
+                    assert_eq!(validate_schedule(degree, blowup_factor, &greedy), Ok(()));
+                    assert!(greedy_size >= opt_size, "{params}");
+                    assert!(100 * greedy_size <= 109 * opt_size, "{params}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_rejects_invalid_parameters() {
+        let cost_model = CostModel::default();
+        assert!(greedy_schedule(1000, 8, 27, &cost_model).is_err());
+        assert!(greedy_schedule(4, 8, 27, &cost_model).is_err());
+    }
+
+    #[test]
+    fn invalid_parameters_are_errors() {
+        let cost_model = CostModel::default();
+        let not_a_power = FoldingError::NotPowerOfTwo {
+            parameter: "degree",
+            value: 1000,
+        };
+        assert_eq!(
+            simple_schedule(1000, 8, 27, 64, 2, &cost_model),
+            Err(not_a_power.clone())
+        );
+        assert_eq!(
+            simple_schedule_result(1000, 8, 27, 64, 2, &cost_model),
+            Err(not_a_power.clone())
+        );
+        assert_eq!(
+            simple_schedule_with_tuned_final_round(1000, 8, 27, 64, 2, &cost_model),
+            Err(not_a_power)
+        );
+        assert!(simple_schedule(0, 8, 27, 64, 2, &cost_model).is_err());
+    }
+}
@@ -0,0 +1,385 @@
+use super::cost_model::CostModel;
+use super::error::FoldingError;
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::{estimate_proof_size_unchecked, validate_query_plan};
+
+/// The largest extension degree [`auto_extension`] draws folding challenges from.
+pub const MAX_EXTENSION_DEGREE: usize = 8;
+
+/// The soundness error of a FRI proof, split into its commit-phase and query-phase components.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundnessBreakdown {
+    /// The probability that a folding challenge maps a far codeword close to the code, summed over
+    /// all folding rounds.
+    pub commit_phase_error: f64,
+    /// The probability that every query misses the positions where a far codeword disagrees with
+    /// the code.
+    pub query_phase_error: f64,
+}
+
+impl SoundnessBreakdown {
+    /// Returns the total soundness error, bounded by the sum of both components.
+    pub fn total_error(&self) -> f64 {
+        self.commit_phase_error + self.query_phase_error
+    }
+
+    /// Returns the security level in bits implied by the total soundness error.
+    pub fn security_bits(&self) -> f64 {
+        -self.total_error().log2()
+    }
+}
+
+/// Estimates the soundness error of a FRI proof for a given folding strategy.
+///
+/// * The commit-phase error charges every folding round with a factor `f` over a domain of size
+///   `n` the proximity-gap term `(f - 1) * n / |F|`, where `|F| = 2^field_bits` is the size of the
+///   field the folding challenges are drawn from. Layers that are not folded add no error.
+/// * The query-phase error uses the conjectured bound `(1 / blowup_factor)^num_queries`.
+///
+/// The rate is the same at every layer, the last one included: a fold divides the polynomial's
+/// degree and its domain by the same factor, and the blowup factor is fixed for the whole proof.
+/// The final low-degree check of the remainder therefore runs at the rate `1 / blowup_factor` for
+/// every valid schedule, and needs no separate diagnostic.
+///
+/// Over a small field the commit-phase error is not negligible: folding challenges must then be
+/// drawn from an extension, whose size is given by [`challenge_field_bits`]. A deep schedule over
+/// BabyBear is far from sound with challenges from the base field, and limited by the queries
+/// alone with challenges from the quartic extension:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::soundness::{challenge_field_bits, fri_soundness};
+///
+/// let schedule = [0, 4, 4, 4, 4, 4, 4];
+/// let base = fri_soundness(1 << 28, 2, 80, &schedule, challenge_field_bits(31, 1));
+/// assert!(base.security_bits() < 0.0);
+///
+/// let quartic = fri_soundness(1 << 28, 2, 80, &schedule, challenge_field_bits(31, 4));
+/// assert!(quartic.commit_phase_error < quartic.query_phase_error);
+/// assert_eq!(quartic.security_bits().round(), 80.0);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `field_bits` - The size in bits of the field the folding challenges are drawn from, e.g. as
+///   returned by [`challenge_field_bits`]
+///
+/// # Returns
+/// * `soundness` - The commit-phase and query-phase soundness errors
+pub fn fri_soundness(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    field_bits: u32,
+) -> SoundnessBreakdown {
+    let field_size = 2f64.powi(field_bits as i32);
+
+    // the domains and factors of the layers do not depend on the cost model.
+    let mut commit_phase_error = 0.0;
+    walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        &CostModel::default(),
+        &mut |layer: &LayerFacts| {
+            commit_phase_error +=
+                (layer.factor() - 1) as f64 * layer.domain_size as f64 / field_size;
+        },
+    );
+
+    let query_phase_error = (blowup_factor as f64).recip().powi(num_queries as i32);
+
+    SoundnessBreakdown {
+        commit_phase_error,
+        query_phase_error,
+    }
+}
+
+/// The soundness of a query plan, see [`query_plan_soundness`], under the plan's own accounting
+/// and under the conservative one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QueryPlanSoundness {
+    /// The soundness when every layer's queries are credited to the layer they check, so that the
+    /// proof is as sound as its most queried layer, as designs reducing the queries of deeper
+    /// layers conjecture.
+    pub conjectured: SoundnessBreakdown,
+    /// The soundness when the proof is only as sound as its least queried layer, where a prover
+    /// may confine its cheating.
+    pub conservative: SoundnessBreakdown,
+}
+
+/// Estimates the soundness error of a FRI proof whose layers are queried as given by a query
+/// plan, see
+/// [`estimate_proof_size_with_query_plan`](super::optimized_schedule::estimate_proof_size_with_query_plan),
+/// under both the conjectured per-layer accounting and the conservative one. The commit-phase
+/// error does not depend on the queries and is the same for both, as in [`fri_soundness`],
+/// while the query-phase error uses the queries of the most and the least queried layer
+/// respectively. For a plan querying every layer equally both are [`fri_soundness`].
+///
+/// With 27 queries at layers 0 to 2 and 16 at the deeper layers, the optimal schedule of the
+/// default parameters is 81 bits secure by the conjectured accounting, `27 * log2(8)`, and 48
+/// bits by the conservative one, `16 * log2(8)`, the commit phase adding about 2^-99:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::soundness::query_plan_soundness;
+///
+/// let soundness =
+///     query_plan_soundness(1 << 25, 8, &[27, 27, 27, 16, 16], &[0, 4, 4, 4, 3], 128).unwrap();
+/// assert_eq!(soundness.conjectured.query_phase_error, 2f64.powi(-81));
+/// assert_eq!(soundness.conservative.query_phase_error, 2f64.powi(-48));
+///
+/// // (15 * 2^25 + 15 * 2^21 + 15 * 2^17 + 7 * 2^13) / 2^128 for both.
+/// let commit_phase_error = (15 * (1 << 25) + 15 * (1 << 21) + 15 * (1 << 17) + 7 * (1 << 13)) as f64
+///     * 2f64.powi(-128);
+/// assert_eq!(soundness.conjectured.commit_phase_error, commit_phase_error);
+/// assert_eq!(soundness.conservative.commit_phase_error, commit_phase_error);
+/// assert_eq!(soundness.conjectured.security_bits().round(), 81.0);
+/// assert_eq!(soundness.conservative.security_bits().round(), 48.0);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `query_plan` - The number of queries of every layer, starting with the initial layer
+/// * `folding_seq` - The folding sequence
+/// * `field_bits` - The size in bits of the field the folding challenges are drawn from
+///
+/// # Returns
+/// * `soundness` - The conjectured and the conservative soundness errors
+///
+/// # Errors
+/// * If the query plan is rejected by [`validate_query_plan`]
+pub fn query_plan_soundness(
+    degree: usize,
+    blowup_factor: usize,
+    query_plan: &[usize],
+    folding_seq: &[usize],
+    field_bits: u32,
+) -> Result<QueryPlanSoundness, FoldingError> {
+    validate_query_plan(query_plan, folding_seq)?;
+    let soundness = |num_queries: Option<&usize>| {
+        let num_queries = num_queries.copied().unwrap_or(0);
+        fri_soundness(degree, blowup_factor, num_queries, folding_seq, field_bits)
+    };
+    Ok(QueryPlanSoundness {
+        conjectured: soundness(query_plan.iter().max()),
+        conservative: soundness(query_plan.iter().min()),
+    })
+}
+
+/// Returns the size in bits of the field folding challenges are drawn from, when they are drawn
+/// from the extension of degree `extension_degree` of a base field of `base_field_bits` bits.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::soundness::challenge_field_bits;
+///
+/// // BabyBear and its quartic extension.
+/// assert_eq!(challenge_field_bits(31, 4), 124);
+/// ```
+pub fn challenge_field_bits(base_field_bits: u32, extension_degree: usize) -> u32 {
+    base_field_bits * extension_degree as u32
+}
+
+/// The soundness of a FRI proof whose folding challenges are drawn from one extension of a base
+/// field, see [`field_erosion`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldErosion {
+    /// The degree of the extension the folding challenges are drawn from.
+    pub extension_degree: usize,
+    /// The soundness over that extension, see [`fri_soundness`].
+    pub soundness: SoundnessBreakdown,
+}
+
+impl FieldErosion {
+    /// Returns the bits of security the commit phase costs below the security the queries alone
+    /// are chosen for, `log2(1 + commit_phase_error / query_phase_error)`: 1 bit when both errors
+    /// are equal, and next to nothing when the field term is negligible.
+    pub fn erosion_bits(&self) -> f64 {
+        let soundness = &self.soundness;
+        (soundness.commit_phase_error / soundness.query_phase_error).ln_1p() / 2f64.ln()
+    }
+
+    /// Returns whether the commit phase erodes the security of the queries by more than
+    /// `max_erosion_bits`.
+    pub fn exceeds(&self, max_erosion_bits: f64) -> bool {
+        self.erosion_bits() > max_erosion_bits
+    }
+}
+
+/// Estimates how much the field folding challenges are drawn from erodes the security of a FRI
+/// proof. The commit-phase term of [`fri_soundness`] grows with the domain of every folded layer
+/// and shrinks with the size of the extension, so at large degrees an extension that was ample
+/// for small proofs may cost a noticeable part of the security the queries were chosen for.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `base_field_bits` - The size in bits of the base field
+/// * `extension_degree` - The degree of the extension the folding challenges are drawn from
+///
+/// # Returns
+/// * `erosion` - The soundness over the extension, with the bits it erodes
+pub fn field_erosion(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    base_field_bits: u32,
+    extension_degree: usize,
+) -> FieldErosion {
+    FieldErosion {
+        extension_degree,
+        soundness: fri_soundness(
+            degree,
+            blowup_factor,
+            num_queries,
+            folding_seq,
+            challenge_field_bits(base_field_bits, extension_degree),
+        ),
+    }
+}
+
+/// The extension [`auto_extension`] settles on, against the one of the cost model, with the size
+/// of the proof over each.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtensionBump {
+    /// The soundness over the extension of the cost model.
+    pub original: FieldErosion,
+    /// The soundness over the smallest extension within the tolerated erosion.
+    pub bumped: FieldErosion,
+    /// The size in field elements of the proof over the extension of the cost model.
+    pub original_size: usize,
+    /// The size in field elements of the proof over the bumped extension.
+    pub bumped_size: usize,
+}
+
+impl ExtensionBump {
+    /// Returns whether the extension of the cost model had to grow.
+    pub fn is_bumped(&self) -> bool {
+        self.bumped.extension_degree > self.original.extension_degree
+    }
+
+    /// Returns the number of field elements the larger extension adds to the proof.
+    pub fn size_penalty(&self) -> usize {
+        self.bumped_size - self.original_size
+    }
+}
+
+/// Finds the smallest extension, no smaller than the one of the cost model, whose folding
+/// challenges erode the security of the queries by at most `max_erosion_bits`, see
+/// [`field_erosion`], and prices the proof over it. Opened values and the remainder are elements
+/// of the extension, so the larger extension is paid for in size, which the bump reports apart.
+///
+/// A deep schedule over a cubic extension of BabyBear loses about 19 of its 80 bits to the
+/// commit phase. Within 1 bit of erosion, the cost model's extension is bumped to the quartic
+/// one; tolerating the erosion, it is kept and only warned about:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::soundness::{auto_extension, field_erosion};
+///
+/// let schedule = [0, 4, 4, 4, 4, 4, 4];
+/// let cubic = CostModel { extension_degree: 3, ..BABYBEAR_QUARTIC };
+/// let erosion = field_erosion(1 << 28, 2, 80, &schedule, 31, 3);
+/// assert_eq!(erosion.erosion_bits().round(), 19.0);
+/// assert!(erosion.exceeds(1.0));
+///
+/// let bump = auto_extension(1 << 28, 2, 80, &schedule, 31, 1.0, &cubic).unwrap();
+/// assert!(bump.is_bumped());
+/// assert_eq!(bump.bumped.extension_degree, 4);
+/// assert!(!bump.bumped.exceeds(1.0));
+/// assert_eq!(bump.bumped_size, estimate_proof_size(1 << 28, 2, 80, &schedule, &BABYBEAR_QUARTIC).unwrap());
+/// assert!(bump.size_penalty() > 0);
+///
+/// // at the erosion of the cubic extension the bump does not trigger, just below it does.
+/// let kept = auto_extension(1 << 28, 2, 80, &schedule, 31, erosion.erosion_bits(), &cubic);
+/// assert!(!kept.unwrap().is_bumped());
+/// let below = erosion.erosion_bits() - 0.01;
+/// assert!(auto_extension(1 << 28, 2, 80, &schedule, 31, below, &cubic).unwrap().is_bumped());
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `base_field_bits` - The size in bits of the base field
+/// * `max_erosion_bits` - The bits of security the commit phase may cost
+/// * `cost_model` - The cost model used to price the proof, whose extension degree is the
+///   smallest one considered
+///
+/// # Returns
+/// * `bump` - The soundness and size over the extension of the cost model and the bumped one
+///
+/// # Errors
+/// * If no extension up to [`MAX_EXTENSION_DEGREE`] stays within the tolerated erosion
+pub fn auto_extension(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    base_field_bits: u32,
+    max_erosion_bits: f64,
+    cost_model: &CostModel,
+) -> Result<ExtensionBump, FoldingError> {
+    let erosion = |extension_degree| {
+        field_erosion(
+            degree,
+            blowup_factor,
+            num_queries,
+            folding_seq,
+            base_field_bits,
+            extension_degree,
+        )
+    };
+    let size = |extension_degree| {
+        let cost_model = CostModel {
+            extension_degree,
+            ..*cost_model
+        };
+        estimate_proof_size_unchecked(degree, blowup_factor, num_queries, folding_seq, &cost_model)
+    };
+
+    let original = erosion(cost_model.extension_degree);
+    let bumped = (cost_model.extension_degree..=MAX_EXTENSION_DEGREE)
+        .map(erosion)
+        .find(|bumped| !bumped.exceeds(max_erosion_bits))
+        .ok_or(FoldingError::FieldErosionUnreachable {
+            max_extension_degree: MAX_EXTENSION_DEGREE,
+        })?;
+    Ok(ExtensionBump {
+        original,
+        bumped,
+        original_size: size(original.extension_degree),
+        bumped_size: size(bumped.extension_degree),
+    })
+}
+
+/// Returns the number of queries needed for the query-phase error alone to reach `security_bits`
+/// bits of security.
+///
+/// * The conjectured bound gains `log2(blowup_factor)` bits per query, matching the query-phase
+///   error of [`fri_soundness`].
+/// * The proven bound, which only allows proximity up to the Johnson bound `1 - sqrt(rho)`, gains
+///   half of that per query.
+///
+/// # Panics
+/// * If the blowup factor is not a power of 2 greater than 1
+pub fn required_queries(blowup_factor: usize, security_bits: u32, conjectured: bool) -> usize {
+    assert!(
+        blowup_factor.is_power_of_two() && blowup_factor > 1,
+        "the blowup factor must be a power of 2 greater than 1, got {blowup_factor}"
+    );
+    let bits_per_query = blowup_factor.ilog2();
+    let target_bits = if conjectured {
+        security_bits
+    } else {
+        2 * security_bits
+    };
+    target_bits.div_ceil(bits_per_query) as usize
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::error::Warning;
+
+/// Statistics collected while searching for an optimal folding strategy. Collecting them does not
+/// influence the search.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The number of folding sequences whose proof size was considered.
+    pub nodes_explored: usize,
+    /// The number of folding sequences an unconstrained search over the same degree would visit.
+    pub theoretical_nodes: usize,
+    /// The number of recursive calls the search made. A layer with a single possible folding
+    /// factor is extended without a call of its own, so this is at most `nodes_explored`.
+    pub calls: usize,
+    /// The wall-clock time spent searching.
+    pub elapsed: Duration,
+    /// What the search noticed without failing: the consistency rules the cost model breaks, see
+    /// [`CostModel::validate`](super::cost_model::CostModel::validate), and the cap on the folding
+    /// factor that keeps the search from a cheaper proof, if the search probed its cap, see
+    /// [`optimal_probing_folding_cap`](super::optimized_schedule::optimal_probing_folding_cap),
+    /// and the cap binds.
+    pub warnings: Vec<Warning>,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "explored {} of {} schedule nodes with {} calls in {:?}",
+            self.nodes_explored, self.theoretical_nodes, self.calls, self.elapsed
+        )
+    }
+}
+
+/// Returns the number of folding sequences an exhaustive search visits when `remaining_bits` bits
+/// of degree are left to fold and every layer folds by between 1 and `max_folding_bits` bits. This
+/// counts the sequence that stops immediately as well as every extension of it.
+pub(crate) fn search_space_size(remaining_bits: usize, max_folding_bits: usize) -> usize {
+    // nodes[b] is the number of sequences reachable with b bits left to fold.
+    let mut nodes = vec![1; remaining_bits + 1];
+    for bits in 1..=remaining_bits {
+        nodes[bits] += (1..=max_folding_bits.min(bits))
+            .map(|factor| nodes[bits - factor])
+            .sum::<usize>();
+    }
+    nodes[remaining_bits]
+}
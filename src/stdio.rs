@@ -0,0 +1,681 @@
+//! The `--stdio-json` mode: one JSON request read from standard input, one JSON response written
+//! to standard output, so that the optimizer can be embedded from any language.
+//!
+//! A request is an object with a `command` and its parameters:
+//! * `{"command": "optimize", "degree": n, "blowup": n, "queries": n}` - the optimal schedule
+//! * `{"command": "estimate", "degree": n, "blowup": n, "queries": n, "schedule": [bits, ...]}` -
+//!   the size of a given schedule
+//! * `{"command": "sweep", "degree": n, "blowup": n, "queries": [n, ...]}` - the optimal schedule
+//!   for every query count
+//!
+//! The searches of `optimize` and `sweep` stop after `time_budget_secs` seconds, all query counts
+//! of a sweep together, or [`DEFAULT_TIME_BUDGET`] if the request does not say. A request asking
+//! for more than [`MAX_TIME_BUDGET`] is rejected, so that no request can keep the process, or a
+//! worker of the `--serve` mode, busy for long.
+//!
+//! Every request may also name a `preset` (`goldilocks-quadratic` by default), the
+//! `schema_version` it was written against, and whether it is `strict`, in which case a cost model
+//! breaking a consistency rule is an error rather than a list of `warnings` in the response, see
+//! [`CostModel::validate`]. The presets follow every rule. Every response carries the
+//! [`SCHEMA_VERSION`]: `{"schema_version": 1, "size_elements": n, "size_bytes": n,
+//! "schedule": [bits, ...], "opened_elements_total": n, "layer_elements": [n, ...],
+//! "remainder_elements": n, "excluded_elements": {"paths": n, "leaves": n, "remainder": n},
+//! "tree_shapes": [{"leaves": n, "internal_nodes": n, "depth": n, "cap_nodes": n}, ...]}`, with
+//! the opened elements counted before any conversion to bytes, the elements the preset leaves out
+//! of the proof reported apart, the Merkle tree of every layer shaped as by
+//! [`LayerBreakdown::tree_shape`](fri_dynamic_folding_scheme::report::LayerBreakdown::tree_shape),
+//! and the sweep returning one such object per query count under `results`. A request that cannot
+//! be served is answered with `{"schema_version": 1, "error": "..."}`.
+//!
+//! A request may ask for the `provenance` of the sizes, in which case every schedule also carries
+//! `"provenance": [{"layer": n, "formula": "...", "inputs": {"queries": n, ...}, "value": n,
+//! "counted": bool}, ...]`, one record per component with the remainder's `layer` null, from which
+//! a reviewer can recompute every number, see [`Provenance`].
+//!
+//! Every optimal schedule also carries its `optimality`, `"Exact"` unless the search ran out of its
+//! time budget, in which case it is `"TimedOut"` and the schedule is possibly suboptimal, see
+//! [`optimal_within_time_budget`].
+
+use std::fmt::Write;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use fri_dynamic_folding_scheme::cost_model::CostModel;
+use fri_dynamic_folding_scheme::optimized_schedule::{
+    estimate_proof_size, optimal_within_time_budget, size_in_bytes, validate_parameters, Optimality,
+};
+use fri_dynamic_folding_scheme::prelude::SearchStats;
+use fri_dynamic_folding_scheme::provenance::Provenance;
+use fri_dynamic_folding_scheme::report::ProofSizeBreakdown;
+
+use super::cli;
+
+/// The version of the request and response schema. It is bumped whenever a field changes meaning
+/// or is removed.
+pub(crate) const SCHEMA_VERSION: u64 = 1;
+
+/// How long the searches of a request may run unless it gives a `time_budget_secs`.
+pub(crate) const DEFAULT_TIME_BUDGET: Duration = Duration::from_secs(10);
+
+/// The longest `time_budget_secs` a request may give.
+pub(crate) const MAX_TIME_BUDGET: Duration = Duration::from_secs(60);
+
+/// The most optimal schedules the cache keeps, the oldest being dropped first.
+const MAX_CACHED_SCHEDULES: usize = 1024;
+
+/// The size of an optimal schedule, and the schedule.
+type Optimum = (usize, Vec<usize>);
+
+/// The parameters an optimal schedule was searched for.
+#[derive(Clone, Copy, PartialEq)]
+struct OptimumKey {
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    cost_model: CostModel,
+}
+
+/// The optimal schedules found so far, with their sizes, oldest first. Every request the process
+/// serves shares them, so that the `--serve` mode answers a repeated search without running it.
+/// Only searches that finished are kept, since a search cut short by its time budget may find a
+/// better schedule with more time.
+static SCHEDULE_CACHE: Mutex<Vec<(OptimumKey, Optimum)>> = Mutex::new(Vec::new());
+
+/// Returns the optimal schedule cached for `key`, if any.
+fn cached_optimum(key: &OptimumKey) -> Option<Optimum> {
+    let cache = SCHEDULE_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    cache
+        .iter()
+        .find(|(cached, _)| cached == key)
+        .map(|(_, optimum)| optimum.clone())
+}
+
+/// Caches the optimal schedule for `key`.
+fn cache_optimum(key: OptimumKey, optimum: Optimum) {
+    let mut cache = SCHEDULE_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if cache.iter().any(|(cached, _)| *cached == key) {
+        return;
+    }
+    if cache.len() == MAX_CACHED_SCHEDULES {
+        cache.remove(0);
+    }
+    cache.push((key, optimum));
+}
+
+/// A parsed JSON value. Numbers keep their text, so that integers beyond the precision of an
+/// `f64` survive.
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Serves one request and returns the response, or the reason the request could not be served,
+/// see [`error_response`].
+pub(crate) fn serve(request: &str) -> Result<String, String> {
+    serve_command(request, None)
+}
+
+/// Serves one request for the command of `endpoint`, if any, as the `http` module routes them. A
+/// request served for an endpoint may leave out its `command`, and must not name another one.
+pub(crate) fn serve_command(request: &str, endpoint: Option<&str>) -> Result<String, String> {
+    let request = Parser::new(request).parse_document()?;
+    let Json::Object(fields) = &request else {
+        return Err("the request must be a JSON object".to_string());
+    };
+    let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, v)| v);
+    let number = |name: &str| match field(name) {
+        Some(value) => as_usize(value).ok_or_else(|| format!("`{name}` must be an integer")),
+        None => Err(format!("missing `{name}`")),
+    };
+
+    if let Some(version) = field("schema_version") {
+        if as_usize(version) != Some(SCHEMA_VERSION as usize) {
+            return Err(format!(
+                "unsupported schema version, expected {SCHEMA_VERSION}"
+            ));
+        }
+    }
+    let cost_model = match field("preset") {
+        Some(Json::String(name)) => cli::parse_preset(name)?,
+        Some(_) => return Err("`preset` must be a string".to_string()),
+        None => CostModel::default(),
+    };
+
+    let strict = match field("strict") {
+        Some(Json::Bool(strict)) => *strict,
+        Some(_) => return Err("`strict` must be a boolean".to_string()),
+        None => false,
+    };
+    if strict {
+        cost_model
+            .check_consistency()
+            .map_err(|err| err.to_string())?;
+    }
+
+    let provenance = match field("provenance") {
+        Some(Json::Bool(provenance)) => *provenance,
+        Some(_) => return Err("`provenance` must be a boolean".to_string()),
+        None => false,
+    };
+
+    let (degree, blowup_factor) = (number("degree")?, number("blowup")?);
+    validate_parameters(degree, blowup_factor).map_err(|err| err.to_string())?;
+
+    let command = match (field("command"), endpoint) {
+        (Some(Json::String(command)), Some(endpoint)) if command != endpoint => {
+            return Err(format!(
+                "the command `{command}` does not match the `{endpoint}` endpoint"
+            ))
+        }
+        (Some(Json::String(command)), _) => Some(command.as_str()),
+        (Some(_), _) => return Err("`command` must be a string".to_string()),
+        (None, endpoint) => endpoint,
+    };
+
+    let time_budget = || {
+        let time_budget = match field("time_budget_secs") {
+            Some(value) => as_f64(value)
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or("`time_budget_secs` must be a non-negative number")?,
+            None => DEFAULT_TIME_BUDGET,
+        };
+        if time_budget > MAX_TIME_BUDGET {
+            return Err(format!(
+                "`time_budget_secs` must not exceed {} seconds",
+                MAX_TIME_BUDGET.as_secs()
+            ));
+        }
+        Ok(time_budget)
+    };
+
+    let mut response = format!("{{\"schema_version\":{SCHEMA_VERSION}");
+    match command {
+        Some("optimize") => {
+            let num_queries = number("queries")?;
+            let time_budget = time_budget()?;
+            let optimum = optimize(
+                degree,
+                blowup_factor,
+                num_queries,
+                time_budget,
+                &cost_model,
+                provenance,
+            )?;
+            response.push(',');
+            response.push_str(&optimum);
+        }
+        Some("estimate") => {
+            let num_queries = number("queries")?;
+            let schedule = match field("schedule") {
+                Some(Json::Array(items)) => items
+                    .iter()
+                    .map(as_usize)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("`schedule` must hold integers")?,
+                _ => return Err("missing `schedule` array".to_string()),
+            };
+            let size =
+                estimate_proof_size(degree, blowup_factor, num_queries, &schedule, &cost_model)
+                    .map_err(|err| err.to_string())?;
+            response.push(',');
+            let breakdown = breakdown(
+                degree,
+                blowup_factor,
+                num_queries,
+                &schedule,
+                &cost_model,
+                provenance,
+            );
+            response.push_str(&sized_schedule(size, &schedule, &breakdown, &cost_model));
+        }
+        Some("sweep") => {
+            let queries = match field("queries") {
+                Some(Json::Array(items)) => items
+                    .iter()
+                    .map(as_usize)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("`queries` must hold integers")?,
+                _ => return Err("missing `queries` array".to_string()),
+            };
+            // every query count gets what is left of the budget of the whole sweep.
+            let deadline = Instant::now() + time_budget()?;
+            let results = queries
+                .iter()
+                .map(|&num_queries| {
+                    let optimum = optimize(
+                        degree,
+                        blowup_factor,
+                        num_queries,
+                        deadline.saturating_duration_since(Instant::now()),
+                        &cost_model,
+                        provenance,
+                    )?;
+                    Ok(format!("{{\"queries\":{num_queries},{optimum}}}"))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            write!(response, ",\"results\":[{}]", results.join(",")).unwrap();
+        }
+        Some(command) => return Err(format!("unknown command `{command}`")),
+        None => return Err("missing `command` string".to_string()),
+    }
+    let warnings: Vec<_> = cost_model
+        .validate()
+        .iter()
+        .map(|violation| quote(&violation.to_string()))
+        .collect();
+    if !warnings.is_empty() {
+        write!(response, ",\"warnings\":[{}]", warnings.join(",")).unwrap();
+    }
+    response.push('}');
+    Ok(response)
+}
+
+/// Returns the response for a request that could not be served.
+pub(crate) fn error_response(message: &str) -> String {
+    format!(
+        "{{\"schema_version\":{SCHEMA_VERSION},\"error\":{}}}",
+        quote(message)
+    )
+}
+
+/// Returns the fields of the optimal schedule found within `time_budget`, and whether it is
+/// optimal, without the enclosing braces.
+fn optimize(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    time_budget: Duration,
+    cost_model: &CostModel,
+    provenance: bool,
+) -> Result<String, String> {
+    let key = OptimumKey {
+        degree,
+        blowup_factor,
+        num_queries,
+        cost_model: *cost_model,
+    };
+    let (size, schedule, optimality) = match cached_optimum(&key) {
+        Some((size, schedule)) => (size, schedule, Optimality::Exact),
+        None => {
+            let (size, schedule, optimality) = optimal_within_time_budget(
+                degree,
+                blowup_factor,
+                num_queries,
+                time_budget,
+                cost_model,
+                &mut SearchStats::default(),
+            )
+            .map_err(|err| err.to_string())?;
+            if optimality == Optimality::Exact {
+                cache_optimum(key, (size, schedule.clone()));
+            }
+            (size, schedule, optimality)
+        }
+    };
+    let breakdown = breakdown(
+        degree,
+        blowup_factor,
+        num_queries,
+        &schedule,
+        cost_model,
+        provenance,
+    );
+    let optimality = match optimality {
+        Optimality::Exact => "Exact",
+        Optimality::TimedOut => "TimedOut",
+    };
+    Ok(format!(
+        "{},\"optimality\":\"{optimality}\"",
+        sized_schedule(size, &schedule, &breakdown, cost_model)
+    ))
+}
+
+/// Returns the breakdown of a schedule, with the provenance of every component if `provenance` is
+/// set.
+fn breakdown(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    schedule: &[usize],
+    cost_model: &CostModel,
+    provenance: bool,
+) -> ProofSizeBreakdown {
+    let build = if provenance {
+        ProofSizeBreakdown::with_provenance
+    } else {
+        ProofSizeBreakdown::new
+    };
+    build(degree, blowup_factor, num_queries, schedule, cost_model)
+}
+
+/// Returns the fields describing a schedule, its size and the elements it opens, without the
+/// enclosing braces.
+fn sized_schedule(
+    size: usize,
+    schedule: &[usize],
+    breakdown: &ProofSizeBreakdown,
+    cost_model: &CostModel,
+) -> String {
+    let bits: Vec<_> = schedule.iter().map(usize::to_string).collect();
+    let layers: Vec<_> = breakdown
+        .layer_elements()
+        .iter()
+        .map(usize::to_string)
+        .collect();
+    let shapes: Vec<_> = breakdown
+        .layers
+        .iter()
+        .map(|layer| {
+            let shape = layer.tree_shape();
+            format!(
+                "{{\"leaves\":{},\"internal_nodes\":{},\"depth\":{},\"cap_nodes\":{}}}",
+                shape.leaves, shape.internal_nodes, shape.depth, shape.cap_nodes
+            )
+        })
+        .collect();
+    let mut fields = format!(
+        "\"size_elements\":{size},\"size_bytes\":{},\"schedule\":[{}],\
+         \"opened_elements_total\":{},\"layer_elements\":[{}],\"remainder_elements\":{},\
+         \"excluded_elements\":{{\"paths\":{},\"leaves\":{},\"remainder\":{}}},\
+         \"tree_shapes\":[{}]",
+        size_in_bytes(size, cost_model),
+        bits.join(","),
+        breakdown.total_elements(),
+        layers.join(","),
+        breakdown.remainder_elements,
+        breakdown.excluded.path_elements,
+        breakdown.excluded.leaf_elements,
+        breakdown.excluded.remainder_elements,
+        shapes.join(",")
+    );
+    if !breakdown.provenance.is_empty() {
+        let records: Vec<_> = breakdown.provenance.iter().map(provenance_record).collect();
+        write!(fields, ",\"provenance\":[{}]", records.join(",")).unwrap();
+    }
+    fields
+}
+
+/// Returns a provenance record as a JSON object.
+fn provenance_record(record: &Provenance) -> String {
+    let layer = record
+        .layer
+        .map_or("null".to_string(), |layer| layer.to_string());
+    let inputs: Vec<_> = record
+        .inputs
+        .iter()
+        .map(|(name, value)| format!("{}:{value}", quote(name)))
+        .collect();
+    format!(
+        "{{\"layer\":{layer},\"formula\":{},\"inputs\":{{{}}},\"value\":{},\"counted\":{}}}",
+        quote(record.formula.name()),
+        inputs.join(","),
+        record.value,
+        record.counted
+    )
+}
+
+/// Returns a number as a `usize` if it is a non-negative integer that fits.
+fn as_usize(value: &Json) -> Option<usize> {
+    match value {
+        Json::Number(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns a number as an `f64` if it is one.
+fn as_f64(value: &Json) -> Option<f64> {
+    match value {
+        Json::Number(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns `text` as a JSON string literal.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The deepest nesting of arrays and objects a request may have. Requests nest two levels at
+/// most, and the parser recurses once per level, so a deeper request is rejected before it can
+/// exhaust the stack.
+const MAX_NESTING_DEPTH: usize = 32;
+
+/// A recursive-descent parser for the subset of JSON requests use: no escapes other than the
+/// simple ones, and no `\u` escapes outside the basic multilingual plane.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    /// The number of arrays and objects the parser is inside of.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            chars: text.chars().peekable(),
+            depth: 0,
+        }
+    }
+
+    /// Parses a single value followed by nothing but whitespace.
+    fn parse_document(&mut self) -> Result<Json, String> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        match self.chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected `{c}` after the request")),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{' | '[') if self.depth == MAX_NESTING_DEPTH => Err(format!(
+                "the request nests deeper than {MAX_NESTING_DEPTH} arrays and objects"
+            )),
+            Some('{') => self.nested(Self::parse_object),
+            Some('[') => self.nested(Self::parse_array),
+            Some('"') => self.parse_string().map(Json::String),
+            Some('t') => self.parse_keyword("true", Json::Bool(true)),
+            Some('f') => self.parse_keyword("false", Json::Bool(false)),
+            Some('n') => self.parse_keyword("null", Json::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => Ok(self.parse_number()),
+            Some(c) => Err(format!("unexpected `{c}` in the request")),
+            None => Err("unexpected end of the request".to_string()),
+        }
+    }
+
+    /// Parses an array or an object one level deeper.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err("expected `,` or `}` in an object".to_string()),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err("expected `,` or `]` in an array".to_string()),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.chars.next() {
+                    Some(c @ ('"' | '\\' | '/')) => text.push(c),
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = self.chars.by_ref().take(4).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape `\\u{hex}`"))?;
+                        text.push(c);
+                    }
+                    _ => return Err("invalid escape in a string".to_string()),
+                },
+                Some(c) => text.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Json {
+        let mut text = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            text.push(c);
+        }
+        Json::Number(text)
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.chars.next() != Some(expected) {
+                return Err(format!("invalid literal, expected `{keyword}`"));
+            }
+        }
+        Ok(value)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}` but found `{c}`")),
+            None => Err(format!("expected `{expected}` but the request ended")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimize() {
+        let response =
+            serve(r#"{"command":"optimize","degree":65536,"blowup":8,"queries":27}"#).unwrap();
+        assert!(response.contains(r#""size_elements":4966"#), "{response}");
+        assert!(response.contains(r#""schedule":[0,4]"#), "{response}");
+        assert!(response.contains(r#""optimality":"Exact""#), "{response}");
+    }
+
+    #[test]
+    fn estimate() {
+        let response = serve(
+            r#"{"command":"estimate","degree":65536,"blowup":8,"queries":27,"schedule":[0,4]}"#,
+        )
+        .unwrap();
+        assert!(response.contains(r#""size_elements":4966"#), "{response}");
+        assert!(!response.contains("optimality"), "{response}");
+    }
+
+    #[test]
+    fn sweep() {
+        let response =
+            serve(r#"{"command":"sweep","degree":65536,"blowup":8,"queries":[27,40]}"#).unwrap();
+        assert!(
+            response.contains(r#"{"queries":27,"size_elements":4966,"#),
+            "{response}"
+        );
+        assert!(response.contains(r#"{"queries":40,"#), "{response}");
+        assert_eq!(response.matches(r#""optimality":"Exact""#).count(), 2);
+    }
+
+    #[test]
+    fn malformed_requests_are_errors() {
+        assert!(serve(r#"{"command":"optimize","degree":"#).is_err());
+        assert_eq!(
+            serve(r#"{"command":"estimate","degree":65536,"blowup":8}"#),
+            Err("missing `queries`".to_string())
+        );
+    }
+
+    #[test]
+    fn time_budgets_are_bounded() {
+        let request = |command: &str, queries: &str, secs: &str| {
+            format!(
+                concat!(
+                    r#"{{"command":"{}","degree":65536,"blowup":8,"queries":{},"#,
+                    r#""time_budget_secs":{}}}"#
+                ),
+                command, queries, secs
+            )
+        };
+        for (command, queries) in [("optimize", "27"), ("sweep", "[27]")] {
+            assert_eq!(
+                serve(&request(command, queries, "61")),
+                Err("`time_budget_secs` must not exceed 60 seconds".to_string())
+            );
+            assert!(serve(&request(command, queries, "60")).is_ok());
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Tables of results with one row per schedule or parameter set, rendered as aligned plain text
+//! or as Markdown, so that every multi-result output is laid out the same way.
+
+use super::optimized_schedule::size_in_bytes;
+use super::report::FoldingReport;
+
+/// A table of results: a header and rows of cells, all rendered as text.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::table::ReportTable;
+///
+/// let mut table = ReportTable::new(["strategy", "size (kB)"]);
+/// table.push_row(["optimal", "110"]);
+/// table.push_row(["uniform", "124"]);
+///
+/// assert_eq!(table.to_plain(), "strategy  size (kB)\noptimal   110\nuniform   124\n");
+/// assert_eq!(
+///     table.to_markdown(),
+///     "| strategy | size (kB) |\n| --- | --- |\n| optimal | 110 |\n| uniform | 124 |\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportTable {
+    /// The column headers.
+    pub headers: Vec<String>,
+    /// The rows, each with at most one cell per column.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ReportTable {
+    /// Creates an empty table with the given column headers.
+    pub fn new<S: ToString>(headers: impl IntoIterator<Item = S>) -> Self {
+        ReportTable {
+            headers: headers
+                .into_iter()
+                .map(|header| header.to_string())
+                .collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row. Missing trailing cells are rendered empty.
+    pub fn push_row<S: ToString>(&mut self, row: impl IntoIterator<Item = S>) {
+        self.rows
+            .push(row.into_iter().map(|cell| cell.to_string()).collect());
+    }
+
+    /// Creates a table with one row per report: its parameters, its schedule and its size. This
+    /// lays out the reports of e.g.
+    /// [`size_vs_blowup_at_security`](super::analysis::size_vs_blowup_at_security).
+    pub fn from_reports<'a>(reports: impl IntoIterator<Item = &'a FoldingReport>) -> Self {
+        let mut table = ReportTable::new([
+            "degree",
+            "blowup",
+            "queries",
+            "schedule",
+            "size (elements)",
+            "size (B)",
+        ]);
+        for report in reports {
+            table.push_row([
+                report.degree.to_string(),
+                report.blowup_factor.to_string(),
+                report.num_queries.to_string(),
+                format!("{:?}", report.schedule),
+                report.total_elements().to_string(),
+                size_in_bytes(report.total_elements(), &report.cost_model).to_string(),
+            ]);
+        }
+        table
+    }
+
+    /// Renders the table as plain text, with every column left-aligned to its widest cell and
+    /// two spaces between columns.
+    pub fn to_plain(&self) -> String {
+        let lines = std::iter::once(&self.headers).chain(&self.rows);
+        let columns = lines.clone().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<_> = (0..columns)
+            .map(|column| {
+                lines
+                    .clone()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut text = String::new();
+        for row in lines {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            text.push_str(cells.join("  ").trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Renders the table as a Markdown table. Pipes in cells are escaped.
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &[String]| {
+            let cells: Vec<_> = (0..self.headers.len())
+                .map(|column| {
+                    cells
+                        .get(column)
+                        .map_or(String::new(), |cell| cell.replace('|', "\\|"))
+                })
+                .collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+
+        let mut text = line(&self.headers);
+        text.push_str(&line(&vec!["---".to_string(); self.headers.len()]));
+        for row in &self.rows {
+            text.push_str(&line(row));
+        }
+        text
+    }
+}
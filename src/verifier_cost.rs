@@ -0,0 +1,354 @@
+use super::cost_model::CostModel;
+use super::layers::{walk_schedule, LayerFacts};
+use super::optimized_schedule::{
+    estimate_proof_size_unchecked, remainder_coefficient_elements, size_in_bytes,
+};
+
+/// Estimates the number of hashes the verifier computes to check the Merkle openings of a FRI
+/// proof for a given folding strategy. Hashing the remainder polynomial is not counted.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+///
+/// # Returns
+/// * `num_hashes` - The estimated number of hash invocations performed by the verifier
+pub fn estimate_verifier_hashes(
+    degree: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    // the remainder is not hashed, so the blowup factor, which only sizes it, does not matter.
+    let mut num_hashes = 0;
+    walk_schedule(
+        degree,
+        1,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // every query authenticates one leaf group in this layer.
+            num_hashes += num_queries
+                * cost_model
+                    .coset_layout
+                    .verifier_hashes(layer.domain_size, layer.leaf_factor());
+        },
+    );
+    num_hashes
+}
+
+/// Estimates the number of field multiplications the verifier performs for a given folding
+/// strategy. Every query folds one coset per layer, priced by the cost model's
+/// [`CosetEvalMethod`](super::cost_model::CosetEvalMethod), and finally evaluates the remainder
+/// polynomial with Horner's rule at one multiplication per coefficient. Batching the inversions
+/// of a layer adds the multiplications of Montgomery's trick, see
+/// [`estimate_verifier_inversions`].
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing how cosets are folded
+///
+/// # Returns
+/// * `num_ops` - The estimated number of field multiplications performed by the verifier
+pub fn estimate_verifier_field_ops(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    let mut num_ops = 0;
+    let remainder = walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            num_ops += num_queries * cost_model.coset_eval.field_ops(layer.factor());
+            let (_, batching_ops) = cost_model.inversion_batching.layer_inversions(
+                num_queries,
+                layer.factor(),
+                cost_model.coset_eval,
+            );
+            num_ops += batching_ops;
+        },
+    );
+
+    // every query checks its folded value against the remainder.
+    num_ops + num_queries * remainder.degree
+}
+
+/// Estimates the number of field inversions the verifier performs in each layer of a given
+/// folding strategy: the inversions every queried coset needs to be folded, priced by the cost
+/// model's [`CosetEvalMethod`](super::cost_model::CosetEvalMethod) and batched as its
+/// [`InversionBatching`](super::cost_model::InversionBatching) says. The remainder is evaluated
+/// from its coefficients and needs none.
+///
+/// For example, with 3 queries and the folding sequence `[0, 2, 2, 2]`, barycentric evaluation
+/// inverts 4 values per coset. Batching per coset leaves one inversion per query at the cost of
+/// 3 * 3 multiplications per query, and batching per layer one inversion at the cost of 3 * 11:
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::InversionBatching;
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::verifier_cost::{
+///     estimate_verifier_field_ops, estimate_verifier_inversions,
+/// };
+///
+/// let seq = [0, 2, 2, 2];
+/// let unbatched = CostModel::default();
+/// let per_coset = CostModel { inversion_batching: InversionBatching::PerCoset, ..unbatched };
+/// let per_layer = CostModel { inversion_batching: InversionBatching::PerLayer, ..unbatched };
+/// assert_eq!(estimate_verifier_inversions(3, &seq, &unbatched), vec![0, 12, 12, 12]);
+/// assert_eq!(estimate_verifier_inversions(3, &seq, &per_coset), vec![0, 3, 3, 3]);
+/// assert_eq!(estimate_verifier_inversions(3, &seq, &per_layer), vec![0, 1, 1, 1]);
+///
+/// let field_ops = |cost_model| estimate_verifier_field_ops(1 << 10, 4, 3, &seq, cost_model);
+/// assert_eq!(field_ops(&per_coset), field_ops(&unbatched) + 3 * 3 * 3 * 3);
+/// assert_eq!(field_ops(&per_layer), field_ops(&unbatched) + 3 * 3 * 11);
+/// ```
+///
+/// # Arguments
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing how cosets are folded and inversions batched
+///
+/// # Returns
+/// * `num_inversions` - The estimated number of inversions in each layer of the folding sequence
+pub fn estimate_verifier_inversions(
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> Vec<usize> {
+    folding_seq
+        .iter()
+        .map(|folding_factors_bits| {
+            let (inversions, _) = cost_model.inversion_batching.layer_inversions(
+                num_queries,
+                1 << folding_factors_bits,
+                cost_model.coset_eval,
+            );
+            inversions
+        })
+        .collect()
+}
+
+/// The gas an EVM verifier spends per hash: a Keccak-256 call over two 32-byte words.
+pub const GAS_PER_HASH: usize = 36;
+
+/// The gas an EVM verifier spends per field multiplication: one `MULMOD` plus stack handling.
+pub const GAS_PER_FIELD_OP: usize = 8;
+
+/// The gas an EVM verifier spends per byte of proof calldata, assuming no zero bytes.
+pub const GAS_PER_CALLDATA_BYTE: usize = 16;
+
+/// The gas an EVM verifier spends per field inversion: an exponentiation by `p - 2`, about 100
+/// multiplications for a 64-bit field.
+pub const GAS_PER_INVERSION: usize = 100 * GAS_PER_FIELD_OP;
+
+/// Estimates the gas an EVM verifier spends on a FRI proof for a given folding strategy: its
+/// hashes, its field multiplications and inversions and the calldata carrying the proof, priced
+/// with [`GAS_PER_HASH`], [`GAS_PER_FIELD_OP`], [`GAS_PER_INVERSION`] and
+/// [`GAS_PER_CALLDATA_BYTE`]. These are rough costs
+/// meant for comparing schedules, not for predicting a transaction's gas exactly.
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `num_queries` - The number of queries used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model used to price the proof
+///
+/// # Returns
+/// * `gas` - The estimated verifier gas
+pub fn estimate_verifier_gas(
+    degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    let hashes = estimate_verifier_hashes(degree, num_queries, folding_seq, cost_model);
+    let field_ops =
+        estimate_verifier_field_ops(degree, blowup_factor, num_queries, folding_seq, cost_model);
+    let inversions: usize = estimate_verifier_inversions(num_queries, folding_seq, cost_model)
+        .iter()
+        .sum();
+    let proof_bytes = size_in_bytes(
+        estimate_proof_size_unchecked(degree, blowup_factor, num_queries, folding_seq, cost_model),
+        cost_model,
+    );
+    hashes * GAS_PER_HASH
+        + field_ops * GAS_PER_FIELD_OP
+        + inversions * GAS_PER_INVERSION
+        + proof_bytes * GAS_PER_CALLDATA_BYTE
+}
+
+/// Estimates the peak working-set size of a verifier, in bytes, for a given folding strategy. The
+/// verifier is assumed to check one query at a time, so at any point it holds:
+/// * the commitment root of every layer,
+/// * the remainder polynomial, and
+/// * the opened coset values and authentication path of a single query in a single layer.
+///
+/// The last term is taken at the layer where it is largest, which is usually the first one. A
+/// large remainder can dominate instead, as the verifier keeps all of its coefficients to evaluate
+/// it at every query.
+///
+/// For example, with a degree of 2^10 and a blowup factor of 4, folding by 4 twice needs 86
+/// elements (the 42-element opening of layer 0, 3 roots and 16 remainder coefficients), while a
+/// single 64-way fold needs 160 elements (a 144-element opening, 2 roots and 4 coefficients):
+///
+/// ```
+/// use fri_dynamic_folding_scheme::cost_model::CostModel;
+/// use fri_dynamic_folding_scheme::verifier_cost::estimate_verifier_memory;
+///
+/// let cost_model = CostModel::default();
+/// assert_eq!(estimate_verifier_memory(1 << 10, 4, &[0, 2, 2], &cost_model), 86 * 8);
+/// assert_eq!(estimate_verifier_memory(1 << 10, 4, &[0, 6], &cost_model), 160 * 8);
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+///
+/// # Returns
+/// * `memory` - The estimated peak verifier memory in bytes
+pub fn estimate_verifier_memory(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    let mut longest_opening = 0;
+    let remainder = walk_schedule(
+        degree,
+        blowup_factor,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // the opened coset values plus the digests needed to authenticate them.
+            let opening = layer.path_depth * cost_model.digest_elements
+                + layer.leaf_factor() * layer.value_elements;
+            longest_opening = longest_opening.max(opening);
+        },
+    );
+
+    let roots = count_commitment_roots(folding_seq, cost_model) * cost_model.digest_elements;
+
+    // the remainder polynomial in coefficient form.
+    let remainder = remainder.degree * cost_model.extension_degree;
+
+    size_in_bytes(longest_opening + roots + remainder, cost_model)
+}
+
+/// Returns the number of Merkle roots in a FRI proof for a given folding strategy, which is also
+/// the number of roots the verifier has to store. Every layer of the folding sequence, including
+/// unfolded ones, is committed to with its own root; the remainder is sent in the clear.
+///
+/// When the cost model's `first_layer_shares_trace_commitment` is set, the root of the first
+/// layer belongs to the trace oracle and is not counted. Only the first layer is shared: with
+/// several leading unfolded layers, the ones after it are still fresh commitments, and a schedule
+/// that folds its first layer shares the oracle of the polynomial before that fold.
+pub fn count_commitment_roots(folding_seq: &[usize], cost_model: &CostModel) -> usize {
+    let shared = cost_model.first_layer_shares_trace_commitment && !folding_seq.is_empty();
+    folding_seq.len() - shared as usize
+}
+
+/// Returns the length, in digests, of the longest authentication path of a single query over the
+/// layers opened in the proof. A recursive verifier that unrolls the path checks into
+/// constraints has to size its circuit for this path.
+///
+/// The layers are committed over shrinking domains, so the longest path is that of the first
+/// layer in the proof. When the unfolded initial layer is part of the proof its path is the
+/// same for every schedule; otherwise folding the first committed layer more shortens it, see
+/// [`CosetLayout::path_digests`](super::cost_model::CosetLayout::path_digests).
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model describing the layout of each layer's Merkle tree
+///
+/// # Returns
+/// * `depth` - The number of digests in the longest path, or 0 if no layer is opened
+pub fn deepest_path(degree: usize, folding_seq: &[usize], cost_model: &CostModel) -> usize {
+    // the remainder has no path, so the blowup factor, which only sizes it, does not matter.
+    let mut deepest = 0;
+    walk_schedule(
+        degree,
+        1,
+        folding_seq,
+        cost_model,
+        &mut |layer: &LayerFacts| {
+            // an excluded initial layer is opened outside of the proof.
+            if layer.counts_paths {
+                deepest = deepest.max(layer.path_depth);
+            }
+        },
+    );
+    deepest
+}
+
+/// Returns the cost, in proof elements, of the verifier's direct check of the remainder: the
+/// cost model's `remainder_verify_cost_per_element` for every element of the remainder. The
+/// remainder is checked whether or not its size is excluded from the proof. The optimizer adds
+/// this cost to the size of every schedule it compares, so that a verifier for which the final
+/// check is expensive gets a schedule folding deeper, while the size it reports stays that of
+/// the proof.
+///
+/// At the default parameters the optimal schedule stops at a remainder of 256 elements, which
+/// a weight of 4 makes too expensive:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use fri_dynamic_folding_scheme::prelude::*;
+/// use fri_dynamic_folding_scheme::verifier_cost::remainder_check_cost;
+///
+/// let optimal = |cost_model: &CostModel| {
+///     optimal_folding_strategy(
+///         1 << 25,
+///         8,
+///         27,
+///         vec![0],
+///         &HashMap::new(),
+///         cost_model,
+///         &mut SearchStats::default(),
+///     )
+///     .unwrap()
+/// };
+/// let size_only = CostModel::default();
+/// let weighted = CostModel { remainder_verify_cost_per_element: 4, ..size_only };
+/// assert_eq!(remainder_check_cost(1 << 25, 8, &[0, 4, 4, 4, 3], &weighted), 4 * 256);
+///
+/// let (size, schedule) = optimal(&size_only);
+/// let (weighted_size, weighted_schedule) = optimal(&weighted);
+/// assert!(weighted_schedule.iter().sum::<usize>() > schedule.iter().sum::<usize>());
+/// assert!(weighted_size > size);
+/// assert_eq!(weighted_size, estimate_proof_size(1 << 25, 8, 27, &weighted_schedule, &weighted).unwrap());
+/// ```
+///
+/// # Arguments
+/// * `degree` - The degree of the polynomial to be proved
+/// * `blowup_factor` - The blowup factor used in the FRI protocol
+/// * `folding_seq` - The folding sequence
+/// * `cost_model` - The cost model weighing the remainder check
+///
+/// # Returns
+/// * `cost` - The cost of the remainder check in proof elements
+pub fn remainder_check_cost(
+    degree: usize,
+    blowup_factor: usize,
+    folding_seq: &[usize],
+    cost_model: &CostModel,
+) -> usize {
+    let remainder_degree = degree >> folding_seq.iter().sum::<usize>();
+    cost_model.remainder_verify_cost_per_element
+        * remainder_coefficient_elements(remainder_degree, blowup_factor, cost_model)
+}
@@ -0,0 +1,347 @@
+//! Calibration of the estimator against FRI proofs generated and measured with Winterfell. The
+//! crate does not depend on Winterfell: the FRI options of the measured proof are restated here,
+//! and the estimate is compared with the byte size the caller measured.
+
+use super::cost_model::CostModel;
+use super::defaults::GOLDILOCKS_QUADRATIC;
+use super::error::FoldingError;
+use super::optimized_schedule::{size_in_bytes, validate_parameters};
+use super::simple_schedule::simple_schedule;
+
+/// The FRI parameters of a Winterfell proof, as set in its `ProofOptions`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WinterfellFriOptions {
+    /// The size of the LDE domain, i.e. the trace length times the blowup factor.
+    pub domain_size: usize,
+    /// The blowup factor.
+    pub blowup_factor: usize,
+    /// The number of queries.
+    pub num_queries: usize,
+    /// The folding factor of every FRI layer: 2, 4, 8 or 16.
+    pub folding_factor: usize,
+    /// The maximum degree of the remainder polynomial.
+    pub remainder_max_degree: usize,
+    /// The field and hash the proof is over; Winterfell's `f64` field with a quadratic extension
+    /// and Blake3 match [`GOLDILOCKS_QUADRATIC`].
+    pub cost_model: CostModel,
+}
+
+impl Default for WinterfellFriOptions {
+    fn default() -> Self {
+        WinterfellFriOptions {
+            domain_size: 1 << 25,
+            blowup_factor: 8,
+            num_queries: 27,
+            folding_factor: 8,
+            remainder_max_degree: 63,
+            cost_model: GOLDILOCKS_QUADRATIC,
+        }
+    }
+}
+
+/// The estimated size of a Winterfell proof against its measured size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WinterfellComparison {
+    /// The folding schedule Winterfell uses for the options.
+    pub schedule: Vec<usize>,
+    /// The estimated size of the proof in bytes.
+    pub estimated_bytes: usize,
+    /// The measured size of the proof in bytes.
+    pub measured_bytes: usize,
+    /// The signed error of the estimate in percent of the measured size: positive when the
+    /// estimate is too large.
+    pub error_percent: f64,
+}
+
+/// Estimates the size of a Winterfell FRI proof with the given options and compares it with the
+/// size measured on a real proof. Winterfell folds every layer by the same factor until the
+/// remainder is within its maximum degree, as [`simple_schedule`] does. An error that stays the
+/// same across options points at a modeling bug rather than at noise.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::winterfell::{compare_to_winterfell, WinterfellFriOptions};
+///
+/// let options = WinterfellFriOptions::default();
+/// let comparison = compare_to_winterfell(&options, 100_000).unwrap();
+/// assert_eq!(comparison.schedule, vec![0, 3, 3, 3, 3, 3, 3]);
+/// let estimated = comparison.estimated_bytes as f64;
+/// assert_eq!(comparison.error_percent, (estimated / 100_000.0 - 1.0) * 100.0);
+/// ```
+///
+/// # Arguments
+/// * `options` - The FRI options the proof was generated with
+/// * `measured_bytes` - The measured size of the FRI part of the proof in bytes
+///
+/// # Errors
+/// * If the domain size or blowup factor is rejected by [`validate_parameters`]
+///
+/// # Panics
+/// * If the folding factor is not a power of 2
+/// * If `measured_bytes` is 0
+pub fn compare_to_winterfell(
+    options: &WinterfellFriOptions,
+    measured_bytes: usize,
+) -> Result<WinterfellComparison, FoldingError> {
+    validate_parameters(options.domain_size, options.blowup_factor)?;
+    assert!(
+        options.folding_factor.is_power_of_two(),
+        "the folding factor {} is not a power of 2",
+        options.folding_factor
+    );
+    assert!(
+        measured_bytes > 0,
+        "the measured proof size must be positive"
+    );
+
+    let (num_elements, schedule) = simple_schedule(
+        options.domain_size,
+        options.blowup_factor,
+        options.num_queries,
+        options.remainder_max_degree,
+        options.folding_factor.ilog2() as usize,
+        &options.cost_model,
+    )?;
+    let estimated_bytes = size_in_bytes(num_elements, &options.cost_model);
+    Ok(WinterfellComparison {
+        schedule,
+        estimated_bytes,
+        measured_bytes,
+        error_percent: (estimated_bytes as f64 / measured_bytes as f64 - 1.0) * 100.0,
+    })
+}
+
+/// The FRI options read from the `Debug` print of a Winterfell `ProofOptions`, together with
+/// what the parser found in it and what it had to assume.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedProofOptions {
+    /// The FRI options, with the defaults of [`WinterfellFriOptions`] for the fields not found.
+    pub options: WinterfellFriOptions,
+    /// The number of grinding bits, which only adds a nonce to the proof and is not sized.
+    pub grinding_bits: usize,
+    /// The names of the fields found in the print, as spelled there.
+    pub found: Vec<&'static str>,
+    /// The options that were not found and were given their default.
+    pub assumed: Vec<&'static str>,
+}
+
+/// Reads the FRI options of a proof over a domain of `domain_size` from the `Debug` print of a
+/// Winterfell `ProofOptions`, as found in logs. `ProofOptions` does not hold the trace length, so
+/// the domain size has to be given.
+///
+/// The parser is forgiving: it looks for every field by name, wherever it appears and whatever
+/// surrounds it, and ignores everything else. Field names changed between Winterfell releases,
+/// and every known spelling is accepted:
+/// * `num_queries`, `blowup_factor` and `fri_folding_factor` (or `folding_factor`) are
+///   required.
+/// * `fri_remainder_max_degree` (or `remainder_max_degree`) bounds the degree of the remainder.
+///   Earlier releases bound the size of the remainder's domain with `fri_max_remainder_size` (or
+///   `max_remainder_size`) instead, which is converted to a degree below `size / blowup_factor`.
+/// * `grinding_factor` and `field_extension` (`None`, `Quadratic` or `Cubic`) are optional.
+///
+/// Optional fields that are not found keep their default, and are listed as assumed.
+///
+/// ```
+/// use fri_dynamic_folding_scheme::winterfell::parse_proof_options_debug;
+///
+/// let parsed = parse_proof_options_debug(
+///     "ProofOptions { num_queries: 42, blowup_factor: 8, grinding_factor: 16, \
+///      field_extension: Quadratic, fri_folding_factor: 4, fri_remainder_max_degree: 255 }",
+///     1 << 20,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     (parsed.options.num_queries, parsed.options.blowup_factor, parsed.options.folding_factor),
+///     (42, 8, 4)
+/// );
+/// assert_eq!(parsed.options.remainder_max_degree, 255);
+/// assert_eq!(parsed.grinding_bits, 16);
+/// assert!(parsed.assumed.is_empty());
+/// ```
+///
+/// # Errors
+/// * If a required field is missing or its value is not a number
+pub fn parse_proof_options_debug(
+    text: &str,
+    domain_size: usize,
+) -> Result<ParsedProofOptions, FoldingError> {
+    let mut found = Vec::new();
+    let mut field = |keys: &[&'static str]| {
+        keys.iter().find_map(|&key| {
+            let value = field_value(text, key)?;
+            found.push(key);
+            Some((key, value))
+        })
+    };
+    let number =
+        |value: Option<(&'static str, &str)>| value.and_then(|(_, v)| v.parse::<usize>().ok());
+
+    let num_queries = number(field(&["num_queries"]));
+    let blowup_factor = number(field(&["blowup_factor"]));
+    let folding_factor = number(field(&["fri_folding_factor", "folding_factor"]));
+    let remainder = field(&[
+        "fri_remainder_max_degree",
+        "remainder_max_degree",
+        "fri_max_remainder_size",
+        "max_remainder_size",
+    ]);
+    let grinding_bits = number(field(&["grinding_factor"]));
+    let field_extension = field(&["field_extension"]);
+
+    let (Some(num_queries), Some(blowup_factor), Some(folding_factor)) =
+        (num_queries, blowup_factor, folding_factor)
+    else {
+        let missing = [
+            ("num_queries", num_queries),
+            ("blowup_factor", blowup_factor),
+            ("fri_folding_factor", folding_factor),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| name)
+        .collect();
+        return Err(FoldingError::IncompleteWinterfellOptions { missing });
+    };
+
+    let defaults = WinterfellFriOptions::default();
+    let mut assumed = Vec::new();
+    let remainder_max_degree = match remainder {
+        Some((key, value)) if key.ends_with("max_degree") => value.parse().ok(),
+        Some((_, value)) => value
+            .parse::<usize>()
+            .ok()
+            .map(|size| (size / blowup_factor.max(1)).saturating_sub(1)),
+        None => None,
+    }
+    .unwrap_or_else(|| {
+        assumed.push("remainder max degree");
+        defaults.remainder_max_degree
+    });
+    let grinding_bits = grinding_bits.unwrap_or_else(|| {
+        assumed.push("grinding factor");
+        0
+    });
+    let extension_degree = match field_extension.map(|(_, value)| value) {
+        Some("None") => 1,
+        Some("Quadratic") => 2,
+        Some("Cubic") => 3,
+        _ => {
+            assumed.push("field extension");
+            defaults.cost_model.extension_degree
+        }
+    };
+
+    Ok(ParsedProofOptions {
+        options: WinterfellFriOptions {
+            domain_size,
+            blowup_factor,
+            num_queries,
+            folding_factor,
+            remainder_max_degree,
+            cost_model: CostModel {
+                extension_degree,
+                ..defaults.cost_model
+            },
+        },
+        grinding_bits,
+        found,
+        assumed,
+    })
+}
+
+/// Returns the value following the first occurrence of the field `key` in a `Debug` print, i.e.
+/// the word after `key:` or `key =`, or `None` if the field is not there.
+fn field_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    text.match_indices(key).find_map(|(start, _)| {
+        // the key must be a whole word, not the end of a longer field name.
+        if text[..start].ends_with(is_word) {
+            return None;
+        }
+        let rest = text[start + key.len()..].trim_start();
+        let rest = rest.strip_prefix([':', '='])?.trim_start();
+        let end = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+        (end > 0).then(|| &rest[..end])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remainder_size_of_earlier_releases_is_a_degree() {
+        let parsed = parse_proof_options_debug(
+            "ProofOptions { num_queries: 27, blowup_factor: 16, hash_fn: Blake3_256, \
+             field_extension: None, fri_folding_factor: 8, fri_max_remainder_size: 256 }",
+            1 << 20,
+        )
+        .unwrap();
+        assert_eq!(parsed.options.remainder_max_degree, 256 / 16 - 1);
+        assert_eq!(parsed.options.cost_model.extension_degree, 1);
+        assert!(parsed.found.contains(&"fri_max_remainder_size"));
+        assert_eq!(parsed.assumed, ["grinding factor"]);
+    }
+
+    #[test]
+    fn missing_fields_are_named() {
+        let err = parse_proof_options_debug("ProofOptions { num_queries: 27, blowup_fa", 1 << 20);
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "the Winterfell proof options lack blowup_factor and fri_folding_factor"
+        );
+
+        // a value that is not a number is as good as missing.
+        let err = parse_proof_options_debug(
+            "num_queries: many, blowup_factor: 8, folding_factor: 4",
+            1 << 20,
+        );
+        assert_eq!(
+            err,
+            Err(FoldingError::IncompleteWinterfellOptions {
+                missing: vec!["num_queries"]
+            })
+        );
+    }
+
+    #[test]
+    fn optional_fields_are_assumed() {
+        let parsed = parse_proof_options_debug(
+            "num_queries: 27, blowup_factor: 8, folding_factor: 4",
+            1 << 20,
+        )
+        .unwrap();
+        let defaults = WinterfellFriOptions::default();
+        assert_eq!(
+            parsed.options.remainder_max_degree,
+            defaults.remainder_max_degree
+        );
+        assert_eq!(parsed.options.cost_model, defaults.cost_model);
+        assert_eq!(
+            parsed.assumed,
+            ["remainder max degree", "grinding factor", "field extension"]
+        );
+    }
+
+    #[test]
+    fn pretty_prints_and_assignments_are_read() {
+        let pretty = "ProofOptions {\n    num_queries: 30,\n    blowup_factor: 4,\n    \
+                      field_extension: Cubic,\n    fri_folding_factor: 16,\n}";
+        let parsed = parse_proof_options_debug(pretty, 1 << 16).unwrap();
+        assert_eq!(
+            (parsed.options.num_queries, parsed.options.folding_factor),
+            (30, 16)
+        );
+        assert_eq!(parsed.options.cost_model.extension_degree, 3);
+
+        assert_eq!(field_value("num_queries = 12", "num_queries"), Some("12"));
+    }
+
+    #[test]
+    fn fields_are_matched_as_whole_words() {
+        let text = "fri_remainder_max_degree: 7, remainder_max_degree: 31";
+        assert_eq!(field_value(text, "remainder_max_degree"), Some("31"));
+        assert_eq!(field_value(text, "fri_remainder_max_degree"), Some("7"));
+        assert_eq!(field_value("max_degree_x: 3", "max_degree"), None);
+        assert_eq!(field_value("num_queries: ,", "num_queries"), None);
+    }
+}
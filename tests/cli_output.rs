@@ -0,0 +1,83 @@
+//! Pins the machine-readable output of the binary, so that a change to the cost model or the
+//! optimizers shows up as a diff of the files under `tests/snapshots/`, with the exact numbers
+//! that moved. Run with `UPDATE_SNAPSHOTS=1` to accept a deliberate change, and review the diff
+//! of the snapshots with the change.
+//!
+//! Every run is pinned as CSV and as JSON: the default parameters, and a small degree, a large
+//! blowup factor and many queries.
+
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// The runs pinned, by snapshot name and arguments.
+const RUNS: [(&str, &[&str]); 4] = [
+    ("default", &[]),
+    ("small_degree", &["--degree", "65536"]),
+    ("large_blowup", &["--blowup", "32"]),
+    ("high_queries", &["--queries", "100"]),
+];
+
+/// Runs the binary with the given arguments and returns its output.
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_fri-dynamic-folding-scheme"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Compares the output with its snapshot, or overwrites the snapshot under `UPDATE_SNAPSHOTS`.
+fn assert_snapshot(name: &str, output: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, output).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    if output == expected {
+        return;
+    }
+    let mut diff = String::new();
+    let lines = expected.lines().count().max(output.lines().count());
+    for line in 0..lines {
+        let (old, new) = (expected.lines().nth(line), output.lines().nth(line));
+        if old != new {
+            diff += &format!(
+                "line {}:\n- {}\n+ {}\n",
+                line + 1,
+                old.unwrap_or(""),
+                new.unwrap_or("")
+            );
+        }
+    }
+    panic!(
+        "the output differs from tests/snapshots/{name}, rerun with UPDATE_SNAPSHOTS=1 if the \
+         change is deliberate\n{diff}"
+    );
+}
+
+#[test]
+fn csv_output_matches_the_snapshots() {
+    for (name, args) in RUNS {
+        let output = run(&[args, &["--emit", "csv"]].concat());
+        assert_snapshot(&format!("{name}.csv"), &output);
+    }
+}
+
+#[test]
+fn json_output_matches_the_snapshots() {
+    for (name, args) in RUNS {
+        let output = run(&[args, &["--emit", "json"]].concat());
+        assert_snapshot(&format!("{name}.json"), &output);
+    }
+}
@@ -0,0 +1,234 @@
+//! Pins the public API of the crate, so that an item made public or private by accident shows up
+//! as a diff of `tests/snapshots/public_api.txt`. Run with `UPDATE_SNAPSHOTS=1` to accept a
+//! deliberate change.
+//!
+//! The crate depends on `std` alone, so the API is read from the sources rather than from
+//! rustdoc: every item, field, variant and inherent method declared `pub` in a module, the traits
+//! implemented for its types, and the prelude. Modules and items are listed in source
+//! order, each with the features it is gated on.
+
+use std::fs;
+use std::path::Path;
+
+const SNAPSHOT: &str = "tests/snapshots/public_api.txt";
+
+/// Reads a file of the package.
+fn read(path: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap_or_default()
+}
+
+const ITEM_KINDS: [&str; 7] = ["const fn", "fn", "struct", "enum", "trait", "const", "type"];
+
+/// Returns the features the attributes gate on, e.g. ` [feature = "sim"]`, or nothing.
+fn gate(attributes: &[&str]) -> String {
+    attributes
+        .iter()
+        .filter_map(|attribute| attribute.strip_prefix("#[cfg(")?.strip_suffix(")]"))
+        .map(|condition| format!(" [{condition}]"))
+        .collect()
+}
+
+/// Returns the name an item declaration starts with, up to its generics, arguments or body.
+fn name_of(declaration: &str) -> &str {
+    let end = declaration
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(declaration.len());
+    &declaration[..end]
+}
+
+/// Returns the type an `impl` block is for, and the trait it implements if any.
+fn impl_target(header: &str) -> (String, Option<String>) {
+    let header = header.trim_end_matches("{}").trim_end_matches('{').trim();
+    let header = header.strip_prefix("impl").unwrap();
+    // skip the generics of the impl itself.
+    let header = if header.starts_with('<') {
+        let mut depth = 0;
+        let end = header
+            .char_indices()
+            .find(|&(_, c)| {
+                depth += match c {
+                    '<' => 1,
+                    '>' => -1,
+                    _ => 0,
+                };
+                depth == 0
+            })
+            .unwrap()
+            .0;
+        &header[end + 1..]
+    } else {
+        header
+    };
+    let header = header.split(" where").next().unwrap().trim();
+    match header.split_once(" for ") {
+        Some((implemented, target)) => (target.to_string(), Some(implemented.to_string())),
+        None => (header.to_string(), None),
+    }
+}
+
+/// Lists the public API of one module.
+fn module_api(module: &str, source: &str, api: &mut Vec<String>) {
+    let mut attributes = Vec::new();
+    // the item whose body is being read: a struct, an enum or an inherent impl.
+    let mut body: Option<(String, &str)> = None;
+    for line in source.lines() {
+        if line == "#[cfg(test)]" {
+            break;
+        }
+        if let Some((owner, kind)) = &body {
+            if line.starts_with('}') {
+                body = None;
+                continue;
+            }
+            let member = line.trim_start();
+            let indent = line.len() - member.len();
+            if indent != 4 || member.starts_with("//") {
+                continue;
+            }
+            if member.starts_with("#[") {
+                attributes.push(member);
+                continue;
+            }
+            let gated = gate(&attributes);
+            attributes.clear();
+            match *kind {
+                "struct" => {
+                    if let Some(field) = member.strip_prefix("pub ") {
+                        api.push(format!("{module}::{owner}.{}{gated}", name_of(field)));
+                    }
+                }
+                "enum" => {
+                    if member.starts_with(char::is_uppercase) {
+                        api.push(format!("{module}::{owner}::{}{gated}", name_of(member)));
+                    }
+                }
+                _ => {
+                    for kind in ["pub const fn ", "pub fn ", "pub const "] {
+                        if let Some(method) = member.strip_prefix(kind) {
+                            api.push(format!("{module}::{owner}::{}{gated}", name_of(method)));
+                            break;
+                        }
+                    }
+                }
+            }
+            continue;
+        }
+
+        if line.starts_with("#[") {
+            attributes.push(line);
+            continue;
+        }
+        if line.starts_with("///") || line.starts_with("//!") {
+            continue;
+        }
+        let gated = gate(&attributes);
+        attributes.clear();
+        if line.starts_with("impl") {
+            let (target, implemented) = impl_target(line);
+            match implemented {
+                Some(implemented) => {
+                    api.push(format!("{module}::{target}: impl {implemented}{gated}"))
+                }
+                None if line.ends_with('{') => body = Some((target, "impl")),
+                None => {}
+            }
+            continue;
+        }
+        let Some(declaration) = line.strip_prefix("pub ") else {
+            continue;
+        };
+        for kind in ITEM_KINDS {
+            if let Some(rest) = declaration
+                .strip_prefix(kind)
+                .and_then(|rest| rest.strip_prefix(' '))
+            {
+                let name = name_of(rest);
+                api.push(format!("{module}::{name} ({kind}){gated}"));
+                if (kind == "struct" || kind == "enum") && line.ends_with('{') {
+                    body = Some((name.to_string(), kind));
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Lists the public API of the crate.
+fn public_api() -> String {
+    let lib = read("src/lib.rs");
+    let mut api = Vec::new();
+    let mut attributes = Vec::new();
+    for line in lib.lines() {
+        if line.starts_with("#[") {
+            attributes.push(line);
+            continue;
+        }
+        let hidden = attributes.contains(&"#[doc(hidden)]");
+        let gated = gate(&attributes);
+        attributes.clear();
+        if let Some(module) = line
+            .strip_prefix("pub mod ")
+            .and_then(|m| m.strip_suffix(';'))
+        {
+            if hidden {
+                api.push(format!("{module} (hidden module){gated}"));
+                continue;
+            }
+            api.push(format!("{module} (module){gated}"));
+            module_api(module, &read(&format!("src/{module}.rs")), &mut api);
+        }
+        // the public items of a private module are reachable through the prelude, if at all.
+        if let Some(module) = line.strip_prefix("mod ").and_then(|m| m.strip_suffix(';')) {
+            if !lib.contains(&format!("pub use crate::{module}::")) {
+                continue;
+            }
+            api.push(format!("{module} (private module){gated}"));
+            module_api(module, &read(&format!("src/{module}.rs")), &mut api);
+        }
+    }
+
+    // the prelude re-exports items of private modules as well.
+    let prelude = &lib[lib.find("pub mod prelude {").unwrap()..];
+    let prelude = &prelude[..prelude.find("\n}").unwrap()];
+    for export in prelude.split(';') {
+        let Some(path) = export.trim().strip_prefix("pub use crate::") else {
+            continue;
+        };
+        let path: String = path.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (module, names) = path.split_once("::").unwrap();
+        let names = names.trim_start_matches('{').trim_end_matches('}');
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            api.push(format!("prelude::{name} (from {module})"));
+        }
+    }
+
+    api.push(String::new());
+    api.join("\n")
+}
+
+#[test]
+fn public_api_matches_the_snapshot() {
+    let api = public_api();
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(Path::new(env!("CARGO_MANIFEST_DIR")).join(SNAPSHOT), &api).unwrap();
+        return;
+    }
+    let expected = read(SNAPSHOT);
+    let removed: Vec<_> = expected
+        .lines()
+        .filter(|line| !api.lines().any(|l| l == *line))
+        .collect();
+    let added: Vec<_> = api
+        .lines()
+        .filter(|line| !expected.lines().any(|l| l == *line))
+        .collect();
+    assert!(
+        api == expected,
+        "the public API differs from {SNAPSHOT}, rerun with UPDATE_SNAPSHOTS=1 if the change is \
+         deliberate\nremoved: {removed:#?}\nadded: {added:#?}"
+    );
+}
@@ -0,0 +1,212 @@
+//! Starts the `--serve` mode on an ephemeral port and exercises every endpoint over real
+//! connections, including requests it must reject with an error response.
+
+#![cfg(feature = "serve")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_fri-dynamic-folding-scheme");
+
+/// A server process, stopped when dropped.
+struct Server {
+    process: Child,
+    address: String,
+}
+
+impl Server {
+    /// Starts a server on a port the system picks, and waits until it listens.
+    fn start() -> Self {
+        let mut process = Command::new(BINARY)
+            .args(["--serve", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(process.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let address = line
+            .trim()
+            .strip_prefix("listening on http://")
+            .unwrap_or_else(|| panic!("unexpected first line `{line}`"))
+            .to_string();
+        Server { process, address }
+    }
+
+    /// Sends a raw request and returns the status and body of the response.
+    fn send(&self, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    /// Sends a request with the given method, path and body.
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        self.send(&format!(
+            "{method} {path} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n{body}",
+            self.address,
+            body.len()
+        ))
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Answers a request in the `--stdio-json` mode.
+fn stdio(request: &str) -> String {
+    let mut process = Command::new(BINARY)
+        .arg("--stdio-json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    process
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(request.as_bytes())
+        .unwrap();
+    let output = process.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn healthz() {
+    let server = Server::start();
+    assert_eq!(
+        server.request("GET", "/healthz", ""),
+        (200, "{\"status\":\"ok\"}".to_string())
+    );
+}
+
+#[test]
+fn optimize_answers_like_the_stdio_mode() {
+    let server = Server::start();
+    let (status, body) = server.request(
+        "POST",
+        "/optimize",
+        r#"{"degree":65536,"blowup":8,"queries":27}"#,
+    );
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""size_elements":4966"#), "{body}");
+    assert!(body.contains(r#""schedule":[0,4]"#), "{body}");
+    assert_eq!(
+        body,
+        stdio(r#"{"command":"optimize","degree":65536,"blowup":8,"queries":27}"#)
+    );
+}
+
+#[test]
+fn estimate() {
+    let server = Server::start();
+    let request = r#"{"degree":65536,"blowup":8,"queries":27,"schedule":[0,3,3]}"#;
+    let (status, body) = server.request("POST", "/estimate", request);
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""schedule":[0,3,3]"#), "{body}");
+    assert_eq!(
+        body,
+        stdio(
+            r#"{"command":"estimate","degree":65536,"blowup":8,"queries":27,"schedule":[0,3,3]}"#
+        )
+    );
+}
+
+#[test]
+fn sweep() {
+    let server = Server::start();
+    let (status, body) = server.request(
+        "POST",
+        "/sweep",
+        r#"{"degree":65536,"blowup":8,"queries":[27,40]}"#,
+    );
+    assert_eq!(status, 200);
+    assert!(
+        body.contains(r#""queries":27"#) && body.contains(r#""queries":40"#),
+        "{body}"
+    );
+}
+
+#[test]
+fn malformed_requests_are_answered_with_structured_errors() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/optimize", "{\"degree\":");
+    assert_eq!(status, 400);
+    assert!(
+        body.starts_with(r#"{"schema_version":1,"error":""#),
+        "{body}"
+    );
+
+    let (status, body) = server.request("POST", "/estimate", r#"{"degree":65536,"blowup":8}"#);
+    assert_eq!(status, 400);
+    assert!(body.contains(r#""error":"#), "{body}");
+
+    let (status, body) = server.send("garbage\r\n\r\n");
+    assert_eq!(
+        (status, body.as_str()),
+        (
+            400,
+            r#"{"schema_version":1,"error":"malformed request line"}"#
+        )
+    );
+
+    assert_eq!(server.request("GET", "/optimize", "").0, 405);
+    assert_eq!(server.request("POST", "/unknown", "{}").0, 404);
+}
+
+#[test]
+fn deeply_nested_requests_are_rejected() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/optimize", &"[".repeat(200_000));
+    assert_eq!(status, 400);
+    assert!(body.contains("nests deeper"), "{body}");
+    // the server is still up.
+    assert_eq!(server.request("GET", "/healthz", "").0, 200);
+}
+
+#[test]
+fn oversized_headers_are_rejected() {
+    let server = Server::start();
+    let header = "x".repeat(16 << 10);
+    let (status, body) = server.send(&format!(
+        "GET /healthz HTTP/1.1\r\nX-Padding: {header}\r\n\r\n"
+    ));
+    assert_eq!(status, 431);
+    assert!(body.contains("exceed"), "{body}");
+    assert_eq!(server.request("GET", "/healthz", "").0, 200);
+}
+
+#[test]
+fn concurrent_requests_get_their_own_answers() {
+    let server = Server::start();
+    let request = |degree: usize| format!(r#"{{"degree":{degree},"blowup":8,"queries":27}}"#);
+    let answers = std::thread::scope(|scope| {
+        [1 << 16, 1 << 20, 1 << 24]
+            .map(|degree| {
+                let (server, request) = (&server, &request);
+                scope.spawn(move || {
+                    (
+                        degree,
+                        server.request("POST", "/optimize", &request(degree)),
+                    )
+                })
+            })
+            .map(|handle| handle.join().unwrap())
+    });
+    for (degree, (status, body)) in answers {
+        assert_eq!(status, 200);
+        let command = request(degree).replacen('{', r#"{"command":"optimize","#, 1);
+        assert_eq!(body, stdio(&command));
+    }
+}
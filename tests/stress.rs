@@ -0,0 +1,1125 @@
+//! Random-walks the parameter space and checks invariants that span several features of the
+//! crate, such as the optimizer never losing to a simple schedule. The checks take minutes, so
+//! they are ignored unless asked for, as the nightly CI job does:
+//!
+//! ```text
+//! cargo test --release --test stress -- --ignored
+//! ```
+//!
+//! Every iteration of the random walk is derived from its own seed, which is printed on failure
+//! so the iteration can be replayed alone. The seed and iteration count are read from the
+//! `STRESS_SEED` and `STRESS_ITERATIONS` environment variables, and default to 0 and 100. Degrees
+//! are drawn from 2^12 up to 2^`STRESS_MAX_DEGREE_BITS`, 24 by default; the exhaustive search
+//! takes about a minute per iteration at 2^30.
+//!
+//! Besides the random walk, the optimality gap of the greedy scheduler is measured over a grid of
+//! parameters and the worst one printed. Invalid parameters are checked to be rejected, or in
+//! release builds only warned about where no error can be returned. The optimizer is run once
+//! along the deepest possible schedule, at the largest degree a `usize` can hold, to check that
+//! its recursion does not exhaust the stack, and every lever raising the security is checked to
+//! reach it. Finally, the optimizer and the uniform schedules are checked to price their
+//! schedules exactly as the estimator does under every combination of cost model toggles.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use fri_dynamic_folding_scheme::analysis::{
+    degree_growth_sensitivity, refinement_waterfall, security_increase_options, uniform_crossovers,
+    SecurityLever,
+};
+use fri_dynamic_folding_scheme::cost_model::{
+    CosetEvalMethod, CosetLayout, DomainLayout, Exclusions, InversionBatching, PathModel,
+    QueryValueTransmission, RemainderStrategy, SUPPORTED_SEMANTICS_VERSIONS,
+};
+use fri_dynamic_folding_scheme::exact_size::exact_proof_size;
+use fri_dynamic_folding_scheme::objective::{optimal_lexicographic, Objective};
+use fri_dynamic_folding_scheme::optimized_schedule::{
+    check_remainder_strategy, estimate_proof_size_with_query_plan, optimal_for_query_counts,
+    optimal_with_min_folding_factor, optimal_with_target_remainder_degree,
+    optimal_within_time_budget, proof_size_coefficients,
+};
+use fri_dynamic_folding_scheme::params::{CommitmentRound, Layer0Layout};
+use fri_dynamic_folding_scheme::prelude::*;
+use fri_dynamic_folding_scheme::prover_cost::{commitment_to_proof_ratio, estimate_prover_memory};
+use fri_dynamic_folding_scheme::simple_schedule::{
+    greedy_schedule, simple_schedule_result, simple_schedule_with_best_head,
+    simple_schedule_with_best_remainder, simple_schedule_with_head,
+    simple_schedule_with_tuned_final_round,
+};
+use fri_dynamic_folding_scheme::soundness::{
+    auto_extension, field_erosion, query_plan_soundness, MAX_EXTENSION_DEGREE,
+};
+use fri_dynamic_folding_scheme::verifier_cost::{estimate_verifier_gas, remainder_check_cost};
+
+/// A small deterministic generator (SplitMix64), so that a seed reproduces an iteration on any
+/// platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `range`.
+    fn range(&mut self, range: std::ops::RangeInclusive<usize>) -> usize {
+        range.start() + (self.next() % (range.end() - range.start() + 1) as u64) as usize
+    }
+
+    /// Returns one of `items`.
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.range(0..=items.len() - 1)]
+    }
+}
+
+/// Checks every invariant for one set of parameters drawn from `seed`, with a degree of at most
+/// `2^max_degree_bits`.
+fn run(seed: u64, max_degree_bits: usize) -> Result<(), String> {
+    let mut rng = Rng(seed);
+
+    let degree = 1 << rng.range(12..=max_degree_bits);
+    let blowup_factor = 1 << rng.range(1..=5);
+    let num_queries = rng.range(10..=200);
+    let remainder_max_degree = 1 << rng.range(0..=8);
+    let cost_model = CostModel {
+        coset_layout: rng.pick(&[CosetLayout::PackedLeaf, CosetLayout::SplitLeaves]),
+        path_model: rng.pick(&[PathModel::Naive, PathModel::IdealCompression]),
+        domain_layout: rng.pick(&[DomainLayout::BitReversed, DomainLayout::Natural]),
+        query_values: rng.pick(&[
+            QueryValueTransmission::AllValues,
+            QueryValueTransmission::OmitFoldedValue,
+        ]),
+        remainder_strategy: rng.pick(&[RemainderStrategy::Sent, RemainderStrategy::InLastLayer]),
+        coset_eval: rng.pick(&[
+            CosetEvalMethod::Barycentric,
+            CosetEvalMethod::Horner,
+            CosetEvalMethod::Naive,
+        ]),
+        inversion_batching: rng.pick(&[
+            InversionBatching::Unbatched,
+            InversionBatching::PerCoset,
+            InversionBatching::PerLayer,
+        ]),
+        first_layer_shares_trace_commitment: rng.pick(&[false, true]),
+        initial_layer_columns: rng.pick(&[None, Some(1), Some(80)]),
+        winterfell_layout: rng.pick(&[false, true]),
+        exclude: Exclusions {
+            layer0_paths: rng.pick(&[false, true]),
+            layer0_leaves: rng.pick(&[false, true]),
+            remainder: rng.pick(&[false, false, false, true]),
+        },
+        remainder_verify_cost_per_element: rng.pick(&[0, 0, 1, 16]),
+        ..rng.pick(&[GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256])
+    };
+    let params = format!(
+        "degree {degree}, blowup {blowup_factor}, {num_queries} queries, remainder max degree \
+         {remainder_max_degree}, {cost_model:?}"
+    );
+
+    let mut stats = SearchStats::default();
+    let (opt_size, opt_schedule) = optimal_folding_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        vec![0],
+        &HashMap::new(),
+        &cost_model,
+        &mut stats,
+    )
+    .map_err(|err| format!("optimizer failed for {params}: {err}"))?;
+
+    // the optimum leaves a remainder the verifier can learn.
+    check_remainder_strategy(
+        degree,
+        blowup_factor,
+        num_queries,
+        &opt_schedule,
+        &cost_model,
+    )
+    .map_err(|err| format!("the optimum {opt_schedule:?} is infeasible for {params}: {err}"))?;
+
+    // the deployed optimum stays optimal at its own degree and, unless the optimizer also weighs
+    // the remainder check, no extension is smaller than the optimum of a grown one.
+    let sensitivity = degree_growth_sensitivity(
+        degree,
+        blowup_factor,
+        num_queries,
+        &opt_schedule,
+        &[1, 2],
+        &cost_model,
+    )
+    .map_err(|err| format!("the degree growth analysis failed for {params}: {err}"))?;
+    for growth in &sensitivity.growths {
+        if (growth.extended_elements < growth.optimal_elements
+            && cost_model.remainder_verify_cost_per_element == 0)
+            || (growth.growth == 1 && growth.extended_elements != opt_size)
+        {
+            return Err(format!(
+                "{opt_schedule:?} grown {}-fold is extended to {growth:?} for {params}",
+                growth.growth
+            ));
+        }
+    }
+
+    // an unconstrained search visits every node, but only recurses where it has a choice.
+    if stats.nodes_explored != stats.theoretical_nodes || stats.calls > stats.nodes_explored {
+        return Err(format!(
+            "the search reports inconsistent statistics {stats} for {params}"
+        ));
+    }
+
+    // the library entry point behind the command-line report reports the same optimum.
+    let analysis = analyze(
+        &FriParams {
+            degree,
+            blowup_factor,
+            num_queries,
+            cost_model,
+        },
+        &AnalysisOptions {
+            remainder_max_degree,
+            sensitivity: false,
+            ..AnalysisOptions::default()
+        },
+    )
+    .map_err(|err| format!("the analysis failed for {params}: {err}"))?;
+    if analysis.report.schedule != opt_schedule
+        || analysis.report.total_elements()
+            != estimate_proof_size(
+                degree,
+                blowup_factor,
+                num_queries,
+                &opt_schedule,
+                &cost_model,
+            )
+            .unwrap()
+    {
+        return Err(format!(
+            "the analysis reports {:?} of {} elements but the optimizer found {opt_schedule:?} \
+             for {params}",
+            analysis.report.schedule,
+            analysis.report.total_elements()
+        ));
+    }
+
+    // the brute-force enumeration visits schedules in the same order and breaks ties alike.
+    let (_, brute_force) = optimal_lexicographic(
+        degree,
+        blowup_factor,
+        num_queries,
+        &[Objective::WeightedProofSize, Objective::ProofSize],
+        &cost_model,
+    );
+    if brute_force != opt_schedule {
+        return Err(format!(
+            "the optimizer found {opt_schedule:?} but brute force found {brute_force:?} for \
+             {params}"
+        ));
+    }
+
+    // no schedule beats the optimum on the cost the optimizer minimizes, which is the size when
+    // the remainder check is free.
+    let weighted = |schedule: &[usize]| {
+        Objective::WeightedProofSize.value(
+            degree,
+            blowup_factor,
+            num_queries,
+            schedule,
+            &cost_model,
+        )
+    };
+    let opt_cost = weighted(&opt_schedule);
+
+    // the greedy schedule is a valid schedule and never beats the optimum.
+    let (greedy_size, greedy) = greedy_schedule(degree, blowup_factor, num_queries, &cost_model)
+        .map_err(|err| format!("greedy scheduler failed for {params}: {err}"))?;
+    if validate_schedule(degree, blowup_factor, &greedy).is_err() || weighted(&greedy) < opt_cost {
+        return Err(format!(
+            "the greedy schedule {greedy:?} of {greedy_size} elements is invalid or beats the \
+             optimum of {opt_size} for {params}"
+        ));
+    }
+
+    // a search without any time still returns a complete schedule, sized as the estimator does.
+    let (budget_size, budget_schedule, _) = optimal_within_time_budget(
+        degree,
+        blowup_factor,
+        num_queries,
+        Duration::ZERO,
+        &cost_model,
+        &mut SearchStats::default(),
+    )
+    .map_err(|err| format!("the budgeted optimizer failed for {params}: {err}"))?;
+    if validate_schedule(degree, blowup_factor, &budget_schedule).is_err()
+        || budget_size
+            != estimate_proof_size(
+                degree,
+                blowup_factor,
+                num_queries,
+                &budget_schedule,
+                &cost_model,
+            )
+            .unwrap()
+    {
+        return Err(format!(
+            "the schedule {budget_schedule:?} of {budget_size} elements found without time is \
+             invalid or mis-sized for {params}"
+        ));
+    }
+
+    // a sweep over query counts finds what the optimizer finds for each of them. The second
+    // count is derived rather than drawn, so that every seed keeps drawing the same parameters.
+    let other_queries = 2 * num_queries;
+    let sweep = optimal_for_query_counts(
+        degree,
+        blowup_factor,
+        &[num_queries, other_queries],
+        &cost_model,
+    )
+    .map_err(|err| format!("the query sweep failed for {params}: {err}"))?;
+    let other_optimum = optimal_folding_strategy(
+        degree,
+        blowup_factor,
+        other_queries,
+        vec![0],
+        &HashMap::new(),
+        &cost_model,
+        &mut SearchStats::default(),
+    )
+    .map_err(|err| format!("optimizer failed for {params}: {err}"))?;
+    if sweep != [(opt_size, opt_schedule.clone()), other_optimum] {
+        return Err(format!(
+            "the sweep over {num_queries} and {other_queries} queries found {sweep:?} for {params}"
+        ));
+    }
+
+    // stopping at the optimum's remainder degree on purpose finds the optimum again.
+    let remainder_degree = (degree / blowup_factor) >> opt_schedule.iter().sum::<usize>();
+    let (target_size, target_schedule) = optimal_with_target_remainder_degree(
+        degree,
+        blowup_factor,
+        num_queries,
+        remainder_degree,
+        &cost_model,
+        &mut SearchStats::default(),
+    )
+    .map_err(|err| format!("the remainder-degree search failed for {params}: {err}"))?;
+    if (target_size, &target_schedule) != (opt_size, &opt_schedule) {
+        return Err(format!(
+            "targeting remainder degree {remainder_degree} found {target_schedule:?} instead of \
+             {opt_schedule:?} for {params}"
+        ));
+    }
+
+    // a floor on the folding factor is honored and can only make the proof larger.
+    let min_folding_bits = rng.range(1..=4);
+    let (floor_size, floor_schedule) = optimal_with_min_folding_factor(
+        degree,
+        blowup_factor,
+        num_queries,
+        min_folding_bits,
+        &cost_model,
+        &mut SearchStats::default(),
+    )
+    .map_err(|err| format!("optimizer failed with a floor of {min_folding_bits} bits: {err}"))?;
+    if floor_schedule[1..]
+        .iter()
+        .any(|&bits| bits < min_folding_bits)
+        || weighted(&floor_schedule) < opt_cost
+    {
+        return Err(format!(
+            "{floor_schedule:?} ({floor_size} elements) is returned for a floor of \
+             {min_folding_bits} bits against the optimal {opt_schedule:?} for {params}"
+        ));
+    }
+
+    let mut schedules = vec![(opt_size, opt_schedule.clone())];
+    for bits in 1..=4 {
+        // the achieved remainder degree is within the bound unless the polynomial ran out of
+        // degree to fold, and a folding round was only made while it was not.
+        let result = simple_schedule_result(
+            degree,
+            blowup_factor,
+            num_queries,
+            remainder_max_degree,
+            bits,
+            &cost_model,
+        )
+        .map_err(|err| format!("uniform schedule failed for {params}: {err}"))?;
+        let within_bound = result.final_remainder_degree <= remainder_max_degree
+            || result.final_remainder_degree < 1 << bits;
+        let needed =
+            result.rounds == 0 || remainder_max_degree < result.final_remainder_degree << bits;
+        if !within_bound || !needed {
+            return Err(format!(
+                "the uniform schedule for {bits} bits achieves remainder degree {} for {params}",
+                result.final_remainder_degree
+            ));
+        }
+
+        schedules.push(
+            simple_schedule(
+                degree,
+                blowup_factor,
+                num_queries,
+                remainder_max_degree,
+                bits,
+                &cost_model,
+            )
+            .map_err(|err| format!("uniform schedule failed for {params}: {err}"))?,
+        );
+        schedules.push(
+            simple_schedule_with_tuned_final_round(
+                degree,
+                blowup_factor,
+                num_queries,
+                remainder_max_degree,
+                bits,
+                &cost_model,
+            )
+            .map_err(|err| format!("tuned schedule failed for {params}: {err}"))?,
+        );
+
+        // the best head must be the smallest of all heads that yield a schedule.
+        let best_head = simple_schedule_with_best_head(
+            degree,
+            blowup_factor,
+            num_queries,
+            remainder_max_degree,
+            bits,
+            &cost_model,
+        );
+        let brute_force = (1..=4)
+            .filter_map(|head| {
+                simple_schedule_with_head(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    remainder_max_degree,
+                    head,
+                    bits,
+                    &cost_model,
+                )
+                .ok()
+            })
+            .map(|(size, _)| size)
+            .min();
+        if brute_force.is_some_and(|size| size != best_head.0) {
+            return Err(format!(
+                "the best head for body bits {bits} is {best_head:?} but a head yields {} \
+                 elements for {params}",
+                brute_force.unwrap()
+            ));
+        }
+        schedules.push(best_head);
+
+        // the best remainder bound is no worse than the requested one, whenever that one leaves
+        // a remainder the verifier can learn.
+        let uniform = schedules[schedules.len() - 3].clone();
+        match simple_schedule_with_best_remainder(
+            degree,
+            blowup_factor,
+            num_queries,
+            bits,
+            &cost_model,
+        ) {
+            Ok(best_remainder) => {
+                let feasible = check_remainder_strategy(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    &uniform.1,
+                    &cost_model,
+                )
+                .is_ok();
+                if feasible && best_remainder.0 > uniform.0 {
+                    return Err(format!(
+                        "the best remainder bound for {bits} bits yields {best_remainder:?} \
+                         against {uniform:?} for {params}"
+                    ));
+                }
+                schedules.push(best_remainder);
+            }
+            // an interpolated remainder may be too wide for the queries under every bound.
+            Err(_) if cost_model.remainder_strategy == RemainderStrategy::InLastLayer => {}
+            Err(err) => {
+                return Err(format!(
+                    "no remainder bound suits {bits} bits for {params}: {err}"
+                ))
+            }
+        }
+    }
+
+    // crossovers lie within the range compared, in increasing order.
+    match uniform_crossovers(degree, blowup_factor, num_queries, &cost_model) {
+        Ok(crossovers) => {
+            for pair in &crossovers.pairs {
+                let consistent =
+                    pair.degrees
+                        .windows(2)
+                        .all(|degrees| degrees[0] < degrees[1])
+                        && pair.degrees.iter().all(|&crossover| {
+                            crossovers.min_degree < crossover && crossover <= degree
+                        })
+                        && (pair.smaller_first.is_some() || pair.degrees.is_empty());
+                if !consistent {
+                    return Err(format!("inconsistent crossovers {pair:?} for {params}"));
+                }
+            }
+        }
+        Err(_) if cost_model.remainder_strategy == RemainderStrategy::InLastLayer => {}
+        Err(err) => return Err(format!("the crossover analysis failed for {params}: {err}")),
+    }
+
+    for (size, schedule) in &schedules {
+        // a uniform schedule may leave a remainder the verifier cannot learn, and is then out of
+        // the competition.
+        let feasible =
+            check_remainder_strategy(degree, blowup_factor, num_queries, schedule, &cost_model)
+                .is_ok();
+        if feasible && weighted(schedule) < opt_cost {
+            return Err(format!(
+                "{schedule:?} ({size} elements) beats the optimal {opt_schedule:?} ({opt_size} \
+                 elements) for {params}"
+            ));
+        }
+        validate_schedule(degree, blowup_factor, schedule)
+            .map_err(|err| format!("{schedule:?} is rejected for {params}: {err}"))?;
+
+        let estimate =
+            estimate_proof_size(degree, blowup_factor, num_queries, schedule, &cost_model).unwrap();
+        if estimate != *size {
+            return Err(format!(
+                "{schedule:?} is reported as {size} but estimated as {estimate} elements for \
+                 {params}"
+            ));
+        }
+
+        // a plan querying every layer equally is the plain proof, and both accountings agree.
+        let uniform_plan = vec![num_queries; schedule.len()];
+        let planned_size = estimate_proof_size_with_query_plan(
+            degree,
+            blowup_factor,
+            &uniform_plan,
+            schedule,
+            &cost_model,
+        );
+        let plan_soundness =
+            query_plan_soundness(degree, blowup_factor, &uniform_plan, schedule, 128);
+        let soundness = fri_soundness(degree, blowup_factor, num_queries, schedule, 128);
+        if planned_size != Ok(estimate)
+            || plan_soundness.map(|plan| (plan.conjectured, plan.conservative))
+                != Ok((soundness, soundness))
+        {
+            return Err(format!(
+                "the uniform query plan prices {schedule:?} as {planned_size:?} for {params}"
+            ));
+        }
+
+        // the auto extension is the smallest one within the erosion tolerated over BabyBear, and
+        // its penalty is the growth of the plain estimate.
+        match auto_extension(
+            degree,
+            blowup_factor,
+            num_queries,
+            schedule,
+            31,
+            1.0,
+            &cost_model,
+        ) {
+            Ok(bump) => {
+                let extension_degree = bump.bumped.extension_degree;
+                let smaller = field_erosion(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    schedule,
+                    31,
+                    extension_degree.saturating_sub(1),
+                );
+                if bump.bumped.exceeds(1.0)
+                    || (bump.is_bumped() && !smaller.exceeds(1.0))
+                    || bump.original_size != estimate
+                    || bump.original_size + bump.size_penalty() != bump.bumped_size
+                {
+                    return Err(format!(
+                        "the auto extension of {schedule:?} settles on degree {extension_degree} \
+                         for {params}"
+                    ));
+                }
+            }
+            Err(_) => {
+                let largest = field_erosion(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    schedule,
+                    31,
+                    MAX_EXTENSION_DEGREE,
+                );
+                if !largest.exceeds(1.0) {
+                    return Err(format!(
+                        "the auto extension of {schedule:?} fails within the tolerance for {params}"
+                    ));
+                }
+            }
+        }
+
+        // merging the trace rounds into one tree saves exactly the paths of the other trees.
+        let rounds = vec![
+            CommitmentRound {
+                num_columns: 3,
+                column_elements: 1,
+            },
+            CommitmentRound {
+                num_columns: 2,
+                column_elements: cost_model.extension_degree,
+            },
+        ];
+        let layout_total = |layout: &Layer0Layout| {
+            FriParams::from_layer0_layout(
+                degree / blowup_factor,
+                layout,
+                blowup_factor,
+                num_queries,
+                &cost_model,
+            )
+            .map(|params| {
+                let openings = params.layer0_openings(layout);
+                let size = estimate_proof_size(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    schedule,
+                    &params.cost_model,
+                )
+                .unwrap();
+                (size + openings.total_elements(), openings.path_elements)
+            })
+        };
+        let separate = layout_total(&Layer0Layout::SeparateTrees(rounds.clone()));
+        let merged = layout_total(&Layer0Layout::MergedLeaf(rounds));
+        if let (Ok((separate, paths)), Ok((merged, _))) = (separate, merged) {
+            if !cost_model.winterfell_layout && separate != merged + paths {
+                return Err(format!(
+                    "{schedule:?} takes {separate} elements over separate trees and {merged} over \
+                     a merged leaf, not {paths} path elements apart, for {params}"
+                ));
+            }
+        }
+
+        let report = FoldingReport::new(
+            degree,
+            blowup_factor,
+            num_queries,
+            schedule.clone(),
+            &cost_model,
+        );
+        if report.total_elements() != *size {
+            return Err(format!(
+                "the report of {schedule:?} holds {} instead of {size} elements for {params}",
+                report.total_elements()
+            ));
+        }
+        if size_in_bytes(*size, &cost_model) != *size * cost_model.element_bytes {
+            return Err(format!(
+                "{size} elements do not convert to bytes consistently for {params}"
+            ));
+        }
+
+        // the size is affine in the queries whenever paths are not shared.
+        if let Some((fixed, per_query)) =
+            proof_size_coefficients(degree, blowup_factor, schedule, &cost_model)
+        {
+            if fixed + num_queries * per_query != *size {
+                return Err(format!(
+                    "{schedule:?} splits into {fixed} + {num_queries} * {per_query} elements \
+                     instead of {size} for {params}"
+                ));
+            }
+        }
+
+        // every layer's tree is binary and agrees with the path length the estimator prices.
+        for layer in &report.breakdown.layers {
+            let shape = layer.tree_shape();
+            let factor = 1 << layer.leaf_bits;
+            let path_depth = match cost_model.coset_layout {
+                CosetLayout::PackedLeaf => shape.depth,
+                CosetLayout::SplitLeaves => shape.depth - layer.leaf_bits,
+            };
+            if shape.internal_nodes != shape.leaves - shape.cap_nodes
+                || shape.leaves != shape.cap_nodes << shape.depth
+                || path_depth
+                    != cost_model
+                        .coset_layout
+                        .path_digests(layer.domain_size, factor)
+            {
+                return Err(format!(
+                    "{schedule:?} commits a layer of {} values to a tree shaped {shape:?} for \
+                     {params}",
+                    layer.domain_size
+                ));
+            }
+        }
+
+        // excluded components are set aside, not lost.
+        let everything = CostModel {
+            exclude: Exclusions::default(),
+            ..cost_model
+        };
+        let full =
+            estimate_proof_size(degree, blowup_factor, num_queries, schedule, &everything).unwrap();
+        if size + report.breakdown.excluded.total_elements() != full {
+            return Err(format!(
+                "{schedule:?} holds {size} elements and excludes {:?} out of {full} for {params}",
+                report.breakdown.excluded
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the optimizer at the largest degree a `usize` can hold, pinning every layer to fold by 2
+/// so that the search follows the single deepest path of recursion instead of the whole tree.
+/// An unconstrained search at this degree would take far too long, but its recursion is never
+/// deeper than this one.
+fn deepest_schedule() -> Result<(), String> {
+    let degree: usize = 1 << (usize::BITS - 1);
+    let blowup_factor = 2;
+    let available_bits = (degree / blowup_factor).ilog2() as usize;
+    let pinned_layers = (1..=available_bits).map(|layer| (layer, 1)).collect();
+
+    let (_, schedule) = optimal_folding_strategy(
+        degree,
+        blowup_factor,
+        1,
+        vec![0],
+        &pinned_layers,
+        &CostModel::default(),
+        &mut SearchStats::default(),
+    )
+    .map_err(|err| format!("optimizer failed at degree {degree}: {err}"))?;
+    if schedule.len() != available_bits + 1 {
+        return Err(format!(
+            "the deepest schedule at degree {degree} has {} instead of {} layers",
+            schedule.len(),
+            available_bits + 1
+        ));
+    }
+    Ok(())
+}
+
+/// Measures how much larger the greedy schedule is than the optimal one over a grid of degrees,
+/// blowup factors and query counts under the default cost model, and returns the worst gap in
+/// percent with the parameters it occurs for.
+fn greedy_gap() -> Result<(f64, String), String> {
+    let cost_model = CostModel::default();
+    let mut worst = (0.0, String::new());
+    for degree_bits in 10..=24 {
+        for blowup_factor in [2, 4, 8, 16] {
+            for num_queries in [8, 27, 64, 128] {
+                let degree = 1 << degree_bits;
+                let params = format!(
+                    "degree 2^{degree_bits}, blowup {blowup_factor}, {num_queries} queries"
+                );
+                let (opt_size, _) = optimal_folding_strategy(
+                    degree,
+                    blowup_factor,
+                    num_queries,
+                    vec![0],
+                    &HashMap::new(),
+                    &cost_model,
+                    &mut SearchStats::default(),
+                )
+                .map_err(|err| format!("optimizer failed for {params}: {err}"))?;
+                let (greedy_size, _) =
+                    greedy_schedule(degree, blowup_factor, num_queries, &cost_model)
+                        .map_err(|err| format!("greedy scheduler failed for {params}: {err}"))?;
+                let gap = 100.0 * (greedy_size as f64 / opt_size as f64 - 1.0);
+                if gap > worst.0 {
+                    worst = (gap, params);
+                }
+            }
+        }
+    }
+    Ok(worst)
+}
+
+/// Checks that invalid parameters are rejected by the entry points in any build.
+fn invalid_parameters() -> Result<(), String> {
+    let cost_model = CostModel::default();
+    for (degree, blowup_factor) in [(3 << 10, 4), (1 << 10, 3), (2, 4), (0, 4)] {
+        let optimum = optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            27,
+            vec![0],
+            &HashMap::new(),
+            &cost_model,
+            &mut SearchStats::default(),
+        );
+        let min_factor = optimal_with_min_folding_factor(
+            degree,
+            blowup_factor,
+            27,
+            2,
+            &cost_model,
+            &mut SearchStats::default(),
+        );
+        let uniform = simple_schedule(degree, blowup_factor, 27, 64, 2, &cost_model);
+        if optimum.is_ok() || min_factor.is_ok() || uniform.is_ok() {
+            return Err(format!(
+                "degree {degree} with blowup {blowup_factor} is accepted"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns every combination of the cost model toggles the crate supports over every preset.
+fn every_cost_model() -> Vec<CostModel> {
+    /// Returns every model of `models` with the field set by `set` to every one of `values`.
+    fn vary<T: Copy>(
+        models: Vec<CostModel>,
+        values: &[T],
+        set: impl Fn(&mut CostModel, T),
+    ) -> Vec<CostModel> {
+        models
+            .into_iter()
+            .flat_map(|model| {
+                let set = &set;
+                values.iter().map(move |&value| {
+                    let mut model = model;
+                    set(&mut model, value);
+                    model
+                })
+            })
+            .collect()
+    }
+
+    let models = vec![GOLDILOCKS_QUADRATIC, BABYBEAR_QUARTIC, POSEIDON_256];
+    let models = vary(
+        models,
+        &[CosetLayout::PackedLeaf, CosetLayout::SplitLeaves],
+        |model, value| model.coset_layout = value,
+    );
+    let models = vary(
+        models,
+        &[PathModel::Naive, PathModel::IdealCompression],
+        |model, value| model.path_model = value,
+    );
+    let models = vary(
+        models,
+        &[DomainLayout::BitReversed, DomainLayout::Natural],
+        |model, value| model.domain_layout = value,
+    );
+    let models = vary(
+        models,
+        &[
+            QueryValueTransmission::AllValues,
+            QueryValueTransmission::OmitFoldedValue,
+        ],
+        |model, value| model.query_values = value,
+    );
+    let models = vary(
+        models,
+        &[RemainderStrategy::Sent, RemainderStrategy::InLastLayer],
+        |model, value| model.remainder_strategy = value,
+    );
+    let models = vary(
+        models,
+        &[
+            CosetEvalMethod::Barycentric,
+            CosetEvalMethod::Horner,
+            CosetEvalMethod::Naive,
+        ],
+        |model, value| model.coset_eval = value,
+    );
+    let models = vary(
+        models,
+        &[
+            InversionBatching::Unbatched,
+            InversionBatching::PerCoset,
+            InversionBatching::PerLayer,
+        ],
+        |model, value| model.inversion_batching = value,
+    );
+    let models = vary(models, &[false, true], |model, value| {
+        model.first_layer_shares_trace_commitment = value
+    });
+    let models = vary(models, &[None, Some(80)], |model, value| {
+        model.initial_layer_columns = value
+    });
+    let models = vary(models, &[false, true], |model, value| {
+        model.winterfell_layout = value
+    });
+    let models = vary(models, &[false, true], |model, value| {
+        model.exclude.layer0_paths = value
+    });
+    let models = vary(models, &[false, true], |model, value| {
+        model.exclude.layer0_leaves = value
+    });
+    let models = vary(models, &[false, true], |model, value| {
+        model.exclude.remainder = value
+    });
+    let models = vary(models, &[0, 16], |model, value| {
+        model.remainder_verify_cost_per_element = value
+    });
+    vary(models, SUPPORTED_SEMANTICS_VERSIONS, |model, value| {
+        model.semantics_version = value
+    })
+}
+
+/// Checks under every combination of cost model toggles that the optimizer and the uniform
+/// schedules price their schedules exactly as [`estimate_proof_size`] does, so that neither
+/// carries pricing logic of its own a new toggle could miss, and that the provenance of every
+/// component recomputes to its value and adds up to the estimate. Returns the number of cost models
+/// checked.
+fn accounting_parity() -> Result<usize, String> {
+    let (degree, blowup_factor, num_queries, remainder_max_degree) = (1 << 10, 4, 27, 3);
+    let cost_models = every_cost_model();
+    for cost_model in &cost_models {
+        let (opt_size, opt_schedule) = optimal_folding_strategy(
+            degree,
+            blowup_factor,
+            num_queries,
+            vec![0],
+            &HashMap::new(),
+            cost_model,
+            &mut SearchStats::default(),
+        )
+        .map_err(|err| format!("optimizer failed for {cost_model:?}: {err}"))?;
+        let mut priced = vec![("optimal", opt_size, opt_schedule)];
+        for bits in 1..=4 {
+            let (size, schedule) = simple_schedule(
+                degree,
+                blowup_factor,
+                num_queries,
+                remainder_max_degree,
+                bits,
+                cost_model,
+            )
+            .map_err(|err| format!("uniform schedule failed for {cost_model:?}: {err}"))?;
+            priced.push(("uniform", size, schedule));
+        }
+        for (strategy, size, schedule) in priced {
+            let estimate =
+                estimate_proof_size(degree, blowup_factor, num_queries, &schedule, cost_model)
+                    .unwrap();
+            if size != estimate {
+                return Err(format!(
+                    "the {strategy} schedule {schedule:?} is priced at {size} but estimated at \
+                     {estimate} elements for {cost_model:?}"
+                ));
+            }
+            let breakdown = ProofSizeBreakdown::with_provenance(
+                degree,
+                blowup_factor,
+                num_queries,
+                &schedule,
+                cost_model,
+            );
+            let mut counted = 0;
+            for record in &breakdown.provenance {
+                if record.recompute() != record.value {
+                    return Err(format!(
+                        "{record:?} recomputes to {} for {cost_model:?}",
+                        record.recompute()
+                    ));
+                }
+                if record.counted {
+                    counted += record.value;
+                }
+            }
+            if counted != estimate {
+                return Err(format!(
+                    "the provenance of the {strategy} schedule {schedule:?} counts {counted} but \
+                     it is estimated at {estimate} elements for {cost_model:?}"
+                ));
+            }
+        }
+    }
+    Ok(cost_models.len())
+}
+
+/// Checks under every combination of cost model toggles that the schedule that does not fold at
+/// all is priced and costed like any other, and that [`baseline_size`] is its size with the
+/// remainder sent whenever the queries cannot interpolate it.
+fn unfolded_baseline() -> Result<(), String> {
+    let unfolded = vec![0];
+    for cost_model in &every_cost_model() {
+        for (degree, blowup_factor, num_queries) in [(1 << 10, 4, 3), (1 << 10, 4, 256), (4, 4, 1)]
+        {
+            validate_schedule(degree, blowup_factor, &unfolded)
+                .map_err(|err| format!("[0] is rejected at degree {degree}: {err}"))?;
+            let report = FoldingReport::new(
+                degree,
+                blowup_factor,
+                num_queries,
+                unfolded.clone(),
+                cost_model,
+            );
+            let positions: Vec<_> = (0..num_queries).map(|i| i * 7 % degree).collect();
+            exact_proof_size(degree, blowup_factor, &positions, &unfolded, cost_model);
+            estimate_verifier_gas(degree, blowup_factor, num_queries, &unfolded, cost_model);
+            let _ = estimate_prover_memory(degree, blowup_factor, &unfolded, cost_model);
+            commitment_to_proof_ratio(degree, blowup_factor, num_queries, &unfolded, cost_model);
+            remainder_check_cost(degree, blowup_factor, &unfolded, cost_model);
+            fri_soundness(degree, blowup_factor, num_queries, &unfolded, 128);
+            refinement_waterfall(degree, blowup_factor, num_queries, &unfolded, cost_model);
+
+            let sent = CostModel {
+                remainder_strategy: RemainderStrategy::Sent,
+                ..*cost_model
+            };
+            let expected = if check_remainder_strategy(
+                degree,
+                blowup_factor,
+                num_queries,
+                &unfolded,
+                cost_model,
+            )
+            .is_ok()
+            {
+                report.total_elements()
+            } else {
+                estimate_proof_size(degree, blowup_factor, num_queries, &unfolded, &sent).unwrap()
+            };
+            let baseline = baseline_size(degree, blowup_factor, num_queries, cost_model);
+            if baseline != expected {
+                return Err(format!(
+                    "the baseline at degree {degree} with {num_queries} queries is {baseline} \
+                     instead of {expected} elements for {cost_model:?}"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every lever of [`security_increase_options`] reaches the raised security with as
+/// few queries as it can, and that grinding leaves the proof and the prover's hashing as they are.
+fn security_levers() -> Result<(), String> {
+    for cost_model in [CostModel::default(), BABYBEAR_QUARTIC, POSEIDON_256] {
+        for (degree, blowup_factor, num_queries, extra_bits) in [
+            (1 << 12, 2, 80, 1),
+            (1 << 14, 8, 27, 20),
+            (1 << 16, 16, 0, 7),
+        ] {
+            let increase = security_increase_options(
+                degree,
+                blowup_factor,
+                num_queries,
+                extra_bits,
+                1e6,
+                &cost_model,
+            )
+            .map_err(|err| format!("the levers at degree {degree} are not priced: {err}"))?;
+            let target = num_queries as u32 * blowup_factor.ilog2() + extra_bits;
+            for option in &increase.options {
+                let report = &option.report;
+                let bits_per_query = report.blowup_factor.ilog2();
+                let bits = report.num_queries as u32 * bits_per_query + option.grinding_bits;
+                let fewer = bits.saturating_sub(bits_per_query);
+                if bits < target || (option.lever != SecurityLever::Grinding && fewer >= target) {
+                    return Err(format!(
+                        "{} reaches {bits} instead of {target} bits at degree {degree} with \
+                         {num_queries} queries",
+                        option.lever
+                    ));
+                }
+            }
+            let grinding = increase.option(SecurityLever::Grinding);
+            if (&grinding.report, grinding.hash_input_elements)
+                != (
+                    &increase.current.report,
+                    increase.current.hash_input_elements,
+                )
+            {
+                return Err(format!(
+                    "grinding changes the proof at degree {degree} with {num_queries} queries"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the value of the environment variable `var`, or `default` if it is unset.
+fn setting(var: &str, default: u64) -> u64 {
+    match std::env::var(var) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value `{value}` for `{var}`")),
+        Err(_) => default,
+    }
+}
+
+/// Fails the test with the error of a check.
+fn check<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn random_walk() {
+    let seed = setting("STRESS_SEED", 0);
+    let iterations = setting("STRESS_ITERATIONS", 100);
+    let max_degree_bits = setting("STRESS_MAX_DEGREE_BITS", 24);
+    assert!(
+        (12..=30).contains(&max_degree_bits),
+        "`STRESS_MAX_DEGREE_BITS` must be between 12 and 30"
+    );
+    for i in 0..iterations {
+        let iteration_seed = seed.wrapping_add(i);
+        if let Err(err) = run(iteration_seed, max_degree_bits as usize) {
+            panic!(
+                "{err}\nreproduce with `STRESS_SEED={iteration_seed} STRESS_ITERATIONS=1 \
+                 STRESS_MAX_DEGREE_BITS={max_degree_bits} cargo test --release --test stress -- \
+                 --ignored random_walk`"
+            );
+        }
+    }
+    println!("{iterations} iterations passed from seed {seed}");
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn greedy_gap_is_bounded() {
+    let (gap, params) = check(greedy_gap());
+    println!("the greedy schedule is at most {gap:.1}% larger ({params})");
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn invalid_parameters_are_rejected() {
+    check(invalid_parameters());
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn deepest_schedule_fits_the_stack() {
+    check(deepest_schedule());
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn unfolded_baseline_is_priced_alike() {
+    check(unfolded_baseline());
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn security_levers_reach_the_target() {
+    check(security_levers());
+}
+
+#[test]
+#[ignore = "stress test, run with --ignored"]
+fn strategies_price_alike_under_every_cost_model() {
+    let count = check(accounting_parity());
+    println!("{count} cost models price schedules alike in every strategy");
+}